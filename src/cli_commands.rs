@@ -1,12 +1,34 @@
+use crate::core::aliases::AliasShell;
+use crate::core::encoding::OutputEncoding;
+use crate::core::import::ImportFormat;
 use clap::{Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
 
 #[derive(Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    after_help = "Run `hoard <COMMAND> --help` for the options and examples of a command"
+)]
 pub struct Cli {
     /// Only used when `hoard` is run as shell plugin
     #[arg(long)]
     pub autocomplete: bool,
 
+    /// Name of the trove to use this time instead of the active one
+    #[arg(long, global = true, value_name = "NAME")]
+    pub trove: Option<String>,
+
+    /// Profile with its own config, troves and statistics, kept in `~/.config/hoard/profiles/NAME`
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// How commands that print data format it
+    #[arg(long, global = true, value_name = "FORMAT")]
+    pub output: Option<OutputFormat>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -27,12 +49,159 @@ pub enum Mode {
     Revert,
 }
 
+#[derive(Subcommand)]
+pub enum TroveCommand {
+    /// Make another trove the active one. A new trove is created when a command is first saved to it
+    Switch {
+        /// Name of the trove, `default` for the trove you started with
+        name: String,
+    },
+    /// List all troves and show which one is active
+    List {},
+}
+
+#[derive(Subcommand)]
+pub enum TrashCommand {
+    /// List the removed commands, the most recently removed last
+    List {},
+    /// Put a removed command back into the trove
+    Restore {
+        /// Name of the command, the most recently removed command if not given
+        #[arg(short = 'n', long)]
+        name: Option<String>,
+    },
+    /// Delete all removed commands for good
+    Purge {},
+}
+
+#[derive(Subcommand)]
+pub enum TagCommand {
+    /// Add a tag to every command matching the filter
+    Add {
+        tag: String,
+
+        /// Commands to tag, matching every term, e.g. `tag:aws`, `ns:work`, `name:deploy` or any text
+        #[arg(short = 'f', long)]
+        filter: String,
+
+        /// Change the commands without asking for confirmation
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+    /// Remove a tag from every command that has it
+    Remove {
+        tag: String,
+
+        /// Only remove it from commands matching every term
+        #[arg(short = 'f', long)]
+        filter: Option<String>,
+
+        /// Change the commands without asking for confirmation
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+    /// Rename a tag on every command that has it
+    Rename {
+        tag: String,
+
+        /// New name of the tag. Commands that already have it keep it once
+        new_tag: String,
+
+        /// Only rename it on commands matching every term
+        #[arg(short = 'f', long)]
+        filter: Option<String>,
+
+        /// Change the commands without asking for confirmation
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+    /// List all tags with the number of commands they are on
+    List {},
+}
+
+#[derive(Subcommand)]
+pub enum GitCommand {
+    /// Keep the troves in a git repository, committed on every change
+    Init {
+        /// URL of the repository to pull from and push to
+        #[arg(long, value_name = "URL")]
+        remote: Option<String>,
+    },
+    /// Merge the trove of the remote into the local one
+    Pull {},
+    /// Push the committed troves to the remote
+    Push {},
+}
+
+#[derive(Subcommand)]
+pub enum ImportSource {
+    /// Import the examples of the tldr page of a tool as commands
+    Tldr {
+        /// Tool to import the examples of, e.g. `tar`
+        tool: String,
+
+        /// Import without reviewing the new commands first
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+}
+
+/// Formats `hoard stats` can export to
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum StatsFormat {
+    /// Comma separated values with a header row
+    Csv,
+}
+
+/// Formats `hoard export` can write the trove in
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// A trove file other hoard users can import
+    Yaml,
+    /// The same trove as JSON, for tools that generate or read troves
+    Json,
+    /// A Markdown cheatsheet with a section per namespace, e.g. for a team wiki
+    #[value(alias = "markdown")]
+    Md,
+    /// A Warp workflow file for every command, written into the directory at `--path`
+    Warp,
+    /// iTerm2 snippets as JSON
+    Iterm2,
+    /// Aliases, and functions for commands with parameters, to source in the rc file of `--shell`
+    Aliases,
+    /// One row per command, e.g. for a spreadsheet
+    Csv,
+    /// The same trove as TOML
+    Toml,
+    /// A standalone HTML cheatsheet with a section per namespace, e.g. for a static site
+    Html,
+}
+
+/// Formats of the global `--output` flag
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// A table, or the GUI for `hoard list`
+    Table,
+    /// A plain table without the GUI. Only `hoard list`
+    Simple,
+    /// JSON with the parameters of every command, for other tools. Only `hoard list`, `hoard pick` and `hoard grep`
+    Json,
+    /// Comma separated values with a header row. Only `hoard stats`
+    Csv,
+}
+
 #[derive(Subcommand)]
 pub enum Commands {
     /// Shows setting file paths
     Info {},
 
     /// Save a new command
+    #[command(after_help = "Examples:
+  hoard new
+  hoard new -n deploy -c 'kubectl apply -f #file:file!' -t k8s,work -d 'Deploy a manifest'
+  hoard new --from-history
+  hoard new --from-history 3
+  pbpaste | hoard new --stdin --name deploy -t k8s")]
     New {
         /// [Optional] Name of the new command
         #[arg(short = 'n', long, value_name = "NAME")]
@@ -46,31 +215,163 @@ pub enum Commands {
         #[arg(short = 'c', long, value_name = "COMMAND")]
         command: Option<String>,
 
+        /// [Optional] Start from the Nth most recent command of your shell history, the last one if N is left out
+        #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "1", conflicts_with = "command")]
+        from_history: Option<usize>,
+
+        /// [Optional] Read the command from stdin and save it without asking, in the namespace its program suggests.
+        /// The other fields are taken from the flags
+        #[arg(long, requires = "name", conflicts_with_all = ["command", "from_history"])]
+        stdin: bool,
+
         /// [Optional] Description of what the command does
         #[arg(short = 'd', long, value_name = "DESCRIPTION")]
         description: Option<String>,
+
+        /// [Optional] Shell the command is executed with, e.g. "bash -c" or "python -c". Defaults to your $SHELL
+        #[arg(long, value_name = "SHELL")]
+        shell: Option<String>,
+
+        /// [Optional] Docker/Podman image the command is executed in by `hoard run`
+        #[arg(long, value_name = "IMAGE")]
+        container: Option<String>,
+
+        /// [Optional] Number of seconds after which `hoard run` stops the command
+        #[arg(long, value_name = "SECONDS")]
+        timeout: Option<u64>,
+
+        /// [Optional] Encoding `hoard run` decodes the output of the command in, `raw` shows undecodable bytes
+        #[arg(long, value_enum, value_name = "ENCODING")]
+        encoding: Option<OutputEncoding>,
+
+        /// [Optional] Commands to suggest after `hoard run` finished this one, comma separated names
+        #[arg(long, value_name = "NAMES")]
+        related: Option<String>,
     },
 
     /// List commands saved in trove.
+    #[command(after_help = "Examples:
+  hoard list
+  hoard list -f 'tag:docker desc:restart'
+  hoard --output json list -f deploy")]
     List {
-        /// Apply filter to listed commands
+        /// Only list commands matching every term: `tag:`, `ns:`, `name:`, `desc:`, `cmd:` or any text. Filters match part of their field ignoring the case
         #[arg(short = 'f', long)]
         filter: Option<String>,
 
-        /// Return hoarded commands in structured format
-        #[arg(short = 'j', long)]
+        /// Same as `--output json`
+        #[arg(short = 'j', long, hide = true)]
         json: bool,
 
-        /// Return hoarded commands in a simplified table view
-        #[arg(short = 's', long)]
+        /// Same as `--output simple`
+        #[arg(short = 's', long, hide = true)]
         simple: bool,
     },
 
     /// Pick a command of the trove and print it
+    #[command(after_help = "Examples:
+  hoard pick -n deploy
+  hoard pick -n ssh --param host=db1 --param user=admin
+  hoard pick -n copy --params a.txt b.txt
+  hoard pick -n deploy --params-file values.yml
+  hoard pick --plain
+  hoard pick -n deploy --param env=prod --print-only | ssh ci sh
+  hoard --output json pick -n deploy --param env=prod")]
     Pick {
-        /// Name of the command to print
+        /// Name of the command to print. `--plain` without a name offers all commands to choose from
+        #[arg(short = 'n', long, required_unless_present = "plain")]
+        name: Option<String>,
+
+        /// Value of a named parameter, e.g. `--param host=db1`. Can be repeated
+        #[arg(short = 'p', long = "param", value_name = "NAME=VALUE", value_parser = parse_named_parameter)]
+        params: Vec<(String, String)>,
+
+        /// Values of the remaining parameters in order of appearance
+        #[arg(long = "params", value_name = "VALUES", num_args = 1..)]
+        positional_params: Vec<String>,
+
+        /// YAML or JSON file with parameter values by name or position. `--param` and `--params` take precedence
+        #[arg(long, value_name = "FILE")]
+        params_file: Option<PathBuf>,
+
+        /// Ask with numbered plain text menus on stdout instead of drawn ones, e.g. for screen readers
+        #[arg(long)]
+        plain: bool,
+
+        /// Print nothing but the resolved command, without warnings or recording the use, and fail if it can't be resolved.
+        /// On by default with `pick_print_only` in the config
+        #[arg(long)]
+        print_only: bool,
+    },
+
+    /// Put a command of the trove on the clipboard, with its parameters filled in
+    #[command(after_help = "Examples:
+  hoard copy -n deploy
+  hoard copy -n ssh --param host=db1
+  hoard copy")]
+    Copy {
+        /// Name of the command to copy. Without one, the command is picked in the GUI
         #[arg(short = 'n', long)]
-        name: String,
+        name: Option<String>,
+
+        /// Value of a named parameter, e.g. `--param host=db1`. Can be repeated
+        #[arg(short = 'p', long = "param", value_name = "NAME=VALUE", value_parser = parse_named_parameter)]
+        params: Vec<(String, String)>,
+
+        /// Values of the remaining parameters in order of appearance
+        #[arg(long = "params", value_name = "VALUES", num_args = 1..)]
+        positional_params: Vec<String>,
+
+        /// YAML or JSON file with parameter values by name or position. `--param` and `--params` take precedence
+        #[arg(long, value_name = "FILE")]
+        params_file: Option<PathBuf>,
+    },
+
+    /// Run a command of the trove with its configured shell
+    #[command(after_help = "Examples:
+  hoard run -n backup
+  hoard run
+  hoard run -n deploy --param env=staging
+  hoard run -n build --in-container rust:1.70 --timeout 600
+  hoard run -n ping_hosts --parallel 4
+  hoard run --no-pane")]
+    Run {
+        /// Name of the command to run. Without one, the command is picked in the GUI
+        #[arg(short = 'n', long)]
+        name: Option<String>,
+
+        /// Value of a named parameter, e.g. `--param host=db1`. Can be repeated
+        #[arg(short = 'p', long = "param", value_name = "NAME=VALUE", value_parser = parse_named_parameter)]
+        params: Vec<(String, String)>,
+
+        /// Values of the remaining parameters in order of appearance
+        #[arg(long = "params", value_name = "VALUES", num_args = 1..)]
+        positional_params: Vec<String>,
+
+        /// YAML or JSON file with parameter values by name or position. `--param` and `--params` take precedence
+        #[arg(long, value_name = "FILE")]
+        params_file: Option<PathBuf>,
+
+        /// Run the command inside a container of this image with the current directory mounted
+        #[arg(long, value_name = "IMAGE")]
+        in_container: Option<String>,
+
+        /// Stop the command after this many seconds. Overrides the timeout saved with the command
+        #[arg(long, value_name = "SECONDS")]
+        timeout: Option<u64>,
+
+        /// Run up to N invocations of a command with list parameters at the same time
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+        parallel: Option<u16>,
+
+        /// Decode the output of the command in this encoding. Overrides the encoding saved with the command
+        #[arg(long, value_enum, value_name = "ENCODING")]
+        encoding: Option<OutputEncoding>,
+
+        /// Run a command picked in the GUI in the terminal instead of showing its output in a pane, e.g. for
+        /// programs that read input
+        #[arg(long)]
+        no_pane: bool,
     },
 
     /// Set a custom parameter token
@@ -80,11 +381,28 @@ pub enum Commands {
         name: String,
     },
 
-    /// Removes a command in the trove by name
+    /// Removes a command in the trove by name, `hoard trash restore` brings it back
+    #[command(after_help = "Examples:
+  hoard remove -n deploy
+  hoard remove --filter 'ns:old tag:deprecated'
+  hoard remove --filter 'tag:tmp' --yes")]
     Remove {
         /// command to remove
-        #[arg(short = 'n', long)]
-        name: String,
+        #[arg(
+            short = 'n',
+            long,
+            required_unless_present = "filter",
+            conflicts_with = "filter"
+        )]
+        name: Option<String>,
+
+        /// Remove every command matching every term, e.g. `ns:old tag:deprecated`, after showing them
+        #[arg(short = 'f', long)]
+        filter: Option<String>,
+
+        /// Remove the commands matching the filter without asking for confirmation
+        #[arg(short = 'y', long, requires = "filter")]
+        yes: bool,
     },
 
     /// Remove all commands of a namespace
@@ -95,24 +413,200 @@ pub enum Commands {
     },
 
     /// Import a trove file from a local file or URL
+    #[command(
+        args_conflicts_with_subcommands = true,
+        after_help = "Examples:
+  hoard import https://example.com/team-trove.yml
+  hoard import ./README.md --yes
+  hoard import tldr tar
+
+YAML, JSON, navi .cheat files and markdown files with the trove in ```yaml or ```json code blocks can be imported"
+    )]
     Import {
-        /// URL or path to .trove file to import
+        #[command(subcommand)]
+        source: Option<ImportSource>,
+
+        /// URL or path to the trove file to import
+        #[arg(required_unless_present_any = ["uri_flag"], value_name = "URI")]
+        uri: Option<String>,
+
+        /// Same as the URI argument
+        #[arg(long = "uri", hide = true, conflicts_with = "uri")]
+        uri_flag: Option<String>,
+
+        /// Format of the trove, detected from its content type, extension or content if not given
+        #[arg(long, value_enum)]
+        format: Option<ImportFormat>,
+
+        /// Import without reviewing the new commands first
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+
+    /// Apply a batch of changes from a YAML or JSON file to the trove in one go
+    Apply {
+        /// File listing the commands to add, update and delete
+        changes: PathBuf,
+    },
+
+    /// Search the names, command strings, descriptions and tags of the saved commands with a regex
+    #[command(after_help = "Examples:
+  hoard grep kubectl
+  hoard grep -i 'us-(east|west)-[0-9]'
+  hoard --output json grep kubectl")]
+    Grep {
+        /// Regex to search for
+        pattern: String,
+
+        /// Ignore the case of letters
+        #[arg(short = 'i', long)]
+        ignore_case: bool,
+    },
+
+    /// Add, remove or rename a tag on many commands at once, or list all tags
+    #[command(after_help = "Examples:
+  hoard tag list
+  hoard tag add aws --filter 'ns:work aws'
+  hoard tag remove old --yes
+  hoard tag rename k8s kubernetes --filter ns:work")]
+    Tag {
+        #[command(subcommand)]
+        command: TagCommand,
+    },
+
+    /// Find and replace with a regex in the saved commands, reviewing every change first
+    #[command(after_help = "Examples:
+  hoard replace --query tag:aws 's/us-east-1/eu-west-1/g'
+  hoard replace --query 'ns:work docker' 's|docker.io/(\\w+)|ghcr.io/team/\\1|' --yes")]
+    Replace {
+        /// Substitution like `s/old/new/g`. The flag `g` replaces every match, `i` ignores case
+        expression: String,
+
+        /// Only change commands matching every term, e.g. `tag:aws`, `ns:work`, `name:deploy` or any text
+        #[arg(short = 'q', long)]
+        query: Option<String>,
+
+        /// Apply every replacement without reviewing it
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+
+    /// Show how often each command was used and how its runs went
+    #[command(after_help = "Examples:
+  hoard stats
+  hoard --output csv stats > stats.csv")]
+    Stats {
+        /// Same as `--output csv`
+        #[arg(long, value_name = "FORMAT", hide = true)]
+        export: Option<StatsFormat>,
+    },
+
+    /// Look for ways to improve the hoarded commands, like words that should be parameters
+    #[command(after_help = "Examples:
+  hoard doctor --history ~/.zsh_history
+  hoard doctor --trove-file
+  hoard doctor --trove-file --fix
+  hoard doctor --env")]
+    Doctor {
+        /// Shell history file to learn from, $HISTFILE or the history of your $SHELL if not given
+        #[arg(long, value_name = "FILE", conflicts_with = "trove_file")]
+        history: Option<PathBuf>,
+
+        // Not `--trove`, which picks the trove for every command
+        /// Check the trove file for broken entries, empty names, name collisions and unbalanced parameter tokens
         #[arg(long)]
-        uri: String,
+        trove_file: bool,
+
+        /// Fix the problems `--trove-file` finds that can be fixed without asking
+        #[arg(long, requires = "trove_file")]
+        fix: bool,
+
+        /// Check the config file, the trove, the shell plugin, the colors of the terminal and the sync server
+        #[arg(long, conflicts_with_all = ["history", "trove_file"])]
+        env: bool,
     },
 
     /// Export a trove file
+    #[command(after_help = "Examples:
+  hoard export --path team.yml --scrub
+  hoard export --path team.json --format json
+  hoard export --path COMMANDS.md --format md
+  hoard export --path ~/.warp/workflows --format warp")]
     Export {
-        /// Path where the trove file should be saved to
+        /// Path where the trove file should be saved to, a directory for `--format warp`
         #[arg(long)]
         path: String,
+
+        /// Format of the exported file
+        #[arg(long, value_enum, default_value_t = ExportFormat::Yaml)]
+        format: ExportFormat,
+
+        /// Replace hostnames, IP addresses, usernames and secrets with parameters before exporting
+        #[arg(long)]
+        scrub: bool,
+
+        /// Shell to write `--format aliases` for, the one of `$SHELL` if not given
+        #[arg(long, value_enum)]
+        shell: Option<AliasShell>,
     },
 
     /// Edit a saved command
+    #[command(after_help = "Examples:
+  hoard edit -n deploy
+  hoard edit -n deploy --editor")]
     Edit {
         /// Name of the command to edit
         #[arg(short = 'n', long)]
         name: String,
+
+        /// Edit the command, description and tags in `$EDITOR` instead of answering prompts
+        #[arg(long)]
+        editor: bool,
+    },
+
+    /// Move a command to another namespace or rename it
+    #[command(after_help = "Examples:
+  hoard mv git/st git/status
+  hoard mv work/deploy personal/
+  hoard mv deploy deploy_eu
+
+If the new place is taken you choose whether to replace that command, keep it or find another name")]
+    Mv {
+        /// The command as `namespace/name`, or its name if no other namespace has a command called that
+        from: String,
+
+        /// Where to move it as `namespace/name`, `namespace/` to keep its name or a new name in the same namespace
+        to: String,
+    },
+
+    /// Print everything hoard knows about a command
+    #[command(after_help = "Examples:
+  hoard show k8s/logs
+  hoard show logs
+  hoard --output json show k8s/logs
+
+Prints the command, its description, tags and parameters with their types and last used values,
+when it was created and how often it was used")]
+    Show {
+        /// The command as `namespace/name`, or its name if no other namespace has a command called that
+        command: String,
+    },
+
+    /// Print a random command to rediscover, rarely used commands come up more often
+    #[command(after_help = "Examples:
+  hoard random
+  hoard random --namespace k8s
+  hoard random --daily
+
+The shell plugins print one with --daily at the first prompt of a day if HOARD_DAILY_TIP is set")]
+    Random {
+        /// Only pick commands of this namespace
+        #[arg(short, long)]
+        namespace: Option<String>,
+
+        /// Print nothing if a command was printed with --daily today already
+        #[arg(long)]
+        daily: bool,
     },
 
     /// Print shell config
@@ -122,6 +616,69 @@ pub enum Commands {
         shell: String,
     },
 
+    /// Restore removed commands or empty the trash
+    #[command(after_help = "Examples:
+  hoard trash list
+  hoard trash restore -n deploy
+  hoard trash purge")]
+    Trash {
+        #[command(subcommand)]
+        command: TrashCommand,
+    },
+
+    /// Keep separate collections of commands, e.g. for work and personal use
+    #[command(after_help = "Examples:
+  hoard trove switch work
+  hoard trove list
+  hoard --trove personal list")]
+    Trove {
+        #[command(subcommand)]
+        command: TroveCommand,
+    },
+
+    /// Translate the trove and config of an older hoard release into the current format
+    #[command(after_help = "Examples:
+  hoard migrate --from-legacy ~/.hoard
+  hoard migrate --from-legacy old/trove.yml --output migrated.yml")]
+    Migrate {
+        /// Legacy trove or config file, or the hoard directory holding its config.yml and trove.yml
+        #[arg(long, value_name = "PATH")]
+        from_legacy: PathBuf,
+
+        /// Write the migrated trove to this file instead of replacing the trove in use
+        #[arg(long)]
+        output: Option<PathBuf>,
+
+        /// Migrate without asking for confirmation
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+
+    /// List the backups taken before every save of a trove, or roll the trove back to one of them
+    #[command(after_help = "Examples:
+  hoard restore
+  hoard restore --from default-20240102-030405-678.yml
+  hoard --trove work restore --from work-20240102-030405-678 --yes")]
+    Restore {
+        /// File name or path of the backup to restore, as listed by `hoard restore`
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Restore the backup without asking for confirmation
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+
+    /// Synchronize your troves with a git repository instead of the hoard sync server
+    #[command(after_help = "Examples:
+  hoard git init --remote git@github.com:me/troves.git
+  hoard git pull
+  hoard git push")]
+    Git {
+        #[command(subcommand)]
+        command: GitCommand,
+    },
+
     /// Synchronize your trove file on multiple clients. If no mode is selected, it will fetch your online trove file and synchronize it with your local trove file
     Sync {
         ///
@@ -129,3 +686,45 @@ pub enum Commands {
         command: Mode,
     },
 }
+
+/// Parse a `NAME=VALUE` pair given to `--param`
+fn parse_named_parameter(s: &str) -> Result<(String, String), String> {
+    s.split_once('=')
+        .filter(|(name, _)| !name.is_empty())
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .ok_or_else(|| format!("expected NAME=VALUE, got '{s}'"))
+}
+
+#[cfg(test)]
+mod test_cli {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn valid_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn global_flags_after_subcommand() {
+        let cli = Cli::try_parse_from(["hoard", "list", "--output", "json", "--profile", "work"])
+            .unwrap();
+        assert_eq!(Some(OutputFormat::Json), cli.output);
+        assert_eq!(Some("work".to_string()), cli.profile);
+    }
+
+    #[test]
+    fn name_commands_with_the_same_flag() {
+        for subcommand in ["pick", "copy", "run", "remove"] {
+            let cli = Cli::try_parse_from(["hoard", subcommand, "-n", "deploy"]).unwrap();
+            let name = match cli.command {
+                Commands::Pick { name, .. }
+                | Commands::Copy { name, .. }
+                | Commands::Run { name, .. }
+                | Commands::Remove { name, .. } => name,
+                _ => None,
+            };
+            assert_eq!(Some("deploy".to_string()), name);
+        }
+    }
+}
@@ -1,16 +1,35 @@
-use crate::gui::prompts::prompt_input;
+use crate::core::shell::{command_binaries, complexity_warnings};
+use crate::gui::prompts::{prompt_input, prompts_answerable};
+use crate::gui::theme::{theme_colors, ThemeColors, CUSTOM_THEME, THEMES};
 use anyhow::{anyhow, Error, Result};
 use log::info;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     fs,
     path::{Path, PathBuf},
 };
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 const HOARD_HOMEDIR: &str = ".config/hoard";
-const HOARD_FILE: &str = "trove.yml";
+pub const HOARD_FILE: &str = "trove.yml";
+const MANAGED_FILE: &str = "managed.yml";
+const TROVES_DIR: &str = "troves";
+/// Name of the trove at `trove_path`
+pub const DEFAULT_TROVE: &str = "default";
 pub const HOARD_CONFIG: &str = "config.yml";
+const PROFILES_DIR: &str = "profiles";
+const DEFAULT_ESCAPE_CHAR: char = '\\';
+const DEFAULT_MAX_COMMAND_LENGTH: usize = 200;
+const DEFAULT_MAX_PIPE_DEPTH: usize = 4;
+/// Namespaces suggested for new commands by the program they run, unless configured otherwise
+const DEFAULT_NAMESPACE_MAPPING: &[(&str, &str)] = &[
+    ("docker", "containers"),
+    ("podman", "containers"),
+    ("kubectl", "k8s"),
+    ("helm", "k8s"),
+    ("git", "git"),
+];
 
 #[allow(clippy::module_name_repetitions)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,21 +38,98 @@ pub struct HoardConfig {
     pub default_namespace: String,
     pub config_home_path: Option<PathBuf>,
     pub trove_path: Option<PathBuf>,
+    // Named trove that is used unless `--trove` picks another one. The trove at trove_path if not set
+    pub active_trove: Option<String>,
     pub query_prefix: String,
     // Color settings
     pub primary_color: Option<(u8, u8, u8)>,
     pub secondary_color: Option<(u8, u8, u8)>,
     pub tertiary_color: Option<(u8, u8, u8)>,
     pub command_color: Option<(u8, u8, u8)>,
+    // Colors of tags in the list view, e.g. `prod: [204, 36, 29]`
+    pub tag_colors: Option<HashMap<String, (u8, u8, u8)>>,
+    // Colors of the GUI and prompts: gruvbox, dracula, solarized, nord or custom. Replaces the colors above if set
+    pub theme: Option<String>,
+    // Colors of the custom theme, e.g. `primary: [242, 229, 188]`, and `secondary`, `tertiary` and `command`
+    pub custom_theme: Option<ThemeColors>,
     // Parameter settings
     pub parameter_token: Option<String>,
     // Token to indicate the end of a named parameter
     pub parameter_ending_token: Option<String>,
+    // Character that keeps the parameter tokens from being read as a parameter, e.g. `\#`. Defaults to a backslash
+    pub parameter_escape_char: Option<char>,
     pub read_from_current_directory: Option<bool>,
     // URL to trove sync server
     pub sync_server_url: Option<String>,
     pub api_token: Option<String>,
     pub gpt_api_key: Option<String>,
+    // Container runtime used by `hoard run` for containerized commands. Looked up in $PATH if not set
+    pub container_runtime: Option<String>,
+    // How to handle commands that use sudo or touch system paths: confirm, warn or allow
+    pub danger_policy: Option<DangerPolicy>,
+    // Resolve environment variables like $HOME in a command when it is picked
+    pub expand_env_vars: Option<bool>,
+    // Read-only trove of commands declared by a dotfile manager. managed.yml in the hoard home directory if not set
+    pub managed_trove_path: Option<PathBuf>,
+    // Read-only troves merged into the list, e.g. a file shared by your team. Their commands are never written to
+    pub overlay_trove_paths: Option<Vec<PathBuf>>,
+    // Troves, paths or URLs, whose commands are merged into every trove read-only. Relative paths start at the hoard home
+    pub include: Option<Vec<String>>,
+    // Namespace suggested for a new command by the program it runs, e.g. `terraform: infra`. Adds to the built-in ones
+    pub namespace_mapping: Option<HashMap<String, String>>,
+    // Longer commands are pointed out by `hoard doctor` and when they are saved. Defaults to 200 characters
+    pub max_command_length: Option<usize>,
+    // Commands piping more programs into each other are pointed out like long ones. Defaults to 4
+    pub max_pipe_depth: Option<usize>,
+    // Shell commands offering values for parameters like `#host@consul!`, e.g. `consul: consul catalog nodes`
+    pub parameter_providers: Option<HashMap<String, String>>,
+    // Save the directory, git remote and kube context with a new command, so the search ranks it higher there
+    pub capture_context: Option<bool>,
+    // Colors the terminal can show: truecolor, 256, 16 or none. Detected from $COLORTERM and terminfo if not set
+    pub color_support: Option<ColorSupport>,
+    // Backups kept of each trove, taken before it is saved. Defaults to 10, 0 turns them off
+    pub backup_count: Option<usize>,
+    // Store every namespace of a trove in a file of its own, e.g. trove.d/git.yml instead of trove.yml
+    pub split_namespaces: Option<bool>,
+    // `hoard pick` prints nothing but the resolved command, as with `--print-only`, e.g. for scripts
+    pub pick_print_only: Option<bool>,
+    // Color the programs, flags, strings and pipes of the command in the preview of the GUI. On if not set
+    pub syntax_highlighting: Option<bool>,
+    // Keys of the actions in the GUI, e.g. `quit: ctrl-q` or `next: "ctrl-j, down"`. Unbound actions keep their keys
+    pub keybindings: Option<HashMap<String, String>>,
+    // Click and scroll through the commands in the GUI. On if not set, turn it off to select text in the terminal
+    pub mouse: Option<bool>,
+    // Show the namespaces in a sidebar instead of tabs when the GUI opens. Off if not set
+    pub namespace_sidebar: Option<bool>,
+}
+
+/// What `hoard run` does before running a command with elevated privileges
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DangerPolicy {
+    /// Ask for confirmation before running the command
+    #[default]
+    Confirm,
+    /// Print a warning and run the command
+    Warn,
+    /// Run the command without a warning
+    Allow,
+}
+
+/// Colors of the terminal the GUI draws in, theme colors are mapped onto the closest ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorSupport {
+    /// Any RGB color
+    Truecolor,
+    /// The 256 color palette of xterm
+    #[serde(rename = "256")]
+    Ansi256,
+    /// The 16 ANSI colors
+    #[serde(rename = "16")]
+    Ansi16,
+    /// No colors, backgrounds are shown reversed
+    None,
 }
 
 impl Default for HoardConfig {
@@ -43,17 +139,41 @@ impl Default for HoardConfig {
             default_namespace: "default".to_string(),
             config_home_path: None,
             trove_path: None,
+            active_trove: None,
             query_prefix: "  >".to_string(),
             primary_color: Some(Self::default_colors(0)),
             secondary_color: Some(Self::default_colors(1)),
             tertiary_color: Some(Self::default_colors(2)),
             command_color: Some(Self::default_colors(3)),
+            tag_colors: None,
+            theme: None,
+            custom_theme: None,
             parameter_token: Some(Self::default_parameter_token()),
             parameter_ending_token: Some(Self::default_ending_parameter_token()),
+            parameter_escape_char: None,
             read_from_current_directory: Some(Self::default_read_from_current_directory()),
             sync_server_url: Some(Self::default_sync_server_url()),
             api_token: None,
             gpt_api_key: None,
+            container_runtime: None,
+            danger_policy: None,
+            expand_env_vars: None,
+            managed_trove_path: None,
+            overlay_trove_paths: None,
+            include: None,
+            namespace_mapping: None,
+            max_command_length: None,
+            max_pipe_depth: None,
+            parameter_providers: None,
+            capture_context: None,
+            color_support: None,
+            backup_count: None,
+            split_namespaces: None,
+            pick_print_only: None,
+            syntax_highlighting: None,
+            keybindings: None,
+            mouse: None,
+            namespace_sidebar: None,
         }
     }
 }
@@ -65,43 +185,171 @@ impl HoardConfig {
             default_namespace: "default".to_string(),
             config_home_path: Some(hoard_home_path.to_path_buf()),
             trove_path: Some(hoard_home_path.join(HOARD_FILE)),
+            active_trove: None,
             query_prefix: "  >".to_string(),
             primary_color: Some(Self::default_colors(0)),
             secondary_color: Some(Self::default_colors(1)),
             tertiary_color: Some(Self::default_colors(2)),
             command_color: Some(Self::default_colors(3)),
+            tag_colors: None,
+            theme: Some(String::from("gruvbox")),
+            custom_theme: None,
             parameter_token: Some(Self::default_parameter_token()),
             parameter_ending_token: Some(Self::default_ending_parameter_token()),
+            parameter_escape_char: None,
             read_from_current_directory: Some(Self::default_read_from_current_directory()),
             sync_server_url: Some(Self::default_sync_server_url()),
             api_token: None,
             gpt_api_key: None,
+            container_runtime: None,
+            danger_policy: None,
+            expand_env_vars: None,
+            managed_trove_path: None,
+            overlay_trove_paths: None,
+            include: None,
+            namespace_mapping: None,
+            max_command_length: None,
+            max_pipe_depth: None,
+            parameter_providers: None,
+            capture_context: None,
+            color_support: None,
+            backup_count: None,
+            split_namespaces: None,
+            pick_print_only: None,
+            syntax_highlighting: None,
+            keybindings: None,
+            mouse: None,
+            namespace_sidebar: None,
         }
     }
 
     pub fn with_default_namespace(self) -> Self {
+        // Nobody to ask when hoard is first run from a script
+        if !prompts_answerable() {
+            return self;
+        }
         let default_namespace = prompt_input(
             "This is the first time running hoard.\nChoose a default namespace where you want to hoard your commands.",
             false,
             Some("default".to_string())
         );
         Self {
-            version: self.version,
             default_namespace,
-            config_home_path: self.config_home_path,
-            trove_path: self.trove_path,
-            query_prefix: self.query_prefix,
-            primary_color: self.primary_color,
-            secondary_color: self.secondary_color,
-            tertiary_color: self.tertiary_color,
-            command_color: self.command_color,
-            parameter_token: self.parameter_token,
-            parameter_ending_token: self.parameter_ending_token,
-            read_from_current_directory: self.read_from_current_directory,
-            sync_server_url: self.sync_server_url,
-            api_token: self.api_token,
-            gpt_api_key: self.gpt_api_key,
+            ..self
+        }
+    }
+
+    /// Directory holding the config file and the run log, `~/.config/hoard` unless configured otherwise
+    pub fn hoard_home_path(&self) -> Option<PathBuf> {
+        self.config_home_path
+            .clone()
+            .or_else(|| dirs::home_dir().map(|home| home.join(HOARD_HOMEDIR)))
+    }
+
+    /// Name of the trove in use, `name` if one is given and the active trove otherwise
+    pub fn trove_name<'a>(&'a self, name: Option<&'a str>) -> &'a str {
+        name.or(self.active_trove.as_deref())
+            .unwrap_or(DEFAULT_TROVE)
+    }
+
+    /// File of the trove called `name`, named troves are kept in the `troves` directory of the hoard home
+    pub fn trove_file(&self, name: &str) -> Option<PathBuf> {
+        if name == DEFAULT_TROVE {
+            return self.trove_path.clone();
+        }
+        self.hoard_home_path()
+            .map(|home| home.join(TROVES_DIR).join(format!("{name}.yml")))
+    }
+
+    /// Names of all troves, the default trove first
+    pub fn trove_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .hoard_home_path()
+            .and_then(|home| fs::read_dir(home.join(TROVES_DIR)).ok())
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let file_name = entry.file_name().to_str()?.to_string();
+                // Troves stored per namespace are directories like `work.d`
+                file_name
+                    .strip_suffix(".yml")
+                    .or_else(|| file_name.strip_suffix(".d"))
+                    .map(ToString::to_string)
+            })
+            .filter(|name| name != DEFAULT_TROVE)
+            .collect();
+        names.sort();
+        names.dedup();
+        names.insert(0, DEFAULT_TROVE.to_string());
+        names
+    }
+
+    /// Path of the read-only trove that is merged into the trove when it is loaded
+    pub fn managed_trove_file(&self) -> Option<PathBuf> {
+        self.managed_trove_path
+            .clone()
+            .or_else(|| self.hoard_home_path().map(|home| home.join(MANAGED_FILE)))
+    }
+
+    /// Colors of the theme set in the config, none if the individual colors are used
+    pub fn theme_colors(&self) -> Result<Option<ThemeColors>> {
+        match self.theme.as_deref() {
+            None => Ok(None),
+            Some(CUSTOM_THEME) => Ok(Some(self.custom_theme.unwrap_or_default())),
+            Some(name) => theme_colors(name).map(Some).ok_or_else(|| {
+                let names: Vec<&str> = THEMES.iter().map(|(name, _)| *name).collect();
+                anyhow!(
+                    "Unknown theme {name}, pick one of {} or {CUSTOM_THEME}",
+                    names.join(", ")
+                )
+            }),
+        }
+    }
+
+    /// Draw the GUI in the colors of the theme
+    fn apply_theme(&mut self) -> Result<()> {
+        if let Some(colors) = self.theme_colors()? {
+            self.primary_color = Some(colors.primary);
+            self.secondary_color = Some(colors.secondary);
+            self.tertiary_color = Some(colors.tertiary);
+            self.command_color = Some(colors.command);
         }
+        Ok(())
+    }
+
+    /// Color configured for `tag`, if any
+    pub fn tag_color(&self, tag: &str) -> Option<(u8, u8, u8)> {
+        self.tag_colors.as_ref()?.get(tag).copied()
+    }
+
+    /// Namespace to suggest for a new `command`, looked up by the program it runs
+    pub fn suggested_namespace(&self, command: &str) -> Option<String> {
+        let binary = command_binaries(command).into_iter().next()?;
+        self.namespace_mapping
+            .as_ref()
+            .and_then(|mapping| mapping.get(binary).cloned())
+            .or_else(|| {
+                DEFAULT_NAMESPACE_MAPPING
+                    .iter()
+                    .find(|(program, _)| *program == binary)
+                    .map(|(_, namespace)| (*namespace).to_string())
+            })
+    }
+
+    /// Reasons why `command` should rather be a script than a hoarded one-liner
+    pub fn complexity_warnings(&self, command: &str) -> Vec<String> {
+        complexity_warnings(
+            command,
+            self.max_command_length
+                .unwrap_or(DEFAULT_MAX_COMMAND_LENGTH),
+            self.max_pipe_depth.unwrap_or(DEFAULT_MAX_PIPE_DEPTH),
+        )
+    }
+
+    /// Character that escapes the parameter tokens in commands
+    pub fn escape_char(&self) -> char {
+        self.parameter_escape_char.unwrap_or(DEFAULT_ESCAPE_CHAR)
     }
 
     fn default_parameter_token() -> String {
@@ -144,6 +392,19 @@ pub fn load_or_build_config(hoard_home_path: Option<String>) -> Result<HoardConf
     })
 }
 
+/// Loads the config of `profile`, which keeps its config, troves and statistics in
+/// `$HOME/.config/hoard/profiles/<profile>`. The profile is created on first use
+pub fn load_or_build_profile_config(profile: &str) -> Result<HoardConfig> {
+    if !is_valid_trove_name(profile) {
+        return Err(anyhow!(
+            "Invalid profile name {profile}, use letters, digits, '-' and '_'"
+        ));
+    }
+    let home =
+        dirs::home_dir().ok_or_else(|| anyhow!("No $HOME directory found for hoard config"))?;
+    load_or_build_in(&home.join(HOARD_HOMEDIR).join(PROFILES_DIR).join(profile))
+}
+
 fn load_or_build_default_path() -> Result<HoardConfig, Error> {
     dirs::home_dir().map_or_else(
         || Err(anyhow!("No $HOME directory found for hoard config")),
@@ -151,16 +412,18 @@ fn load_or_build_default_path() -> Result<HoardConfig, Error> {
     )
 }
 
-#[allow(clippy::useless_let_if_seq)]
 fn load_or_build(path: &Path) -> Result<HoardConfig, Error> {
     info!("Loading or building in {:?}", path);
     let home_path = Path::new(&path);
+    load_or_build_in(&home_path.join(HOARD_HOMEDIR))
+}
 
+#[allow(clippy::useless_let_if_seq)]
+fn load_or_build_in(hoard_dir: &Path) -> Result<HoardConfig, Error> {
     // Check if $HOME/.hoard directory exists. Create it if it does not exist
-    let hoard_dir = home_path.join(HOARD_HOMEDIR);
     if !hoard_dir.exists() {
         info!("Creating {:?}", hoard_dir);
-        fs::create_dir_all(&hoard_dir)?;
+        fs::create_dir_all(hoard_dir)?;
     }
 
     let hoard_config_path = hoard_dir.join(HOARD_CONFIG);
@@ -171,11 +434,8 @@ fn load_or_build(path: &Path) -> Result<HoardConfig, Error> {
         let f = std::fs::File::open(&hoard_config_path)?;
         let mut loaded_config: HoardConfig = serde_yaml::from_reader::<_, HoardConfig>(f)?;
 
-        append_missing_default_values_to_config(
-            &mut loaded_config,
-            &hoard_dir,
-            &hoard_config_path,
-        )?;
+        append_missing_default_values_to_config(&mut loaded_config, hoard_dir, &hoard_config_path)?;
+        loaded_config.apply_theme()?;
 
         let path_buf = Path::new(HOARD_FILE).to_path_buf();
         if loaded_config.read_from_current_directory.unwrap() && path_buf.exists() {
@@ -187,7 +447,7 @@ fn load_or_build(path: &Path) -> Result<HoardConfig, Error> {
         Ok(loaded_config)
     } else {
         info!("Config file does not exist. Creating new one");
-        let new_config = HoardConfig::new(&hoard_dir).with_default_namespace();
+        let new_config = HoardConfig::new(hoard_dir).with_default_namespace();
         save_config(&new_config, &hoard_config_path)?;
         Ok(new_config)
     };
@@ -240,6 +500,27 @@ fn append_missing_default_values_to_config(
     Ok(())
 }
 
+pub fn save_active_trove(config: &HoardConfig, config_path: &Path, name: &str) -> bool {
+    let mut new_config = config.clone();
+    let path_buf = config_path.join(HOARD_CONFIG);
+    new_config.active_trove = (name != DEFAULT_TROVE).then(|| name.to_string());
+    match save_config(&new_config, path_buf.as_path()) {
+        Ok(()) => true,
+        Err(err) => {
+            eprintln!("ERROR: {err}");
+            false
+        }
+    }
+}
+
+/// Trove names become file names, so they are kept to letters, digits, `-` and `_`
+pub fn is_valid_trove_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
 pub fn save_parameter_token(
     config: &HoardConfig,
     config_path: &Path,
@@ -298,7 +579,9 @@ pub fn save_hoard_config_file(config_to_save: &HoardConfig, base_path: &Path) ->
 
 #[cfg(test)]
 mod test_config {
-    use super::{save_parameter_token, HoardConfig, HOARD_CONFIG};
+    use super::{
+        is_valid_trove_name, save_parameter_token, HoardConfig, DEFAULT_TROVE, HOARD_CONFIG,
+    };
     use std::fs::File;
     use tempfile::tempdir;
 
@@ -317,4 +600,67 @@ mod test_config {
         let parsed_config = serde_yaml::from_reader::<_, HoardConfig>(f).ok().unwrap();
         assert_eq!(parsed_config.parameter_token, Some(String::from("@")));
     }
+
+    #[test]
+    fn test_named_troves() {
+        let tmp_dir = tempdir().ok().unwrap();
+        let config = HoardConfig::new(tmp_dir.path());
+        assert_eq!(config.trove_path, config.trove_file(DEFAULT_TROVE));
+        let work = config.trove_file("work").unwrap();
+        std::fs::create_dir_all(work.parent().unwrap()).unwrap();
+        std::fs::write(&work, "").unwrap();
+        assert_eq!(vec!["default", "work"], config.trove_names());
+        assert!(!is_valid_trove_name("../work"));
+    }
+
+    #[test]
+    fn test_suggested_namespace() {
+        let config = HoardConfig {
+            namespace_mapping: serde_yaml::from_str("terraform: infra\ngit: vcs").ok(),
+            ..HoardConfig::default()
+        };
+        assert_eq!(
+            Some(String::from("k8s")),
+            config.suggested_namespace("kubectl get pods")
+        );
+        assert_eq!(
+            Some(String::from("vcs")),
+            config.suggested_namespace("git status")
+        );
+        assert_eq!(
+            Some(String::from("infra")),
+            config.suggested_namespace("TF_LOG=debug terraform plan")
+        );
+        assert_eq!(None, config.suggested_namespace("ls -l"));
+    }
+
+    #[test]
+    fn test_tag_colors() {
+        let config = HoardConfig {
+            tag_colors: serde_yaml::from_str("prod: [204, 36, 29]\ndev: [152, 151, 26]").ok(),
+            ..HoardConfig::default()
+        };
+        assert_eq!(Some((204, 36, 29)), config.tag_color("prod"));
+        assert_eq!(None, config.tag_color("staging"));
+    }
+
+    #[test]
+    fn test_themes() {
+        let mut config = HoardConfig {
+            theme: Some(String::from("Dracula")),
+            ..HoardConfig::default()
+        };
+        config.apply_theme().unwrap();
+        assert_eq!(Some((189, 147, 249)), config.secondary_color);
+
+        config.theme = Some(String::from("custom"));
+        config.custom_theme = serde_yaml::from_str("primary: [1, 2, 3]").ok();
+        config.apply_theme().unwrap();
+        assert_eq!(Some((1, 2, 3)), config.primary_color);
+        // Colors left out of a custom theme are the ones of gruvbox
+        assert_eq!(Some(HoardConfig::default_colors(2)), config.tertiary_color);
+
+        config.theme = Some(String::from("neon"));
+        assert!(config.apply_theme().is_err());
+    }
 }
@@ -0,0 +1,229 @@
+use crate::core::parameters::{map_parameters, Parameter};
+use crate::core::HoardCmd;
+use clap::ValueEnum;
+use std::fmt::Write as _;
+
+/// Shells `hoard export --format aliases` writes definitions for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AliasShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl AliasShell {
+    /// The shell of `$SHELL`, if it is one aliases can be written for
+    pub fn from_env() -> Option<Self> {
+        let shell = std::env::var("SHELL").ok()?;
+        let name = shell.rsplit('/').next()?;
+        Self::from_str(name, true).ok()
+    }
+}
+
+/// Definitions of `commands` to source in an rc file of `shell`
+///
+/// Commands without parameters become aliases. The others become functions that take their parameters as arguments
+/// in order of appearance, a parameter named more than once is one argument. Names used in more than one namespace
+/// get the namespace in front
+pub fn shell_aliases(
+    commands: &[HoardCmd],
+    shell: AliasShell,
+    start_token: &str,
+    end_token: &str,
+    escape: char,
+) -> String {
+    let mut out = String::from("# Commands of hoard, written by `hoard export --format aliases`\n");
+    for command in commands {
+        let is_shared = commands
+            .iter()
+            .filter(|other| other.name == command.name)
+            .count()
+            > 1;
+        let name = if is_shared {
+            alias_name(&format!("{}_{}", command.namespace, command.name))
+        } else {
+            alias_name(&command.name)
+        };
+
+        let quotes = parameter_quotes(&command.command, shell, start_token, end_token, escape);
+        let mut arguments: Vec<String> = Vec::new();
+        let body = map_parameters(
+            &command.command,
+            start_token,
+            end_token,
+            escape,
+            |parameter: &Parameter, index| {
+                // Unnamed parameters are one argument each
+                let position = arguments
+                    .iter()
+                    .position(|argument| !parameter.name.is_empty() && *argument == parameter.name)
+                    .unwrap_or_else(|| {
+                        arguments.push(parameter.name.clone());
+                        arguments.len() - 1
+                    });
+                let quote = quotes.get(index).copied().unwrap_or(Quote::None);
+                // Single quotes are closed around the argument, nothing is expanded inside of them
+                match (shell, quote) {
+                    (AliasShell::Bash | AliasShell::Zsh, Quote::None) => {
+                        format!("\"${}\"", position + 1)
+                    }
+                    (AliasShell::Bash | AliasShell::Zsh, Quote::Single) => {
+                        format!("'\"${}\"'", position + 1)
+                    }
+                    (AliasShell::Bash | AliasShell::Zsh, Quote::Double) => {
+                        format!("${}", position + 1)
+                    }
+                    (AliasShell::Fish, Quote::Single) => format!("'$argv[{}]'", position + 1),
+                    (AliasShell::Fish, Quote::None | Quote::Double) => {
+                        format!("$argv[{}]", position + 1)
+                    }
+                }
+            },
+        );
+
+        out.push('\n');
+        if !command.description.is_empty() {
+            let _ = writeln!(out, "# {}", command.description.replace('\n', " "));
+        }
+        if arguments.is_empty() {
+            let _ = match shell {
+                AliasShell::Bash | AliasShell::Zsh => {
+                    writeln!(out, "alias {name}={}", posix_quote(&command.command))
+                }
+                AliasShell::Fish => writeln!(out, "alias {name} {}", fish_quote(&command.command)),
+            };
+            continue;
+        }
+        let usage: Vec<String> = arguments
+            .iter()
+            .enumerate()
+            .map(|(i, argument)| {
+                let argument = if argument.is_empty() {
+                    format!("arg{}", i + 1)
+                } else {
+                    argument.clone()
+                };
+                format!("<{argument}>")
+            })
+            .collect();
+        let usage = format!("Usage: {name} {}", usage.join(" "));
+        let count = arguments.len();
+        let _ = match shell {
+            AliasShell::Bash | AliasShell::Zsh => writeln!(
+                out,
+                "{name}() {{\n    if [ $# -lt {count} ]; then\n        echo {} >&2\n        return 1\n    fi\n    {body}\n}}",
+                posix_quote(&usage)
+            ),
+            AliasShell::Fish => writeln!(
+                out,
+                "function {name}\n    if test (count $argv) -lt {count}\n        echo {} >&2\n        return 1\n    end\n    {body}\nend",
+                fish_quote(&usage)
+            ),
+        };
+    }
+    out
+}
+
+/// Quotes of the shell a parameter of a command is written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Quote {
+    None,
+    Single,
+    Double,
+}
+
+/// The quotes every parameter of `command` is in, in order of appearance
+fn parameter_quotes(
+    command: &str,
+    shell: AliasShell,
+    start_token: &str,
+    end_token: &str,
+    escape: char,
+) -> Vec<Quote> {
+    // Stands in for the parameters, so the quotes are read from the command as the shell gets it
+    const MARKER: char = '\0';
+    let marked = map_parameters(command, start_token, end_token, escape, |_, _| {
+        MARKER.to_string()
+    });
+    let mut quotes = Vec::new();
+    let mut quote = Quote::None;
+    let mut chars = marked.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (_, MARKER) => quotes.push(quote),
+            // Fish also escapes quotes inside of single quotes
+            (_, '\\') if quote != Quote::Single || shell == AliasShell::Fish => {
+                // The escaped character may be a parameter
+                quotes.extend(chars.next().filter(|&next| next == MARKER).map(|_| quote));
+            }
+            (Quote::None, '\'') => quote = Quote::Single,
+            (Quote::None, '"') => quote = Quote::Double,
+            (Quote::Single, '\'') | (Quote::Double, '"') => quote = Quote::None,
+            _ => {}
+        }
+    }
+    quotes
+}
+
+/// `name` with everything that can't be in the name of an alias or function replaced by `_`
+fn alias_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// `text` in single quotes for bash and zsh
+fn posix_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+/// `text` in single quotes for fish, which escapes quotes and backslashes inside of them
+fn fish_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\\', r"\\").replace('\'', r"\'"))
+}
+
+#[cfg(test)]
+mod test_aliases {
+    use super::*;
+
+    fn command(name: &str, namespace: &str, command: &str) -> HoardCmd {
+        HoardCmd::default()
+            .with_name(name)
+            .with_namespace(namespace)
+            .with_command(command)
+    }
+
+    #[test]
+    fn aliases_and_functions() {
+        let commands = [
+            command("gs", "git", "git status -sb").with_description("Short status"),
+            command("logs", "k8s", "kubectl logs #pod! -n #ns! | grep '#ns!' #!"),
+            command("up", "git", "echo 'it''s up'"),
+            command("up", "ops", "docker compose up -d"),
+        ];
+        let bash = shell_aliases(&commands, AliasShell::Bash, "#", "!", '\\');
+        assert!(bash.contains("# Short status\nalias gs='git status -sb'\n"));
+        assert!(bash.contains(
+            "logs() {\n    if [ $# -lt 3 ]; then\n        echo 'Usage: logs <pod> <ns> <arg3>' >&2\n        return 1\n    fi\n    kubectl logs \"$1\" -n \"$2\" | grep ''\"$2\"'' \"$3\"\n}"
+        ));
+        assert!(bash.contains(r"alias git_up='echo '\''it'\'''\''s up'\'''"));
+        assert!(bash.contains("alias ops_up='docker compose up -d'"));
+
+        // Already quoted by double quotes
+        let quoted = [command("greet", "", "echo \"hi #name!\"")];
+        let bash = shell_aliases(&quoted, AliasShell::Bash, "#", "!", '\\');
+        assert!(bash.contains("    echo \"hi $1\"\n"));
+
+        let fish = shell_aliases(&commands, AliasShell::Fish, "#", "!", '\\');
+        assert!(fish.contains("alias gs 'git status -sb'\n"));
+        assert!(fish.contains("function logs\n    if test (count $argv) -lt 3\n"));
+        assert!(fish.contains("kubectl logs $argv[1] -n $argv[2] | grep ''$argv[2]''"));
+        assert!(fish.contains(r"alias git_up 'echo \'it\'\'s up\''"));
+    }
+}
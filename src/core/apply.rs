@@ -0,0 +1,338 @@
+use crate::core::error::HoardErr;
+use crate::core::trove::Trove;
+use crate::core::HoardCmd;
+use serde::Deserialize;
+use std::time::SystemTime;
+
+/// A batch of changes to a trove, as read by `hoard apply`
+///
+/// ```yaml
+/// operations:
+///   - add:
+///       name: deploy
+///       command: 'kubectl apply -f #file:file!'
+///       namespace: work
+///   - update:
+///       name: logs
+///       description: Follow the logs of the app
+///   - delete:
+///       name: old_deploy
+///   - delete:
+///       id: work/old_logs
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Changes {
+    pub operations: Vec<Operation>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Operation {
+    /// Add a new command, nothing happens if the same command is already in the trove
+    Add(CommandChange),
+    /// Change the given fields of an existing command
+    Update(CommandChange),
+    /// Move a command to the trash, nothing happens if it is not in the trove
+    Delete(CommandRef),
+}
+
+/// Fields of a command to add or update. Fields that are not given are left as they are
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommandChange {
+    #[serde(default)]
+    pub name: String,
+    /// `<namespace>/<name>` of the command, instead of its name and namespace
+    pub id: Option<String>,
+    /// Namespace of the command, the default namespace if not given
+    pub namespace: Option<String>,
+    pub command: Option<String>,
+    pub description: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub shell: Option<String>,
+    pub container: Option<String>,
+}
+
+/// Identifies a command by its name and optionally its namespace, or by its id `<namespace>/<name>`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommandRef {
+    #[serde(default)]
+    pub name: String,
+    pub id: Option<String>,
+    pub namespace: Option<String>,
+}
+
+/// How many commands `apply_changes` added, updated and deleted
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ApplySummary {
+    pub added: usize,
+    pub updated: usize,
+    pub deleted: usize,
+}
+
+/// Parse a batch of changes from YAML or JSON
+pub fn parse_changes(content: &str) -> Result<Changes, HoardErr> {
+    serde_yaml::from_str(content).map_err(|e| HoardErr::new(&format!("Invalid changes file: {e}")))
+}
+
+/// Apply all operations of `changes` to a copy of `trove`
+///
+/// Either every operation succeeds and the changed trove is returned, or the first failing operation is reported
+/// and `trove` stays untouched
+pub fn apply_changes(
+    trove: &Trove,
+    changes: &Changes,
+    default_namespace: &str,
+) -> Result<(Trove, ApplySummary), HoardErr> {
+    let mut new_trove = trove.clone();
+    let mut summary = ApplySummary::default();
+    for (nr, operation) in changes.operations.iter().enumerate() {
+        let fail = |msg: String| HoardErr::new(&format!("Operation {}: {msg}", nr + 1));
+        match operation {
+            Operation::Add(change) => {
+                let (name, namespace) = resolve_ref(
+                    &change.name,
+                    change.namespace.as_deref(),
+                    change.id.as_deref(),
+                )
+                .map_err(fail)?;
+                let namespace = namespace.unwrap_or(default_namespace);
+                let command = change
+                    .apply_to(HoardCmd::default().with_name(name))
+                    .with_namespace(namespace);
+                if !command.is_valid() {
+                    return Err(fail(format!(
+                        "[{name}] needs a name, a command and a namespace"
+                    )));
+                }
+                match find_command(&new_trove, name, Some(namespace)) {
+                    Some(index) if new_trove.commands[index] == command => {}
+                    Some(_) => {
+                        return Err(fail(format!(
+                        "[{name}] already exists in namespace {namespace}, use update to change it"
+                    )))
+                    }
+                    None => {
+                        new_trove.add_namespace(namespace);
+                        new_trove.commands.push(command);
+                        summary.added += 1;
+                    }
+                }
+            }
+            Operation::Update(change) => {
+                let (name, namespace) = resolve_ref(
+                    &change.name,
+                    change.namespace.as_deref(),
+                    change.id.as_deref(),
+                )
+                .map_err(fail)?;
+                let index = find_command(&new_trove, name, namespace)
+                    .ok_or_else(|| fail(format!("No command [{name}] to update")))?;
+                if new_trove.commands[index].is_managed {
+                    return Err(fail(format!("[{name}] is a managed command")));
+                }
+                let mut command = change.apply_to(new_trove.commands[index].clone());
+                command.modified = SystemTime::now();
+                new_trove.commands[index] = command;
+                summary.updated += 1;
+            }
+            Operation::Delete(command) => {
+                let (name, namespace) = resolve_ref(
+                    &command.name,
+                    command.namespace.as_deref(),
+                    command.id.as_deref(),
+                )
+                .map_err(fail)?;
+                if let Some(index) = find_command(&new_trove, name, namespace) {
+                    if new_trove.commands[index].is_managed {
+                        return Err(fail(format!("[{name}] is a managed command")));
+                    }
+                    let removed = new_trove.commands.remove(index);
+                    new_trove.move_to_trash(vec![removed]);
+                    summary.deleted += 1;
+                }
+            }
+        }
+    }
+    Ok((new_trove, summary))
+}
+
+/// Name and namespace of the command an operation is about, from its `id` if it has one
+///
+/// An id is `<namespace>/<name>` and can't be combined with a name or namespace
+fn resolve_ref<'a>(
+    name: &'a str,
+    namespace: Option<&'a str>,
+    id: Option<&'a str>,
+) -> Result<(&'a str, Option<&'a str>), String> {
+    let Some(id) = id else {
+        if name.is_empty() {
+            return Err(String::from("A command needs a name or an id"));
+        }
+        return Ok((name, namespace));
+    };
+    if !name.is_empty() || namespace.is_some() {
+        return Err(format!(
+            "[{id}] is given by its id, leave out its name and namespace"
+        ));
+    }
+    match id.split_once('/') {
+        Some((namespace, name)) if !namespace.is_empty() && !name.is_empty() => {
+            Ok((name, Some(namespace)))
+        }
+        _ => Err(format!(
+            "Invalid id [{id}], ids look like <namespace>/<name>"
+        )),
+    }
+}
+
+/// Position of the command with `name`, in `namespace` if one is given
+fn find_command(trove: &Trove, name: &str, namespace: Option<&str>) -> Option<usize> {
+    trove.commands.iter().position(|command| {
+        command.name == name && namespace.is_none_or(|namespace| command.namespace == namespace)
+    })
+}
+
+impl CommandChange {
+    fn apply_to(&self, mut command: HoardCmd) -> HoardCmd {
+        if let Some(namespace) = &self.namespace {
+            command.namespace.clone_from(namespace);
+        }
+        if let Some(cmd) = &self.command {
+            command.command.clone_from(cmd);
+        }
+        if let Some(description) = &self.description {
+            command.description.clone_from(description);
+        }
+        if let Some(tags) = &self.tags {
+            command.tags.clone_from(tags);
+        }
+        if let Some(shell) = &self.shell {
+            command = command.with_shell(shell);
+        }
+        if let Some(container) = &self.container {
+            command = command.with_container(container);
+        }
+        command
+    }
+}
+
+#[cfg(test)]
+mod test_apply {
+    use super::*;
+
+    fn trove() -> Trove {
+        Trove::from_commands(&[HoardCmd::default()
+            .with_name("logs")
+            .with_command("tail -f app.log")
+            .with_namespace("default")])
+    }
+
+    #[test]
+    fn apply_all_operations() {
+        let changes = parse_changes(
+            "operations:
+  - add:
+      name: deploy
+      command: kubectl apply -f deploy.yml
+      namespace: work
+  - update:
+      name: logs
+      description: Follow the app logs
+  - delete:
+      name: missing
+",
+        )
+        .unwrap();
+        let (new_trove, summary) = apply_changes(&trove(), &changes, "default").unwrap();
+        assert_eq!(
+            ApplySummary {
+                added: 1,
+                updated: 1,
+                deleted: 0
+            },
+            summary
+        );
+        assert_eq!("work", new_trove.get_command("deploy").unwrap().namespace);
+        assert_eq!(
+            "Follow the app logs",
+            new_trove.get_command("logs").unwrap().description
+        );
+    }
+
+    #[test]
+    fn failing_operation_changes_nothing() {
+        let changes = parse_changes(
+            r#"{"operations": [{"delete": {"name": "logs"}}, {"update": {"name": "unknown", "command": "ls"}}]}"#,
+        )
+        .unwrap();
+        let result = apply_changes(&trove(), &changes, "default");
+        assert_eq!(
+            "Operation 2: No command [unknown] to update",
+            result.unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn adding_the_same_command_again_is_no_change() {
+        let changes = parse_changes(
+            "operations:
+  - add:
+      name: logs
+      command: tail -f app.log
+",
+        )
+        .unwrap();
+        let (_, summary) = apply_changes(&trove(), &changes, "default").unwrap();
+        assert_eq!(ApplySummary::default(), summary);
+    }
+
+    #[test]
+    fn find_commands_by_id() {
+        let mut trove = trove();
+        trove.commands.push(
+            HoardCmd::default()
+                .with_name("logs")
+                .with_command("journalctl -f")
+                .with_namespace("work"),
+        );
+        let changes = parse_changes(
+            "operations:
+  - update:
+      id: work/logs
+      description: Follow the journal
+  - delete:
+      id: default/logs
+",
+        )
+        .unwrap();
+        let (new_trove, summary) = apply_changes(&trove, &changes, "default").unwrap();
+        assert_eq!((1, 1), (summary.updated, summary.deleted));
+        assert_eq!(1, new_trove.commands.len());
+        assert_eq!("Follow the journal", new_trove.commands[0].description);
+
+        for (operation, error) in [
+            (
+                "{delete: {id: logs}}",
+                "Invalid id [logs], ids look like <namespace>/<name>",
+            ),
+            (
+                "{delete: {id: work/logs, name: logs}}",
+                "[work/logs] is given by its id, leave out its name and namespace",
+            ),
+            (
+                "{delete: {namespace: work}}",
+                "A command needs a name or an id",
+            ),
+        ] {
+            let changes = parse_changes(&format!("operations: [{operation}]")).unwrap();
+            let result = apply_changes(&trove, &changes, "default");
+            assert_eq!(
+                format!("Operation 1: {error}"),
+                result.unwrap_err().to_string()
+            );
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use crate::core::error::HoardErr;
+use crate::core::trove::Trove;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory of the trove backups in the hoard home directory
+pub const BACKUP_DIR: &str = "backups";
+
+/// Backups kept of each trove if `backup_count` isn't set in the config
+pub const DEFAULT_BACKUP_COUNT: usize = 10;
+
+/// Timestamp in the file name of a backup, in UTC so they sort by the time they were taken
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S-%3f";
+
+/// Length of a formatted timestamp like `20240102-030405-678`
+const TIMESTAMP_LENGTH: usize = 19;
+
+/// A copy of a trove file taken just before it was overwritten
+pub struct Backup {
+    pub path: PathBuf,
+    pub trove_name: String,
+    pub taken: DateTime<Utc>,
+}
+
+impl Backup {
+    fn from_path(path: PathBuf) -> Option<Self> {
+        let stem = path.file_stem()?.to_str()?;
+        // The timestamp has a fixed length, trove names can contain dashes as well
+        let split = stem.len().checked_sub(TIMESTAMP_LENGTH + 1)?;
+        let (trove_name, timestamp) = (stem.get(..split)?, stem.get(split + 1..)?);
+        let taken = NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).ok()?;
+        Some(Self {
+            trove_name: trove_name.to_string(),
+            taken: DateTime::from_utc(taken, Utc),
+            path,
+        })
+    }
+
+    pub fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    /// The trove in the backup, an error if the file can't be read or isn't a trove
+    pub fn load(&self) -> Result<Trove, HoardErr> {
+        let content = fs::read_to_string(&self.path)
+            .map_err(|e| HoardErr::new(&format!("Could not read {}: {e}", self.path.display())))?;
+        serde_yaml::from_str(&content)
+            .map_err(|e| HoardErr::new(&format!("{} is no valid trove: {e}", self.file_name())))
+    }
+}
+
+/// Copy the file of `trove_name`, or its directory of namespace files, into the backups directory before it is overwritten
+///
+/// Only the `keep` latest backups of the trove are kept, none at all are taken if it is 0. No backup is taken either
+/// if the trove is the same as in its latest backup, e.g. when it is saved after only browsing it. Returns the new
+/// backup
+pub fn back_up_trove(
+    hoard_home: &Path,
+    trove_name: &str,
+    trove_file: &Path,
+    keep: usize,
+) -> Result<Option<PathBuf>, HoardErr> {
+    if keep == 0 || !trove_file.exists() {
+        return Ok(None);
+    }
+    // A trove stored per namespace is backed up as one file, so it can be restored in either layout
+    let content = if trove_file.is_dir() {
+        Trove::load_namespace_files(trove_file)
+            .to_yaml()
+            .into_bytes()
+    } else {
+        fs::read(trove_file)
+            .map_err(|e| HoardErr::new(&format!("Could not back up the trove: {e}")))?
+    };
+    let backups = list_backups(hoard_home, Some(trove_name));
+    if backups
+        .first()
+        .is_some_and(|latest| fs::read(&latest.path).is_ok_and(|latest| latest == content))
+    {
+        return Ok(None);
+    }
+
+    let dir = hoard_home.join(BACKUP_DIR);
+    fs::create_dir_all(&dir)
+        .map_err(|e| HoardErr::new(&format!("Could not create {}: {e}", dir.display())))?;
+    let backup = dir.join(format!(
+        "{trove_name}-{}.yml",
+        Utc::now().format(TIMESTAMP_FORMAT)
+    ));
+    fs::write(&backup, content)
+        .map_err(|e| HoardErr::new(&format!("Could not back up the trove: {e}")))?;
+
+    let backups = list_backups(hoard_home, Some(trove_name));
+    for old in backups.iter().skip(keep) {
+        let _ = fs::remove_file(&old.path);
+    }
+    Ok(Some(backup))
+}
+
+/// Backups in the hoard home, of the trove `trove_name` only if it is given. The latest ones come first
+pub fn list_backups(hoard_home: &Path, trove_name: Option<&str>) -> Vec<Backup> {
+    let mut backups: Vec<Backup> = fs::read_dir(hoard_home.join(BACKUP_DIR))
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter_map(|entry| Backup::from_path(entry.path()))
+        .filter(|backup| trove_name.is_none_or(|name| backup.trove_name == name))
+        .collect();
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.taken));
+    backups
+}
+
+/// The backup `name` refers to, either a file in the backups directory or the path of one
+pub fn find_backup(hoard_home: &Path, name: &str) -> Result<Backup, HoardErr> {
+    let dir = hoard_home.join(BACKUP_DIR);
+    [
+        PathBuf::from(name),
+        dir.join(name),
+        dir.join(format!("{name}.yml")),
+    ]
+    .into_iter()
+    .find(|path| path.is_file())
+    .and_then(Backup::from_path)
+    .ok_or_else(|| {
+        HoardErr::new(&format!(
+            "There is no backup {name}, [ hoard restore ] lists them"
+        ))
+    })
+}
+
+#[cfg(test)]
+mod test_backup {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn keep_the_latest_backups_of_each_trove() {
+        let home = tempdir().unwrap();
+        let trove_file = home.path().join("trove.yml");
+        assert_eq!(
+            None,
+            back_up_trove(home.path(), "work-infra", &trove_file, 2).unwrap()
+        );
+
+        for version in 0..3 {
+            Trove {
+                version: version.to_string(),
+                ..Trove::default()
+            }
+            .save_trove_file(&trove_file);
+            back_up_trove(home.path(), "work-infra", &trove_file, 2).unwrap();
+            back_up_trove(home.path(), "default", &trove_file, 2).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+        let backups = list_backups(home.path(), Some("work-infra"));
+        assert_eq!(2, backups.len());
+        assert!(backups[0].taken > backups[1].taken);
+        assert_eq!(4, list_backups(home.path(), None).len());
+        assert_eq!(
+            None,
+            back_up_trove(home.path(), "default", &trove_file, 0).unwrap()
+        );
+
+        let found = find_backup(home.path(), &backups[0].file_name()).unwrap();
+        assert_eq!("work-infra", found.trove_name);
+        assert!(found.load().unwrap().commands.is_empty());
+        assert!(find_backup(home.path(), "default-20200101-000000-000").is_err());
+    }
+
+    #[test]
+    fn skip_backups_of_an_unchanged_trove() {
+        let home = tempdir().unwrap();
+        let trove_file = home.path().join("trove.yml");
+        Trove::default().save_trove_file(&trove_file);
+        assert!(back_up_trove(home.path(), "default", &trove_file, 2)
+            .unwrap()
+            .is_some());
+        for _ in 0..3 {
+            assert_eq!(
+                None,
+                back_up_trove(home.path(), "default", &trove_file, 2).unwrap()
+            );
+        }
+        assert_eq!(1, list_backups(home.path(), Some("default")).len());
+    }
+}
@@ -0,0 +1,81 @@
+use crate::core::trove::Trove;
+use crate::core::watch::{fingerprint, Fingerprint};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// Directory of the parsed copies of troves in the hoard home
+pub const CACHE_DIR: &str = "cache";
+
+/// A trove as it was parsed, with the files it was parsed from. Reading it back is a lot faster than parsing YAML
+#[derive(Serialize, Deserialize)]
+struct CachedTrove {
+    fingerprint: Fingerprint,
+    trove: Trove,
+}
+
+/// The trove stored at `path` as it was cached, `None` if it was changed since or never cached
+pub fn load_cached(hoard_home: &Path, path: &Path) -> Option<Trove> {
+    let content = fs::read(cache_file(hoard_home, path)).ok()?;
+    let cached: CachedTrove = serde_json::from_slice(&content).ok()?;
+    (cached.fingerprint == fingerprint(path)).then_some(cached.trove)
+}
+
+/// Keep a parsed copy of `trove`, which was just loaded from or saved at `path`
+///
+/// Troves without commands aren't cached, a file that can't be parsed is loaded as one and has to be reported again
+pub fn save_cached(hoard_home: &Path, path: &Path, trove: &Trove) {
+    let file = cache_file(hoard_home, path);
+    if trove.commands.is_empty() {
+        let _ = fs::remove_file(file);
+        return;
+    }
+    let cached = CachedTrove {
+        fingerprint: fingerprint(path),
+        trove: trove.clone(),
+    };
+    if let (Some(dir), Ok(content)) = (file.parent(), serde_json::to_vec(&cached)) {
+        let _ = fs::create_dir_all(dir).and_then(|()| fs::write(&file, content));
+    }
+}
+
+/// Cached troves are named after their path, the trove of the current directory is cached apart from the named ones
+fn cache_file(hoard_home: &Path, path: &Path) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    path.canonicalize()
+        .unwrap_or_else(|_| path.to_path_buf())
+        .hash(&mut hasher);
+    hoard_home
+        .join(CACHE_DIR)
+        .join(format!("{:016x}.json", hasher.finish()))
+}
+
+#[cfg(test)]
+mod test_cache {
+    use super::*;
+    use crate::core::HoardCmd;
+    use tempfile::tempdir;
+
+    #[test]
+    fn only_use_the_cache_of_an_unchanged_trove() {
+        let home = tempdir().unwrap();
+        let path = home.path().join("trove.yml");
+        let trove = Trove::from_commands(&[HoardCmd::default()
+            .with_name("build")
+            .with_namespace("default")
+            .with_command("make")]);
+        trove.save_trove_file(&path);
+        assert!(load_cached(home.path(), &path).is_none());
+
+        save_cached(home.path(), &path, &trove);
+        let cached = load_cached(home.path(), &path).unwrap();
+        assert_eq!(trove.commands, cached.commands);
+
+        fs::write(&path, "commands: [").unwrap();
+        assert!(load_cached(home.path(), &path).is_none());
+        save_cached(home.path(), &path, &Trove::default());
+        assert!(!cache_file(home.path(), &path).exists());
+    }
+}
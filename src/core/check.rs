@@ -0,0 +1,330 @@
+use crate::config::HoardConfig;
+use crate::core::error::HoardErr;
+use crate::core::import::imported_command_name;
+use crate::core::parameters::{map_parameters, stray_end_tokens};
+use crate::core::trove::{TrashedCommand, Trove};
+use crate::core::HoardCmd;
+use serde_yaml::Value;
+use std::collections::HashSet;
+
+/// Something wrong with a trove that `hoard doctor --trove-file` reports
+#[derive(Debug, PartialEq, Eq)]
+pub struct Problem {
+    /// The command or entry the problem is in
+    pub subject: String,
+    pub message: String,
+    /// What `--fix` does about it, e.g. `removed`. `None` if it has to be fixed by hand
+    pub fix: Option<String>,
+}
+
+impl Problem {
+    fn new(subject: &str, message: &str, fix: Option<&str>) -> Self {
+        Self {
+            subject: subject.to_string(),
+            message: message.to_string(),
+            fix: fix.map(ToString::to_string),
+        }
+    }
+
+    /// The problem as one line, with what is done or would be done about it
+    pub fn describe(&self, is_fixed: bool) -> String {
+        match (&self.fix, is_fixed) {
+            (Some(fix), true) => format!("[{}] {}, {fix}", self.subject, self.message),
+            (Some(fix), false) => format!("[{}] {}, would be {fix}", self.subject, self.message),
+            (None, _) => format!("[{}] {}", self.subject, self.message),
+        }
+    }
+}
+
+/// Read the trove in `content` entry by entry, so a single broken command doesn't hide all the others
+///
+/// Entries that can't be read are left out of the returned trove and reported
+pub fn read_trove_entries(
+    content: &str,
+    file_name: &str,
+) -> Result<(Trove, Vec<Problem>), HoardErr> {
+    let value: Value = serde_yaml::from_str(content)
+        .map_err(|e| HoardErr::new(&format!("{file_name} is no valid YAML: {e}")))?;
+    let mut problems = Vec::new();
+    let mut entries = |key: &str| -> Vec<Value> {
+        match value.get(key) {
+            Some(Value::Sequence(entries)) => entries.clone(),
+            None | Some(Value::Null) => Vec::new(),
+            Some(_) => {
+                problems.push(Problem::new(file_name, &format!("{key} is no list"), None));
+                Vec::new()
+            }
+        }
+    };
+    let (commands, trash) = (entries("commands"), entries("trash"));
+
+    let mut trove = Trove::default();
+    for (index, entry) in commands.into_iter().enumerate() {
+        match serde_yaml::from_value::<HoardCmd>(entry) {
+            Ok(command) => trove.commands.push(command),
+            Err(e) => problems.push(Problem::new(
+                &format!("command {} of {file_name}", index + 1),
+                &format!("can't be read: {e}"),
+                Some("removed"),
+            )),
+        }
+    }
+    for (index, entry) in trash.into_iter().enumerate() {
+        match serde_yaml::from_value::<TrashedCommand>(entry) {
+            Ok(trashed) => trove.trash.push(trashed),
+            Err(e) => problems.push(Problem::new(
+                &format!("trashed command {} of {file_name}", index + 1),
+                &format!("can't be read: {e}"),
+                Some("removed"),
+            )),
+        }
+    }
+    if let Some(version) = value.get("version").and_then(Value::as_str) {
+        trove.version = version.to_string();
+    }
+    if let Some(include) = value.get("include") {
+        trove.include = serde_yaml::from_value(include.clone()).unwrap_or_default();
+    }
+    trove.namespaces = trove
+        .namespaces()
+        .into_iter()
+        .map(ToString::to_string)
+        .collect();
+    Ok((trove, problems))
+}
+
+/// Check the commands of `trove` and fix whatever can be fixed without asking, in place
+///
+/// Looks for empty names and namespaces, names used twice in a namespace, ending tokens that close no parameter,
+/// empty and repeated tags and related commands that don't exist
+pub fn check_commands(trove: &mut Trove, config: &HoardConfig) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut commands: Vec<HoardCmd> = Vec::new();
+    let mut taken: HashSet<(String, String)> = HashSet::new();
+
+    for mut command in std::mem::take(&mut trove.commands) {
+        let subject = if command.name.trim().is_empty() {
+            format!("command in {}", command.namespace)
+        } else {
+            command.name.clone()
+        };
+        if command.command.trim().is_empty() {
+            problems.push(Problem::new(
+                &subject,
+                "has no command string",
+                Some("removed"),
+            ));
+            continue;
+        }
+        if command.name.trim().is_empty() {
+            let program = command
+                .command
+                .split_whitespace()
+                .next()
+                .unwrap_or_default();
+            command.name = imported_command_name(program, &command.description, &commands);
+            problems.push(Problem::new(
+                &subject,
+                "has an empty name",
+                Some(&format!("named {}", command.name)),
+            ));
+        } else if command.name.contains(char::is_whitespace) {
+            command.name = command
+                .name
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join("_");
+            problems.push(Problem::new(
+                &subject,
+                "has whitespace in its name",
+                Some(&format!("renamed to {}", command.name)),
+            ));
+        }
+        if command.namespace.trim().is_empty() {
+            command.namespace.clone_from(&config.default_namespace);
+            problems.push(Problem::new(
+                &command.name,
+                "has no namespace",
+                Some(&format!("moved to {}", config.default_namespace)),
+            ));
+        }
+        if taken.contains(&(command.name.clone(), command.namespace.clone())) {
+            let base_name = command.name.clone();
+            let mut count = 1;
+            while taken.contains(&(command.name.clone(), command.namespace.clone())) {
+                count += 1;
+                command.name = format!("{base_name}_{count}");
+            }
+            problems.push(Problem::new(
+                &base_name,
+                &format!("is in the {} namespace more than once", command.namespace),
+                Some(&format!("renamed to {}", command.name)),
+            ));
+        }
+        taken.insert((command.name.clone(), command.namespace.clone()));
+
+        let tag_count = command.tags.len();
+        let mut seen_tags = HashSet::new();
+        command
+            .tags
+            .retain(|tag| !tag.trim().is_empty() && seen_tags.insert(tag.clone()));
+        if command.tags.len() < tag_count {
+            problems.push(Problem::new(
+                &command.name,
+                "has empty or repeated tags",
+                Some("removed"),
+            ));
+        }
+
+        check_parameters(&mut command, config, &mut problems);
+        commands.push(command);
+    }
+
+    check_links(&mut commands, config, &mut problems);
+
+    *trove = Trove {
+        version: trove.version.clone(),
+        trash: std::mem::take(&mut trove.trash),
+        include: std::mem::take(&mut trove.include),
+        ..Trove::from_commands(&commands)
+    };
+    problems
+}
+
+/// Escape ending tokens that close no parameter and report parameters that were probably closed by accident
+fn check_parameters(command: &mut HoardCmd, config: &HoardConfig, problems: &mut Vec<Problem>) {
+    let start_token = config.parameter_token.clone().unwrap_or_default();
+    let end_token = config.parameter_ending_token.clone().unwrap_or_default();
+    let escape = config.escape_char();
+    let stray = stray_end_tokens(&command.command, &start_token, &end_token, escape);
+    if !stray.is_empty() {
+        for position in stray.iter().rev() {
+            command.command.insert(*position, escape);
+        }
+        problems.push(Problem::new(
+            &command.name,
+            &format!("has an ending token {end_token} that closes no parameter"),
+            Some(&format!("escaped as {escape}{end_token}")),
+        ));
+    }
+    map_parameters(
+        &command.command,
+        &start_token,
+        &end_token,
+        escape,
+        |parameter, _| {
+            if parameter.name.contains(char::is_whitespace) {
+                problems.push(Problem::new(
+                    &command.name,
+                    &format!(
+                        "has the parameter {start_token}{}{end_token}, the {end_token} probably belongs to the command, escape it as {escape}{end_token}",
+                        parameter.name
+                    ),
+                    None,
+                ));
+            }
+            String::new()
+        },
+    );
+}
+
+/// Unlink related commands that don't exist and report tag colors of tags no command has
+fn check_links(commands: &mut [HoardCmd], config: &HoardConfig, problems: &mut Vec<Problem>) {
+    let names: HashSet<String> = commands.iter().map(|c| c.name.clone()).collect();
+    for command in commands.iter_mut() {
+        let related_count = command.related.len();
+        command.related.retain(|name| names.contains(name));
+        if command.related.len() < related_count {
+            problems.push(Problem::new(
+                &command.name,
+                "is related to commands that don't exist",
+                Some("unlinked from them"),
+            ));
+        }
+    }
+
+    let tags: HashSet<&String> = commands.iter().flat_map(|c| &c.tags).collect();
+    let mut unused_colors: Vec<&String> = config
+        .tag_colors
+        .iter()
+        .flat_map(|colors| colors.keys())
+        .filter(|tag| !tags.contains(tag))
+        .collect();
+    unused_colors.sort();
+    for tag in unused_colors {
+        problems.push(Problem::new(
+            "config",
+            &format!("tag_colors has a color for {tag}, which no command is tagged with"),
+            None,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod test_check {
+    use super::*;
+
+    #[test]
+    fn read_the_entries_that_are_valid() {
+        let content = "---
+version: 1.4.0
+commands:
+  - name: status
+    namespace: git
+    command: git status
+    description: Show the status
+    tags: []
+  - name: broken
+    command: 42
+";
+        let (trove, problems) = read_trove_entries(content, "trove.yml").unwrap();
+        assert_eq!(1, trove.commands.len());
+        assert_eq!("1.4.0", trove.version);
+        assert_eq!(1, problems.len());
+        assert_eq!("command 2 of trove.yml", problems[0].subject);
+        assert!(read_trove_entries("commands: [", "trove.yml").is_err());
+    }
+
+    #[test]
+    fn fix_what_can_be_fixed() {
+        let command = |name: &str, command: &str| {
+            HoardCmd::default()
+                .with_name(name)
+                .with_namespace("default")
+                .with_command(command)
+        };
+        let config = HoardConfig {
+            tag_colors: Some([(String::from("prod"), (1, 2, 3))].into()),
+            ..HoardConfig::default()
+        };
+        let mut trove = Trove::from_commands(&[
+            command("", "ls -la").with_description("List files"),
+            command("check", "test ! -f #file!").with_tags_raw("fs,fs"),
+            command("check", "echo # && test ! -d"),
+            command("empty", " "),
+        ]);
+        trove.commands[1].related = vec![String::from("deploy")];
+
+        let problems: Vec<String> = check_commands(&mut trove, &config)
+            .iter()
+            .map(|problem| problem.describe(true))
+            .collect();
+        assert_eq!(
+            vec![
+                "[command in default] has an empty name, named ls_list_files",
+                "[check] has empty or repeated tags, removed",
+                "[check] has an ending token ! that closes no parameter, escaped as \\!",
+                "[check] is in the default namespace more than once, renamed to check_2",
+                "[check_2] has the parameter # && test !, the ! probably belongs to the command, escape it as \\!",
+                "[empty] has no command string, removed",
+                "[check] is related to commands that don't exist, unlinked from them",
+                "[config] tag_colors has a color for prod, which no command is tagged with",
+            ],
+            problems
+        );
+        let names: Vec<&str> = trove.commands.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(vec!["ls_list_files", "check", "check_2"], names);
+        assert_eq!("test \\! -f #file!", trove.commands[1].command);
+        assert_eq!(2, check_commands(&mut trove, &config).len());
+    }
+}
@@ -0,0 +1,133 @@
+use crate::core::stats::{command_frecency, Frecency};
+use crate::core::HoardCmd;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fmt;
+use std::process::{Command, Stdio};
+
+/// Where a command was hoarded, saved with it if `capture_context` is enabled in the config
+///
+/// The search ranks commands hoarded in the same directory, git repository or kube context higher
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandContext {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_remote: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kube_context: Option<String>,
+}
+
+impl CommandContext {
+    /// Context hoard is running in. Parts that can't be found out, e.g. without kubectl, are left out
+    pub fn capture() -> Self {
+        Self {
+            cwd: std::env::current_dir()
+                .ok()
+                .map(|cwd| cwd.to_string_lossy().to_string()),
+            git_remote: command_output("git", &["remote", "get-url", "origin"]),
+            kube_context: command_output("kubectl", &["config", "current-context"]),
+        }
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.cwd.is_none() && self.git_remote.is_none() && self.kube_context.is_none()
+    }
+
+    /// How many parts of the context a command was hoarded in are the same as this one
+    pub fn matches(&self, other: Option<&Self>) -> usize {
+        let Some(other) = other else {
+            return 0;
+        };
+        [
+            (&self.cwd, &other.cwd),
+            (&self.git_remote, &other.git_remote),
+            (&self.kube_context, &other.kube_context),
+        ]
+        .iter()
+        .filter(|(part, other_part)| part.is_some() && part == other_part)
+        .count()
+    }
+}
+
+impl fmt::Display for CommandContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts = [
+            ("directory", &self.cwd),
+            ("git remote", &self.git_remote),
+            ("kube context", &self.kube_context),
+        ];
+        let parts: Vec<String> = parts
+            .iter()
+            .filter_map(|(label, part)| part.as_ref().map(|part| format!("{label}: {part}")))
+            .collect();
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// Sort commands by how well their context matches `context`, then by their `frecency` and by how often they were used
+pub fn sort_by_context(
+    commands: &mut [HoardCmd],
+    context: Option<&CommandContext>,
+    frecency: &Frecency,
+) {
+    let score =
+        |command: &HoardCmd| context.map_or(0, |context| context.matches(command.context.as_ref()));
+    let frecency = |command: &HoardCmd| command_frecency(frecency, command);
+    commands
+        .sort_by_key(|command| Reverse((score(command), frecency(command), command.usage_count)));
+}
+
+/// Trimmed output of a program, `None` if it isn't installed, fails or prints nothing
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let output = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!output.is_empty()).then_some(output)
+}
+
+#[cfg(test)]
+mod test_context {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn context(cwd: &str, kube_context: Option<&str>) -> CommandContext {
+        CommandContext {
+            cwd: Some(cwd.to_string()),
+            git_remote: None,
+            kube_context: kube_context.map(ToString::to_string),
+        }
+    }
+
+    #[test]
+    fn commands_of_the_same_context_come_first() {
+        let mut commands = vec![
+            HoardCmd::default().with_name("used"),
+            HoardCmd::default()
+                .with_name("elsewhere")
+                .with_context(Some(context("/srv", None))),
+            HoardCmd::default()
+                .with_name("here")
+                .with_context(Some(context("/app", Some("prod")))),
+        ];
+        commands[0].usage_count = 5;
+        let current = context("/app", Some("prod"));
+        assert_eq!(2, current.matches(commands[2].context.as_ref()));
+        assert_eq!(0, current.matches(None));
+
+        sort_by_context(&mut commands, Some(&current), &HashMap::new());
+        let names: Vec<&str> = commands.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(vec!["here", "used", "elsewhere"], names);
+        sort_by_context(&mut commands, None, &HashMap::new());
+        assert_eq!("used", commands[0].name);
+        let frecency = HashMap::from([((String::new(), String::from("elsewhere")), 100)]);
+        sort_by_context(&mut commands, None, &frecency);
+        assert_eq!("elsewhere", commands[0].name);
+        assert_eq!("directory: /app, kube context: prod", current.to_string());
+    }
+}
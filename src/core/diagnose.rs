@@ -0,0 +1,305 @@
+use crate::config::{ColorSupport, HoardConfig};
+use crate::core::shell::{
+    is_stale_shell_integration, SHELL_INTEGRATION_VERSION, SHELL_INTEGRATION_VERSION_VAR,
+};
+use crate::core::trove::{namespace_dir, trove_files, Trove};
+use crate::util::pluralize;
+use serde_yaml::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How a check of `hoard doctor --env` went
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    /// Hoard works, but not as well as it could
+    Warning,
+    /// Something hoard needs is broken
+    Error,
+}
+
+/// The outcome of a check of `hoard doctor --env`, with what to do about it if it didn't pass
+#[derive(Debug, PartialEq, Eq)]
+pub struct Diagnosis {
+    pub check: &'static str,
+    pub status: Status,
+    pub message: String,
+    pub fix: Option<String>,
+}
+
+impl Diagnosis {
+    fn ok(check: &'static str, message: &str) -> Self {
+        Self {
+            check,
+            status: Status::Ok,
+            message: message.to_string(),
+            fix: None,
+        }
+    }
+
+    fn warning(check: &'static str, message: &str, fix: &str) -> Self {
+        Self {
+            check,
+            status: Status::Warning,
+            message: message.to_string(),
+            fix: Some(fix.to_string()),
+        }
+    }
+
+    fn error(check: &'static str, message: &str, fix: &str) -> Self {
+        Self {
+            status: Status::Error,
+            ..Self::warning(check, message, fix)
+        }
+    }
+}
+
+/// Check that the config file can be read and only has options hoard knows, a misspelled option is ignored otherwise
+pub fn check_config_file(path: &Path) -> Diagnosis {
+    const CHECK: &str = "Config file";
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            return Diagnosis::error(
+                CHECK,
+                &format!("{} can't be read: {e}", path.display()),
+                "Run any hoard command to create a new config file",
+            )
+        }
+    };
+    let config: HoardConfig = match serde_yaml::from_str(&content) {
+        Ok(config) => config,
+        Err(e) => {
+            return Diagnosis::error(
+                CHECK,
+                &format!("{} is no valid config: {e}", path.display()),
+                "Fix the line the error points at, or move the file away for a new one",
+            )
+        }
+    };
+    if config.parameter_token.is_some() && config.parameter_token == config.parameter_ending_token {
+        return Diagnosis::error(
+            CHECK,
+            "parameter_token and parameter_ending_token are the same",
+            "Set one of them to another character",
+        );
+    }
+    let known = serde_yaml::to_value(HoardConfig::default()).unwrap_or(Value::Null);
+    let unknown: Vec<String> = serde_yaml::from_str::<Value>(&content)
+        .ok()
+        .as_ref()
+        .and_then(Value::as_mapping)
+        .into_iter()
+        .flat_map(|options| options.iter())
+        .filter_map(|(key, _)| key.as_str())
+        .filter(|key| known.get(key).is_none())
+        .map(|key| format!("`{key}`"))
+        .collect();
+    if !unknown.is_empty() {
+        return Diagnosis::warning(
+            CHECK,
+            &format!("Unknown options are ignored: {}", unknown.join(", ")),
+            "Check their spelling against the options in the README",
+        );
+    }
+    Diagnosis::ok(CHECK, &format!("{} is valid", path.display()))
+}
+
+/// Check that the trove file, or every file of a trove split by namespace, can be read
+pub fn check_trove_file(path: Option<&Path>) -> Diagnosis {
+    const CHECK: &str = "Trove";
+    let Some(path) = path else {
+        return Diagnosis::error(
+            CHECK,
+            "No trove file is configured",
+            "Set `trove_path` in your config",
+        );
+    };
+    let files: Vec<PathBuf> = if path.exists() {
+        vec![path.to_path_buf()]
+    } else {
+        trove_files(&namespace_dir(path)).collect()
+    };
+    if files.is_empty() {
+        return Diagnosis::ok(
+            CHECK,
+            &format!(
+                "{} doesn't exist yet, it is created with the first command",
+                path.display()
+            ),
+        );
+    }
+    let mut commands = 0;
+    for file in &files {
+        let read = fs::read_to_string(file)
+            .map_err(|e| e.to_string())
+            .and_then(|content| serde_yaml::from_str::<Trove>(&content).map_err(|e| e.to_string()));
+        match read {
+            Ok(trove) => commands += trove.commands.len(),
+            Err(e) => {
+                return Diagnosis::error(
+                    CHECK,
+                    &format!("{} can't be read: {e}", file.display()),
+                    "Run `hoard doctor --trove-file` to find the broken entries",
+                )
+            }
+        }
+    }
+    Diagnosis::ok(
+        CHECK,
+        &format!(
+            "{} with {} can be read",
+            path.display(),
+            pluralize(commands, "command", "commands")
+        ),
+    )
+}
+
+/// Check the shell plugin that set `version`, `shell` is the path of `$SHELL`
+pub fn check_shell_integration(version: Option<&str>, shell: Option<&str>) -> Diagnosis {
+    const CHECK: &str = "Shell plugin";
+    let shell = shell
+        .and_then(|shell| shell.rsplit('/').next())
+        .filter(|name| ["bash", "zsh", "fish"].contains(name));
+    let install = shell.map_or_else(
+        || String::from("Run `hoard shell-config --shell <bash|zsh|fish>` and source its output in your shell's rc file"),
+        |shell| format!("Run `hoard shell-config --shell {shell}` and source its output in your shell's rc file"),
+    );
+    match version {
+        None => Diagnosis::warning(
+            CHECK,
+            &format!("Not loaded in this shell, {SHELL_INTEGRATION_VERSION_VAR} isn't set"),
+            &install,
+        ),
+        Some(version) if is_stale_shell_integration(Some(version)) => Diagnosis::warning(
+            CHECK,
+            &format!(
+                "Version {version} is loaded, this hoard comes with version {SHELL_INTEGRATION_VERSION}"
+            ),
+            &install,
+        ),
+        Some(version) => Diagnosis::ok(CHECK, &format!("Version {version} is loaded")),
+    }
+}
+
+/// Check the colors the GUI is drawn in, `detected` from the terminal and overridden by `configured`
+pub fn check_colors(detected: ColorSupport, configured: Option<ColorSupport>) -> Diagnosis {
+    const CHECK: &str = "Terminal colors";
+    let name = |support: ColorSupport| match support {
+        ColorSupport::Truecolor => "truecolor",
+        ColorSupport::Ansi256 => "256 colors",
+        ColorSupport::Ansi16 => "16 colors",
+        ColorSupport::None => "no colors",
+    };
+    if let Some(configured) = configured {
+        return Diagnosis::ok(
+            CHECK,
+            &format!(
+                "Set to {} by `color_support`, the terminal reports {}",
+                name(configured),
+                name(detected)
+            ),
+        );
+    }
+    if detected == ColorSupport::Truecolor {
+        return Diagnosis::ok(CHECK, "The terminal supports truecolor");
+    }
+    Diagnosis::warning(
+        CHECK,
+        &format!(
+            "The terminal reports {}, the theme colors are approximated",
+            name(detected)
+        ),
+        "If your terminal supports truecolor, export COLORTERM=truecolor or set `color_support: truecolor` in your config",
+    )
+}
+
+/// Check that the sync server at `url` can be reached with `reach` and that there is a token to sync with
+pub fn check_sync(
+    url: Option<&str>,
+    api_token: Option<&str>,
+    reach: impl Fn(&str) -> Result<(), String>,
+) -> Diagnosis {
+    const CHECK: &str = "Sync server";
+    let Some(url) = url.filter(|url| !url.trim().is_empty()) else {
+        return Diagnosis::warning(
+            CHECK,
+            "No sync server is configured",
+            "Set `sync_server_url` in your config to sync your trove",
+        );
+    };
+    if let Err(e) = reach(url) {
+        return Diagnosis::warning(
+            CHECK,
+            &format!("{url} can't be reached: {e}"),
+            "Check your network connection and `sync_server_url`, hoard works without syncing",
+        );
+    }
+    if api_token.is_none_or(|token| token.trim().is_empty()) {
+        return Diagnosis::warning(
+            CHECK,
+            &format!("{url} can be reached, but you aren't logged in"),
+            "Run `hoard sync login`, or `hoard sync register` first",
+        );
+    }
+    Diagnosis::ok(
+        CHECK,
+        &format!("{url} can be reached and you are logged in"),
+    )
+}
+
+#[cfg(test)]
+mod test_diagnose {
+    use super::*;
+
+    #[test]
+    fn check_config_and_trove_files() {
+        let dir = std::env::temp_dir().join(format!("hoard-diagnose-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let config = dir.join("config.yml");
+        let mut yaml = serde_yaml::to_string(&HoardConfig::default()).unwrap();
+        fs::write(&config, &yaml).unwrap();
+        assert_eq!(Status::Ok, check_config_file(&config).status);
+        yaml.push_str("colour_support: truecolor\n");
+        fs::write(&config, &yaml).unwrap();
+        let diagnosis = check_config_file(&config);
+        assert_eq!(Status::Warning, diagnosis.status);
+        assert!(diagnosis.message.contains("`colour_support`"));
+        fs::write(&config, "version: [").unwrap();
+        assert_eq!(Status::Error, check_config_file(&config).status);
+
+        let trove = dir.join("trove.yml");
+        assert_eq!(Status::Ok, check_trove_file(Some(&trove)).status);
+        fs::write(&trove, "commands: 3").unwrap();
+        assert_eq!(Status::Error, check_trove_file(Some(&trove)).status);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn check_the_environment() {
+        let shell = check_shell_integration(None, Some("/usr/bin/zsh"));
+        assert_eq!(Status::Warning, shell.status);
+        assert!(shell.fix.unwrap().contains("--shell zsh"));
+        let current = SHELL_INTEGRATION_VERSION.to_string();
+        assert_eq!(
+            Status::Ok,
+            check_shell_integration(Some(&current), None).status
+        );
+
+        assert_eq!(
+            Status::Warning,
+            check_colors(ColorSupport::Ansi256, None).status
+        );
+        assert_eq!(
+            Status::Ok,
+            check_colors(ColorSupport::Ansi16, Some(ColorSupport::Ansi16)).status
+        );
+
+        let reachable = |_: &str| Ok(());
+        let url = Some("https://sync.example.com/");
+        assert_eq!(Status::Ok, check_sync(url, Some("token"), reachable).status);
+        assert_eq!(Status::Warning, check_sync(url, None, reachable).status);
+        let offline = check_sync(url, Some("token"), |_| Err(String::from("timed out")));
+        assert!(offline.message.ends_with("can't be reached: timed out"));
+    }
+}
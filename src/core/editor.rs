@@ -0,0 +1,117 @@
+use crate::core::error::HoardErr;
+use crate::core::HoardCmd;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::process::Command;
+
+/// Comment on top of the file a command is edited in
+const BUFFER_HEADER: &str =
+    "# Edit the command, its description and tags, then save and close the editor
+# Empty the file to leave the command as it is
+";
+
+/// Fields of a command that are edited in `$EDITOR`
+#[derive(Serialize, Deserialize)]
+struct EditedFields {
+    command: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// The command, description and tags of `command` as YAML to edit
+pub fn command_buffer(command: &HoardCmd) -> String {
+    let fields = EditedFields {
+        command: command.command.clone(),
+        description: command.description.clone(),
+        tags: command.tags.clone(),
+    };
+    let yaml = serde_yaml::to_string(&fields).unwrap_or_default();
+    format!(
+        "{BUFFER_HEADER}{}",
+        yaml.strip_prefix("---\n").unwrap_or(&yaml)
+    )
+}
+
+/// `command` with the fields of the edited `buffer`, `None` if the buffer was emptied or nothing changed
+pub fn apply_buffer(command: &HoardCmd, buffer: &str) -> Result<Option<HoardCmd>, HoardErr> {
+    let is_empty = buffer
+        .lines()
+        .all(|line| line.trim().is_empty() || line.trim_start().starts_with('#'));
+    if is_empty {
+        return Ok(None);
+    }
+    let fields: EditedFields = serde_yaml::from_str(buffer)
+        .map_err(|e| HoardErr::new(&format!("The edited command can't be read: {e}")))?;
+    if fields.command.trim().is_empty() {
+        return Err(HoardErr::new("The command can't be empty"));
+    }
+    let tags: Vec<String> = fields
+        .tags
+        .iter()
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    let edited = HoardCmd {
+        command: fields.command.trim_end().to_string(),
+        description: fields.description.trim().to_string(),
+        tags,
+        ..command.clone()
+    };
+    Ok((edited != *command).then_some(edited))
+}
+
+/// Open `text` in `$VISUAL` or `$EDITOR`, `vi` if neither is set, and return what was saved
+///
+/// The editor may come with arguments, like `code --wait`
+pub fn edit_text(text: &str, file_name: &str) -> Result<String, HoardErr> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| String::from("vi"));
+    let path = std::env::temp_dir().join(format!("hoard-{}-{file_name}", std::process::id()));
+    fs::write(&path, text)
+        .map_err(|e| HoardErr::new(&format!("Could not write {}: {e}", path.display())))?;
+
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    let status = Command::new(program).args(words).arg(&path).status();
+    let edited = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+    match status {
+        Ok(status) if status.success() => {
+            edited.map_err(|e| HoardErr::new(&format!("Could not read {}: {e}", path.display())))
+        }
+        Ok(status) => Err(HoardErr::new(&format!("{editor} exited with {status}"))),
+        Err(e) => Err(HoardErr::new(&format!("Could not start {editor}: {e}"))),
+    }
+}
+
+#[cfg(test)]
+mod test_editor {
+    use super::*;
+
+    #[test]
+    fn apply_the_edited_fields() {
+        let command = HoardCmd::default()
+            .with_name("status")
+            .with_namespace("git")
+            .with_command("git status")
+            .with_tags_raw("git");
+        let buffer = command_buffer(&command);
+        assert!(buffer.starts_with(BUFFER_HEADER));
+        assert!(apply_buffer(&command, &buffer).unwrap().is_none());
+        assert!(apply_buffer(&command, BUFFER_HEADER).unwrap().is_none());
+
+        let edited = buffer
+            .replace("git status", "git status -sb")
+            .replace("- git", "- git\n  - ' '\n  - short");
+        let edited = apply_buffer(&command, &edited).unwrap().unwrap();
+        assert_eq!("git status -sb", edited.command);
+        assert_eq!(vec!["git", "short"], edited.tags);
+        assert_eq!("status", edited.name);
+        assert!(apply_buffer(&command, "command: ''").is_err());
+    }
+}
@@ -0,0 +1,79 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+
+/// Byte order mark some tools write at the start of UTF-8 output
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// How `hoard run` decodes the output of a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputEncoding {
+    /// UTF-8, or Latin-1 for lines that look like it, e.g. the output of legacy tools
+    #[default]
+    Auto,
+    /// UTF-8, invalid bytes are replaced by `�`
+    Utf8,
+    /// ISO-8859-1, every byte is one character
+    Latin1,
+    /// Printable ASCII as is and every other byte escaped like `\xff`
+    Raw,
+}
+
+/// Decode one output line in `encoding`, without its line ending
+///
+/// Never fails, so no output of a command is lost because it isn't valid UTF-8
+pub fn decode_line(line: &[u8], encoding: OutputEncoding) -> String {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    match encoding {
+        OutputEncoding::Auto => {
+            let line = line.strip_prefix(UTF8_BOM).unwrap_or(line);
+            match std::str::from_utf8(line) {
+                Ok(text) => text.to_string(),
+                // Latin-1 text hardly ever has C1 control characters, while most UTF-8 sequences do
+                Err(_) if !line.iter().any(|byte| (0x80..0xA0).contains(byte)) => {
+                    decode_line(line, OutputEncoding::Latin1)
+                }
+                Err(_) => decode_line(line, OutputEncoding::Utf8),
+            }
+        }
+        OutputEncoding::Utf8 => String::from_utf8_lossy(line).into_owned(),
+        OutputEncoding::Latin1 => line.iter().copied().map(char::from).collect(),
+        OutputEncoding::Raw => {
+            let mut text = String::new();
+            for &byte in line {
+                match byte {
+                    b'\\' => text.push_str("\\\\"),
+                    b' '..=b'~' => text.push(char::from(byte)),
+                    _ => {
+                        let _ = write!(text, "\\x{byte:02x}");
+                    }
+                }
+            }
+            text
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_encoding {
+    use super::*;
+
+    #[test]
+    fn decode_lines_in_every_encoding() {
+        assert_eq!(
+            "grüß\t",
+            decode_line("grüß\t\r\n".as_bytes(), OutputEncoding::Auto)
+        );
+        assert_eq!("ok", decode_line(b"\xEF\xBB\xBFok", OutputEncoding::Auto));
+        assert_eq!("café", decode_line(b"caf\xE9", OutputEncoding::Auto));
+        assert_eq!("a\u{FFFD}b", decode_line(b"a\x80b", OutputEncoding::Auto));
+        assert_eq!("caf\u{FFFD}", decode_line(b"caf\xE9", OutputEncoding::Utf8));
+        assert_eq!("Ã©", decode_line("é".as_bytes(), OutputEncoding::Latin1));
+        assert_eq!(
+            "caf\\xc3\\xa9 \\\\n\\x1b",
+            decode_line("café \\n\x1b\n".as_bytes(), OutputEncoding::Raw)
+        );
+    }
+}
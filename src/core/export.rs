@@ -0,0 +1,395 @@
+use crate::core::stats::csv_field;
+use crate::core::trove::Trove;
+use crate::core::HoardCmd;
+use serde_json::{Map, Value};
+use std::fmt::Write as _;
+
+/// One row per command with its name, namespace, command, description and comma separated tags, e.g. for a spreadsheet
+pub fn to_csv(trove: &Trove) -> String {
+    let mut csv = String::from("name,namespace,command,description,tags\n");
+    for command in &trove.commands {
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{}",
+            csv_field(&command.name),
+            csv_field(&command.namespace),
+            csv_field(&command.command),
+            csv_field(&command.description),
+            csv_field(&command.get_tags_as_string())
+        );
+    }
+    csv
+}
+
+/// The trove as TOML, with the same fields as the YAML and JSON formats
+///
+/// Every command is a `[[commands]]` table, nested values like times are inline tables
+pub fn to_toml(trove: &Trove) -> String {
+    let mut toml = String::new();
+    if let Ok(Value::Object(table)) = serde_json::to_value(trove) {
+        write_toml_table(&mut toml, "", &table);
+    }
+    toml
+}
+
+/// Write the keys of `table` first and its arrays of tables after them, those are headed by `[[path.key]]`
+fn write_toml_table(toml: &mut String, path: &str, table: &Map<String, Value>) {
+    let is_table_array = |value: &Value| {
+        value
+            .as_array()
+            .is_some_and(|items| !items.is_empty() && items.iter().all(Value::is_object))
+    };
+    for (key, value) in table {
+        if !value.is_null() && !is_table_array(value) {
+            let _ = writeln!(toml, "{} = {}", toml_key(key), toml_value(value));
+        }
+    }
+    for (key, value) in table.iter().filter(|(_, value)| is_table_array(value)) {
+        let path = if path.is_empty() {
+            toml_key(key)
+        } else {
+            format!("{path}.{}", toml_key(key))
+        };
+        for item in value.as_array().into_iter().flatten() {
+            let _ = write!(toml, "\n[[{path}]]\n");
+            if let Value::Object(item) = item {
+                write_toml_table(toml, &path, item);
+            }
+        }
+    }
+}
+
+fn toml_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => toml_string(text),
+        Value::Array(items) => {
+            let items: Vec<String> = items
+                .iter()
+                .filter(|item| !item.is_null())
+                .map(toml_value)
+                .collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Object(table) => {
+            let entries: Vec<String> = table
+                .iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| format!("{} = {}", toml_key(key), toml_value(value)))
+                .collect();
+            format!("{{ {} }}", entries.join(", "))
+        }
+        // TOML has no null, they are left out before
+        Value::Null | Value::Bool(_) | Value::Number(_) => value.to_string(),
+    }
+}
+
+/// Keys with only letters, digits, `-` and `_` are written bare, the others quoted
+fn toml_key(key: &str) -> String {
+    let is_bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if is_bare {
+        key.to_string()
+    } else {
+        toml_string(key)
+    }
+}
+
+fn toml_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\u{:04X}", u32::from(c));
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// A web page to put up as a cheatsheet, with a section per namespace and the commands in code blocks
+pub fn to_html(trove: &Trove) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<title>Hoard cheatsheet</title>
+<style>
+body { font-family: sans-serif; max-width: 60em; margin: 2em auto; padding: 0 1em; }
+pre { background: #f4f4f4; padding: 0.8em; overflow-x: auto; }
+.tag { background: #e0e7ff; border-radius: 0.3em; padding: 0.1em 0.4em; margin-right: 0.3em; font-size: 0.9em; }
+</style>
+</head>
+<body>
+<h1>Hoard cheatsheet</h1>
+",
+    );
+    let namespaces = trove.namespaces();
+    html.push_str("<ul>\n");
+    for namespace in &namespaces {
+        let _ = writeln!(
+            html,
+            "<li><a href=\"#{}\">{}</a></li>",
+            html_escape(&anchor(namespace)),
+            html_escape(namespace)
+        );
+    }
+    html.push_str("</ul>\n");
+    for namespace in namespaces {
+        let _ = writeln!(
+            html,
+            "<h2 id=\"{}\">{}</h2>",
+            html_escape(&anchor(namespace)),
+            html_escape(namespace)
+        );
+        let mut commands: Vec<&HoardCmd> = trove
+            .commands
+            .iter()
+            .filter(|command| command.namespace == namespace)
+            .collect();
+        commands.sort_by(|a, b| a.name.cmp(&b.name));
+        for command in commands {
+            let _ = writeln!(html, "<h3>{}</h3>", html_escape(&command.name));
+            if !command.description.is_empty() {
+                let _ = writeln!(html, "<p>{}</p>", html_escape(&command.description));
+            }
+            if !command.tags.is_empty() {
+                let tags: Vec<String> = command
+                    .tags
+                    .iter()
+                    .map(|tag| format!("<span class=\"tag\">{}</span>", html_escape(tag)))
+                    .collect();
+                let _ = writeln!(html, "<p>{}</p>", tags.join(""));
+            }
+            let _ = writeln!(
+                html,
+                "<pre><code>{}</code></pre>",
+                html_escape(&command.command)
+            );
+        }
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Id of the section of `namespace`, namespaces that differ in more than letters and digits could share one
+fn anchor(namespace: &str) -> String {
+    let anchor: String = namespace
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("ns-{anchor}")
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod test_export {
+    use super::*;
+
+    fn trove() -> Trove {
+        Trove::from_commands(&[
+            HoardCmd::default()
+                .with_name("logs")
+                .with_namespace("k8s")
+                .with_command("kubectl logs \"$POD\" | grep -v '<none>'")
+                .with_description("Follow the logs, of a pod")
+                .with_tags_raw("kube,logs"),
+            HoardCmd::default()
+                .with_name("status")
+                .with_namespace("git")
+                .with_command("git status\ngit log -1"),
+        ])
+    }
+
+    #[test]
+    fn csv_rows() {
+        let csv = to_csv(&trove());
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!("name,namespace,command,description,tags", lines[0]);
+        assert_eq!(
+            r#"logs,k8s,"kubectl logs ""$POD"" | grep -v '<none>'","Follow the logs, of a pod","kube,logs""#,
+            lines[1]
+        );
+    }
+
+    #[test]
+    fn toml_tables() {
+        let toml = to_toml(&trove());
+        assert!(toml.find("version = ").unwrap() < toml.find("[[commands]]").unwrap());
+        assert!(toml.contains("\n[[commands]]\ncommand = "));
+        assert!(toml.contains("\nname = \"logs\"\n"));
+        assert!(toml.contains(r#"command = "kubectl logs \"$POD\" | grep -v '<none>'""#));
+        assert!(toml.contains(r#"command = "git status\ngit log -1""#));
+        assert!(toml.contains("tags = [\"kube\", \"logs\"]"));
+        assert!(toml.contains("created = { nanos_since_epoch = "));
+        assert_eq!("\"a b\"", toml_key("a b"));
+    }
+
+    /// Read back the TOML `to_toml` writes, one `key = value` per line under `[[path]]` headers
+    fn parse_toml(toml: &str) -> Value {
+        let mut root = Map::new();
+        let mut path: Vec<String> = Vec::new();
+        for line in toml.lines().filter(|line| !line.is_empty()) {
+            if let Some(header) = line.strip_prefix("[[").and_then(|l| l.strip_suffix("]]")) {
+                path = header.split('.').map(String::from).collect();
+                let mut table = &mut root;
+                for (i, key) in path.iter().enumerate() {
+                    let items = table
+                        .entry(key.clone())
+                        .or_insert_with(|| Value::Array(Vec::new()))
+                        .as_array_mut()
+                        .unwrap();
+                    if i == path.len() - 1 {
+                        items.push(Value::Object(Map::new()));
+                    }
+                    table = items.last_mut().unwrap().as_object_mut().unwrap();
+                }
+                continue;
+            }
+            let mut table = &mut root;
+            for key in &path {
+                table = table[key]
+                    .as_array_mut()
+                    .and_then(|items| items.last_mut())
+                    .and_then(Value::as_object_mut)
+                    .unwrap();
+            }
+            let mut chars = line.chars().peekable();
+            let (key, value) = parse_entry(&mut chars);
+            assert!(chars.next().is_none(), "Trailing characters in {line}");
+            table.insert(key, value);
+        }
+        Value::Object(root)
+    }
+
+    type Chars<'a> = std::iter::Peekable<std::str::Chars<'a>>;
+
+    fn parse_entry(chars: &mut Chars) -> (String, Value) {
+        let key = if chars.peek() == Some(&'"') {
+            parse_string(chars)
+        } else {
+            let mut key = String::new();
+            while let Some(c) =
+                chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+            {
+                key.push(c);
+            }
+            key
+        };
+        for expected in " = ".chars() {
+            assert_eq!(Some(expected), chars.next());
+        }
+        (key, parse_value(chars))
+    }
+
+    fn parse_value(chars: &mut Chars) -> Value {
+        match chars.peek() {
+            Some('"') => Value::String(parse_string(chars)),
+            Some('[') => {
+                chars.next();
+                let mut items = Vec::new();
+                while chars.next_if_eq(&']').is_none() {
+                    items.push(parse_value(chars));
+                    chars.next_if_eq(&',');
+                    chars.next_if_eq(&' ');
+                }
+                Value::Array(items)
+            }
+            Some('{') => {
+                chars.next();
+                let mut table = Map::new();
+                chars.next_if_eq(&' ');
+                while chars.next_if_eq(&'}').is_none() {
+                    let (key, value) = parse_entry(chars);
+                    table.insert(key, value);
+                    chars.next_if_eq(&',');
+                    chars.next_if_eq(&' ');
+                }
+                Value::Object(table)
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(c) = chars.next_if(|c| !matches!(c, ',' | ' ' | ']' | '}')) {
+                    word.push(c);
+                }
+                serde_json::from_str(&word).unwrap()
+            }
+        }
+    }
+
+    fn parse_string(chars: &mut Chars) -> String {
+        assert_eq!(Some('"'), chars.next());
+        let mut text = String::new();
+        loop {
+            match chars.next().unwrap() {
+                '"' => return text,
+                '\\' => match chars.next().unwrap() {
+                    'n' => text.push('\n'),
+                    'r' => text.push('\r'),
+                    't' => text.push('\t'),
+                    'u' => {
+                        let code: String = chars.by_ref().take(4).collect();
+                        text.push(char::from_u32(u32::from_str_radix(&code, 16).unwrap()).unwrap());
+                    }
+                    c => text.push(c),
+                },
+                c => text.push(c),
+            }
+        }
+    }
+
+    #[test]
+    fn toml_parses_back_into_the_trove() {
+        let mut trove = trove();
+        trove.commands[1].description = String::from("Tab\t, bell\u{7} and \\ \"quotes\"");
+        trove.include = vec![String::from("https://example.com/trove.yml")];
+        let parsed: Trove = serde_json::from_value(parse_toml(&to_toml(&trove))).unwrap();
+        assert_eq!(trove.namespaces, parsed.namespaces);
+        // Namespaces are a set and come out in any order
+        let without_nulls = |trove: &Trove| {
+            let mut value = serde_json::to_value(trove).unwrap();
+            value.as_object_mut().unwrap().remove("namespaces");
+            for command in value["commands"].as_array_mut().unwrap() {
+                command
+                    .as_object_mut()
+                    .unwrap()
+                    .retain(|_, value| !value.is_null());
+            }
+            value
+        };
+        assert_eq!(without_nulls(&trove), without_nulls(&parsed));
+    }
+
+    #[test]
+    fn html_cheatsheet() {
+        let html = to_html(&trove());
+        assert!(html.contains("<h2 id=\"ns-k8s\">k8s</h2>"));
+        assert!(html.contains(
+            "<pre><code>kubectl logs &quot;$POD&quot; | grep -v &#39;&lt;none&gt;&#39;</code></pre>"
+        ));
+        assert!(html.contains("<span class=\"tag\">kube</span><span class=\"tag\">logs</span>"));
+        assert!(html.find("ns-git").unwrap() < html.find("ns-k8s").unwrap());
+    }
+}
@@ -0,0 +1,122 @@
+/// Every character of the query that is found
+const SCORE_MATCH: i64 = 16;
+/// A gap between two characters of the query costs more the first character than the following ones
+const GAP_START: i64 = -3;
+const GAP_EXTENSION: i64 = -1;
+/// Characters at the start of a word, like the `c` of `git commit`
+const BONUS_BOUNDARY: i64 = 8;
+/// Upper case characters after lower case ones, like the `C` of `gitCommit`, and digits after letters
+const BONUS_CAMEL: i64 = 7;
+/// Characters right after the character before them in the query
+const BONUS_CONSECUTIVE: i64 = 4;
+/// The first character of the query counts its bonus twice
+const BONUS_FIRST_CHAR_MULTIPLIER: i64 = 2;
+
+/// Score of the best way the characters of `query` are found in `text` in their order, `None` if they aren't
+///
+/// Scored like fzf does: characters at the start of words and characters following each other score higher,
+/// gaps between them lower. The query matches any case unless it has upper case characters itself
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let query: Vec<char> = query.chars().collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+    let case_sensitive = query.iter().any(|c| c.is_uppercase());
+    let text: Vec<char> = text.chars().collect();
+    let matches = |q: char, t: char| {
+        if case_sensitive {
+            q == t
+        } else {
+            t.to_lowercase().eq(q.to_lowercase())
+        }
+    };
+
+    // Best score with the previous character of the query found at each position of the text
+    let mut previous: Vec<Option<i64>> = vec![None; text.len()];
+    for (i, q) in query.iter().enumerate() {
+        let mut current: Vec<Option<i64>> = vec![None; text.len()];
+        // Best score of the previous character found before the one before `j`, with the gap up to `j`
+        let mut gapped: Option<i64> = None;
+        for (j, t) in text.iter().enumerate() {
+            if j > 1 {
+                let opened = previous[j - 2].map(|score| score + GAP_START);
+                gapped = gapped.map(|score| score + GAP_EXTENSION).max(opened);
+            }
+            if !matches(*q, *t) {
+                continue;
+            }
+            let bonus = bonus(j.checked_sub(1).map(|before| text[before]), *t);
+            current[j] = if i == 0 {
+                Some(SCORE_MATCH + bonus * BONUS_FIRST_CHAR_MULTIPLIER)
+            } else {
+                let consecutive = j
+                    .checked_sub(1)
+                    .and_then(|before| previous[before])
+                    .map(|score| score + BONUS_CONSECUTIVE);
+                consecutive
+                    .max(gapped)
+                    .map(|score| score + SCORE_MATCH + bonus)
+            };
+        }
+        if current.iter().all(Option::is_none) {
+            return None;
+        }
+        previous = current;
+    }
+    previous.into_iter().flatten().max()
+}
+
+/// Score of `query` against any of `fields`; every word of the query has to be found in one of them
+pub fn fuzzy_score_fields(query: &str, fields: &[&str]) -> Option<i64> {
+    query.split_whitespace().try_fold(0, |total, word| {
+        fields
+            .iter()
+            .filter_map(|field| fuzzy_score(word, field))
+            .max()
+            .map(|score| total + score)
+    })
+}
+
+/// Bonus of the character `current` for the character `before` it
+fn bonus(before: Option<char>, current: char) -> i64 {
+    match before {
+        None => BONUS_BOUNDARY,
+        Some(before) if !before.is_alphanumeric() && current.is_alphanumeric() => BONUS_BOUNDARY,
+        Some(before) if before.is_lowercase() && current.is_uppercase() => BONUS_CAMEL,
+        Some(before) if before.is_alphabetic() && current.is_ascii_digit() => BONUS_CAMEL,
+        Some(_) => 0,
+    }
+}
+
+#[cfg(test)]
+mod test_fuzzy {
+    use super::*;
+
+    #[test]
+    fn match_characters_in_order() {
+        assert!(fuzzy_score("gcm", "git commit -m").is_some());
+        assert!(fuzzy_score("GCM", "git commit -m").is_none());
+        assert!(fuzzy_score("mcg", "git commit -m").is_none());
+        assert!(fuzzy_score("kgp", "kubectl get pods").is_some());
+        assert_eq!(Some(0), fuzzy_score("", "anything"));
+    }
+
+    #[test]
+    fn rank_word_starts_and_consecutive_characters_higher() {
+        let word_starts = fuzzy_score("gcm", "git commit -m").unwrap();
+        let scattered = fuzzy_score("gcm", "docker image").unwrap_or(0);
+        assert!(word_starts > scattered);
+        let consecutive = fuzzy_score("comm", "git commit").unwrap();
+        let gapped = fuzzy_score("comm", "cargo bump -m").unwrap();
+        assert!(consecutive > gapped);
+        assert!(fuzzy_score("log", "kubectl logs").unwrap() > fuzzy_score("log", "blog").unwrap());
+    }
+
+    #[test]
+    fn match_every_word_in_some_field() {
+        let fields = ["deploy", "git push origin", "ci"];
+        assert!(fuzzy_score_fields("dep push", &fields).is_some());
+        assert!(fuzzy_score_fields("dep pull", &fields).is_none());
+        assert_eq!(Some(0), fuzzy_score_fields("", &fields));
+    }
+}
@@ -0,0 +1,453 @@
+use crate::core::error::HoardErr;
+use crate::core::trove::{namespace_dir, Trove};
+use crate::core::HoardCmd;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Directory of the git repository in the hoard home directory
+pub const GIT_DIR: &str = "git";
+
+/// Name of the remote `hoard git pull` and `hoard git push` use
+const GIT_REMOTE: &str = "origin";
+
+/// A git repository keeping a copy of every trove, committed whenever a trove is saved
+pub struct GitTrove {
+    dir: PathBuf,
+    /// Commit troves as a directory with a file per namespace, see `Trove::save_namespace_files`
+    per_namespace: bool,
+}
+
+/// Outcome of merging the trove of the remote into the local one
+pub struct TroveMerge {
+    pub trove: Trove,
+    /// Commands changed on both sides, the more recently modified version was kept
+    pub conflicts: Vec<String>,
+    /// The other troves of the repository the remote changed, by name and merged the same way
+    pub other_troves: Vec<(String, Trove)>,
+}
+
+impl GitTrove {
+    /// The repository in `hoard_home`, `None` if `hoard git init` wasn't run
+    pub fn open(hoard_home: &Path) -> Option<Self> {
+        let dir = hoard_home.join(GIT_DIR);
+        dir.join(".git").exists().then_some(Self {
+            dir,
+            per_namespace: false,
+        })
+    }
+
+    /// Create the repository in `hoard_home` if there is none yet and point its remote at `remote`
+    pub fn init(hoard_home: &Path, remote: Option<&str>) -> Result<Self, HoardErr> {
+        let repository = Self {
+            dir: hoard_home.join(GIT_DIR),
+            per_namespace: false,
+        };
+        if !repository.dir.join(".git").exists() {
+            fs::create_dir_all(&repository.dir).map_err(|e| {
+                HoardErr::new(&format!(
+                    "Could not create {}: {e}",
+                    repository.dir.display()
+                ))
+            })?;
+            repository.git(&["init", "--quiet"])?;
+        }
+        // hoard commits on its own, so it has to be able to without an identity set up for git
+        if repository.git(&["config", "user.email"]).is_err() {
+            repository.git(&["config", "user.name", "hoard"])?;
+            repository.git(&["config", "user.email", "hoard@localhost"])?;
+        }
+        if let Some(remote) = remote {
+            if repository.git(&["remote", "get-url", GIT_REMOTE]).is_ok() {
+                repository.git(&["remote", "set-url", GIT_REMOTE, remote])?;
+            } else {
+                repository.git(&["remote", "add", GIT_REMOTE, remote])?;
+            }
+        }
+        Ok(repository)
+    }
+
+    pub const fn with_namespace_files(mut self, per_namespace: bool) -> Self {
+        self.per_namespace = per_namespace;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Write `trove` to the repository as `<trove_name>.yml`, or `<trove_name>.d/`, and commit it. Returns whether
+    /// anything changed
+    pub fn commit(&self, trove: &Trove, trove_name: &str, message: &str) -> Result<bool, HoardErr> {
+        self.write_trove(trove, trove_name)?;
+        if self.git(&["diff", "--cached", "--quiet"]).is_ok() {
+            return Ok(false);
+        }
+        self.git(&["commit", "--quiet", "-m", message])?;
+        Ok(true)
+    }
+
+    /// Fetch the remote and merge its version of the trove into `local`, which has to be committed already
+    ///
+    /// Returns `None` if the remote has nothing new. Every other trove in the repository is merged as well, and the
+    /// merge is committed with both histories as parents
+    pub fn pull(&self, local: &Trove, trove_name: &str) -> Result<Option<TroveMerge>, HoardErr> {
+        self.git(&["fetch", "--quiet", GIT_REMOTE])?;
+        let branch = self.git(&["symbolic-ref", "--short", "HEAD"])?;
+        let upstream = format!("{GIT_REMOTE}/{branch}");
+        if self
+            .git(&["rev-parse", "--verify", "--quiet", &upstream])
+            .is_err()
+        {
+            // Nothing was pushed to the remote yet
+            return Ok(None);
+        }
+        let head = self.git(&["rev-parse", "HEAD"])?;
+        let upstream_head = self.git(&["rev-parse", &upstream])?;
+        let base = self.git(&["merge-base", "HEAD", &upstream]).ok();
+        if base.as_deref() == Some(upstream_head.as_str()) {
+            return Ok(None);
+        }
+
+        let theirs = self.trove_at(&upstream, trove_name)?;
+        let base_trove = match &base {
+            Some(base) => self.trove_at(base, trove_name)?,
+            None => Trove::default(),
+        };
+        let mut merge = merge_troves(&base_trove, local, &theirs);
+
+        let mut other_names: Vec<String> = Vec::new();
+        for revision in [
+            Some(head.as_str()),
+            Some(upstream.as_str()),
+            base.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            for name in self.trove_names_at(revision) {
+                if name != trove_name && !other_names.contains(&name) {
+                    other_names.push(name);
+                }
+            }
+        }
+        for name in other_names {
+            let ours = self.trove_at(&head, &name)?;
+            let base_trove = match &base {
+                Some(base) => self.trove_at(base, &name)?,
+                None => Trove::default(),
+            };
+            let other = merge_troves(&base_trove, &ours, &self.trove_at(&upstream, &name)?);
+            merge.conflicts.extend(
+                other
+                    .conflicts
+                    .iter()
+                    .map(|command| format!("{command} ({name})")),
+            );
+            if other.trove.commands != ours.commands {
+                merge.other_troves.push((name, other.trove));
+            }
+        }
+
+        if base.as_deref() == Some(head.as_str()) {
+            self.git(&["merge", "--quiet", "--ff-only", &upstream])?;
+        } else {
+            // Record the merge with the local tree and then replace the troves with the merged ones
+            self.git(&[
+                "merge",
+                "--quiet",
+                "--no-ff",
+                "--no-commit",
+                "--allow-unrelated-histories",
+                "-s",
+                "ours",
+                &upstream,
+            ])?;
+            self.write_trove(&merge.trove, trove_name)?;
+            for (name, trove) in &merge.other_troves {
+                self.write_trove(trove, name)?;
+            }
+            self.git(&["commit", "--quiet", "-m", &format!("Merge {upstream}")])?;
+        }
+        Ok(Some(merge))
+    }
+
+    /// Push the commits to the remote
+    pub fn push(&self) -> Result<(), HoardErr> {
+        self.git(&["push", "--quiet", "--set-upstream", GIT_REMOTE, "HEAD"])
+            .map(|_| ())
+    }
+
+    /// Write `trove` in the layout of the repository and stage it, the trove is removed from the other layout
+    fn write_trove(&self, trove: &Trove, trove_name: &str) -> Result<(), HoardErr> {
+        let file = self.dir.join(trove_file_name(trove_name));
+        let dir = namespace_dir(&file);
+        if self.per_namespace {
+            trove.save_namespace_files(&dir);
+            let _ = fs::remove_file(&file);
+        } else {
+            fs::write(&file, trove.to_yaml())
+                .map_err(|e| HoardErr::new(&format!("Could not write {}: {e}", file.display())))?;
+            let _ = fs::remove_dir_all(&dir);
+        }
+        self.git(&["add", "--all"]).map(|_| ())
+    }
+
+    /// The trove committed at `revision`, in either layout
+    fn trove_at(&self, revision: &str, trove_name: &str) -> Result<Trove, HoardErr> {
+        let file_name = trove_file_name(trove_name);
+        let files = if let Ok(content) = self.git(&["show", &format!("{revision}:{file_name}")]) {
+            vec![(file_name, content)]
+        } else {
+            self.namespace_files_at(revision, trove_name)?
+        };
+        let mut commands = Vec::new();
+        let mut trash = Vec::new();
+        for (file_name, content) in files {
+            let trove: Trove = serde_yaml::from_str(&content)
+                .map_err(|e| HoardErr::new(&format!("Invalid {file_name} at {revision}: {e}")))?;
+            commands.extend(trove.commands);
+            trash.extend(trove.trash);
+        }
+        Ok(Trove {
+            trash,
+            ..Trove::from_commands(&commands)
+        })
+    }
+
+    /// Names of the troves committed at `revision`, in either layout
+    fn trove_names_at(&self, revision: &str) -> Vec<String> {
+        let listed = self
+            .git(&["ls-tree", "--name-only", revision])
+            .unwrap_or_default();
+        listed
+            .lines()
+            .filter_map(|file| {
+                file.strip_suffix(".yml")
+                    .or_else(|| file.strip_suffix(".d"))
+                    .map(ToString::to_string)
+            })
+            .collect()
+    }
+
+    /// Names and contents of the namespace files of a trove committed at `revision`
+    fn namespace_files_at(
+        &self,
+        revision: &str,
+        trove_name: &str,
+    ) -> Result<Vec<(String, String)>, HoardErr> {
+        let listed = self
+            .git(&[
+                "ls-tree",
+                "--name-only",
+                revision,
+                &format!("{trove_name}.d/"),
+            ])
+            .unwrap_or_default();
+        // Nothing is listed if the trove wasn't committed at that revision yet
+        listed
+            .lines()
+            .filter(|file| Path::new(file).extension().is_some_and(|e| e == "yml"))
+            .map(|file| {
+                self.git(&["show", &format!("{revision}:{file}")])
+                    .map(|content| (file.to_string(), content))
+            })
+            .collect()
+    }
+
+    /// Run git in the repository and return its trimmed output
+    fn git(&self, args: &[&str]) -> Result<String, HoardErr> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.dir)
+            .args(args)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| HoardErr::new(&format!("Could not run git: {e}")))?;
+        if !output.status.success() {
+            return Err(HoardErr::new(&format!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+fn trove_file_name(trove_name: &str) -> String {
+    format!("{trove_name}.yml")
+}
+
+/// Three-way merge of the commands of two troves that both started out as `base`
+///
+/// Commands changed or removed on one side only take that change. Commands changed on both sides keep the version
+/// modified last, and a command removed on one side but changed on the other one is kept
+pub fn merge_troves(base: &Trove, ours: &Trove, theirs: &Trove) -> TroveMerge {
+    let by_key = |trove: &Trove| -> HashMap<(String, String), HoardCmd> {
+        trove
+            .commands
+            .iter()
+            .map(|command| {
+                (
+                    (command.namespace.clone(), command.name.clone()),
+                    command.clone(),
+                )
+            })
+            .collect()
+    };
+    let base_commands = by_key(base);
+    let our_commands = by_key(ours);
+    let their_commands = by_key(theirs);
+
+    let mut keys: Vec<(String, String)> = Vec::new();
+    for command in ours.commands.iter().chain(&theirs.commands) {
+        let key = (command.namespace.clone(), command.name.clone());
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    let mut commands = Vec::new();
+    let mut conflicts = Vec::new();
+    for key in &keys {
+        let base_command = base_commands.get(key);
+        let merged = match (our_commands.get(key), their_commands.get(key)) {
+            (Some(our), Some(their)) if our == their => {
+                let mut command = our.clone();
+                command.usage_count = our.usage_count.max(their.usage_count);
+                Some(command)
+            }
+            (Some(our), Some(their)) => match base_command {
+                Some(base) if base == our => Some(their.clone()),
+                Some(base) if base == their => Some(our.clone()),
+                _ => {
+                    conflicts.push(our.name.clone());
+                    Some(if their.modified > our.modified {
+                        their.clone()
+                    } else {
+                        our.clone()
+                    })
+                }
+            },
+            (Some(command), None) | (None, Some(command)) => match base_command {
+                // Removed on the other side without being changed here
+                Some(base) if base == command => None,
+                _ => Some(command.clone()),
+            },
+            (None, None) => None,
+        };
+        commands.extend(merged);
+    }
+    TroveMerge {
+        trove: Trove {
+            version: ours.version.clone(),
+            trash: ours.trash.clone(),
+            include: ours.include.clone(),
+            ..Trove::from_commands(&commands)
+        },
+        conflicts,
+        other_troves: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod test_git {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn command(name: &str, command: &str, modified_secs: u64) -> HoardCmd {
+        let mut command = HoardCmd::default()
+            .with_name(name)
+            .with_namespace("default")
+            .with_command(command);
+        command.modified = SystemTime::UNIX_EPOCH + Duration::from_secs(modified_secs);
+        command
+    }
+
+    fn clone_of(remote: &Path, home: &Path) -> GitTrove {
+        let repository = GitTrove::init(home, Some(&remote.display().to_string())).unwrap();
+        // Like hoard does, the trove is committed before pulling
+        repository
+            .commit(&Trove::default(), "trove", "Add trove")
+            .unwrap();
+        repository.pull(&Trove::default(), "trove").unwrap();
+        repository
+    }
+
+    #[test]
+    fn pull_merges_the_other_troves_too() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote = dir.path().join("remote.git");
+        Command::new("git")
+            .args(["init", "--quiet", "--bare"])
+            .arg(&remote)
+            .status()
+            .unwrap();
+        let ours = clone_of(&remote, &dir.path().join("ours"));
+        let work = Trove::from_commands(&[command("deploy", "make deploy", 1)]);
+        ours.commit(&work, "work", "Add work").unwrap();
+        ours.commit(&Trove::default(), "trove", "Add trove")
+            .unwrap();
+        ours.push().unwrap();
+
+        // The remote changes the work trove while the trove in use changes here
+        let theirs = clone_of(&remote, &dir.path().join("theirs"));
+        let changed_work = Trove::from_commands(&[
+            command("deploy", "make deploy", 1),
+            command("rollback", "make rollback", 2),
+        ]);
+        theirs.commit(&changed_work, "work", "Change work").unwrap();
+        theirs.push().unwrap();
+        let local = Trove::from_commands(&[command("build", "make", 3)]);
+        ours.commit(&local, "trove", "Change trove").unwrap();
+
+        let merge = ours.pull(&local, "trove").unwrap().unwrap();
+        assert_eq!("build", merge.trove.commands[0].name);
+        assert_eq!("work", merge.other_troves[0].0);
+        assert_eq!(2, merge.other_troves[0].1.commands.len());
+        let committed = ours.trove_at("HEAD", "work").unwrap();
+        assert_eq!(2, committed.commands.len());
+    }
+
+    #[test]
+    fn merge_takes_the_changes_of_both_sides() {
+        let base = Trove::from_commands(&[
+            command("build", "make", 1),
+            command("deploy", "kubectl apply", 1),
+            command("logs", "kubectl logs", 1),
+            command("test", "make test", 1),
+        ]);
+        let ours = Trove::from_commands(&[
+            command("build", "make -j8", 2),
+            command("deploy", "kubectl apply -f app.yml", 3),
+            command("test", "make test", 1),
+            command("lint", "cargo clippy", 2),
+        ]);
+        let theirs = Trove::from_commands(&[
+            command("build", "make", 1),
+            command("deploy", "helm upgrade", 5),
+            command("logs", "kubectl logs -f", 4),
+            command("fmt", "cargo fmt", 4),
+        ]);
+        let merge = merge_troves(&base, &ours, &theirs);
+        let commands: Vec<(&str, &str)> = merge
+            .trove
+            .commands
+            .iter()
+            .map(|c| (c.name.as_str(), c.command.as_str()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("build", "make -j8"),
+                ("deploy", "helm upgrade"),
+                ("lint", "cargo clippy"),
+                ("logs", "kubectl logs -f"),
+                ("fmt", "cargo fmt"),
+            ],
+            commands
+        );
+        assert_eq!(vec!["deploy"], merge.conflicts);
+    }
+}
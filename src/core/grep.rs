@@ -0,0 +1,70 @@
+use crate::core::error::HoardErr;
+use crate::core::HoardCmd;
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+/// A field of a command that a `hoard grep` pattern matched, with where it matched
+#[derive(Debug, PartialEq, Eq)]
+pub struct FieldMatch {
+    pub field: &'static str,
+    pub text: String,
+    pub ranges: Vec<Range<usize>>,
+}
+
+/// The regex of `hoard grep`, an error that points at the pattern if it isn't one
+pub fn grep_pattern(pattern: &str, ignore_case: bool) -> Result<Regex, HoardErr> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(ignore_case)
+        .build()
+        .map_err(|e| HoardErr::new(&format!("'{pattern}' is no valid regex: {e}")))
+}
+
+/// Fields of `command` that `pattern` matches, in the order name, command, description and tags
+///
+/// Tags are matched as one comma separated text, so a pattern can span them
+pub fn grep_command(command: &HoardCmd, pattern: &Regex) -> Vec<FieldMatch> {
+    [
+        ("name", command.name.clone()),
+        ("command", command.command.clone()),
+        ("description", command.description.clone()),
+        ("tags", command.tags.join(", ")),
+    ]
+    .into_iter()
+    .filter_map(|(field, text)| {
+        let ranges: Vec<Range<usize>> = pattern
+            .find_iter(&text)
+            .map(|found| found.range())
+            .filter(|range| !range.is_empty())
+            .collect();
+        (!ranges.is_empty()).then_some(FieldMatch {
+            field,
+            text,
+            ranges,
+        })
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod test_grep {
+    use super::*;
+
+    #[test]
+    fn find_the_fields_that_match() {
+        let command = HoardCmd::default()
+            .with_name("deploy_eu")
+            .with_namespace("work")
+            .with_command("kubectl apply -f eu.yml --context eu-west-1")
+            .with_description("Deploy to the EU cluster")
+            .with_tags_raw("k8s,eu");
+        let matches = grep_command(&command, &grep_pattern(r"eu(-west)?", false).unwrap());
+        let fields: Vec<&str> = matches.iter().map(|m| m.field).collect();
+        assert_eq!(vec!["name", "command", "tags"], fields);
+        assert_eq!(vec![17..19, 34..41], matches[1].ranges);
+
+        let matches = grep_command(&command, &grep_pattern("eu cluster", true).unwrap());
+        assert_eq!("description", matches[0].field);
+        assert!(grep_command(&command, &grep_pattern("q*", false).unwrap()).is_empty());
+        assert!(grep_pattern("(", false).is_err());
+    }
+}
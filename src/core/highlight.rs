@@ -0,0 +1,264 @@
+use crate::core::parameters::parameter_len;
+
+/// What a piece of a command is to the shell, to highlight it in the preview
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellToken {
+    /// The program a command runs, e.g. `kubectl`
+    Program,
+    /// The first word after a program that has subcommands, e.g. `logs` of `kubectl logs`
+    Subcommand,
+    Flag,
+    /// A quoted string with its quotes
+    String,
+    /// Pipes, command lists, redirections and subshells
+    Operator,
+    Variable,
+    /// A hoard parameter like `#pod!`, inside of strings as well
+    Parameter,
+    /// Arguments and whitespace
+    Text,
+}
+
+/// Programs that run whatever program comes after them
+const COMMAND_PREFIXES: &[&str] = &[
+    "sudo", "doas", "env", "time", "nohup", "exec", "nice", "xargs", "watch",
+];
+
+/// Programs whose first argument is a subcommand
+const PROGRAMS_WITH_SUBCOMMANDS: &[&str] = &[
+    "apt",
+    "aws",
+    "az",
+    "brew",
+    "cargo",
+    "docker",
+    "gcloud",
+    "gh",
+    "git",
+    "go",
+    "hoard",
+    "helm",
+    "kubectl",
+    "nix",
+    "npm",
+    "pip",
+    "pnpm",
+    "podman",
+    "poetry",
+    "systemctl",
+    "terraform",
+    "yarn",
+];
+
+/// Operators in the order they are matched, longer ones first
+const OPERATORS: &[&str] = &[
+    "&&", "||", "|&", ">>", "$(", "|", ";", "&", ">", "<", "(", ")",
+];
+
+/// What the next word of a command is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expect {
+    Program,
+    Subcommand,
+    Arguments,
+}
+
+/// Split `command` into the pieces it is made of for the shell, the pieces put together are `command` again
+///
+/// This is no parser, it only tells apart what is worth a color of its own in a preview
+pub fn shell_tokens<'a>(
+    command: &'a str,
+    start_token: &str,
+    end_token: &str,
+    escape: char,
+) -> Vec<(ShellToken, &'a str)> {
+    let mut pieces: Vec<(ShellToken, usize, usize)> = Vec::new();
+    let mut push = |kind: ShellToken, start: usize, end: usize| match pieces.last_mut() {
+        Some((last, _, last_end)) if *last == kind && *last_end == start => *last_end = end,
+        _ if start < end => pieces.push((kind, start, end)),
+        _ => {}
+    };
+    let mut expect = Expect::Program;
+    // Kind of the word that is read, `None` between words
+    let mut word: Option<ShellToken> = None;
+    let mut i = 0;
+    while i < command.len() {
+        let rest = &command[i..];
+        let c = rest.chars().next().unwrap_or_default();
+        if let Some(len) = parameter_len(rest, start_token, end_token, escape) {
+            word.get_or_insert_with(|| word_kind(rest, &mut expect));
+            push(ShellToken::Parameter, i, i + len);
+            i += len;
+        } else if c == escape || c == '\\' {
+            let kind = *word.get_or_insert_with(|| word_kind(rest, &mut expect));
+            let next = rest[c.len_utf8()..]
+                .chars()
+                .next()
+                .map_or(0, char::len_utf8);
+            push(kind, i, i + c.len_utf8() + next);
+            i += c.len_utf8() + next;
+        } else if c.is_whitespace() {
+            if c == '\n' {
+                expect = Expect::Program;
+            }
+            word = None;
+            push(ShellToken::Text, i, i + c.len_utf8());
+            i += c.len_utf8();
+        } else if c == '\'' || c == '"' {
+            word.get_or_insert_with(|| word_kind(rest, &mut expect));
+            let end = closing_quote(rest, c).map_or(command.len(), |end| i + end);
+            // Parameters are filled in inside of quotes as well
+            let mut start = i;
+            let mut j = i + 1;
+            while j < end {
+                if let Some(len) = parameter_len(&command[j..end], start_token, end_token, escape) {
+                    push(ShellToken::String, start, j);
+                    push(ShellToken::Parameter, j, j + len);
+                    j += len;
+                    start = j;
+                } else {
+                    j += command[j..].chars().next().map_or(1, char::len_utf8);
+                }
+            }
+            push(ShellToken::String, start, end);
+            i = end;
+        } else if let Some(len) = variable_len(rest) {
+            word.get_or_insert_with(|| word_kind(rest, &mut expect));
+            push(ShellToken::Variable, i, i + len);
+            i += len;
+        } else if let Some(operator) = OPERATORS
+            .iter()
+            .find(|operator| rest.starts_with(**operator))
+        {
+            expect = match *operator {
+                // The word after a redirection is a file
+                ">" | ">>" | "<" if expect == Expect::Subcommand => Expect::Arguments,
+                ">" | ">>" | "<" => expect,
+                ")" => Expect::Arguments,
+                _ => Expect::Program,
+            };
+            word = None;
+            push(ShellToken::Operator, i, i + operator.len());
+            i += operator.len();
+        } else {
+            let kind = *word.get_or_insert_with(|| word_kind(rest, &mut expect));
+            push(kind, i, i + c.len_utf8());
+            i += c.len_utf8();
+        }
+    }
+    pieces
+        .into_iter()
+        .map(|(kind, start, end)| (kind, &command[start..end]))
+        .collect()
+}
+
+/// Kind of the word `rest` starts with, and what the word after it is
+fn word_kind(rest: &str, expect: &mut Expect) -> ShellToken {
+    let word: &str = rest
+        .split(|c: char| c.is_whitespace() || "|&;<>()".contains(c))
+        .next()
+        .unwrap_or_default();
+    match *expect {
+        // Assignments like `FOO=bar` come before the program
+        Expect::Program if word.contains('=') && !word.starts_with('=') => ShellToken::Text,
+        Expect::Program => {
+            let program = word.rsplit('/').next().unwrap_or(word);
+            if PROGRAMS_WITH_SUBCOMMANDS.contains(&program) {
+                *expect = Expect::Subcommand;
+            } else if !COMMAND_PREFIXES.contains(&program) {
+                *expect = Expect::Arguments;
+            }
+            ShellToken::Program
+        }
+        _ if word.starts_with('-') => ShellToken::Flag,
+        Expect::Subcommand => {
+            *expect = Expect::Arguments;
+            ShellToken::Subcommand
+        }
+        Expect::Arguments => ShellToken::Text,
+    }
+}
+
+/// Position after the quote closing the string `rest` starts with, double quotes can be escaped inside of it
+fn closing_quote(rest: &str, quote: char) -> Option<usize> {
+    let mut chars = rest.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        if c == '\\' && quote == '"' {
+            chars.next();
+        } else if c == quote {
+            return Some(i + c.len_utf8());
+        }
+    }
+    None
+}
+
+/// Length of the variable like `$HOME`, `${HOME}` or `$1` that `rest` starts with
+fn variable_len(rest: &str) -> Option<usize> {
+    let name = rest.strip_prefix('$')?;
+    if let Some(braced) = name.strip_prefix('{') {
+        return Some(braced.find('}').map_or(rest.len(), |end| end + 3));
+    }
+    let len = if name.starts_with(|c: char| "?!#$@*-".contains(c) || c.is_ascii_digit()) {
+        1
+    } else {
+        name.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(name.len())
+    };
+    (len > 0).then_some(len + 1)
+}
+
+#[cfg(test)]
+mod test_highlight {
+    use super::*;
+    use ShellToken::*;
+
+    fn tokens(command: &str) -> Vec<(ShellToken, &str)> {
+        let tokens = shell_tokens(command, "#", "!", '\\');
+        let joined: std::string::String = tokens.iter().map(|(_, piece)| *piece).collect();
+        assert_eq!(command, joined);
+        tokens
+    }
+
+    #[test]
+    fn highlight_pipelines() {
+        assert_eq!(
+            vec![
+                (Program, "kubectl"),
+                (Text, " "),
+                (Subcommand, "logs"),
+                (Text, " "),
+                (Parameter, "#pod!"),
+                (Text, " "),
+                (Flag, "-n"),
+                (Text, " "),
+                (Variable, "$NS"),
+                (Text, " "),
+                (Operator, "|"),
+                (Text, " "),
+                (Program, "grep"),
+                (Text, " "),
+                (String, "\"error "),
+                (Parameter, "#id!"),
+                (String, "\""),
+                (Text, " "),
+                (Operator, ">"),
+                (Text, " out.log"),
+            ],
+            tokens("kubectl logs #pod! -n $NS | grep \"error #id!\" > out.log")
+        );
+    }
+
+    #[test]
+    fn highlight_prefixes_and_subshells() {
+        let tokens = tokens("FOO=1 sudo git -C repo status && echo $(date) 'a b' \\#x");
+        assert_eq!((Text, "FOO=1 "), tokens[0]);
+        assert_eq!((Program, "sudo"), tokens[1]);
+        assert_eq!((Program, "git"), tokens[3]);
+        assert_eq!((Flag, "-C"), tokens[5]);
+        assert!(tokens.contains(&(Subcommand, "repo")));
+        assert!(tokens.contains(&(Operator, "$(")));
+        assert!(tokens.contains(&(Program, "date")));
+        assert!(tokens.contains(&(String, "'a b'")));
+        assert_eq!(Some(&(Text, " \\#x")), tokens.last());
+    }
+}
@@ -0,0 +1,288 @@
+use crate::config::HoardConfig;
+use crate::core::error::HoardErr;
+use crate::core::navi::cheat_commands;
+use crate::core::trove::Trove;
+use crate::core::HoardCmd;
+use clap::ValueEnum;
+use std::io::Read;
+use std::path::Path;
+use std::time::Duration;
+use url::Url;
+
+/// Largest trove file `hoard import` reads, bigger files are most likely not a trove
+pub const MAX_IMPORT_SIZE: u64 = 1024 * 1024;
+
+/// Formats a trove can be imported from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ImportFormat {
+    Yaml,
+    Json,
+    /// A document with the trove in ```` ```yaml ```` or ```` ```json ```` code blocks, e.g. a team README
+    #[value(alias = "md")]
+    Markdown,
+    /// A `.cheat` file of navi
+    Navi,
+}
+
+/// Format of an imported trove, by its content type, the extension of its location or else its content
+pub fn detect_format(content_type: Option<&str>, location: &str, content: &str) -> ImportFormat {
+    let mime = content_type
+        .and_then(|content_type| content_type.split(';').next())
+        .map(|mime| mime.trim().to_lowercase())
+        .unwrap_or_default();
+    if mime.ends_with("json") {
+        return ImportFormat::Json;
+    }
+    if mime.ends_with("markdown") {
+        return ImportFormat::Markdown;
+    }
+    if mime.ends_with("yaml") {
+        return ImportFormat::Yaml;
+    }
+
+    let path = location.split(['?', '#']).next().unwrap_or(location);
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_lowercase);
+    match extension.as_deref() {
+        Some("json") => return ImportFormat::Json,
+        Some("md" | "markdown") => return ImportFormat::Markdown,
+        Some("yml" | "yaml") => return ImportFormat::Yaml,
+        Some("cheat") => return ImportFormat::Navi,
+        _ => {}
+    }
+
+    let start = content.trim_start();
+    if start.starts_with('{') {
+        ImportFormat::Json
+    } else if start.starts_with('%') {
+        ImportFormat::Navi
+    } else if start.starts_with('#') || start.contains("```") {
+        ImportFormat::Markdown
+    } else {
+        ImportFormat::Yaml
+    }
+}
+
+/// Parse an imported trove. All trove code blocks of a markdown document are merged into one trove
+///
+/// The parameters of navi cheats are written with the parameter tokens of `config`
+pub fn parse_trove(
+    content: &str,
+    format: ImportFormat,
+    config: &HoardConfig,
+) -> Result<Trove, HoardErr> {
+    let parse = |content: &str| {
+        let trove: Trove = match format {
+            ImportFormat::Json => serde_json::from_str(content).map_err(|e| e.to_string())?,
+            _ => serde_yaml::from_str(content).map_err(|e| e.to_string())?,
+        };
+        Ok::<_, String>(Trove::from_commands(&trove.commands))
+    };
+    match format {
+        ImportFormat::Yaml | ImportFormat::Json => {
+            parse(content).map_err(|e| HoardErr::new(&format!("Invalid trove file: {e}")))
+        }
+        ImportFormat::Markdown => {
+            let blocks = code_blocks(content, &["yaml", "yml", "json"]);
+            let mut trove = Trove::default();
+            for (language, block) in &blocks {
+                let block_format = if *language == "json" {
+                    ImportFormat::Json
+                } else {
+                    ImportFormat::Yaml
+                };
+                // Other code blocks of the document, like install instructions, are skipped
+                if let Ok(block_trove) = parse_trove(block, block_format, config) {
+                    trove.merge_trove(&block_trove);
+                }
+            }
+            if trove.is_empty() {
+                return Err(HoardErr::new(
+                    "No trove found in the code blocks of the markdown document",
+                ));
+            }
+            Ok(trove)
+        }
+        ImportFormat::Navi => {
+            let commands = cheat_commands(
+                content,
+                &config.parameter_token.clone().unwrap_or_default(),
+                &config.parameter_ending_token.clone().unwrap_or_default(),
+                config.escape_char(),
+            );
+            if commands.is_empty() {
+                return Err(HoardErr::new("No commands found in the navi cheat"));
+            }
+            Ok(Trove::from_commands(&commands))
+        }
+    }
+}
+
+/// Contents of the fenced code blocks in `markdown` tagged with one of `languages`
+fn code_blocks<'a>(markdown: &'a str, languages: &[&'a str]) -> Vec<(&'a str, String)> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(&str, String)> = None;
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        if let Some(info) = trimmed.strip_prefix("```") {
+            if let Some(block) = current.take() {
+                blocks.push(block);
+            } else {
+                let language = info.trim().to_lowercase();
+                current = Some((
+                    languages
+                        .iter()
+                        .find(|candidate| **candidate == language)
+                        .copied()
+                        .unwrap_or(""),
+                    String::new(),
+                ));
+            }
+        } else if let Some((_, block)) = current.as_mut() {
+            block.push_str(line);
+            block.push('\n');
+        }
+    }
+    blocks.retain(|(language, _)| !language.is_empty());
+    blocks
+}
+
+/// Name of an imported command, `prefix` followed by the first words of its description, e.g. `tar_create_an_archive`
+///
+/// Commands starting with the same words get a number appended to the name, so it isn't used by any of `taken`
+pub fn imported_command_name(prefix: &str, description: &str, taken: &[HoardCmd]) -> String {
+    let mut name = vec![prefix.to_lowercase()];
+    name.extend(words(description).into_iter().take(3));
+    let base_name = name.join("_");
+    let mut name = base_name.clone();
+    let mut count = 1;
+    while taken.iter().any(|command| command.name == name) {
+        count += 1;
+        name = format!("{base_name}_{count}");
+    }
+    name
+}
+
+/// Lowercase words of `text`, anything but letters and digits separates them
+pub fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Download a text file like a trove from `url`, with its content type if the server sent one
+///
+/// The download is stopped once it gets bigger than `MAX_IMPORT_SIZE`
+pub fn fetch_text(url: Url) -> Result<(String, Option<String>), HoardErr> {
+    fetch_text_within(url, None)
+}
+
+/// Download like `fetch_text`, giving up once the server took longer than `timeout`
+pub fn fetch_text_within(
+    url: Url,
+    timeout: Option<Duration>,
+) -> Result<(String, Option<String>), HoardErr> {
+    // hoard runs in an async runtime, which the blocking client must not be used in
+    std::thread::spawn(move || {
+        let mut client = reqwest::blocking::Client::builder();
+        if let Some(timeout) = timeout {
+            client = client.connect_timeout(timeout).timeout(timeout);
+        }
+        let response = client
+            .build()
+            .and_then(|client| client.get(url).send())
+            .and_then(reqwest::blocking::Response::error_for_status)
+            .map_err(|e| HoardErr::new(&format!("Could not import from url: {e}")))?;
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(ToString::to_string);
+        let content = read_limited(response)?;
+        Ok((content, content_type))
+    })
+    .join()
+    .unwrap_or_else(|_| Err(HoardErr::new("Could not import from url")))
+}
+
+/// Read a trove file, refusing files bigger than `MAX_IMPORT_SIZE`
+pub fn read_trove_file(path: &Path) -> Result<String, HoardErr> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| HoardErr::new(&format!("Could not read {}: {e}", path.display())))?;
+    read_limited(file)
+}
+
+fn read_limited(reader: impl Read) -> Result<String, HoardErr> {
+    let mut content = String::new();
+    reader
+        .take(MAX_IMPORT_SIZE + 1)
+        .read_to_string(&mut content)
+        .map_err(|e| HoardErr::new(&format!("Could not read the trove: {e}")))?;
+    if content.len() as u64 > MAX_IMPORT_SIZE {
+        return Err(HoardErr::new(&format!(
+            "The trove is bigger than {} KiB, not importing it",
+            MAX_IMPORT_SIZE / 1024
+        )));
+    }
+    Ok(content)
+}
+
+#[cfg(test)]
+mod test_import {
+    use super::*;
+
+    const TROVE: &str = "version: 1.0.0
+commands:
+- name: deploy
+  namespace: team
+  tags: []
+  command: kubectl apply -f deploy.yml
+  description: Deploy the app
+";
+
+    #[test]
+    fn detect_format_by_type_extension_and_content() {
+        assert_eq!(
+            ImportFormat::Json,
+            detect_format(Some("application/json; charset=utf-8"), "t.yml", "")
+        );
+        assert_eq!(
+            ImportFormat::Markdown,
+            detect_format(Some("text/plain"), "https://x.org/README.md?raw=1", "")
+        );
+        assert_eq!(ImportFormat::Json, detect_format(None, "trove", " {}"));
+        assert_eq!(ImportFormat::Yaml, detect_format(None, "trove", TROVE));
+        assert_eq!(ImportFormat::Navi, detect_format(None, "git.cheat", ""));
+    }
+
+    #[test]
+    fn parse_trove_from_markdown_code_blocks() {
+        let markdown = format!(
+            "# Team commands\n\nInstall hoard first:\n```sh\ncargo install hoard-rs\n```\n```yaml\n{TROVE}```\n"
+        );
+        let config = HoardConfig::default();
+        let trove = parse_trove(&markdown, ImportFormat::Markdown, &config).unwrap();
+        assert_eq!("team", trove.get_command("deploy").unwrap().namespace);
+        assert!(parse_trove("# Nothing here", ImportFormat::Markdown, &config).is_err());
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let config = HoardConfig::default();
+        let trove = parse_trove(TROVE, ImportFormat::Yaml, &config).unwrap();
+        let json = trove.to_json();
+        assert_eq!(ImportFormat::Json, detect_format(None, "trove", &json));
+        let imported = parse_trove(&json, ImportFormat::Json, &config).unwrap();
+        assert_eq!(trove.commands, imported.commands);
+        assert_eq!(trove.version, imported.version);
+    }
+
+    #[test]
+    fn refuse_too_big_troves() {
+        let content = "#".repeat(usize::try_from(MAX_IMPORT_SIZE).unwrap() + 1);
+        assert!(read_limited(content.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,289 @@
+use crate::core::cache::CACHE_DIR;
+use crate::core::error::HoardErr;
+use crate::core::import::{fetch_text_within, read_trove_file};
+use crate::core::trove::Trove;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use url::Url;
+
+/// How deep included troves can include others, so a chain of URLs including each other ends
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// How long a downloaded trove is used before it is downloaded again
+const INCLUDE_CACHE_TTL: Duration = Duration::from_hours(1);
+
+/// How long hoard waits for the server of an included trove before it starts without it, or with its old copy
+const FETCH_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// A trove pulled in by an `include:` entry, named after its file
+pub struct IncludedTrove {
+    pub name: String,
+    pub trove: Trove,
+}
+
+/// Where an included trove is read from
+#[derive(Clone)]
+enum Source {
+    File(PathBuf),
+    Url(Url),
+}
+
+impl Source {
+    /// Resolve `entry`, relative paths and URLs are relative to `parent`, the file that includes it
+    ///
+    /// A trove at a URL can only include other URLs, it must not pull files of this machine into the trove
+    fn resolve(entry: &str, parent: &Self) -> Result<Self, HoardErr> {
+        if entry.starts_with("http://") || entry.starts_with("https://") {
+            return Url::parse(entry)
+                .map(Self::Url)
+                .map_err(|e| HoardErr::new(&format!("{entry} is no valid URL: {e}")));
+        }
+        let parent = match parent {
+            Self::File(parent) => parent,
+            Self::Url(parent) => {
+                return parent
+                    .join(entry)
+                    .ok()
+                    .filter(|url| {
+                        ["http", "https"].contains(&url.scheme())
+                            && !entry.starts_with('~')
+                            && !Path::new(entry).is_absolute()
+                    })
+                    .map(Self::Url)
+                    .ok_or_else(|| {
+                        HoardErr::new(&format!(
+                            "{entry} is no URL, a trove at {parent} can only include URLs relative to it"
+                        ))
+                    })
+            }
+        };
+        let path = match entry.strip_prefix("~/").zip(dirs::home_dir()) {
+            Some((rest, home)) => home.join(rest),
+            None => PathBuf::from(entry),
+        };
+        if path.is_absolute() {
+            Ok(Self::File(path))
+        } else {
+            Ok(Self::File(
+                parent.parent().unwrap_or_else(|| Path::new("")).join(path),
+            ))
+        }
+    }
+
+    /// Identifies the source, so a trove included twice is only loaded once
+    fn key(&self) -> String {
+        match self {
+            Self::File(path) => path
+                .canonicalize()
+                .unwrap_or_else(|_| path.clone())
+                .display()
+                .to_string(),
+            Self::Url(url) => url.to_string(),
+        }
+    }
+
+    fn name(&self) -> String {
+        let file_name = match self {
+            Self::File(path) => path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string()),
+            Self::Url(url) => url
+                .path_segments()
+                .and_then(|mut segments| segments.next_back())
+                .map(ToString::to_string),
+        };
+        let file_name = file_name.unwrap_or_default();
+        Path::new(&file_name).file_stem().map_or_else(
+            || file_name.clone(),
+            |stem| stem.to_string_lossy().to_string(),
+        )
+    }
+
+    /// Read the trove, a trove at a URL from its copy in `hoard_home` while it is younger than `INCLUDE_CACHE_TTL`
+    fn load(&self, hoard_home: Option<&Path>) -> Result<Trove, HoardErr> {
+        let content = match self {
+            Self::File(path) => read_trove_file(path)?,
+            Self::Url(url) => fetch_cached(url, hoard_home)?,
+        };
+        serde_yaml::from_str(&content).map_err(|e| HoardErr::new(&format!("No valid trove: {e}")))
+    }
+}
+
+/// Download the trove at `url`, unless its copy is fresh. An outdated copy is used if the download fails
+fn fetch_cached(url: &Url, hoard_home: Option<&Path>) -> Result<String, HoardErr> {
+    let Some(file) = hoard_home.map(|home| cache_file(home, url)) else {
+        return Ok(fetch_text_within(url.clone(), Some(FETCH_TIMEOUT))?.0);
+    };
+    let age = fs::metadata(&file)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+    if age.is_some_and(|age| age < INCLUDE_CACHE_TTL) {
+        if let Ok(content) = read_trove_file(&file) {
+            return Ok(content);
+        }
+    }
+    match fetch_text_within(url.clone(), Some(FETCH_TIMEOUT)) {
+        Ok((content, _)) => {
+            if let Some(dir) = file.parent() {
+                let _ = fs::create_dir_all(dir).and_then(|()| fs::write(&file, &content));
+            }
+            Ok(content)
+        }
+        Err(e) => read_trove_file(&file).map_err(|_| e),
+    }
+}
+
+/// Downloaded troves are named after their URL
+fn cache_file(hoard_home: &Path, url: &Url) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    url.as_str().hash(&mut hasher);
+    hoard_home
+        .join(CACHE_DIR)
+        .join("includes")
+        .join(format!("{:016x}.yml", hasher.finish()))
+}
+
+/// Load the troves `includes` points at, and the ones they include in turn
+///
+/// `parent` is the file with the `include:` list. Troves that can't be loaded are reported and skipped, downloaded
+/// ones are kept in `hoard_home`
+pub fn load_includes(
+    includes: &[String],
+    parent: &Path,
+    hoard_home: Option<&Path>,
+) -> Vec<IncludedTrove> {
+    let mut troves = Vec::new();
+    let mut seen = HashSet::from([Source::File(parent.to_path_buf()).key()]);
+    collect(
+        includes,
+        &Source::File(parent.to_path_buf()),
+        0,
+        hoard_home,
+        &mut seen,
+        &mut troves,
+    );
+    troves
+}
+
+fn collect(
+    includes: &[String],
+    parent: &Source,
+    depth: usize,
+    hoard_home: Option<&Path>,
+    seen: &mut HashSet<String>,
+    troves: &mut Vec<IncludedTrove>,
+) {
+    if depth >= MAX_INCLUDE_DEPTH {
+        eprintln!("Troves are included more than {MAX_INCLUDE_DEPTH} levels deep, not following them further");
+        return;
+    }
+    for entry in includes {
+        let source = match Source::resolve(entry, parent) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("Could not include {entry}: {e}");
+                continue;
+            }
+        };
+        if !seen.insert(source.key()) {
+            continue;
+        }
+        match source.load(hoard_home) {
+            Ok(trove) => {
+                let nested = trove.include.clone();
+                troves.push(IncludedTrove {
+                    name: source.name(),
+                    trove,
+                });
+                collect(&nested, &source, depth + 1, hoard_home, seen, troves);
+            }
+            Err(e) => eprintln!("Could not include {entry}: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_include {
+    use super::*;
+    use crate::core::HoardCmd;
+    use tempfile::tempdir;
+
+    fn trove_with(name: &str, include: &[&str]) -> Trove {
+        Trove {
+            include: include.iter().map(ToString::to_string).collect(),
+            ..Trove::from_commands(&[HoardCmd::default()
+                .with_name(name)
+                .with_namespace("default")])
+        }
+    }
+
+    #[test]
+    fn follow_nested_includes_once() {
+        let home = tempdir().unwrap();
+        let trove_file = home.path().join("trove.yml");
+        Trove::default().save_trove_file(&trove_file);
+        std::fs::create_dir(home.path().join("shared")).unwrap();
+        trove_with("pods", &["git.yml", "../trove.yml"])
+            .save_trove_file(&home.path().join("shared/k8s.yml"));
+        trove_with("status", &["k8s.yml"]).save_trove_file(&home.path().join("shared/git.yml"));
+
+        let included = load_includes(
+            &[
+                String::from("shared/k8s.yml"),
+                String::from("missing.yml"),
+                String::from("shared/git.yml"),
+            ],
+            &trove_file,
+            None,
+        );
+        let names: Vec<&str> = included.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(vec!["k8s", "git"], names);
+        assert_eq!("status", included[1].trove.commands[0].name);
+    }
+
+    #[test]
+    fn use_the_copy_of_a_downloaded_trove() {
+        let home = tempdir().unwrap();
+        // Nothing listens on the port, the download fails right away
+        let url = Url::parse("http://127.0.0.1:9/k8s.yml").unwrap();
+        let file = cache_file(home.path(), &url);
+        assert!(fetch_cached(&url, Some(home.path())).is_err());
+
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        trove_with("pods", &[]).save_trove_file(&file);
+        let included = load_includes(
+            &[url.to_string()],
+            &home.path().join("trove.yml"),
+            Some(home.path()),
+        );
+        assert_eq!("pods", included[0].trove.commands[0].name);
+
+        // An outdated copy is better than none while the server is down
+        let outdated = SystemTime::now() - INCLUDE_CACHE_TTL * 2;
+        fs::File::options()
+            .write(true)
+            .open(&file)
+            .and_then(|file| file.set_modified(outdated))
+            .unwrap();
+        assert!(fetch_cached(&url, Some(home.path())).is_ok());
+    }
+
+    #[test]
+    fn resolve_relative_to_the_including_file() {
+        let parent = Source::Url(Url::parse("https://example.com/troves/main.yml").unwrap());
+        let Source::Url(url) = Source::resolve("k8s.yml", &parent).unwrap() else {
+            panic!("a trove included by a URL is a URL");
+        };
+        assert_eq!("https://example.com/troves/k8s.yml", url.as_str());
+        assert_eq!("k8s", Source::Url(url).name());
+        // A remote trove can't include local files
+        for entry in ["/etc/hosts", "~/.ssh/config", "file:///etc/hosts"] {
+            assert!(Source::resolve(entry, &parent).is_err());
+        }
+    }
+}
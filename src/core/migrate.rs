@@ -0,0 +1,344 @@
+use crate::config::HoardConfig;
+use crate::core::error::HoardErr;
+use crate::core::trove::Trove;
+use crate::core::HoardCmd;
+use serde_yaml::{Mapping, Value};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Fields of a command hoard still reads, anything else in a legacy trove is reported as left out
+const COMMAND_FIELDS: &[&str] = &[
+    "name",
+    "command",
+    "description",
+    "tags",
+    "created",
+    "modified",
+    "last_used",
+    "usage_count",
+    "is_favorite",
+    "is_hidden",
+    "is_deleted",
+    "namespace",
+    "shell",
+    "container",
+    "timeout",
+    "encoding",
+    "related",
+    "context",
+];
+
+/// Timestamps of a command, written in other formats by some releases
+const TIME_FIELDS: &[&str] = &["created", "modified", "last_used"];
+
+/// Settings of a legacy config that point into its own hoard home, so they aren't taken over
+const LOCAL_SETTINGS: &[&str] = &[
+    "version",
+    "config_home_path",
+    "trove_path",
+    "managed_trove_path",
+];
+
+/// Trove translated from the format of an older hoard release
+pub struct TroveMigration {
+    pub trove: Trove,
+    /// Release of hoard the trove was written by, if it says
+    pub version: Option<String>,
+    /// Everything that couldn't be translated as it was
+    pub notes: Vec<String>,
+}
+
+/// Settings of a legacy config that can be taken over
+pub struct ConfigMigration {
+    pub config: HoardConfig,
+    /// Names of the settings that were taken over
+    pub settings: Vec<String>,
+    /// Trove the legacy config used
+    pub trove_path: Option<PathBuf>,
+    pub notes: Vec<String>,
+}
+
+/// Whether `content` is a config rather than a trove
+pub fn is_legacy_config(content: &str) -> bool {
+    serde_yaml::from_str::<Value>(content).is_ok_and(|value| {
+        value.get("commands").is_none()
+            && ["default_namespace", "trove_path", "query_prefix"]
+                .iter()
+                .any(|key| value.get(key).is_some())
+    })
+}
+
+/// Translate a trove written by an older hoard release
+///
+/// Missing descriptions and tags, tags written as one comma separated string and namespaces that weren't stored
+/// are filled in. Commands without a name or command string and fields hoard doesn't know are left out and reported
+pub fn migrate_trove(content: &str, default_namespace: &str) -> Result<TroveMigration, HoardErr> {
+    let value: Value = serde_yaml::from_str(content)
+        .map_err(|e| HoardErr::new(&format!("The legacy trove is no valid YAML: {e}")))?;
+    let Some(commands) = value.get("commands").and_then(Value::as_sequence) else {
+        return Err(HoardErr::new("The legacy trove has no list of commands"));
+    };
+    let version = value.get("version").and_then(scalar_text);
+
+    let mut notes = Vec::new();
+    let trove_fields = value.as_mapping().into_iter().flat_map(Mapping::iter);
+    for key in trove_fields.filter_map(|(key, _)| key.as_str()) {
+        if !["version", "commands", "namespaces", "trash"].contains(&key) {
+            notes.push(format!(
+                "The trove field {key} isn't used anymore, left out"
+            ));
+        }
+    }
+    let mut migrated: Vec<HoardCmd> = Vec::new();
+    for (index, command) in commands.iter().enumerate() {
+        let Some(command) = migrate_command(command, index, default_namespace, &mut notes) else {
+            continue;
+        };
+        if migrated
+            .iter()
+            .any(|c| c.name == command.name && c.namespace == command.namespace)
+        {
+            notes.push(format!(
+                "{} is in the {} namespace twice, only the first one was kept",
+                command.name, command.namespace
+            ));
+            continue;
+        }
+        migrated.push(command);
+    }
+    if value.get("trash").is_some() {
+        notes.push(String::from("The trash was left out"));
+    }
+    Ok(TroveMigration {
+        trove: Trove::from_commands(&migrated),
+        version,
+        notes,
+    })
+}
+
+fn migrate_command(
+    value: &Value,
+    index: usize,
+    default_namespace: &str,
+    notes: &mut Vec<String>,
+) -> Option<HoardCmd> {
+    let Some(fields) = value.as_mapping() else {
+        notes.push(format!(
+            "Command {} is no list of fields, left out",
+            index + 1
+        ));
+        return None;
+    };
+    let text = |key: &str| value.get(key).and_then(scalar_text);
+    let (Some(name), Some(command)) = (text("name"), text("command")) else {
+        notes.push(format!(
+            "Command {} has no name or command string, left out",
+            index + 1
+        ));
+        return None;
+    };
+
+    let mut migrated = Mapping::new();
+    for (key, field) in fields {
+        let Some(key) = key.as_str() else {
+            continue;
+        };
+        if !COMMAND_FIELDS.contains(&key) {
+            notes.push(format!(
+                "{name}: the field {key} isn't used anymore, left out"
+            ));
+            continue;
+        }
+        if TIME_FIELDS.contains(&key)
+            && serde_yaml::from_value::<SystemTime>(field.clone()).is_err()
+        {
+            notes.push(format!(
+                "{name}: the {key} time can't be read, it is set to now"
+            ));
+            continue;
+        }
+        migrated.insert(Value::from(key), field.clone());
+    }
+
+    migrated.insert(Value::from("name"), Value::from(name.clone()));
+    migrated.insert(Value::from("command"), Value::from(command));
+    let description = text("description").unwrap_or_default();
+    migrated.insert(Value::from("description"), Value::from(description));
+    let tags: Vec<Value> = match value.get("tags") {
+        Some(Value::Sequence(tags)) => tags
+            .iter()
+            .filter_map(scalar_text)
+            .map(Value::from)
+            .collect(),
+        // Tags were written as one string like `git,work`
+        Some(Value::String(tags)) => tags
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(Value::from)
+            .collect(),
+        _ => Vec::new(),
+    };
+    migrated.insert(Value::from("tags"), Value::Sequence(tags));
+    let namespace = text("namespace").filter(|namespace| !namespace.is_empty());
+    if namespace.is_none() {
+        notes.push(format!(
+            "{name} had no namespace, it is in {default_namespace} now"
+        ));
+    }
+    migrated.insert(
+        Value::from("namespace"),
+        Value::from(namespace.unwrap_or_else(|| default_namespace.to_string())),
+    );
+
+    match serde_yaml::from_value::<HoardCmd>(Value::Mapping(migrated)) {
+        Ok(command) => Some(command),
+        Err(e) => {
+            notes.push(format!("{name} can't be translated, left out: {e}"));
+            None
+        }
+    }
+}
+
+/// Take the settings of a legacy config over into `current`
+///
+/// Paths pointing into the legacy hoard home aren't taken over, its trove path is returned to migrate the trove
+pub fn migrate_config(content: &str, current: &HoardConfig) -> Result<ConfigMigration, HoardErr> {
+    let invalid =
+        |e: &dyn std::fmt::Display| HoardErr::new(&format!("The legacy config can't be read: {e}"));
+    let legacy: Value = serde_yaml::from_str(content).map_err(|e| invalid(&e))?;
+    let Some(legacy_settings) = legacy.as_mapping() else {
+        return Err(HoardErr::new("The legacy config has no settings"));
+    };
+    let Ok(Value::Mapping(mut merged)) = serde_yaml::to_value(current) else {
+        return Err(HoardErr::new("The current config can't be read"));
+    };
+
+    let mut settings = Vec::new();
+    let mut notes = Vec::new();
+    for (key, value) in legacy_settings {
+        let Some(name) = key.as_str() else {
+            continue;
+        };
+        if LOCAL_SETTINGS.contains(&name) || value.is_null() {
+            continue;
+        }
+        if !merged.contains_key(key) {
+            notes.push(format!("The setting {name} isn't used anymore, left out"));
+            continue;
+        }
+        let mut candidate = merged.clone();
+        candidate.insert(key.clone(), value.clone());
+        if serde_yaml::from_value::<HoardConfig>(Value::Mapping(candidate.clone())).is_ok() {
+            merged = candidate;
+            settings.push(name.to_string());
+        } else {
+            notes.push(format!(
+                "The setting {name} has a value that can't be read, left out"
+            ));
+        }
+    }
+    let config = serde_yaml::from_value(Value::Mapping(merged)).map_err(|e| invalid(&e))?;
+    Ok(ConfigMigration {
+        config,
+        settings,
+        trove_path: legacy
+            .get("trove_path")
+            .and_then(Value::as_str)
+            .map(PathBuf::from),
+        notes,
+    })
+}
+
+/// A string, number or boolean as text
+fn scalar_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(flag) => Some(flag.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test_migrate {
+    use super::*;
+
+    const LEGACY_TROVE: &str = "---
+version: 0.4.0
+commands:
+  - name: status
+    namespace: git
+    tags: ~
+    command: git status
+    description: ~
+  - name: pods
+    tags: kube, work
+    command: kubectl get pods
+    description: List pods
+    created: 2021-05-01T10:00:00Z
+    alias: kgp
+  - name: status
+    namespace: git
+    command: git status -sb
+  - namespace: git
+    command: git log
+";
+
+    #[test]
+    fn translate_legacy_troves() {
+        let migration = migrate_trove(LEGACY_TROVE, "default").unwrap();
+        assert_eq!(Some(String::from("0.4.0")), migration.version);
+        let commands = &migration.trove.commands;
+        assert_eq!(2, commands.len());
+        assert_eq!("", commands[0].description);
+        assert!(commands[0].tags.is_empty());
+        assert_eq!("default", commands[1].namespace);
+        assert_eq!(vec!["kube", "work"], commands[1].tags);
+        assert_eq!(
+            vec![
+                "pods: the created time can't be read, it is set to now",
+                "pods: the field alias isn't used anymore, left out",
+                "pods had no namespace, it is in default now",
+                "status is in the git namespace twice, only the first one was kept",
+                "Command 4 has no name or command string, left out",
+            ],
+            migration.notes
+        );
+        assert!(migrate_trove("commands: 3", "default").is_err());
+        assert!(!is_legacy_config(LEGACY_TROVE));
+    }
+
+    #[test]
+    fn take_over_legacy_settings() {
+        let legacy = "---
+version: 1.0.1
+default_namespace: work
+trove_path: /home/me/.hoard/trove.yml
+query_prefix: \"  >\"
+primary_color: [1, 2, 3]
+parameter_token: 42
+sync_server_url: ~
+gui_theme: dark
+";
+        assert!(is_legacy_config(legacy));
+        let migration = migrate_config(legacy, &HoardConfig::default()).unwrap();
+        assert_eq!("work", migration.config.default_namespace);
+        assert_eq!(Some((1, 2, 3)), migration.config.primary_color);
+        assert_eq!(
+            Some(PathBuf::from("/home/me/.hoard/trove.yml")),
+            migration.trove_path
+        );
+        assert_eq!(
+            vec!["default_namespace", "query_prefix", "primary_color"],
+            migration.settings
+        );
+        assert_eq!(
+            vec![
+                "The setting parameter_token has a value that can't be read, left out",
+                "The setting gui_theme isn't used anymore, left out",
+            ],
+            migration.notes
+        );
+    }
+}
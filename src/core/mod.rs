@@ -1,11 +1,47 @@
+pub mod aliases;
+pub mod apply;
+pub mod backup;
+pub mod cache;
+pub mod check;
+pub mod context;
+pub mod diagnose;
+pub mod editor;
+pub mod encoding;
 pub mod error;
+pub mod export;
+pub mod fuzzy;
+pub mod git;
+pub mod grep;
+pub mod highlight;
+pub mod import;
+pub mod include;
+pub mod migrate;
+pub mod navi;
+pub mod output;
 pub mod parameters;
+pub mod privilege;
+pub mod process;
+pub mod providers;
+pub mod query;
+pub mod replace;
+pub mod scrub;
+pub mod shell;
+pub mod stats;
+pub mod tags;
+pub mod tips;
+pub mod tldr;
 pub mod trove;
+pub mod watch;
+pub mod workflows;
 
+use crate::config::HoardConfig;
+use crate::core::context::CommandContext;
+use crate::core::encoding::OutputEncoding;
 use crate::core::error::HoardErr;
+use crate::core::shell::expand_env_vars;
 use crate::core::trove::Trove;
 use crate::gui::merge::{with_conflict_resolve_prompt, ConflictResolve};
-use crate::gui::prompts::{prompt_input, prompt_input_validate, prompt_select_with_options};
+use crate::gui::prompts::{prompt_input, prompt_input_validate, prompt_select_with_default};
 use rand::distributions::Alphanumeric;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
@@ -31,6 +67,14 @@ fn default_time() -> time::SystemTime {
 /// - `is_deleted`: A flag to indicate if the command is deleted
 /// - `namespace`: The namespace the command belongs to
 /// - `namespace_id`: The id of the namespace the command belongs to
+/// - `shell`: The shell/interpreter the command should be executed with
+/// - `container`: The container image the command should be executed in
+/// - `timeout`: The number of seconds after which a running command is stopped
+/// - `encoding`: How the output of the command is decoded by `hoard run`
+/// - `related`: Names of commands suggested after the command was run
+/// - `context`: The directory, git remote and kube context the command was hoarded in
+/// - `is_managed`: A flag to indicate if the command comes from the read-only managed trove
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HoardCmd {
     /// The name of the command by which it is referenced
@@ -75,6 +119,39 @@ pub struct HoardCmd {
 
     /// The namespace the command belongs to
     pub namespace: String,
+
+    /// The shell/interpreter the command should be executed with, e.g. `bash -c` or `python -c`
+    /// Falls back to the user's shell if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell: Option<String>,
+
+    /// The Docker/Podman image the command should be executed in, e.g. `alpine:latest`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+
+    /// The number of seconds after which `hoard run` stops the command
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+
+    /// How `hoard run` decodes the output of the command. Without one a single run writes straight to the terminal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<OutputEncoding>,
+
+    /// Names of commands `hoard run` suggests to run next, e.g. the logs command after a deploy command
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub related: Vec<String>,
+
+    /// Where the command was hoarded, if `capture_context` is enabled in the config
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<CommandContext>,
+
+    /// A flag to indicate if the command was loaded from the managed trove and can't be changed
+    #[serde(skip)]
+    pub is_managed: bool,
+
+    /// Name of the read-only overlay trove the command was loaded from, overlay commands are managed as well
+    #[serde(skip)]
+    pub overlay: Option<String>,
 }
 
 impl PartialEq for HoardCmd {
@@ -103,6 +180,14 @@ impl HoardCmd {
             is_hidden: false,
             is_deleted: false,
             namespace: String::new(),
+            shell: None,
+            container: None,
+            timeout: None,
+            encoding: None,
+            related: Vec::new(),
+            context: None,
+            is_managed: false,
+            overlay: None,
         }
     }
 
@@ -132,6 +217,90 @@ impl HoardCmd {
         }
     }
 
+    /// Set the shell the command should be executed with
+    /// An empty string resets it to the user's shell
+    pub fn with_shell(self, shell: &str) -> Self {
+        let shell = shell.trim();
+        Self {
+            shell: (!shell.is_empty()).then(|| shell.to_string()),
+            ..self
+        }
+    }
+
+    /// Shell invocation the command will be executed with
+    pub fn effective_shell(&self) -> String {
+        self.shell.clone().unwrap_or_else(shell::default_shell)
+    }
+
+    /// Programs of the command that are not installed
+    ///
+    /// Commands running in a container or with an interpreter other than a shell are not checked, programs are looked
+    /// up with `is_installed`, e.g. `shell::is_installed`
+    pub fn missing_binaries(
+        &self,
+        token: &str,
+        is_installed: impl FnMut(&str) -> bool,
+    ) -> Vec<String> {
+        let runs_in_shell = self.shell.as_deref().is_none_or(|shell| {
+            let (program, _) = shell::split_shell(shell);
+            let program = program.rsplit('/').next().unwrap_or_default();
+            ["sh", "bash", "zsh", "fish", "dash", "ksh"].contains(&program)
+        });
+        if self.container.is_some() || !runs_in_shell {
+            return Vec::new();
+        }
+        shell::missing_binaries(&self.command, token, is_installed)
+    }
+
+    /// Set the container image the command should be executed in
+    /// An empty string runs the command locally
+    pub fn with_container(self, image: &str) -> Self {
+        let image = image.trim();
+        Self {
+            container: (!image.is_empty()).then(|| image.to_string()),
+            ..self
+        }
+    }
+
+    /// Set the number of seconds after which a running command is stopped
+    pub fn with_timeout(self, timeout: Option<u64>) -> Self {
+        Self { timeout, ..self }
+    }
+
+    /// Set how `hoard run` decodes the output of the command
+    pub fn with_encoding(self, encoding: Option<OutputEncoding>) -> Self {
+        Self { encoding, ..self }
+    }
+
+    /// Set where the command was hoarded
+    pub fn with_context(self, context: Option<CommandContext>) -> Self {
+        Self { context, ..self }
+    }
+
+    /// Set the related commands from a comma separated list of command names
+    pub fn with_related(self, related: &str) -> Self {
+        Self {
+            related: related
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(ToString::to_string)
+                .collect(),
+            ..self
+        }
+    }
+
+    /// Resolve the environment variables in the command string if `expand_env_vars` is enabled in the config
+    pub fn with_env_vars_expanded(self, config: &HoardConfig) -> Self {
+        if config.expand_env_vars != Some(true) {
+            return self;
+        }
+        Self {
+            command: expand_env_vars(&self.command, |name| std::env::var(name).ok()),
+            ..self
+        }
+    }
+
     #[allow(dead_code)]
     /// set the tags of the command from a vector of strings
     pub fn with_tags(self, tags: Vec<String>) -> Self {
@@ -176,16 +345,6 @@ impl HoardCmd {
         Ok(())
     }
 
-    /// Check if the tags are valid for saving
-    /// A valid tag vector cant be empty
-    /// Returns a Result with the error if the tags are invalid
-    pub fn are_tags_valid(c: &str) -> Result<(), HoardErr> {
-        if c.is_empty() {
-            return Err(HoardErr::new("Tags can't be empty"));
-        }
-        Ok(())
-    }
-
     /// Return vector of tags as a string
     /// Tags are separated by a comma
     /// # Example  
@@ -328,19 +487,29 @@ impl HoardCmd {
         self.with_tags_raw(&tags)
     }
 
-    pub fn with_namespace_input(self, selection: &[&str]) -> Self {
+    /// Ask for the namespace of the command, `suggestion` is preselected or offered as the new namespace
+    pub fn with_namespace_input(self, selection: &[&str], suggestion: Option<&str>) -> Self {
         // Add "New namespace" option to selction
         let mut selection = selection.to_vec();
         selection.push("New namespace");
 
-        let selected: usize = prompt_select_with_options("Namespace of the command", &selection);
+        let default_index = suggestion
+            .and_then(|suggestion| {
+                selection
+                    .iter()
+                    .position(|namespace| *namespace == suggestion)
+            })
+            .or_else(|| suggestion.map(|_| selection.len() - 1))
+            .unwrap_or(0);
+        let selected: usize =
+            prompt_select_with_default("Namespace of the command", &selection, default_index);
 
         let mut selected_namespace: String = (*selection.get(selected).unwrap()).to_string();
         if selected_namespace == "New namespace" {
             selected_namespace = prompt_input(
                 "Namespace of the command",
                 false,
-                Some(String::from("default")),
+                Some(suggestion.unwrap_or("default").to_string()),
             );
         }
 
@@ -350,7 +519,7 @@ impl HoardCmd {
         }
     }
 
-    fn with_name_input_prompt(
+    pub fn with_name_input_prompt(
         self,
         default_value: Option<String>,
         trove: &Trove,
@@ -457,6 +626,10 @@ impl HoardCmd {
                     ),
                 )) , None)
             }
+            ConflictResolve::KeepBoth => {
+                let name = trove.free_name(&name, &namespace);
+                (Some(Self { name, ..self }), None)
+            }
         }
     }
 
@@ -589,4 +762,9 @@ mod test_commands {
         let expected: Vec<String> = Vec::new();
         assert_eq!(expected, command.tags);
     }
+    #[test]
+    fn parse_related_commands() {
+        let command = HoardCmd::default().with_related(" logs, ,status ");
+        assert_eq!(vec!["logs", "status"], command.related);
+    }
 }
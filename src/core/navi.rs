@@ -0,0 +1,184 @@
+use crate::core::import::imported_command_name;
+use crate::core::parameters::Parameterized;
+use crate::core::HoardCmd;
+use regex::{Captures, Regex};
+use std::collections::{HashMap, HashSet};
+
+/// Namespace of imported commands of cheats without tags
+pub const NAVI_NAMESPACE: &str = "navi";
+
+/// Placeholder of a navi variable, e.g. `<branch>`
+const NAVI_VARIABLE: &str = r"<([A-Za-z0-9_-]+)>";
+
+/// Separates the command of a navi variable from its options for fzf, e.g. `$ file: ls --- --multi`
+const NAVI_VARIABLE_OPTIONS: &str = " --- ";
+
+/// A cheat of a navi cheatsheet, the commands below a `% tags` line
+#[derive(Default)]
+struct Cheat {
+    tags: Vec<String>,
+    /// Description and lines of every command
+    commands: Vec<(String, Vec<String>)>,
+    /// Shell commands offering the values of variables, by variable name
+    variables: HashMap<String, String>,
+}
+
+/// Turn the commands of a navi `.cheat` file into hoard commands
+///
+/// The first tag of a cheat is the namespace of its commands and all of its tags become tags. `<variables>` become
+/// named parameters, with the output lines of the `$ variable: command` of the cheat offered as values
+pub fn cheat_commands(
+    content: &str,
+    start_token: &str,
+    end_token: &str,
+    escape: char,
+) -> Vec<HoardCmd> {
+    let mut commands: Vec<HoardCmd> = Vec::new();
+    for cheat in parse_cheats(content) {
+        let namespace = cheat
+            .tags
+            .first()
+            .map_or(NAVI_NAMESPACE, String::as_str)
+            .to_string();
+        for (description, lines) in &cheat.commands {
+            let command = parameterize(
+                &lines.join("\n"),
+                &cheat.variables,
+                start_token,
+                end_token,
+                escape,
+            );
+            let description = if description.is_empty() {
+                lines[0].clone()
+            } else {
+                description.clone()
+            };
+            let name = imported_command_name(&namespace, &description, &commands);
+            commands.push(
+                HoardCmd::default()
+                    .with_name(&name)
+                    .with_command(&command)
+                    .with_description(&description)
+                    .with_namespace(&namespace)
+                    .with_tags_raw(&cheat.tags.join(",")),
+            );
+        }
+    }
+    commands
+}
+
+fn parse_cheats(content: &str) -> Vec<Cheat> {
+    let mut cheats = vec![Cheat::default()];
+    let mut description = String::new();
+    let mut in_command = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        let cheat = cheats.last_mut().expect("there is always a cheat");
+        if trimmed.is_empty() {
+            in_command = false;
+        } else if let Some(tags) = trimmed.strip_prefix('%') {
+            cheats.push(Cheat {
+                tags: tags
+                    .split(',')
+                    .map(|tag| tag.trim().replace(char::is_whitespace, "_"))
+                    .filter(|tag| !tag.is_empty())
+                    .collect(),
+                ..Cheat::default()
+            });
+            in_command = false;
+        } else if let Some(text) = trimmed.strip_prefix('#') {
+            description = text.trim().to_string();
+            in_command = false;
+        } else if let Some(variable) = trimmed.strip_prefix('$') {
+            if let Some((name, command)) = variable.split_once(':') {
+                let command = command
+                    .split(NAVI_VARIABLE_OPTIONS)
+                    .next()
+                    .unwrap_or_default()
+                    .trim();
+                cheat
+                    .variables
+                    .insert(name.trim().to_string(), command.to_string());
+            }
+            in_command = false;
+        } else if trimmed.starts_with(';') || trimmed.starts_with('@') {
+            // Comments and references to other cheats
+            in_command = false;
+        } else if in_command {
+            if let Some((_, lines)) = cheat.commands.last_mut() {
+                lines.push(line.to_string());
+            }
+        } else {
+            cheat
+                .commands
+                .push((std::mem::take(&mut description), vec![line.to_string()]));
+            in_command = true;
+        }
+    }
+    cheats.retain(|cheat| !cheat.commands.is_empty());
+    cheats
+}
+
+/// Replace the `<variables>` of a navi command by named parameters, the first one offering the values of its variable
+fn parameterize(
+    command: &str,
+    variables: &HashMap<String, String>,
+    start_token: &str,
+    end_token: &str,
+    escape: char,
+) -> String {
+    let command = HoardCmd::escape_input(command, start_token, end_token, escape);
+    let variable = Regex::new(NAVI_VARIABLE).expect("valid regex");
+    let mut seen = HashSet::new();
+    variable
+        .replace_all(&command, |caps: &Captures| {
+            let name = &caps[1];
+            let spec = match variables.get(name) {
+                Some(provider) if seen.insert(name.to_string()) => format!(":$({provider})"),
+                _ => String::new(),
+            };
+            format!("{start_token}{name}{spec}{end_token}")
+        })
+        .to_string()
+}
+
+#[cfg(test)]
+mod test_navi {
+    use super::*;
+
+    const CHEAT: &str = "% git, code
+
+# Change branch
+git checkout <branch>
+
+# Show the log of a branch
+git log <branch> \\
+  --oneline -n 10 --grep '#fix'
+
+$ branch: git branch | awk '{print $NF}' --- --column 1
+
+; no tags for these
+%
+echo <name> > out.txt
+";
+
+    #[test]
+    fn cheats_become_commands() {
+        let commands = cheat_commands(CHEAT, "#", "!", '\\');
+        assert_eq!(3, commands.len());
+        assert_eq!("git_change_branch", commands[0].name);
+        assert_eq!("git", commands[0].namespace);
+        assert_eq!(vec!["git", "code"], commands[0].tags);
+        assert_eq!(
+            "git checkout #branch:$(git branch | awk '{print $NF}')!",
+            commands[0].command
+        );
+        assert_eq!(
+            "git log #branch:$(git branch | awk '{print $NF}')! \\\\\n  --oneline -n 10 --grep '\\#fix'",
+            commands[1].command
+        );
+        assert_eq!("navi", commands[2].namespace);
+        assert_eq!("echo #name! > out.txt", commands[2].command);
+        assert!(commands.iter().all(HoardCmd::is_valid));
+    }
+}
@@ -0,0 +1,173 @@
+use crate::core::parameters::{map_parameters, Parameter};
+use crate::core::providers::ProviderRef;
+use crate::core::HoardCmd;
+use serde::Serialize;
+
+/// A command as `--output json` prints it, for tools that wrap hoard like fzf scripts, editors or launchers
+#[derive(Debug, Serialize)]
+pub struct CommandOutput {
+    pub name: String,
+    pub namespace: String,
+    pub command: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub parameters: Vec<ParameterOutput>,
+    /// The command with its parameters filled in, printed by `hoard pick`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved: Option<String>,
+    /// Fields a pattern of `hoard grep` matched
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub matched_fields: Vec<&'static str>,
+}
+
+/// A parameter of a command in the order it appears in, a named parameter used more than once is listed once
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct ParameterOutput {
+    /// Empty for unnamed parameters
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub description: Option<String>,
+    pub pattern: Option<String>,
+    pub provider: Option<ProviderRef>,
+    pub filters: Vec<&'static str>,
+}
+
+impl CommandOutput {
+    pub fn new(command: &HoardCmd, start_token: &str, end_token: &str, escape: char) -> Self {
+        let mut parameters: Vec<Parameter> = Vec::new();
+        map_parameters(
+            &command.command,
+            start_token,
+            end_token,
+            escape,
+            |parameter, _| {
+                let is_listed = !parameter.name.is_empty()
+                    && parameters
+                        .iter()
+                        .any(|listed| listed.name == parameter.name);
+                if !is_listed {
+                    parameters.push(parameter.clone());
+                }
+                String::new()
+            },
+        );
+        Self {
+            name: command.name.clone(),
+            namespace: command.namespace.clone(),
+            command: command.command.clone(),
+            description: command.description.clone(),
+            tags: command.tags.clone(),
+            parameters: parameters
+                .into_iter()
+                .map(|parameter| ParameterOutput {
+                    kind: parameter.kind.to_string(),
+                    filters: parameter
+                        .filters
+                        .iter()
+                        .map(|filter| filter.name())
+                        .collect(),
+                    name: parameter.name,
+                    description: parameter.description,
+                    pattern: parameter.pattern,
+                    provider: parameter.provider,
+                })
+                .collect(),
+            resolved: None,
+            matched_fields: Vec::new(),
+        }
+    }
+
+    pub fn with_resolved(self, resolved: &str) -> Self {
+        Self {
+            resolved: Some(resolved.to_string()),
+            ..self
+        }
+    }
+
+    pub fn with_matched_fields(self, matched_fields: Vec<&'static str>) -> Self {
+        Self {
+            matched_fields,
+            ..self
+        }
+    }
+}
+
+impl ParameterOutput {
+    /// One line about the parameter for `hoard show`, e.g. `pod [text, values from pods, last used api]: The pod`
+    pub fn summary(&self, last_used: Option<&str>) -> String {
+        let mut details = vec![self.kind.clone()];
+        details.extend(
+            self.pattern
+                .as_ref()
+                .map(|pattern| format!("matching {pattern}")),
+        );
+        details.extend(self.provider.as_ref().map(|provider| {
+            provider.argument.as_ref().map_or_else(
+                || format!("values from {}", provider.name),
+                |argument| format!("values from {} {argument}", provider.name),
+            )
+        }));
+        if !self.filters.is_empty() {
+            details.push(format!("filtered by {}", self.filters.join(", ")));
+        }
+        details.extend(last_used.map(|value| format!("last used {value}")));
+        let name = if self.name.is_empty() {
+            "(unnamed)"
+        } else {
+            &self.name
+        };
+        let description = self
+            .description
+            .as_ref()
+            .map(|description| format!(": {description}"))
+            .unwrap_or_default();
+        format!("{name} [{}]{description}", details.join(", "))
+    }
+}
+
+/// `value` as pretty printed JSON
+pub fn to_json<T: Serialize + ?Sized>(value: &T) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test_output {
+    use super::*;
+
+    #[test]
+    fn list_the_parameters() {
+        let command = HoardCmd::default()
+            .with_name("logs")
+            .with_namespace("k8s")
+            .with_command(
+                "kubectl logs #pod@pods! -n #ns:[a-z-]+! --tail #lines:int! #ns! #|trim! \\#x",
+            )
+            .with_tags_raw("kube");
+        let output = CommandOutput::new(&command, "#", "!", '\\');
+        let names: Vec<&str> = output.parameters.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(vec!["pod", "ns", "lines", ""], names);
+        assert_eq!(Some(String::from("[a-z-]+")), output.parameters[1].pattern);
+        assert_eq!("int", output.parameters[2].kind);
+        assert_eq!(vec!["trim"], output.parameters[3].filters);
+        assert_eq!(
+            "pod [text, values from pods, last used api]",
+            output.parameters[0].summary(Some("api"))
+        );
+        assert_eq!(
+            "ns [text, matching [a-z-]+]",
+            output.parameters[1].summary(None)
+        );
+        assert_eq!(
+            "(unnamed) [text, filtered by trim]",
+            output.parameters[3].summary(None)
+        );
+
+        let json = to_json(&output.with_resolved("kubectl logs api"));
+        assert!(json.contains(
+            "\"provider\": {\n        \"name\": \"pods\",\n        \"argument\": null\n      }"
+        ));
+        assert!(json.contains("\"resolved\": \"kubectl logs api\""));
+        assert!(!json.contains("matched_fields"));
+    }
+}
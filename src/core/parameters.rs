@@ -1,9 +1,636 @@
+use crate::core::error::HoardErr;
+use crate::core::providers::{ProviderRef, ProviderRegistry};
 use crate::core::HoardCmd;
-use crate::gui::prompts::prompt_input;
+use crate::gui::prompts::{prompt_input_validate, prompt_password, prompt_select_with_options};
+use crate::util::ordinal;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use url::Url;
+
+/// Separates the name of a named parameter from its type, e.g. `#count:int!`
+const PARAMETER_SPEC_SEPARATOR: char = ':';
+
+/// Wrap a shell command in a parameter spec to offer its output lines as values, e.g. `#branch:$(git branch)!`
+const PROVIDER_PREFIX: &str = "$(";
+const PROVIDER_SUFFIX: &str = ")";
+
+/// Separates the name of a parameter from the provider of its values, e.g. `#host@consul!`
+const PROVIDER_SEPARATOR: char = '@';
+
+/// Separates filters from the rest of a parameter, e.g. `#query|urlencode!`
+const PARAMETER_FILTER_SEPARATOR: char = '|';
+
+/// Transformation of the value of a parameter before it is put into the command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterFilter {
+    Upper,
+    Lower,
+    Trim,
+    /// Percent-encode everything but unreserved characters, for values in URLs
+    UrlEncode,
+    /// Quote the value so the shell passes it on as a single argument
+    ShellQuote,
+}
+
+impl ParameterFilter {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "upper" => Some(Self::Upper),
+            "lower" => Some(Self::Lower),
+            "trim" => Some(Self::Trim),
+            "urlencode" => Some(Self::UrlEncode),
+            "shellquote" => Some(Self::ShellQuote),
+            _ => None,
+        }
+    }
+
+    /// The name the filter is written with after a `|`
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Upper => "upper",
+            Self::Lower => "lower",
+            Self::Trim => "trim",
+            Self::UrlEncode => "urlencode",
+            Self::ShellQuote => "shellquote",
+        }
+    }
+
+    pub fn apply(self, value: &str) -> String {
+        match self {
+            Self::Upper => value.to_uppercase(),
+            Self::Lower => value.to_lowercase(),
+            Self::Trim => value.trim().to_string(),
+            Self::UrlEncode => value
+                .bytes()
+                .map(|byte| {
+                    if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+                        char::from(byte).to_string()
+                    } else {
+                        format!("%{byte:02X}")
+                    }
+                })
+                .collect(),
+            Self::ShellQuote => format!("'{}'", value.replace('\'', r"'\''")),
+        }
+    }
+}
+
+/// Type a named parameter can declare to have its input validated
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterType {
+    /// Any input is accepted
+    #[default]
+    Text,
+    Int,
+    Float,
+    Bool,
+    /// An existing file
+    File,
+    /// An existing directory
+    Dir,
+    Url,
+    /// Comma separated values, `hoard run` runs the command once for every value
+    List,
+    /// Any input, which is masked while typing and in previews of the command
+    Secret,
+    /// Any input spanning several lines, e.g. the body of a heredoc
+    Multiline,
+}
+
+impl ParameterType {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.trim() {
+            "text" | "string" => Some(Self::Text),
+            "int" => Some(Self::Int),
+            "float" => Some(Self::Float),
+            "bool" => Some(Self::Bool),
+            "file" => Some(Self::File),
+            "dir" => Some(Self::Dir),
+            "url" => Some(Self::Url),
+            "list" => Some(Self::List),
+            "secret" => Some(Self::Secret),
+            "multiline" => Some(Self::Multiline),
+            _ => None,
+        }
+    }
+
+    /// Check if `input` is a valid value for this type
+    /// Returns a Result with an error message to show to the user if the input is invalid
+    pub fn validate(self, input: &str) -> Result<(), HoardErr> {
+        let input = input.trim();
+        let is_valid = match self {
+            Self::Text | Self::Secret | Self::Multiline => true,
+            Self::Int => input.parse::<i64>().is_ok(),
+            Self::Float => input.parse::<f64>().is_ok(),
+            Self::Bool => matches!(input, "true" | "false"),
+            Self::File => Path::new(input).is_file(),
+            Self::Dir => Path::new(input).is_dir(),
+            Self::Url => Url::parse(input).is_ok(),
+            Self::List => !split_list_value(input).is_empty(),
+        };
+        if is_valid {
+            return Ok(());
+        }
+        let msg = match self {
+            Self::File => format!("'{input}' is not an existing file"),
+            Self::Dir => format!("'{input}' is not an existing directory"),
+            Self::List => format!("'{input}' is not a comma separated list of values"),
+            _ => format!("'{input}' is not a valid {self}"),
+        };
+        Err(HoardErr::new(&msg))
+    }
+}
+
+impl fmt::Display for ParameterType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Text => write!(f, "text"),
+            Self::Int => write!(f, "int"),
+            Self::Float => write!(f, "float"),
+            Self::Bool => write!(f, "bool"),
+            Self::File => write!(f, "file"),
+            Self::Dir => write!(f, "dir"),
+            Self::Url => write!(f, "url"),
+            Self::List => write!(f, "list"),
+            Self::Secret => write!(f, "secret"),
+            Self::Multiline => write!(f, "multiline"),
+        }
+    }
+}
+
+/// A parameter of a command as written between the parameter and ending token
+///
+/// `#count:int!` is parsed into a parameter with the name `count` of type `ParameterType::Int`.
+/// `#sha:[0-9a-f]{7,40}!` is parsed into a parameter with the name `sha` whose input has to match the pattern.
+/// `#branch:$(git branch --format '%(refname:short)')!` offers the output lines of the shell command as values.
+/// `#ctx@kube-contexts!` offers the values of a registered `ParameterProvider`.
+/// `#host:The target hostname!` describes the parameter to the user, any spec with whitespace is a description.
+/// `#query|trim|urlencode!` passes the input through the filters, in order, before it is put into the command.
+/// Parameters without a known type are treated as `ParameterType::Text`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Parameter {
+    pub name: String,
+    pub kind: ParameterType,
+    /// Regex the whole input has to match
+    pub pattern: Option<String>,
+    /// Provider of the values that are offered
+    pub provider: Option<ProviderRef>,
+    /// Shown when asking for the value of the parameter
+    pub description: Option<String>,
+    /// Applied to the input, in order, before it is put into the command
+    pub filters: Vec<ParameterFilter>,
+}
+
+impl Parameter {
+    /// Parse the content of a parameter, meaning everything between the parameter and the ending token
+    pub fn parse(content: &str) -> Self {
+        let mut content = content;
+        let mut filters = Vec::new();
+        while let Some((rest, filter)) = content
+            .rsplit_once(PARAMETER_FILTER_SEPARATOR)
+            .and_then(|(rest, name)| Some((rest, ParameterFilter::from_name(name)?)))
+        {
+            filters.insert(0, filter);
+            content = rest;
+        }
+        Self {
+            filters,
+            ..Self::parse_spec(content)
+        }
+    }
+
+    /// Parse the name, provider and spec of a parameter without its filters
+    fn parse_spec(content: &str) -> Self {
+        let parameter = Self::parse_type(content);
+        let Some((name, provider)) = parameter.name.split_once(PROVIDER_SEPARATOR) else {
+            return parameter;
+        };
+        if name.is_empty() || provider.is_empty() || parameter.provider.is_some() {
+            return parameter;
+        }
+        Self {
+            name: name.to_string(),
+            provider: Some(ProviderRef::new(provider)),
+            ..parameter
+        }
+    }
+
+    /// Parse the name and spec of a parameter without its filters and provider
+    fn parse_type(content: &str) -> Self {
+        let Some((name, spec)) = content.split_once(PARAMETER_SPEC_SEPARATOR) else {
+            return Self {
+                name: content.to_string(),
+                ..Self::default()
+            };
+        };
+        if let Some(provider) = spec
+            .strip_prefix(PROVIDER_PREFIX)
+            .and_then(|spec| spec.strip_suffix(PROVIDER_SUFFIX))
+        {
+            return Self {
+                name: name.to_string(),
+                provider: Some(ProviderRef::shell(provider)),
+                ..Self::default()
+            };
+        }
+        if let Some(kind) = ParameterType::from_name(spec) {
+            return Self {
+                name: name.to_string(),
+                kind,
+                ..Self::default()
+            };
+        }
+        if is_pattern(spec) {
+            return Self {
+                name: name.to_string(),
+                pattern: Some(spec.to_string()),
+                ..Self::default()
+            };
+        }
+        if spec.trim().contains(char::is_whitespace) {
+            return Self {
+                name: name.to_string(),
+                description: Some(spec.trim().to_string()),
+                ..Self::default()
+            };
+        }
+        Self {
+            name: content.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Check if `input` is a valid value for this parameter
+    pub fn validate(&self, input: &str) -> Result<(), HoardErr> {
+        self.kind.validate(input)?;
+        if let Some(pattern) = &self.pattern {
+            // The pattern is checked against the whole input, not just a part of it
+            let is_match =
+                Regex::new(&format!("^(?:{pattern})$")).is_ok_and(|regex| regex.is_match(input));
+            if !is_match {
+                return Err(HoardErr::new(&format!(
+                    "'{input}' does not match the pattern {pattern}"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Pass `value` through the filters of this parameter
+    pub fn apply_filters(&self, value: &str) -> String {
+        self.filters
+            .iter()
+            .fold(value.to_string(), |value, filter| filter.apply(&value))
+    }
+
+    /// Like `apply_filters`, but for a value that was prepared with `protect_value`
+    fn apply_filters_protected(
+        &self,
+        value: &str,
+        start_token: &str,
+        end_token: &str,
+        escape: char,
+    ) -> String {
+        if self.filters.is_empty() {
+            return value.to_string();
+        }
+        let is_secret = value.starts_with(SECRET_PLACEHOLDER);
+        let filtered = self.apply_filters(&restore_values(value, start_token, end_token, escape));
+        let protected = protect_value(&filtered, start_token, end_token, escape);
+        if is_secret {
+            mark_secret(&protected)
+        } else {
+            protected
+        }
+    }
+
+    /// Values offered by the provider of this parameter, none if it has no provider or the provider fails
+    pub fn provided_values(&self, providers: &ProviderRegistry) -> Vec<String> {
+        self.provider
+            .as_ref()
+            .map(|provider| providers.values(provider))
+            .unwrap_or_default()
+    }
+}
+
+/// Characters that make a parameter spec a regex rather than a type
+const REGEX_METACHARACTERS: &[char] = &[
+    '[', ']', '(', ')', '{', '}', '^', '$', '*', '+', '?', '|', '\\', '.',
+];
+
+/// Check if the spec of a parameter is meant as a regex constraint
+fn is_pattern(spec: &str) -> bool {
+    !spec.contains(char::is_whitespace)
+        && spec.contains(REGEX_METACHARACTERS)
+        && Regex::new(spec).is_ok()
+}
+
+/// Stand-ins for tokens and escape characters inside of parameter values, so they aren't treated as parameters or escapes
+const TOKEN_PLACEHOLDER: char = '\u{E000}';
+const ENDING_TOKEN_PLACEHOLDER: char = '\u{E001}';
+const ESCAPE_PLACEHOLDER: char = '\u{E002}';
+/// Put around the values of secret parameters until the command is complete, so only they are masked
+const SECRET_PLACEHOLDER: char = '\u{E004}';
+
+/// Hide tokens and escape characters of a parameter value before it is put into a command with `replace_parameter`
+pub fn protect_value(value: &str, start_token: &str, end_token: &str, escape: char) -> String {
+    let mut protected = value
+        .replace(escape, &ESCAPE_PLACEHOLDER.to_string())
+        .replace(start_token, &TOKEN_PLACEHOLDER.to_string());
+    if !end_token.is_empty() {
+        protected = protected.replace(end_token, &ENDING_TOKEN_PLACEHOLDER.to_string());
+    }
+    protected
+}
+
+/// Restore the values hidden by `protect_value` once all parameters of a command are replaced
+pub fn restore_values(command: &str, start_token: &str, end_token: &str, escape: char) -> String {
+    command
+        .replace(TOKEN_PLACEHOLDER, start_token)
+        .replace(ENDING_TOKEN_PLACEHOLDER, end_token)
+        .replace(ESCAPE_PLACEHOLDER, &escape.to_string())
+        .replace(SECRET_PLACEHOLDER, "")
+}
+
+/// Shown instead of the values of secret parameters
+pub const SECRET_MASK: &str = "*****";
+
+/// Mark the value of a secret parameter, which `mask_secrets` hides and `restore_values` puts back
+pub fn mark_secret(value: &str) -> String {
+    format!("{SECRET_PLACEHOLDER}{value}{SECRET_PLACEHOLDER}")
+}
+
+/// Hide the values of secret parameters in a preview of a command. Only the marked values are hidden, the same text
+/// elsewhere in the command is shown
+pub fn mask_secrets(text: &str) -> String {
+    text.split(SECRET_PLACEHOLDER)
+        .enumerate()
+        .map(|(i, part)| if i % 2 == 1 { SECRET_MASK } else { part })
+        .collect()
+}
+
+/// Stand-in for the values of list parameters until a command is expanded into its invocations
+const LIST_MARKER: char = '\u{E003}';
+
+fn list_marker(name: &str) -> String {
+    format!("{LIST_MARKER}{name}{LIST_MARKER}")
+}
+
+/// Read parameter values from the YAML or JSON content of a values file
+///
+/// A mapping fills named parameters by their name and positional parameters by their position, starting at 1.
+/// A sequence fills the positional parameters in order. Returns the named and the positional values
+pub fn parse_values_file(
+    content: &str,
+) -> Result<(HashMap<String, String>, Vec<String>), HoardErr> {
+    let invalid = |msg: &str| HoardErr::new(&format!("Invalid values file: {msg}"));
+    let values: serde_yaml::Value =
+        serde_yaml::from_str(content).map_err(|e| invalid(&e.to_string()))?;
+    let to_string = |value: &serde_yaml::Value| match value {
+        serde_yaml::Value::String(s) => Ok(s.clone()),
+        serde_yaml::Value::Number(n) => Ok(n.to_string()),
+        serde_yaml::Value::Bool(b) => Ok(b.to_string()),
+        _ => Err(invalid("values have to be strings, numbers or booleans")),
+    };
+    match &values {
+        serde_yaml::Value::Sequence(sequence) => Ok((
+            HashMap::new(),
+            sequence.iter().map(to_string).collect::<Result<_, _>>()?,
+        )),
+        serde_yaml::Value::Mapping(mapping) => {
+            let mut named = HashMap::new();
+            let mut positions: Vec<(u64, String)> = Vec::new();
+            for (key, value) in mapping {
+                match key {
+                    serde_yaml::Value::Number(n) => {
+                        let position = n
+                            .as_u64()
+                            .filter(|position| *position > 0)
+                            .ok_or_else(|| invalid(&format!("{n} is not a parameter position")))?;
+                        positions.push((position, to_string(value)?));
+                    }
+                    _ => {
+                        named.insert(to_string(key)?, to_string(value)?);
+                    }
+                }
+            }
+            positions.sort_by_key(|(position, _)| *position);
+            for (expected, (position, _)) in (1..).zip(&positions) {
+                if *position != expected {
+                    return Err(invalid(&format!("no value for position {expected}")));
+                }
+            }
+            Ok((
+                named,
+                positions.into_iter().map(|(_, value)| value).collect(),
+            ))
+        }
+        serde_yaml::Value::Null => Ok((HashMap::new(), Vec::new())),
+        _ => Err(invalid("expected a mapping or a list of values")),
+    }
+}
+
+/// Split the input of a list parameter into its values
+pub fn split_list_value(input: &str) -> Vec<String> {
+    input
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// Expand a command string with list markers into one command string per combination of list values
+///
+/// `values` holds the name and the values of every list parameter passed to `with_list_markers`.
+/// Returns the values used for each invocation together with its command string
+pub fn expand_list_markers(
+    command: &str,
+    values: &[(String, Vec<String>)],
+) -> Vec<(Vec<String>, String)> {
+    let mut invocations = vec![(Vec::new(), command.to_string())];
+    for (name, parameter_values) in values {
+        let marker = list_marker(name);
+        let mut expanded = Vec::with_capacity(invocations.len() * parameter_values.len());
+        for (used, command) in &invocations {
+            for value in parameter_values {
+                let mut used = used.clone();
+                used.push(value.clone());
+                expanded.push((used, command.replace(&marker, value)));
+            }
+        }
+        invocations = expanded;
+    }
+    invocations
+}
+
+/// Find the position of `end_token` closing the parameter whose content starts at `from`
+///
+/// The search stops without a result if another parameter starts before the parameter is closed
+fn find_parameter_end(
+    s: &str,
+    from: usize,
+    start_token: &str,
+    end_token: &str,
+    escape: char,
+) -> Option<usize> {
+    let mut search_idx = from;
+    while search_idx < s.len() {
+        if s[search_idx..].starts_with(escape) {
+            search_idx += escape.len_utf8();
+            if search_idx < s.len() {
+                let c = s[search_idx..].chars().next().unwrap();
+                search_idx += c.len_utf8();
+            }
+            continue;
+        }
+
+        if !end_token.is_empty() && s[search_idx..].starts_with(end_token) {
+            return Some(search_idx);
+        }
+
+        if s[search_idx..].starts_with(start_token) {
+            return None;
+        }
+
+        let c = s[search_idx..].chars().next().unwrap();
+        search_idx += c.len_utf8();
+    }
+    None
+}
+
+/// Length of the parameter `text` starts with, its token and everything up to the ending token that closes it
+///
+/// A token that isn't closed is an unnamed parameter of its own. `None` if `text` doesn't start with a token
+pub fn parameter_len(
+    text: &str,
+    start_token: &str,
+    end_token: &str,
+    escape: char,
+) -> Option<usize> {
+    if start_token.is_empty() || !text.starts_with(start_token) {
+        return None;
+    }
+    let content_start = start_token.len();
+    Some(
+        find_parameter_end(text, content_start, start_token, end_token, escape)
+            .map_or(content_start, |end| end + end_token.len()),
+    )
+}
+
+/// Start and length of the first parameter of `text`, escaped tokens are skipped
+pub fn first_parameter_span(
+    text: &str,
+    start_token: &str,
+    end_token: &str,
+    escape: char,
+) -> Option<(usize, usize)> {
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        if let Some(escaped) = rest.strip_prefix(escape) {
+            i += escape.len_utf8();
+            i += if !start_token.is_empty() && escaped.starts_with(start_token) {
+                start_token.len()
+            } else {
+                escaped.chars().next().map_or(0, char::len_utf8)
+            };
+            continue;
+        }
+        if let Some(len) = parameter_len(rest, start_token, end_token, escape) {
+            return Some((i, len));
+        }
+        i += rest.chars().next().map_or(1, char::len_utf8);
+    }
+    None
+}
+
+/// Write every parameter of `command` as `placeholder(parameter, position)`, e.g. in the syntax of another tool
+///
+/// Escaped tokens lose their escape character, the other tool doesn't know it
+pub fn map_parameters(
+    command: &str,
+    start_token: &str,
+    end_token: &str,
+    escape: char,
+    mut placeholder: impl FnMut(&Parameter, usize) -> String,
+) -> String {
+    let mut out = String::with_capacity(command.len());
+    let mut position = 0;
+    let mut i = 0;
+    while i < command.len() {
+        let rest = &command[i..];
+        if let Some(escaped) = rest.strip_prefix(escape) {
+            let token = [start_token, end_token]
+                .into_iter()
+                .find(|token| !token.is_empty() && escaped.starts_with(token));
+            if let Some(token) = token {
+                out.push_str(token);
+                i += escape.len_utf8() + token.len();
+                continue;
+            }
+        } else if rest.starts_with(start_token) {
+            let content_start = i + start_token.len();
+            let (parameter, next) =
+                find_parameter_end(command, content_start, start_token, end_token, escape)
+                    .map_or_else(
+                        || (Parameter::default(), content_start),
+                        |end| {
+                            (
+                                Parameter::parse(&command[content_start..end]),
+                                end + end_token.len(),
+                            )
+                        },
+                    );
+            out.push_str(&placeholder(&parameter, position));
+            position += 1;
+            i = next;
+            continue;
+        }
+        let c = rest.chars().next().unwrap();
+        out.push(c);
+        i += c.len_utf8();
+    }
+    out
+}
+
+/// Positions of the ending tokens in `command` that close no parameter, like the `!` of `test ! -f`
+pub fn stray_end_tokens(
+    command: &str,
+    start_token: &str,
+    end_token: &str,
+    escape: char,
+) -> Vec<usize> {
+    let mut positions = Vec::new();
+    let mut i = 0;
+    while i < command.len() {
+        let rest = &command[i..];
+        if rest.starts_with(escape) {
+            i += escape.len_utf8();
+            i += command[i..].chars().next().map_or(0, char::len_utf8);
+            continue;
+        }
+        if rest.starts_with(start_token) {
+            let content_start = i + start_token.len();
+            i = find_parameter_end(command, content_start, start_token, end_token, escape)
+                .map_or(content_start, |end| end + end_token.len());
+            continue;
+        }
+        if !end_token.is_empty() && rest.starts_with(end_token) {
+            positions.push(i);
+            i += end_token.len();
+            continue;
+        }
+        i += rest.chars().next().map_or(1, char::len_utf8);
+    }
+    positions
+}
 
 pub trait Parameterized {
-    fn escape_input(input: &str, start_token: &str, end_token: &str) -> String;
-    fn cleanup_escapes(&self, start_token: &str, end_token: &str) -> HoardCmd;
+    fn escape_input(input: &str, start_token: &str, end_token: &str, escape: char) -> String;
+    fn cleanup_escapes(&self, start_token: &str, end_token: &str, escape: char) -> HoardCmd;
     fn is_parameterized(&self, token: &str) -> bool;
     /// Counts the number of occurrences of a specific token in the command string.
     ///
@@ -12,6 +639,7 @@ pub trait Parameterized {
     /// # Arguments
     ///
     /// * `token` - A string slice that holds the token to be counted.
+    /// * `escape` - The character that escapes tokens, a backslash by default.
     ///
     /// # Returns
     ///
@@ -20,11 +648,11 @@ pub trait Parameterized {
     /// # Example
     ///
     /// ```
-    /// let command = HoardCmd::default()::with_command("echo $ $");
-    /// assert_eq!(command.get_parameter_count("$"), 2);
+    /// let command = HoardCmd::default().with_command("echo $ $");
+    /// assert_eq!(command.get_parameter_count("$", '\\'), 2);
     /// ```
 
-    fn get_parameter_count(&self, token: &str) -> usize;
+    fn get_parameter_count(&self, token: &str, escape: char) -> usize;
     /// Splits the command string into a vector of substrings at each occurrence of a specific token.
     ///
     /// This function takes a token and splits the command string into a vector of substrings
@@ -41,7 +669,7 @@ pub trait Parameterized {
     /// # Example
     ///
     /// ```
-    /// let command = HoardCmd::default()::with_command("This is a #1 with one parameter token");
+    /// let command = HoardCmd::default().with_command("This is a #1 with one parameter token");
     /// assert_eq!(command.split("#"), vec!["This is a ", " with one parameter token"]);
     /// ```
     fn split(&self, token: &str) -> Vec<String>;
@@ -62,7 +690,7 @@ pub trait Parameterized {
     /// # Example
     ///
     /// ```
-    /// let command = HoardCmd::default()::with_command("This is a #1 with one parameter token");
+    /// let command = HoardCmd::default().with_command("This is a #1 with one parameter token");
     /// assert_eq!(command.split("#"), vec!["This is a ", "#1", " with one parameter token"]);
     /// ```
     fn split_inclusive_token(&self, token: &str) -> Vec<String>;
@@ -85,11 +713,69 @@ pub trait Parameterized {
     /// # Example
     ///
     /// ```
-    /// let command = HoardCmd::default()::with_command("echo #param1$");
-    /// let replaced_command = command.replace_parameter("#", "$", "Hello, world!");
+    /// let command = HoardCmd::default().with_command("echo #param1$");
+    /// let replaced_command = command.replace_parameter("#", "$", '\\', "Hello, world!");
     /// assert_eq!(replaced_command.get_command(), "echo Hello, world!");
     /// ```
-    fn replace_parameter(&self, token: &str, ending_token: &str, parameter: &str) -> HoardCmd;
+    fn replace_parameter(
+        &self,
+        token: &str,
+        ending_token: &str,
+        escape: char,
+        parameter: &str,
+    ) -> HoardCmd;
+
+    /// Replaces every occurrence of the named parameter `name` in the command string with `value`.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - A string slice that holds the token of the parameter.
+    /// * `ending_token` - A string slice that holds the ending token of the parameter.
+    /// * `escape` - The character that escapes tokens, a backslash by default.
+    /// * `name` - The name of the parameter to replace, regardless of its type.
+    /// * `value` - A string slice that holds the value to replace the parameter with.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let command = HoardCmd::default().with_command("docker tag #img! registry/#img!");
+    /// let replaced_command = command.replace_named_parameter("#", "!", '\\', "img", "nginx");
+    /// assert_eq!(replaced_command.command, "docker tag nginx registry/nginx");
+    /// ```
+    fn replace_named_parameter(
+        &self,
+        token: &str,
+        ending_token: &str,
+        escape: char,
+        name: &str,
+        value: &str,
+    ) -> HoardCmd;
+
+    /// Returns the next parameter of the command string that would be replaced by `replace_parameter`.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - A string slice that holds the token of the parameter.
+    /// * `ending_token` - A string slice that holds the ending token of the parameter.
+    /// * `escape` - The character that escapes tokens, a backslash by default.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `None` if the command has no parameters left.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let command = HoardCmd::default().with_command("head -n #lines:int!");
+    /// let parameter = command.get_next_parameter("#", "!", '\\').unwrap();
+    /// assert_eq!(parameter.kind, ParameterType::Int);
+    /// ```
+    fn get_next_parameter(
+        &self,
+        token: &str,
+        ending_token: &str,
+        escape: char,
+    ) -> Option<Parameter>;
 
     /// Replaces all occurrences of a parameter, identified by a token and an ending token, in the command string with user input.
     ///
@@ -100,6 +786,7 @@ pub trait Parameterized {
     ///
     /// * `token` - A string slice that holds the token of the parameter.
     /// * `ending_token` - A string slice that holds the ending token of the parameter.
+    /// * `escape` - The character that escapes tokens, a backslash by default.
     ///
     /// # Returns
     ///
@@ -108,12 +795,91 @@ pub trait Parameterized {
     /// # Example
     ///
     /// ```
-    /// let mut command = HoardCmd::default()::with_command("echo #param1$");
-    /// command = command.with_input_parameters("#", "$");
+    /// let mut command = HoardCmd::default().with_command("echo #param1$");
+    /// command = command.with_input_parameters("#", "$", '\\', &ProviderRegistry::default());
     /// // The user is prompted for input for each occurrence of the parameter.
     /// // The command string is updated with the user's input.
     /// ```
-    fn with_input_parameters(&mut self, token: &str, ending_token: &str) -> HoardCmd;
+    fn with_input_parameters(
+        &mut self,
+        token: &str,
+        ending_token: &str,
+        escape: char,
+        providers: &ProviderRegistry,
+    ) -> HoardCmd;
+
+    /// Replaces all parameters of the command string with the given values without prompting the user.
+    ///
+    /// Named parameters take their value from `named`, every other parameter takes the next value of `positional`.
+    /// Values are validated against the type or pattern of their parameter.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - A string slice that holds the token of the parameter.
+    /// * `ending_token` - A string slice that holds the ending token of the parameter.
+    /// * `escape` - The character that escapes tokens, a backslash by default.
+    /// * `named` - Values by parameter name.
+    /// * `positional` - Values for the remaining parameters, in order.
+    ///
+    /// # Returns
+    ///
+    /// This function returns an error if a parameter has no value, a value is invalid or a value is not used.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let command = HoardCmd::default().with_command("psql -h #host! -p #port:int!");
+    /// let named = HashMap::from([(String::from("host"), String::from("db1"))]);
+    /// let replaced_command = command.with_parameter_values("#", "!", '\\', &named, &[String::from("5432")])?;
+    /// assert_eq!(replaced_command.command, "psql -h db1 -p 5432");
+    /// ```
+    fn with_parameter_values(
+        &self,
+        token: &str,
+        ending_token: &str,
+        escape: char,
+        named: &HashMap<String, String>,
+        positional: &[String],
+    ) -> Result<HoardCmd, HoardErr>;
+
+    /// Returns the named parameters of type `list` in the order they first appear, each only once.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - A string slice that holds the token of the parameter.
+    /// * `ending_token` - A string slice that holds the ending token of the parameter.
+    /// * `escape` - The character that escapes tokens, a backslash by default.
+    fn list_parameters(&self, token: &str, ending_token: &str, escape: char) -> Vec<Parameter>;
+
+    /// Replaces the given list parameters with markers, to be expanded by `expand_list_markers` once all other
+    /// parameters are filled.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - A string slice that holds the token of the parameter.
+    /// * `ending_token` - A string slice that holds the ending token of the parameter.
+    /// * `escape` - The character that escapes tokens, a backslash by default.
+    /// * `parameters` - The list parameters as returned by `list_parameters`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let command = HoardCmd::default().with_command("ping -c #count:int! #host:list!");
+    /// let list_parameters = command.list_parameters("#", "!", '\\');
+    /// let mut marked = command.with_list_markers("#", "!", '\\', &list_parameters);
+    /// // Only the count is asked for
+    /// let marked = marked.with_input_parameters("#", "!", '\\', &ProviderRegistry::default());
+    /// let hosts = (String::from("host"), vec![String::from("db1"), String::from("db2")]);
+    /// let invocations = expand_list_markers(&marked.command, &[hosts]);
+    /// assert_eq!(invocations.len(), 2);
+    /// ```
+    fn with_list_markers(
+        &self,
+        token: &str,
+        ending_token: &str,
+        escape: char,
+        parameters: &[Parameter],
+    ) -> HoardCmd;
 }
 
 impl Parameterized for HoardCmd {
@@ -121,26 +887,27 @@ impl Parameterized for HoardCmd {
         self.command.contains(token)
     }
     // Escapet einen String so, dass get_parameter_count ihn komplett ignoriert
-    fn escape_input(input: &str, start_token: &str, end_token: &str) -> String {
+    fn escape_input(input: &str, start_token: &str, end_token: &str, escape: char) -> String {
         let mut escaped = String::with_capacity(input.len() * 2);
         let mut i = 0;
 
         while i < input.len() {
-            if input.as_bytes()[i] == b'\\' {
-                escaped.push_str("\\\\");
-                i += 1;
+            if input[i..].starts_with(escape) {
+                escaped.push(escape);
+                escaped.push(escape);
+                i += escape.len_utf8();
                 continue;
             }
 
             if input[i..].starts_with(start_token) {
-                escaped.push('\\');
+                escaped.push(escape);
                 escaped.push_str(start_token);
                 i += start_token.len();
                 continue;
             }
 
             if !end_token.is_empty() && input[i..].starts_with(end_token) {
-                escaped.push('\\');
+                escaped.push(escape);
                 escaped.push_str(end_token);
                 i += end_token.len();
                 continue;
@@ -154,14 +921,14 @@ impl Parameterized for HoardCmd {
         escaped
     }
 
-    fn cleanup_escapes(&self, start_token: &str, end_token: &str) -> HoardCmd {
+    fn cleanup_escapes(&self, start_token: &str, end_token: &str, escape: char) -> HoardCmd {
         let s = &self.command;
         let mut out = String::with_capacity(s.len());
         let mut i = 0;
 
         while i < s.len() {
-            if s.as_bytes()[i] == b'\\' {
-                i += 1;
+            if s[i..].starts_with(escape) {
+                i += escape.len_utf8();
                 if i < s.len() {
                     if s[i..].starts_with(start_token) {
                         out.push_str(start_token);
@@ -169,9 +936,9 @@ impl Parameterized for HoardCmd {
                     } else if s[i..].starts_with(end_token) && !end_token.is_empty() {
                         out.push_str(end_token);
                         i += end_token.len();
-                    } else if s.as_bytes()[i] == b'\\' {
-                        out.push('\\');
-                        i += 1;
+                    } else if s[i..].starts_with(escape) {
+                        out.push(escape);
+                        i += escape.len_utf8();
                     } else {
                         let c = s[i..].chars().next().unwrap();
                         out.push(c);
@@ -189,14 +956,14 @@ impl Parameterized for HoardCmd {
         Self::default().with_command(&out)
     }
 
-    fn get_parameter_count(&self, token: &str) -> usize {
+    fn get_parameter_count(&self, token: &str, escape: char) -> usize {
         let s = &self.command;
         let mut count = 0;
         let mut i = 0;
 
         while i < s.len() {
-            if s.as_bytes()[i] == b'\\' {
-                i += 1;
+            if s[i..].starts_with(escape) {
+                i += escape.len_utf8();
                 if i < s.len() {
                     if s[i..].starts_with(token) {
                         i += token.len();
@@ -239,16 +1006,22 @@ impl Parameterized for HoardCmd {
         collected
     }
 
-    fn replace_parameter(&self, start_token: &str, end_token: &str, value: &str) -> Self {
+    fn replace_parameter(
+        &self,
+        start_token: &str,
+        end_token: &str,
+        escape: char,
+        value: &str,
+    ) -> Self {
         let s = &self.command;
         let mut out = String::with_capacity(s.len());
         let mut i = 0;
         let mut replaced = false;
 
         while i < s.len() {
-            if s.as_bytes()[i] == b'\\' {
-                out.push('\\'); // Keep Backslash for final cleanup
-                i += 1;
+            if s[i..].starts_with(escape) {
+                out.push(escape); // Keep the escape for final cleanup
+                i += escape.len_utf8();
                 if i < s.len() {
                     if s[i..].starts_with(start_token) {
                         out.push_str(start_token);
@@ -267,44 +1040,87 @@ impl Parameterized for HoardCmd {
 
             if !replaced && s[i..].starts_with(start_token) {
                 let param_content_start = i + start_token.len();
+                let found_end =
+                    find_parameter_end(s, param_content_start, start_token, end_token, escape);
 
-                let mut search_idx = param_content_start;
-                let mut found_end = None;
+                if let Some(end_idx) = found_end {
+                    let parameter = Parameter::parse(&s[param_content_start..end_idx]);
+                    out.push_str(&parameter.apply_filters_protected(
+                        value,
+                        start_token,
+                        end_token,
+                        escape,
+                    ));
+                    i = end_idx + end_token.len();
+                } else {
+                    out.push_str(value);
+                    i += start_token.len();
+                }
+                replaced = true;
+                continue;
+            }
 
-                while search_idx < s.len() {
-                    if s.as_bytes()[search_idx] == b'\\' {
-                        search_idx += 1;
-                        if search_idx < s.len() {
-                            let c = s[search_idx..].chars().next().unwrap();
-                            search_idx += c.len_utf8();
-                        }
-                        continue;
-                    }
+            let c = s[i..].chars().next().unwrap();
+            out.push(c);
+            i += c.len_utf8();
+        }
 
-                    if !end_token.is_empty() && s[search_idx..].starts_with(end_token) {
-                        found_end = Some(search_idx);
-                        break;
-                    }
+        Self::default().with_command(&out)
+    }
 
-                    if s[search_idx..].starts_with(start_token) {
-                        break;
-                    }
+    fn replace_named_parameter(
+        &self,
+        start_token: &str,
+        end_token: &str,
+        escape: char,
+        name: &str,
+        value: &str,
+    ) -> Self {
+        let s = &self.command;
+        let mut out = String::with_capacity(s.len());
+        let mut i = 0;
 
-                    let c = s[search_idx..].chars().next().unwrap();
-                    search_idx += c.len_utf8();
+        while i < s.len() {
+            if s[i..].starts_with(escape) {
+                out.push(escape); // Keep the escape for final cleanup
+                i += escape.len_utf8();
+                if i < s.len() {
+                    if s[i..].starts_with(start_token) {
+                        out.push_str(start_token);
+                        i += start_token.len();
+                    } else if s[i..].starts_with(end_token) && !end_token.is_empty() {
+                        out.push_str(end_token);
+                        i += end_token.len();
+                    } else {
+                        let c = s[i..].chars().next().unwrap();
+                        out.push(c);
+                        i += c.len_utf8();
+                    }
                 }
+                continue;
+            }
 
-                if let Some(end_idx) = found_end {
-                    out.push_str(value);
-                    i = end_idx + end_token.len();
-                    replaced = true;
-                    continue;
-                } else {
-                    out.push_str(value);
-                    i += start_token.len();
-                    replaced = true;
-                    continue;
+            if s[i..].starts_with(start_token) {
+                let param_content_start = i + start_token.len();
+                if let Some(end_idx) =
+                    find_parameter_end(s, param_content_start, start_token, end_token, escape)
+                {
+                    let parameter = Parameter::parse(&s[param_content_start..end_idx]);
+                    if parameter.name == name {
+                        // Every occurrence can have filters of its own
+                        out.push_str(&parameter.apply_filters_protected(
+                            value,
+                            start_token,
+                            end_token,
+                            escape,
+                        ));
+                        i = end_idx + end_token.len();
+                        continue;
+                    }
                 }
+                out.push_str(start_token);
+                i += start_token.len();
+                continue;
             }
 
             let c = s[i..].chars().next().unwrap();
@@ -315,46 +1131,133 @@ impl Parameterized for HoardCmd {
         Self::default().with_command(&out)
     }
 
-    fn with_input_parameters(&mut self, token: &str, ending_token: &str) -> Self {
+    fn get_next_parameter(
+        &self,
+        token: &str,
+        ending_token: &str,
+        escape: char,
+    ) -> Option<Parameter> {
+        let s = &self.command;
+        let mut i = 0;
+
+        while i < s.len() {
+            if s[i..].starts_with(escape) {
+                i += escape.len_utf8();
+                if i < s.len() {
+                    if s[i..].starts_with(token) {
+                        i += token.len();
+                    } else {
+                        let c = s[i..].chars().next().unwrap();
+                        i += c.len_utf8();
+                    }
+                }
+                continue;
+            }
+
+            if s[i..].starts_with(token) {
+                let content_start = i + token.len();
+                let content = find_parameter_end(s, content_start, token, ending_token, escape)
+                    .map_or("", |end| &s[content_start..end]);
+                return Some(Parameter::parse(content));
+            }
+
+            let c = s[i..].chars().next().unwrap();
+            i += c.len_utf8();
+        }
+        None
+    }
+
+    fn with_input_parameters(
+        &mut self,
+        token: &str,
+        ending_token: &str,
+        escape: char,
+        providers: &ProviderRegistry,
+    ) -> Self {
         let s = &self.command;
         let mut out = String::with_capacity(s.len());
         let mut i = 0;
         let mut param_count = 0;
+        // Named parameters are only asked for once and reused for every occurrence
+        let mut named_values: HashMap<String, String> = HashMap::new();
+        let mut secret_names: HashSet<String> = HashSet::new();
 
         while i < s.len() {
-            if s.as_bytes()[i] == b'\\' {
-                if i + 1 < s.len() {
-                    let next_pos = i + 1;
-
+            if s[i..].starts_with(escape) {
+                let next_pos = i + escape.len_utf8();
+                if next_pos < s.len() {
                     if s[next_pos..].starts_with(token) {
                         out.push_str(token);
                         i = next_pos + token.len();
                         continue;
                     }
 
-                    if s.as_bytes()[next_pos] == b'\\' {
-                        out.push('\\');
-                        i = next_pos + 1;
+                    if s[next_pos..].starts_with(escape) {
+                        out.push(escape);
+                        i = next_pos + escape.len_utf8();
                         continue;
                     }
                 }
-                out.push('\\');
-                i += 1;
+                out.push(escape);
+                i = next_pos;
                 continue;
             }
 
             if s[i..].starts_with(token) {
-                param_count += 1;
                 let param_content_start = i + token.len();
+                let param_end =
+                    find_parameter_end(s, param_content_start, token, ending_token, escape);
+                let parameter = param_end.map_or_else(Parameter::default, |end| {
+                    Parameter::parse(&s[param_content_start..end])
+                });
+                if let (Some(end), Some(value)) = (param_end, named_values.get(&parameter.name)) {
+                    let value = parameter.apply_filters(value);
+                    if secret_names.contains(&parameter.name) {
+                        out.push_str(&mark_secret(&value));
+                    } else {
+                        out.push_str(&value);
+                    }
+                    i = end + ending_token.len();
+                    continue;
+                }
+                param_count += 1;
 
-                let current_preview = format!("{}{}[...]", out, &s[i..]);
+                let current_preview = mask_secrets(&format!("{}{}[...]", out, &s[i..]));
 
-                let prompt_dialog = format!(
-                    "Enter parameter({}) nr {}\n~> {}\n",
-                    token, param_count, current_preview
+                let prompt_dialog = parameter.description.as_ref().map_or_else(
+                    || {
+                        format!(
+                            "Enter the {} parameter({})\n~> {}\n",
+                            ordinal(param_count - 1),
+                            token,
+                            current_preview
+                        )
+                    },
+                    |description| format!("{description}\n~> {current_preview}\n"),
                 );
 
-                let user_input = prompt_input(&prompt_dialog, false, None);
+                let name = parameter.name.clone();
+                let provided_values = parameter.provided_values(providers);
+                let user_input = if !provided_values.is_empty() {
+                    let options: Vec<&str> = provided_values.iter().map(String::as_str).collect();
+                    provided_values[prompt_select_with_options(&prompt_dialog, &options)].clone()
+                } else if parameter.kind == ParameterType::Secret {
+                    secret_names.insert(name.clone());
+                    prompt_password(&prompt_dialog)
+                } else {
+                    let validator = |input: &String| -> Result<(), String> {
+                        parameter.validate(input).map_err(|e| e.to_string())
+                    };
+                    prompt_input_validate(&prompt_dialog, false, None, Some(validator))
+                };
+                if !name.is_empty() {
+                    named_values.insert(name, user_input.clone());
+                }
+                let user_input = if parameter.kind == ParameterType::Secret {
+                    mark_secret(&parameter.apply_filters(&user_input))
+                } else {
+                    parameter.apply_filters(&user_input)
+                };
 
                 if let Some(end_offset) = s[param_content_start..].find(ending_token) {
                     out.push_str(&user_input);
@@ -372,19 +1275,399 @@ impl Parameterized for HoardCmd {
             i += c.len_utf8();
         }
 
-        self.command = out;
+        self.command = out.replace(SECRET_PLACEHOLDER, "");
         self.clone()
     }
+
+    fn with_parameter_values(
+        &self,
+        token: &str,
+        ending_token: &str,
+        escape: char,
+        named: &HashMap<String, String>,
+        positional: &[String],
+    ) -> Result<Self, HoardErr> {
+        let mut command = self.clone();
+        let mut positional_values = positional.iter();
+        let mut parameter_nr = 0;
+        let mut parameter_names = HashSet::new();
+        while let Some(parameter) = command.get_next_parameter(token, ending_token, escape) {
+            parameter_nr += 1;
+            parameter_names.insert(parameter.name.clone());
+            let value = named
+                .get(&parameter.name)
+                .or_else(|| positional_values.next())
+                .ok_or_else(|| {
+                    if parameter.name.is_empty() {
+                        HoardErr::new(&format!("No value given for parameter nr {parameter_nr}"))
+                    } else {
+                        HoardErr::new(&format!(
+                            "No value given for parameter '{}'",
+                            parameter.name
+                        ))
+                    }
+                })?;
+            parameter.validate(value)?;
+            let protected = protect_value(value, token, ending_token, escape);
+            let replaced = if parameter.name.is_empty() {
+                command.replace_parameter(token, ending_token, escape, &protected)
+            } else {
+                command.replace_named_parameter(
+                    token,
+                    ending_token,
+                    escape,
+                    &parameter.name,
+                    &protected,
+                )
+            };
+            command.command = replaced.command;
+        }
+        if let Some(unknown) = named.keys().find(|name| !parameter_names.contains(*name)) {
+            return Err(HoardErr::new(&format!(
+                "[{}] has no parameter named '{unknown}'",
+                self.name
+            )));
+        }
+        if positional_values.next().is_some() {
+            return Err(HoardErr::new(&format!(
+                "Got {} values, but [{}] only has {parameter_nr} parameters to fill",
+                positional.len(),
+                self.name
+            )));
+        }
+
+        let cleaned = command.cleanup_escapes(token, ending_token, escape);
+        command.command = restore_values(&cleaned.command, token, ending_token, escape);
+        Ok(command)
+    }
+
+    fn list_parameters(&self, token: &str, ending_token: &str, escape: char) -> Vec<Parameter> {
+        let mut remaining = self.clone();
+        let mut parameters: Vec<Parameter> = Vec::new();
+        while let Some(parameter) = remaining.get_next_parameter(token, ending_token, escape) {
+            remaining = remaining.replace_parameter(token, ending_token, escape, "");
+            let is_list = parameter.kind == ParameterType::List && !parameter.name.is_empty();
+            if is_list && !parameters.iter().any(|p| p.name == parameter.name) {
+                parameters.push(parameter);
+            }
+        }
+        parameters
+    }
+
+    fn with_list_markers(
+        &self,
+        token: &str,
+        ending_token: &str,
+        escape: char,
+        parameters: &[Parameter],
+    ) -> Self {
+        let mut command = self.clone();
+        for parameter in parameters {
+            command.command = command
+                .replace_named_parameter(
+                    token,
+                    ending_token,
+                    escape,
+                    &parameter.name,
+                    &list_marker(&parameter.name),
+                )
+                .command;
+        }
+        command
+    }
 }
 
 #[cfg(test)]
 mod test_commands {
     use super::*;
 
+    fn named(values: &[(&str, &str)]) -> HashMap<String, String> {
+        values
+            .iter()
+            .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_with_parameter_values_named() {
+        let command = HoardCmd::default().with_command("psql -h #host! -p #port:int!");
+        let values = named(&[("host", "db1"), ("port", "5432")]);
+        let replaced = command
+            .with_parameter_values("#", "!", '\\', &values, &[])
+            .unwrap();
+        assert_eq!("psql -h db1 -p 5432", replaced.command);
+    }
+
+    #[test]
+    fn test_with_parameter_values_positional() {
+        let command = HoardCmd::default().with_command("scp # #target!:#");
+        let positional = vec![String::from("a.txt"), String::from("/tmp")];
+        let values = named(&[("target", "host")]);
+        let replaced = command
+            .with_parameter_values("#", "!", '\\', &values, &positional)
+            .unwrap();
+        assert_eq!("scp a.txt host:/tmp", replaced.command);
+    }
+
+    #[test]
+    fn test_with_parameter_values_keeps_tokens_in_values() {
+        let command = HoardCmd::default().with_command("echo # \\#escaped");
+        let positional = vec![String::from("#not_a_param! C:\\dir")];
+        let replaced = command
+            .with_parameter_values("#", "!", '\\', &HashMap::new(), &positional)
+            .unwrap();
+        assert_eq!("echo #not_a_param! C:\\dir #escaped", replaced.command);
+    }
+
+    #[test]
+    fn test_with_parameter_values_repeated_positional() {
+        let command = HoardCmd::default().with_command("docker tag #img! registry/#img! #");
+        let positional = vec![String::from("nginx"), String::from("-q")];
+        let replaced = command
+            .with_parameter_values("#", "!", '\\', &HashMap::new(), &positional)
+            .unwrap();
+        assert_eq!("docker tag nginx registry/nginx -q", replaced.command);
+    }
+
+    #[test]
+    fn test_parse_values_file() {
+        let (named, positional) =
+            parse_values_file("host: db1\nport: 5432\n2: second\n1: first\n").unwrap();
+        assert_eq!(Some(&String::from("db1")), named.get("host"));
+        assert_eq!(Some(&String::from("5432")), named.get("port"));
+        assert_eq!(vec!["first", "second"], positional);
+
+        let (named, positional) = parse_values_file(r#"["a", 2, true]"#).unwrap();
+        assert!(named.is_empty());
+        assert_eq!(vec!["a", "2", "true"], positional);
+    }
+
+    #[test]
+    fn test_parse_values_file_with_gap() {
+        let result = parse_values_file("1: first\n3: third\n");
+        assert_eq!(
+            "Invalid values file: no value for position 2",
+            result.unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_parameter_spans() {
+        assert_eq!(Some(5), parameter_len("#pod! -n #ns!", "#", "!", '\\'));
+        assert_eq!(Some(1), parameter_len("# -n #ns!", "#", "!", '\\'));
+        assert_eq!(None, parameter_len("pod", "#", "!", '\\'));
+        assert_eq!(
+            Some((13, 12)),
+            first_parameter_span("echo \\#x and #first name! #city", "#", "!", '\\')
+        );
+        assert_eq!(None, first_parameter_span("echo \\#x", "#", "!", '\\'));
+    }
+
+    #[test]
+    fn test_parse_secret_parameter() {
+        let parameter = Parameter::parse("token:secret");
+        assert_eq!("token", parameter.name);
+        assert_eq!(ParameterType::Secret, parameter.kind);
+    }
+
+    #[test]
+    fn test_multiline_parameter_keeps_line_breaks() {
+        let parameter = Parameter::parse("body:multiline");
+        assert_eq!(ParameterType::Multiline, parameter.kind);
+        assert!(parameter.validate("first line\nsecond line").is_ok());
+    }
+
+    #[test]
+    fn test_parse_provider_parameter() {
+        let parameter = Parameter::parse("branch:$(git branch --format '%(refname:short)')");
+        assert_eq!("branch", parameter.name);
+        assert_eq!(
+            Some(ProviderRef::shell("git branch --format '%(refname:short)'")),
+            parameter.provider
+        );
+        assert_eq!(None, parameter.pattern);
+    }
+
+    #[test]
+    fn test_parse_named_provider_parameter() {
+        let parameter = Parameter::parse("host@consul:The target host|trim");
+        assert_eq!("host", parameter.name);
+        assert_eq!(Some(ProviderRef::new("consul")), parameter.provider);
+        assert_eq!(Some(String::from("The target host")), parameter.description);
+        assert_eq!(vec![ParameterFilter::Trim], parameter.filters);
+    }
+
+    #[test]
+    fn test_with_parameter_values_custom_escape() {
+        let command = HoardCmd::default().with_command(r"dir C:\Users\#user! ^#tag");
+        let replaced = command
+            .with_parameter_values("#", "!", '^', &named(&[("user", "bob")]), &[])
+            .unwrap();
+        assert_eq!(r"dir C:\Users\bob #tag", replaced.command);
+    }
+
+    #[test]
+    fn test_parse_parameter_filters() {
+        let parameter = Parameter::parse("query:int|trim|upper");
+        assert_eq!("query", parameter.name);
+        assert_eq!(ParameterType::Int, parameter.kind);
+        assert_eq!(
+            vec![ParameterFilter::Trim, ParameterFilter::Upper],
+            parameter.filters
+        );
+        // Alternatives of a pattern are not filters
+        let parameter = Parameter::parse("env:dev|prod");
+        assert_eq!(Some(String::from("dev|prod")), parameter.pattern);
+        assert!(parameter.filters.is_empty());
+    }
+
+    #[test]
+    fn test_with_parameter_values_filtered() {
+        let command = HoardCmd::default()
+            .with_command("curl example.com/?q=#q|urlencode! && echo #q|shellquote! #name|upper!");
+        let values = named(&[("q", "it's #1"), ("name", "abc")]);
+        let replaced = command
+            .with_parameter_values("#", "!", '\\', &values, &[])
+            .unwrap();
+        assert_eq!(
+            r"curl example.com/?q=it%27s%20%231 && echo 'it'\''s #1' ABC",
+            replaced.command
+        );
+    }
+
+    #[test]
+    fn test_provided_values() {
+        let parameter = Parameter::parse("env:$(printf 'dev\\n\\nprod\\n')");
+        let providers = ProviderRegistry::default();
+        assert_eq!(vec!["dev", "prod"], parameter.provided_values(&providers));
+        let failing = Parameter::parse("env:$(exit 1)");
+        assert!(failing.provided_values(&providers).is_empty());
+    }
+
+    #[test]
+    fn test_mask_secrets() {
+        let command = format!("curl -u a:{} #url! -o a.txt", mark_secret("a"));
+        assert_eq!("curl -u a:***** #url! -o a.txt", mask_secrets(&command));
+        assert_eq!(
+            "curl -u a:a #url! -o a.txt",
+            restore_values(&command, "#", "!", '\\')
+        );
+    }
+
+    #[test]
+    fn test_list_parameters() {
+        let command =
+            HoardCmd::default().with_command("ssh #host:list! -p #port:int! uptime #host:list! #");
+        let names: Vec<_> = command
+            .list_parameters("#", "!", '\\')
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        assert_eq!(vec!["host"], names);
+    }
+
+    #[test]
+    fn test_expand_list_parameters() {
+        let command = HoardCmd::default().with_command("ping -c 1 #host:list! # #host:list!");
+        let list_parameters = command.list_parameters("#", "!", '\\');
+        let marked = command.with_list_markers("#", "!", '\\', &list_parameters);
+        let filled = marked
+            .with_parameter_values("#", "!", '\\', &HashMap::new(), &[String::from("-q")])
+            .unwrap();
+        let hosts = (String::from("host"), split_list_value("db1, db2,"));
+        let invocations = expand_list_markers(&filled.command, &[hosts]);
+        assert_eq!(
+            vec![
+                (
+                    vec![String::from("db1")],
+                    String::from("ping -c 1 db1 -q db1")
+                ),
+                (
+                    vec![String::from("db2")],
+                    String::from("ping -c 1 db2 -q db2")
+                ),
+            ],
+            invocations
+        );
+    }
+
+    #[test]
+    fn test_expand_multiple_list_parameters() {
+        let values = vec![
+            (
+                String::from("x"),
+                vec![String::from("a"), String::from("b")],
+            ),
+            (
+                String::from("y"),
+                vec![String::from("1"), String::from("2")],
+            ),
+        ];
+        let command = format!("echo {} {}", list_marker("x"), list_marker("y"));
+        let commands: Vec<_> = expand_list_markers(&command, &values)
+            .into_iter()
+            .map(|(_, command)| command)
+            .collect();
+        assert_eq!(
+            vec!["echo a 1", "echo a 2", "echo b 1", "echo b 2"],
+            commands
+        );
+    }
+
+    #[test]
+    fn test_replace_named_parameter() {
+        let command = HoardCmd::default().with_command("docker tag #img! registry/#img:text! #");
+        let replaced = command.replace_named_parameter("#", "!", '\\', "img", "nginx");
+        assert_eq!("docker tag nginx registry/nginx #", replaced.command);
+    }
+
+    #[test]
+    fn test_replace_named_parameter_keeps_other_parameters() {
+        let command = HoardCmd::default().with_command("cp #src! #dst! \\#src!");
+        let replaced = command.replace_named_parameter("#", "!", '\\', "src", "a.txt");
+        assert_eq!("cp a.txt #dst! \\#src!", replaced.command);
+    }
+
+    #[test]
+    fn test_with_parameter_values_missing() {
+        let command = HoardCmd::default().with_command("psql -h #host! -p #port!");
+        let err = command
+            .with_parameter_values("#", "!", '\\', &named(&[("host", "db1")]), &[])
+            .unwrap_err();
+        assert_eq!("No value given for parameter 'port'", err.to_string());
+    }
+
+    #[test]
+    fn test_with_parameter_values_unknown_name() {
+        let command = HoardCmd::default().with_command("psql -h #host!");
+        let values = named(&[("host", "db1"), ("hos", "db2")]);
+        assert!(command
+            .with_parameter_values("#", "!", '\\', &values, &[])
+            .is_err());
+    }
+
+    #[test]
+    fn test_with_parameter_values_too_many() {
+        let command = HoardCmd::default().with_command("echo #");
+        let positional = vec![String::from("a"), String::from("b")];
+        assert!(command
+            .with_parameter_values("#", "!", '\\', &HashMap::new(), &positional)
+            .is_err());
+    }
+
+    #[test]
+    fn test_with_parameter_values_invalid() {
+        let command = HoardCmd::default().with_command("head -n #lines:int!");
+        let err = command
+            .with_parameter_values("#", "!", '\\', &named(&[("lines", "ten")]), &[])
+            .unwrap_err();
+        assert!(err.to_string().contains("ten"));
+    }
+
     #[test]
     fn test_get_parameter_count() {
         let command = HoardCmd::default().with_command("test test test");
-        assert_eq!(3, command.get_parameter_count("test"));
+        assert_eq!(3, command.get_parameter_count("test", '\\'));
     }
 
     #[test]
@@ -438,21 +1721,30 @@ mod test_commands {
     fn test_replace_parameter() {
         let command = HoardCmd::default().with_command("test1 # test3");
         let expected = HoardCmd::default().with_command("test1 replacement test3");
-        assert_eq!(expected, command.replace_parameter("#", "", "replacement"));
+        assert_eq!(
+            expected,
+            command.replace_parameter("#", "", '\\', "replacement")
+        );
     }
 
     #[test]
     fn test_replace_parameter_with_endtoken() {
         let command = HoardCmd::default().with_command("test1 #thisisacommand! test3");
         let expected = HoardCmd::default().with_command("test1 replacement test3");
-        assert_eq!(expected, command.replace_parameter("#", "!", "replacement"));
+        assert_eq!(
+            expected,
+            command.replace_parameter("#", "!", '\\', "replacement")
+        );
     }
 
     #[test]
     fn test_replace_parameter_with_endtoken_no_spaces() {
         let command = HoardCmd::default().with_command("test1#thisisacommand!test3");
         let expected = HoardCmd::default().with_command("test1replacementtest3");
-        assert_eq!(expected, command.replace_parameter("#", "!", "replacement"));
+        assert_eq!(
+            expected,
+            command.replace_parameter("#", "!", '\\', "replacement")
+        );
     }
 
     #[test]
@@ -460,31 +1752,135 @@ mod test_commands {
         let command = HoardCmd::default().with_command("wewantto\\\\#escape");
         // Backslash-Cleanup happens later
         let expected = HoardCmd::default().with_command("wewantto\\\\replacementescape");
-        assert_eq!(expected, command.replace_parameter("#", "!", "replacement"));
+        assert_eq!(
+            expected,
+            command.replace_parameter("#", "!", '\\', "replacement")
+        );
     }
     #[test]
     fn test_escape_single_backslash_before_token() {
         let command = HoardCmd::default().with_command("wewantto\\#escape");
         let expected = HoardCmd::default().with_command("wewantto\\#escape");
-        assert_eq!(expected, command.replace_parameter("#", "!", "replacement"));
+        assert_eq!(
+            expected,
+            command.replace_parameter("#", "!", '\\', "replacement")
+        );
     }
     #[test]
     fn test_escape_no_backslash_before_token() {
         let command = HoardCmd::default().with_command("wewantto#!escape");
         let expected = HoardCmd::default().with_command("wewanttoreplacementescape");
-        assert_eq!(expected, command.replace_parameter("#", "!", "replacement"));
+        assert_eq!(
+            expected,
+            command.replace_parameter("#", "!", '\\', "replacement")
+        );
     }
     #[test]
     fn test_escape_backslash_before_token_with_end() {
         let command = HoardCmd::default().with_command("wewantto\\##!escape");
         let expected = HoardCmd::default().with_command("wewantto\\#replacementescape");
-        assert_eq!(expected, command.replace_parameter("#", "!", "replacement"));
+        assert_eq!(
+            expected,
+            command.replace_parameter("#", "!", '\\', "replacement")
+        );
     }
     #[test]
     fn test_escape_backslash_before_multiple_token_with_end() {
         let command = HoardCmd::default().with_command("wewantto\\##!escape##");
         // Only the first gets replaced in a single iteration
         let expected = HoardCmd::default().with_command("wewantto\\#replacementescape##");
-        assert_eq!(expected, command.replace_parameter("#", "!", "replacement"));
+        assert_eq!(
+            expected,
+            command.replace_parameter("#", "!", '\\', "replacement")
+        );
+    }
+
+    #[test]
+    fn test_parse_typed_parameter() {
+        let parameter = Parameter::parse("count:int");
+        assert_eq!("count", parameter.name);
+        assert_eq!(ParameterType::Int, parameter.kind);
+    }
+
+    #[test]
+    fn test_parse_untyped_parameter() {
+        let parameter = Parameter::parse("host");
+        assert_eq!("host", parameter.name);
+        assert_eq!(ParameterType::Text, parameter.kind);
+    }
+
+    #[test]
+    fn test_parse_unknown_type_keeps_name() {
+        let parameter = Parameter::parse("host:port");
+        assert_eq!("host:port", parameter.name);
+        assert_eq!(ParameterType::Text, parameter.kind);
+    }
+
+    #[test]
+    fn test_parse_described_parameter() {
+        let parameter = Parameter::parse("host:The target hostname");
+        assert_eq!("host", parameter.name);
+        assert_eq!(
+            Some(String::from("The target hostname")),
+            parameter.description
+        );
+        assert_eq!(ParameterType::Text, parameter.kind);
+    }
+
+    #[test]
+    fn test_parse_pattern_parameter() {
+        let parameter = Parameter::parse("sha:[0-9a-f]{7,40}");
+        assert_eq!("sha", parameter.name);
+        assert_eq!(Some(String::from("[0-9a-f]{7,40}")), parameter.pattern);
+    }
+
+    #[test]
+    fn test_validate_pattern() {
+        let parameter = Parameter::parse("sha:[0-9a-f]{7,40}");
+        assert!(parameter.validate("a1b2c3d").is_ok());
+        assert!(parameter.validate("a1b2").is_err());
+        // The pattern has to match the whole input
+        assert!(parameter.validate("a1b2c3d-dirty").is_err());
+    }
+
+    #[test]
+    fn test_get_next_parameter() {
+        let command = HoardCmd::default().with_command("head -n #lines:int! #file:file!");
+        let parameter = command.get_next_parameter("#", "!", '\\').unwrap();
+        assert_eq!("lines", parameter.name);
+        assert_eq!(ParameterType::Int, parameter.kind);
+    }
+
+    #[test]
+    fn test_get_next_parameter_skips_escaped() {
+        let command = HoardCmd::default().with_command("echo \\#no! #url:url!");
+        let parameter = command.get_next_parameter("#", "!", '\\').unwrap();
+        assert_eq!(ParameterType::Url, parameter.kind);
+    }
+
+    #[test]
+    fn test_get_next_parameter_none() {
+        let command = HoardCmd::default().with_command("echo test");
+        assert!(command.get_next_parameter("#", "!", '\\').is_none());
+    }
+
+    #[test]
+    fn test_validate_int() {
+        assert!(ParameterType::Int.validate("42").is_ok());
+        assert!(ParameterType::Int.validate("forty-two").is_err());
+    }
+
+    #[test]
+    fn test_validate_url() {
+        assert!(ParameterType::Url.validate("https://example.com").is_ok());
+        assert!(ParameterType::Url.validate("example").is_err());
+    }
+
+    #[test]
+    fn test_validate_file() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let path = file.path().to_str().unwrap();
+        assert!(ParameterType::File.validate(path).is_ok());
+        assert!(ParameterType::Dir.validate(path).is_err());
     }
 }
@@ -0,0 +1,108 @@
+use crate::core::shell::{command_binaries, command_segments};
+use std::fmt;
+
+/// Programs that run the rest of the command with elevated privileges
+const PRIVILEGE_ESCALATION_BINARIES: &[&str] = &["sudo", "doas", "pkexec", "su"];
+
+/// Paths that usually can't be changed without elevated privileges
+const SYSTEM_PATHS: &[&str] = &[
+    "/etc", "/boot", "/usr", "/bin", "/sbin", "/lib", "/lib64", "/sys", "/root", "/var/lib",
+];
+
+/// Reason why running a command needs a second thought
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrivilegeWarning {
+    /// The command runs a program like `sudo`
+    Escalation(String),
+    /// The command references a system path
+    SystemPath(String),
+}
+
+impl PrivilegeWarning {
+    /// Short badge to display next to a command
+    pub const fn badge(&self) -> &'static str {
+        match self {
+            Self::Escalation(_) => "[sudo]",
+            Self::SystemPath(_) => "[sys]",
+        }
+    }
+}
+
+impl fmt::Display for PrivilegeWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Escalation(binary) => write!(f, "runs with elevated privileges ({binary})"),
+            Self::SystemPath(path) => write!(f, "touches the system path {path}"),
+        }
+    }
+}
+
+/// Check if a command string runs with elevated privileges or touches system paths
+pub fn privilege_warning(command: &str) -> Option<PrivilegeWarning> {
+    if let Some(binary) = command_binaries(command)
+        .into_iter()
+        .find(|binary| PRIVILEGE_ESCALATION_BINARIES.contains(binary))
+    {
+        return Some(PrivilegeWarning::Escalation(binary.to_string()));
+    }
+    command_segments(command)
+        .into_iter()
+        .flat_map(str::split_whitespace)
+        // Paths can be attached to redirects or flags, e.g. `>/etc/hosts` or `--config=/etc/app.conf`
+        .filter_map(|word| {
+            let word = word.rsplit('=').next().unwrap_or(word);
+            let path = word
+                .trim_start_matches(['>', '<'])
+                .trim_matches(['"', '\'']);
+            is_system_path(path).then(|| path.to_string())
+        })
+        .map(PrivilegeWarning::SystemPath)
+        .next()
+}
+
+fn is_system_path(path: &str) -> bool {
+    SYSTEM_PATHS.iter().any(|system_path| {
+        path.strip_prefix(system_path)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    })
+}
+
+#[cfg(test)]
+mod test_privilege {
+    use super::*;
+
+    #[test]
+    fn detect_sudo() {
+        assert_eq!(
+            Some(PrivilegeWarning::Escalation(String::from("sudo"))),
+            privilege_warning("sudo apt update")
+        );
+    }
+
+    #[test]
+    fn detect_sudo_in_pipeline() {
+        let warning = privilege_warning("echo 127.0.0.1 | doas tee -a hosts");
+        assert_eq!(
+            Some(PrivilegeWarning::Escalation(String::from("doas"))),
+            warning
+        );
+    }
+
+    #[test]
+    fn detect_system_path() {
+        assert_eq!(
+            Some(PrivilegeWarning::SystemPath(String::from("/etc/hosts"))),
+            privilege_warning("echo 127.0.0.1 >>/etc/hosts")
+        );
+    }
+
+    #[test]
+    fn ignore_similar_paths() {
+        assert_eq!(None, privilege_warning("ls /etcetera /dev/null ~/usr"));
+    }
+
+    #[test]
+    fn no_warning() {
+        assert_eq!(None, privilege_warning("git status"));
+    }
+}
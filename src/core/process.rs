@@ -0,0 +1,556 @@
+use crate::core::encoding::{decode_line, OutputEncoding};
+use std::io::{self, BufRead, BufReader, Read};
+#[cfg(unix)]
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+#[cfg(windows)]
+use std::os::windows::process::ExitStatusExt;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// How often a running command is checked for having finished
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Time a cancelled or timed out command gets to shut down before it is killed
+#[cfg(unix)]
+const GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// Set by the `SIGINT` handler while hoard is waiting for a command
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" fn on_interrupt(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// How a command run by hoard ended
+#[derive(Debug)]
+pub enum RunOutcome {
+    /// The command finished on its own
+    Exited(ExitStatus),
+    /// The command ran longer than its timeout and was stopped
+    TimedOut,
+    /// The command was cancelled with Ctrl+C
+    Cancelled,
+}
+
+impl RunOutcome {
+    /// Exit code hoard hands back to its caller, following the conventions of shells and `timeout(1)`
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Exited(status) => status
+                .code()
+                .or_else(|| signal_of(*status).map(|signal| 128 + signal))
+                .unwrap_or(1),
+            Self::TimedOut => 124,
+            Self::Cancelled => 130,
+        }
+    }
+}
+
+/// Runs `process` in its own process group and waits for it to finish
+///
+/// While the command runs it is the foreground process group of the terminal, so Ctrl+C reaches the command and
+/// its children but not hoard. If the command times out or is cancelled, its whole process group is cleaned up.
+/// With an `encoding` the output of the command is decoded by hoard instead of going straight to the terminal
+pub fn run_process(
+    mut process: Command,
+    timeout: Option<Duration>,
+    encoding: Option<OutputEncoding>,
+) -> io::Result<RunOutcome> {
+    // Only hand over the terminal if hoard owns it, e.g. not when it was started in the background
+    #[cfg(unix)]
+    let hand_over_terminal = unsafe { libc::tcgetpgrp(libc::STDIN_FILENO) == libc::getpgrp() };
+    // Windows has no process groups to hand the console to
+    #[cfg(windows)]
+    let hand_over_terminal = false;
+    if encoding.is_some() {
+        process.stdout(Stdio::piped()).stderr(Stdio::piped());
+    }
+    let outcome = with_interrupt_handler(|| {
+        let mut running = RunningProcess::spawn(&mut process, hand_over_terminal, timeout)?;
+        let readers = encoding
+            .map(|encoding| forward_output(None, encoding, &mut running.child))
+            .unwrap_or_default();
+        loop {
+            if let Some(outcome) = running.poll()? {
+                for reader in readers {
+                    reader.join().ok();
+                }
+                return Ok(outcome);
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+    #[cfg(unix)]
+    if hand_over_terminal {
+        unsafe { take_terminal(libc::getpgrp()) };
+    }
+    outcome
+}
+
+/// Runs several labeled processes with at most `parallel` of them at the same time
+///
+/// The output of every process is decoded in `encoding` and printed line by line, prefixed with its label. Ctrl+C
+/// cancels all running processes and skips the ones that haven't started yet. Returns the outcomes in the order of
+/// `processes`
+pub fn run_parallel(
+    processes: Vec<(String, Command)>,
+    parallel: usize,
+    timeout: Option<Duration>,
+    encoding: OutputEncoding,
+) -> io::Result<Vec<(String, RunOutcome)>> {
+    with_interrupt_handler(|| {
+        let mut pending = processes.into_iter().enumerate();
+        let mut running: Vec<(usize, String, RunningProcess, Vec<JoinHandle<()>>)> = Vec::new();
+        let mut finished: Vec<(usize, String, RunOutcome)> = Vec::new();
+        loop {
+            while running.len() < parallel.max(1) {
+                let Some((index, (label, mut process))) = pending.next() else {
+                    break;
+                };
+                if INTERRUPTED.load(Ordering::SeqCst) {
+                    finished.push((index, label, RunOutcome::Cancelled));
+                    continue;
+                }
+                process
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped());
+                match RunningProcess::spawn(&mut process, false, timeout) {
+                    Ok(mut started) => {
+                        let readers = forward_output(Some(&label), encoding, &mut started.child);
+                        running.push((index, label, started, readers));
+                    }
+                    Err(e) => {
+                        eprintln!("[{label}] could not be started: {e}");
+                        // Same exit code a shell uses for commands it can't run
+                        let status = exit_status(127);
+                        finished.push((index, label, RunOutcome::Exited(status)));
+                    }
+                }
+            }
+            if running.is_empty() {
+                break;
+            }
+            let mut i = 0;
+            while i < running.len() {
+                if let Some(outcome) = running[i].2.poll()? {
+                    let (index, label, _, readers) = running.remove(i);
+                    for reader in readers {
+                        reader.join().ok();
+                    }
+                    finished.push((index, label, outcome));
+                } else {
+                    i += 1;
+                }
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        finished.sort_by_key(|(index, _, _)| *index);
+        Ok(finished
+            .into_iter()
+            .map(|(_, label, outcome)| (label, outcome))
+            .collect())
+    })
+}
+
+/// A line a command wrote, decoded in the encoding of the command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputLine {
+    pub text: String,
+    /// Written to stderr instead of stdout
+    pub is_error: bool,
+}
+
+/// A command in its own process group whose output is collected line by line instead of going to the terminal, as in
+/// the output pane of the GUI
+///
+/// It doesn't get the terminal, so Ctrl+C has to be passed on with `cancel`
+pub struct StreamedProcess {
+    running: RunningProcess,
+    lines: Receiver<OutputLine>,
+    readers: Vec<JoinHandle<()>>,
+}
+
+impl StreamedProcess {
+    pub fn spawn(
+        mut process: Command,
+        timeout: Option<Duration>,
+        encoding: OutputEncoding,
+    ) -> io::Result<Self> {
+        INTERRUPTED.store(false, Ordering::SeqCst);
+        process
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let mut running = RunningProcess::spawn(&mut process, false, timeout)?;
+        let (sender, lines) = mpsc::channel();
+        let readers = collect_output(encoding, &mut running.child, &sender);
+        Ok(Self {
+            running,
+            lines,
+            readers,
+        })
+    }
+
+    /// Lines written since the last call
+    pub fn lines(&self) -> impl Iterator<Item = OutputLine> + '_ {
+        self.lines.try_iter()
+    }
+
+    /// Check if the command has finished, stopping it if it timed out. Its last lines are ready once it has
+    pub fn poll(&mut self) -> io::Result<Option<RunOutcome>> {
+        let outcome = self.running.poll()?;
+        if outcome.is_some() {
+            self.join_readers();
+        }
+        Ok(outcome)
+    }
+
+    /// Stop the command and everything it started, like Ctrl+C in the terminal would
+    pub fn cancel(&mut self) -> io::Result<RunOutcome> {
+        self.running.stop(INTERRUPT)?;
+        self.join_readers();
+        Ok(RunOutcome::Cancelled)
+    }
+
+    fn join_readers(&mut self) {
+        for reader in self.readers.drain(..) {
+            reader.join().ok();
+        }
+    }
+}
+
+/// Sends every line a process writes decoded in `encoding` to `sender`
+fn collect_output(
+    encoding: OutputEncoding,
+    child: &mut Child,
+    sender: &Sender<OutputLine>,
+) -> Vec<JoinHandle<()>> {
+    let mut readers = Vec::new();
+    let streams: [(Option<Box<dyn Read + Send>>, bool); 2] = [
+        (child.stdout.take().map(|out| Box::new(out) as _), false),
+        (child.stderr.take().map(|err| Box::new(err) as _), true),
+    ];
+    for (stream, is_error) in streams {
+        let Some(stream) = stream else {
+            continue;
+        };
+        let sender = sender.clone();
+        readers.push(thread::spawn(move || {
+            for_each_line(stream, |line| {
+                let text = decode_line(line, encoding);
+                sender.send(OutputLine { text, is_error }).ok();
+            });
+        }));
+    }
+    readers
+}
+
+/// Catches `SIGINT` while `f` runs, so hoard can clean up the commands it started instead of exiting
+#[cfg(unix)]
+fn with_interrupt_handler<T>(f: impl FnOnce() -> T) -> T {
+    INTERRUPTED.store(false, Ordering::SeqCst);
+    let handler = on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    let previous_handler = unsafe { libc::signal(libc::SIGINT, handler) };
+    let result = f();
+    unsafe {
+        libc::signal(libc::SIGINT, previous_handler);
+    }
+    result
+}
+
+/// Runs `f`, Ctrl+C on Windows reaches hoard and the commands it started alike
+#[cfg(windows)]
+fn with_interrupt_handler<T>(f: impl FnOnce() -> T) -> T {
+    INTERRUPTED.store(false, Ordering::SeqCst);
+    f()
+}
+
+/// Signal a process was killed with, Windows has none
+#[cfg(unix)]
+fn signal_of(status: ExitStatus) -> Option<i32> {
+    status.signal()
+}
+
+#[cfg(windows)]
+const fn signal_of(_: ExitStatus) -> Option<i32> {
+    None
+}
+
+/// Status of a process that exited with `code`
+#[cfg(unix)]
+fn exit_status(code: i32) -> ExitStatus {
+    ExitStatus::from_raw(code << 8)
+}
+
+#[cfg(windows)]
+fn exit_status(code: u32) -> ExitStatus {
+    ExitStatus::from_raw(code)
+}
+
+/// Makes `pgid` the foreground process group of the terminal
+///
+/// Has to ignore `SIGTTOU`, otherwise a background process group is stopped when it tries to take the terminal
+#[cfg(unix)]
+unsafe fn take_terminal(pgid: libc::pid_t) {
+    let previous_handler = libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+    libc::tcsetpgrp(libc::STDIN_FILENO, pgid);
+    libc::signal(libc::SIGTTOU, previous_handler);
+}
+
+/// Prints every line a process writes decoded in `encoding`, prefixed with `label` if there is one
+fn forward_output(
+    label: Option<&str>,
+    encoding: OutputEncoding,
+    child: &mut Child,
+) -> Vec<JoinHandle<()>> {
+    let prefix = label.map(|label| format!("[{label}] ")).unwrap_or_default();
+    let mut readers = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        let prefix = prefix.clone();
+        readers.push(thread::spawn(move || {
+            for_each_line(stdout, |line| {
+                println!("{prefix}{}", decode_line(line, encoding));
+            });
+        }));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        readers.push(thread::spawn(move || {
+            for_each_line(stderr, |line| {
+                eprintln!("{prefix}{}", decode_line(line, encoding));
+            });
+        }));
+    }
+    readers
+}
+
+/// Calls `f` with the raw bytes of every line `reader` yields, until it is closed
+fn for_each_line(reader: impl Read, mut f: impl FnMut(&[u8])) {
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    while matches!(reader.read_until(b'\n', &mut line), Ok(read) if read > 0) {
+        f(&line);
+        line.clear();
+    }
+}
+
+/// A command running in its own process group, a plain child process on Windows
+struct RunningProcess {
+    child: Child,
+    #[cfg(unix)]
+    pgid: libc::pid_t,
+    started: Instant,
+    timeout: Option<Duration>,
+}
+
+/// Signal to stop a process with, every process is killed at once on Windows
+#[cfg(unix)]
+type Signal = libc::c_int;
+#[cfg(windows)]
+type Signal = ();
+
+#[cfg(unix)]
+const INTERRUPT: Signal = libc::SIGINT;
+#[cfg(unix)]
+const TERMINATE: Signal = libc::SIGTERM;
+#[cfg(windows)]
+const INTERRUPT: Signal = ();
+#[cfg(windows)]
+const TERMINATE: Signal = ();
+
+impl RunningProcess {
+    #[cfg(unix)]
+    fn spawn(
+        process: &mut Command,
+        hand_over_terminal: bool,
+        timeout: Option<Duration>,
+    ) -> io::Result<Self> {
+        unsafe {
+            process.pre_exec(move || {
+                if libc::setpgid(0, 0) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                if hand_over_terminal && libc::isatty(libc::STDIN_FILENO) == 1 {
+                    take_terminal(libc::getpgrp());
+                }
+                Ok(())
+            });
+        }
+        let child = process.spawn()?;
+        let pgid = libc::pid_t::try_from(child.id()).expect("process ids fit into pid_t");
+        Ok(Self {
+            child,
+            pgid,
+            started: Instant::now(),
+            timeout,
+        })
+    }
+
+    #[cfg(windows)]
+    fn spawn(
+        process: &mut Command,
+        _hand_over_terminal: bool,
+        timeout: Option<Duration>,
+    ) -> io::Result<Self> {
+        Ok(Self {
+            child: process.spawn()?,
+            started: Instant::now(),
+            timeout,
+        })
+    }
+
+    /// Check if the process has finished, stopping it if it timed out or hoard was interrupted
+    fn poll(&mut self) -> io::Result<Option<RunOutcome>> {
+        if let Some(status) = self.child.try_wait()? {
+            #[cfg(unix)]
+            if status.signal() == Some(libc::SIGINT) {
+                // Ctrl+C in the terminal reaches the command directly, make sure nothing it started is left behind
+                signal_group(self.pgid, libc::SIGKILL);
+                return Ok(Some(RunOutcome::Cancelled));
+            }
+            return Ok(Some(RunOutcome::Exited(status)));
+        }
+        if INTERRUPTED.load(Ordering::SeqCst) {
+            self.stop(INTERRUPT)?;
+            return Ok(Some(RunOutcome::Cancelled));
+        }
+        if self
+            .timeout
+            .is_some_and(|timeout| self.started.elapsed() >= timeout)
+        {
+            self.stop(TERMINATE)?;
+            return Ok(Some(RunOutcome::TimedOut));
+        }
+        Ok(None)
+    }
+
+    /// Sends `signal` to the process group and kills the group if it doesn't exit within the grace period
+    #[cfg(unix)]
+    fn stop(&mut self, signal: Signal) -> io::Result<()> {
+        signal_group(self.pgid, signal);
+        let deadline = Instant::now() + GRACE_PERIOD;
+        while self.child.try_wait()?.is_none() {
+            if Instant::now() >= deadline {
+                signal_group(self.pgid, libc::SIGKILL);
+                self.child.wait()?;
+                break;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        signal_group(self.pgid, libc::SIGKILL);
+        Ok(())
+    }
+
+    /// Kills the process, Windows has no signal to ask it to exit first
+    #[cfg(windows)]
+    fn stop(&mut self, (): Signal) -> io::Result<()> {
+        // It may have exited in the meantime, which is fine
+        self.child.kill().ok();
+        self.child.wait()?;
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn signal_group(pgid: libc::pid_t, signal: libc::c_int) {
+    // The group may already be gone, which is fine
+    unsafe {
+        libc::kill(-pgid, signal);
+    }
+}
+
+// The tests run their commands with `sh`
+#[cfg(all(test, unix))]
+mod test_process {
+    use super::*;
+
+    fn sh(command: &str) -> Command {
+        let mut process = Command::new("sh");
+        process.args(["-c", command]).stdin(Stdio::null());
+        process
+    }
+
+    #[test]
+    fn exit_code_is_passed_on() {
+        let outcome = run_process(sh("exit 3"), None, None).unwrap();
+        assert_eq!(3, outcome.exit_code());
+    }
+
+    #[test]
+    fn command_times_out() {
+        let started = Instant::now();
+        let outcome = run_process(sh("sleep 10"), Some(Duration::from_millis(100)), None).unwrap();
+        assert!(matches!(outcome, RunOutcome::TimedOut));
+        assert_eq!(124, outcome.exit_code());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn parallel_outcomes_keep_their_order() {
+        let processes = vec![
+            (String::from("slow"), sh("sleep 0.2; exit 1")),
+            (String::from("fast"), sh("exit 0")),
+            (String::from("timeout"), sh("sleep 10")),
+        ];
+        let outcomes = run_parallel(
+            processes,
+            3,
+            Some(Duration::from_millis(500)),
+            OutputEncoding::Auto,
+        )
+        .unwrap();
+        let labels: Vec<_> = outcomes.iter().map(|(label, _)| label.as_str()).collect();
+        let codes: Vec<_> = outcomes
+            .iter()
+            .map(|(_, outcome)| outcome.exit_code())
+            .collect();
+        assert_eq!(vec!["slow", "fast", "timeout"], labels);
+        assert_eq!(vec![1, 0, 124], codes);
+    }
+
+    #[test]
+    fn stream_output_of_a_command() {
+        let mut process = StreamedProcess::spawn(
+            sh("echo one; echo two >&2; exit 4"),
+            None,
+            OutputEncoding::Auto,
+        )
+        .unwrap();
+        let outcome = loop {
+            if let Some(outcome) = process.poll().unwrap() {
+                break outcome;
+            }
+            thread::sleep(POLL_INTERVAL);
+        };
+        assert_eq!(4, outcome.exit_code());
+        let mut lines: Vec<OutputLine> = process.lines().collect();
+        lines.sort_by_key(|line| line.is_error);
+        assert_eq!(
+            vec![("one", false), ("two", true)],
+            lines
+                .iter()
+                .map(|line| (line.text.as_str(), line.is_error))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn cancel_streamed_command() {
+        let started = Instant::now();
+        let mut process =
+            StreamedProcess::spawn(sh("sleep 10"), None, OutputEncoding::Auto).unwrap();
+        assert!(process.poll().unwrap().is_none());
+        assert_eq!(130, process.cancel().unwrap().exit_code());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn command_within_timeout_exits() {
+        let outcome = run_process(sh("true"), Some(Duration::from_secs(10)), None).unwrap();
+        assert!(matches!(outcome, RunOutcome::Exited(status) if status.success()));
+    }
+}
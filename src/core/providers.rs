@@ -0,0 +1,205 @@
+use crate::config::HoardConfig;
+use crate::core::shell::{default_shell, shell_command};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::process::Stdio;
+
+/// Name of the provider that runs the shell command of `#name:$(command)!`
+pub const SHELL_PROVIDER: &str = "shell";
+
+/// Source of the values offered for a parameter, e.g. the contexts of `kubectl`
+pub trait ParameterProvider {
+    /// Values to offer. `argument` is given by some parameters, like the command of `#branch:$(git branch)!`
+    fn values(&self, argument: Option<&str>) -> Vec<String>;
+}
+
+/// Provider a parameter takes its values from, `#host@consul!` is provided by `consul`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProviderRef {
+    pub name: String,
+    pub argument: Option<String>,
+}
+
+impl ProviderRef {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            argument: None,
+        }
+    }
+
+    /// Provider running the shell `command` for its values
+    pub fn shell(command: &str) -> Self {
+        Self {
+            name: SHELL_PROVIDER.to_string(),
+            argument: Some(command.to_string()),
+        }
+    }
+}
+
+/// Runs a shell command and offers its non-empty output lines
+///
+/// The command is either fixed, for providers configured in `parameter_providers`, or given by the parameter
+pub struct CommandProvider {
+    command: Option<String>,
+}
+
+impl CommandProvider {
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: Some(command.to_string()),
+        }
+    }
+}
+
+impl ParameterProvider for CommandProvider {
+    fn values(&self, argument: Option<&str>) -> Vec<String> {
+        let Some(command) = argument.or(self.command.as_deref()) else {
+            return Vec::new();
+        };
+        let output = shell_command(&default_shell(), command)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output();
+        match output {
+            Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(ToString::to_string)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Offers the names of the environment variables
+pub struct EnvProvider;
+
+impl ParameterProvider for EnvProvider {
+    fn values(&self, _argument: Option<&str>) -> Vec<String> {
+        let mut names: Vec<String> = std::env::vars().map(|(name, _)| name).collect();
+        names.sort();
+        names
+    }
+}
+
+/// Offers the files and directories of the current directory
+pub struct FileProvider;
+
+impl ParameterProvider for FileProvider {
+    fn values(&self, _argument: Option<&str>) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(".")
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let name = entry.file_name().to_str()?.to_string();
+                let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+                Some(if is_dir { format!("{name}/") } else { name })
+            })
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+/// Providers that come with hoard and run a fixed command
+const BUILTIN_COMMAND_PROVIDERS: &[(&str, &str)] = &[
+    ("kube-contexts", "kubectl config get-contexts -o name"),
+    (
+        "kube-namespaces",
+        "kubectl get namespaces --no-headers -o custom-columns=:metadata.name",
+    ),
+    ("docker-containers", "docker ps --format '{{.Names}}'"),
+    (
+        "docker-images",
+        "docker images --format '{{.Repository}}:{{.Tag}}'",
+    ),
+    ("git-branches", "git branch --format '%(refname:short)'"),
+];
+
+/// Parameter providers by name
+pub struct ProviderRegistry {
+    providers: HashMap<String, Box<dyn ParameterProvider>>,
+}
+
+impl Default for ProviderRegistry {
+    /// Registry with the providers that come with hoard
+    fn default() -> Self {
+        let mut registry = Self {
+            providers: HashMap::new(),
+        };
+        registry.register(SHELL_PROVIDER, Box::new(CommandProvider { command: None }));
+        registry.register("env", Box::new(EnvProvider));
+        registry.register("files", Box::new(FileProvider));
+        for (name, command) in BUILTIN_COMMAND_PROVIDERS {
+            registry.register(name, Box::new(CommandProvider::new(command)));
+        }
+        registry
+    }
+}
+
+impl ProviderRegistry {
+    /// Registry with the providers that come with hoard and the ones in `parameter_providers` of the config
+    pub fn from_config(config: &HoardConfig) -> Self {
+        let mut registry = Self::default();
+        for (name, command) in config.parameter_providers.iter().flatten() {
+            registry.register(name, Box::new(CommandProvider::new(command)));
+        }
+        registry
+    }
+
+    /// Add a provider, replacing the one registered with the same name before
+    pub fn register(&mut self, name: &str, provider: Box<dyn ParameterProvider>) {
+        self.providers.insert(name.to_string(), provider);
+    }
+
+    /// Values of the provider `provider` refers to, none if there is no such provider
+    pub fn values(&self, provider: &ProviderRef) -> Vec<String> {
+        self.providers
+            .get(&provider.name)
+            .map(|registered| registered.values(provider.argument.as_deref()))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test_providers {
+    use super::*;
+
+    struct Fixed;
+
+    impl ParameterProvider for Fixed {
+        fn values(&self, argument: Option<&str>) -> Vec<String> {
+            vec![format!("db1{}", argument.unwrap_or_default())]
+        }
+    }
+
+    #[test]
+    fn registered_providers_offer_values() {
+        let mut registry = ProviderRegistry::default();
+        registry.register("consul", Box::new(Fixed));
+        assert_eq!(vec!["db1"], registry.values(&ProviderRef::new("consul")));
+        assert_eq!(
+            vec!["dev", "prod"],
+            registry.values(&ProviderRef::shell("printf 'dev\\n\\nprod\\n'"))
+        );
+        assert!(registry.values(&ProviderRef::shell("exit 1")).is_empty());
+    }
+
+    #[test]
+    fn configured_providers_are_commands() {
+        let config = HoardConfig {
+            parameter_providers: Some(HashMap::from([(
+                String::from("hosts"),
+                String::from("echo web1"),
+            )])),
+            ..HoardConfig::default()
+        };
+        let registry = ProviderRegistry::from_config(&config);
+        assert_eq!(vec!["web1"], registry.values(&ProviderRef::new("hosts")));
+        assert!(registry.values(&ProviderRef::new("unknown")).is_empty());
+    }
+}
@@ -0,0 +1,152 @@
+use crate::core::HoardCmd;
+
+/// A term of a query, either a filter on one field or text that can be in any of them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Term {
+    /// `tag:docker`, a tag of the command
+    Tag(String),
+    /// `ns:k8s`, the namespace of the command
+    Namespace(String),
+    /// `name:deploy`, part of the name
+    Name(String),
+    /// `desc:restart`, part of the description
+    Description(String),
+    /// `cmd:kubectl`, part of the command
+    Command(String),
+    /// Anything else
+    Text(String),
+}
+
+/// What is searched for in the search field of the GUI and with `--filter`, e.g. `tag:docker ns:k8s logs`
+///
+/// Terms are separated by whitespace, quotes keep whitespace in a term like `desc:"restart pods"`. A command
+/// matches if it matches every term, filters match part of their field ignoring the case
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    pub terms: Vec<Term>,
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Self {
+        let terms = split_terms(query)
+            .into_iter()
+            .map(|term| match term.split_once(':') {
+                Some(("tag", tag)) if !tag.is_empty() => Term::Tag(tag.to_string()),
+                Some(("ns" | "namespace", namespace)) if !namespace.is_empty() => {
+                    Term::Namespace(namespace.to_string())
+                }
+                Some(("name", name)) if !name.is_empty() => Term::Name(name.to_string()),
+                Some(("desc" | "description", description)) if !description.is_empty() => {
+                    Term::Description(description.to_string())
+                }
+                Some(("cmd" | "command", command)) if !command.is_empty() => {
+                    Term::Command(command.to_string())
+                }
+                _ => Term::Text(term),
+            })
+            .collect();
+        Self { terms }
+    }
+
+    /// Whether `command` matches every term, text has to be part of any field
+    pub fn matches(&self, command: &HoardCmd) -> bool {
+        self.matches_filters(command)
+            && self.text().all(|text| {
+                command.name.contains(text)
+                    || command.namespace.contains(text)
+                    || command.get_tags_as_string().contains(text)
+                    || command.command.contains(text)
+                    || command.description.contains(text)
+            })
+    }
+
+    /// Whether `command` matches every term that filters on a field, text is left to be matched another way
+    pub fn matches_filters(&self, command: &HoardCmd) -> bool {
+        self.terms.iter().all(|term| match term {
+            Term::Tag(tag) => command.tags.iter().any(|t| contains_ignoring_case(t, tag)),
+            Term::Namespace(namespace) => contains_ignoring_case(&command.namespace, namespace),
+            Term::Name(name) => contains_ignoring_case(&command.name, name),
+            Term::Description(description) => {
+                contains_ignoring_case(&command.description, description)
+            }
+            Term::Command(part) => contains_ignoring_case(&command.command, part),
+            Term::Text(_) => true,
+        })
+    }
+
+    /// The terms that don't filter on a field
+    pub fn text(&self) -> impl Iterator<Item = &str> {
+        self.terms.iter().filter_map(|term| match term {
+            Term::Text(text) => Some(text.as_str()),
+            _ => None,
+        })
+    }
+}
+
+fn contains_ignoring_case(text: &str, part: &str) -> bool {
+    text.to_lowercase().contains(&part.to_lowercase())
+}
+
+/// Terms of `query` split at whitespace outside of double quotes, the quotes are removed
+fn split_terms(query: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut term = String::new();
+    let mut quoted = false;
+    for c in query.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !term.is_empty() {
+                    terms.push(std::mem::take(&mut term));
+                }
+            }
+            c => term.push(c),
+        }
+    }
+    if !term.is_empty() {
+        terms.push(term);
+    }
+    terms
+}
+
+#[cfg(test)]
+mod test_query {
+    use super::*;
+
+    #[test]
+    fn parse_terms() {
+        assert_eq!(
+            vec![
+                Term::Tag(String::from("docker")),
+                Term::Namespace(String::from("k8s")),
+                Term::Description(String::from("restart pods")),
+                Term::Text(String::from("logs")),
+                Term::Text(String::from("tag:")),
+                Term::Text(String::from("http://host")),
+            ],
+            Query::parse("tag:docker  ns:k8s desc:\"restart pods\" logs tag: http://host").terms
+        );
+        assert!(Query::parse("  ").terms.is_empty());
+    }
+
+    #[test]
+    fn match_filters_and_text() {
+        let command = HoardCmd::default()
+            .with_name("restart")
+            .with_namespace("k8s")
+            .with_command("kubectl rollout restart deploy")
+            .with_description("Restart the pods of a deployment")
+            .with_tags_raw("kubernetes,ops");
+        assert!(Query::parse("tag:ops ns:k8s desc:pods rollout").matches(&command));
+        assert!(Query::parse("cmd:kubectl name:start").matches(&command));
+        assert!(!Query::parse("tag:docker").matches(&command));
+        assert!(!Query::parse("ns:helm rollout").matches(&command));
+        assert!(Query::parse("tag:OPS ns:K8 name:Start desc:POD cmd:Kubectl").matches(&command));
+        assert!(!Query::parse("tag:ops helm").matches(&command));
+        assert!(Query::parse("tag:ops helm").matches_filters(&command));
+        assert_eq!(
+            vec!["helm"],
+            Query::parse("tag:ops helm").text().collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use crate::core::error::HoardErr;
+use regex::{Regex, RegexBuilder};
+
+/// A sed style substitution like `s/us-east-1/eu-west-1/g`, as given to `hoard replace`
+///
+/// The pattern is a regex with groups like `(\w+)`. Any character can separate the parts instead of `/`. The flag `g`
+/// replaces every match instead of the first one and `i` ignores case. In the replacement `&` stands for the match
+/// and `\1` to `\9` for its groups
+#[derive(Debug)]
+pub struct Substitution {
+    pattern: Regex,
+    replacement: String,
+    global: bool,
+}
+
+impl Substitution {
+    pub fn parse(expression: &str) -> Result<Self, HoardErr> {
+        let invalid = |reason: &str| {
+            HoardErr::new(&format!(
+                "Invalid substitution '{expression}', {reason}. Write it like s/old/new/g"
+            ))
+        };
+        let Some(rest) = expression.strip_prefix('s') else {
+            return Err(invalid("it has to start with s"));
+        };
+        let Some(separator) = rest
+            .chars()
+            .next()
+            .filter(|c| !c.is_alphanumeric() && !c.is_whitespace() && *c != '\\')
+        else {
+            return Err(invalid("the s has to be followed by a separator like /"));
+        };
+        let parts = split_unescaped(&rest[separator.len_utf8()..], separator);
+        let (pattern, replacement, flags) = match parts.as_slice() {
+            [pattern, replacement] => (pattern, replacement, ""),
+            [pattern, replacement, flags] => (pattern, replacement, flags.as_str()),
+            _ => {
+                return Err(invalid(
+                    "it needs a pattern, a replacement and optional flags",
+                ))
+            }
+        };
+        if pattern.is_empty() {
+            return Err(invalid("the pattern is empty"));
+        }
+        let mut global = false;
+        let mut ignore_case = false;
+        for flag in flags.chars() {
+            match flag {
+                'g' => global = true,
+                'i' => ignore_case = true,
+                _ => return Err(invalid(&format!("the flag {flag} is unknown"))),
+            }
+        }
+        let escaped_separator = format!("\\{separator}");
+        let pattern = RegexBuilder::new(
+            &pattern.replace(&escaped_separator, &regex::escape(&separator.to_string())),
+        )
+        .case_insensitive(ignore_case)
+        .build()
+        .map_err(|e| invalid(&format!("the pattern is no valid regex: {e}")))?;
+        Ok(Self {
+            pattern,
+            replacement: regex_replacement(replacement),
+            global,
+        })
+    }
+
+    /// The text with the substitution applied, `None` if it doesn't change the text
+    pub fn apply(&self, text: &str) -> Option<String> {
+        let replaced = if self.global {
+            self.pattern.replace_all(text, self.replacement.as_str())
+        } else {
+            self.pattern.replace(text, self.replacement.as_str())
+        };
+        (replaced != text).then(|| replaced.into_owned())
+    }
+}
+
+/// Split `text` at every `separator` that isn't escaped with a backslash. Escapes are kept for the parts
+fn split_unescaped(text: &str, separator: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        let part = parts.last_mut().expect("there is always a part");
+        if c == '\\' {
+            part.push(c);
+            if let Some(escaped) = chars.next() {
+                part.push(escaped);
+            }
+        } else if c == separator {
+            parts.push(String::new());
+        } else {
+            part.push(c);
+        }
+    }
+    parts
+}
+
+/// Turn a sed replacement into one of the regex crate, e.g. `\1-&` into `${1}-${0}`
+fn regex_replacement(replacement: &str) -> String {
+    let mut converted = String::new();
+    let mut chars = replacement.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(group @ '0'..='9') => {
+                    converted.push_str("${");
+                    converted.push(group);
+                    converted.push('}');
+                }
+                Some('n') => converted.push('\n'),
+                Some('$') => converted.push_str("$$"),
+                Some(escaped) => converted.push(escaped),
+                None => converted.push('\\'),
+            },
+            '&' => converted.push_str("${0}"),
+            '$' => converted.push_str("$$"),
+            _ => converted.push(c),
+        }
+    }
+    converted
+}
+
+#[cfg(test)]
+mod test_replace {
+    use super::*;
+
+    #[test]
+    fn substitutions_replace_like_sed() {
+        let region = Substitution::parse("s/us-east-1/eu-west-1/g").unwrap();
+        assert_eq!(
+            Some(String::from("aws --region eu-west-1 && echo eu-west-1")),
+            region.apply("aws --region us-east-1 && echo us-east-1")
+        );
+        assert_eq!(None, region.apply("aws --region eu-west-1"));
+
+        let registry =
+            Substitution::parse(r"s|(docker\.io)/(\w*)|ghcr.io/\2 (was &, $HOME)|i").unwrap();
+        assert_eq!(
+            Some(String::from(
+                "pull ghcr.io/app (was DOCKER.IO/app, $HOME) DOCKER.IO/db"
+            )),
+            registry.apply("pull DOCKER.IO/app DOCKER.IO/db")
+        );
+        let path = Substitution::parse(r"s/\/tmp\//\/var\/tmp\//").unwrap();
+        assert_eq!(Some(String::from("ls /var/tmp/")), path.apply("ls /tmp/"));
+    }
+
+    #[test]
+    fn invalid_substitutions() {
+        for expression in [
+            "us-east-1/eu-west-1",
+            "s/a",
+            "s//b/",
+            "s/a/b/x",
+            "s/(/b/",
+            "sa",
+        ] {
+            assert!(Substitution::parse(expression).is_err(), "{expression}");
+        }
+    }
+}
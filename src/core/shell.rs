@@ -0,0 +1,407 @@
+use crate::util::find_in_path;
+use std::io::IsTerminal;
+use std::process::Command;
+
+/// Flag appended to a shell that is configured without one, e.g. `bash` becomes `bash -c`
+const DEFAULT_COMMAND_FLAG: &str = "-c";
+
+/// Shell used for commands that don't specify their own
+///
+/// Reads out `$SHELL` and falls back to `sh` if it is not set
+pub fn default_shell() -> String {
+    let shell = std::env::var("SHELL").unwrap_or_default();
+    if shell.trim().is_empty() {
+        return format!("sh {DEFAULT_COMMAND_FLAG}");
+    }
+    format!("{} {DEFAULT_COMMAND_FLAG}", shell.trim())
+}
+
+/// Splits a shell invocation such as `zsh -ic` or `pwsh -Command` into the program and its arguments
+///
+/// If only the program is given, `-c` is used as the flag to pass the command string with
+pub fn split_shell(shell: &str) -> (String, Vec<String>) {
+    let mut parts = shell.split_whitespace().map(ToString::to_string);
+    let program = parts.next().unwrap_or_else(|| String::from("sh"));
+    let mut args: Vec<String> = parts.collect();
+    if args.is_empty() {
+        args.push(DEFAULT_COMMAND_FLAG.to_string());
+    }
+    (program, args)
+}
+
+/// Builds a process that runs `command` with the given `shell` invocation
+pub fn shell_command(shell: &str, command: &str) -> Command {
+    let (program, args) = split_shell(shell);
+    let mut process = Command::new(program);
+    process.args(args).arg(command);
+    process
+}
+
+/// Operators that separate the commands of a pipeline or command list
+const COMMAND_SEPARATORS: &[&str] = &["&&", "||", "|", ";"];
+
+/// Splits a command string into the single commands of its pipelines and command lists
+///
+/// `ls -l | grep foo && echo done` is split into `ls -l`, `grep foo` and `echo done`
+pub fn command_segments(command: &str) -> Vec<&str> {
+    let mut segments = vec![command];
+    for separator in COMMAND_SEPARATORS {
+        segments = segments
+            .into_iter()
+            .flat_map(|segment| segment.split(separator))
+            .collect();
+    }
+    segments
+        .into_iter()
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+/// Number of commands in the longest pipeline of a command string
+///
+/// `ls | grep foo | wc -l && echo done` has a pipe depth of 3
+pub fn pipe_depth(command: &str) -> usize {
+    let mut lists = vec![command];
+    for separator in ["&&", "||", ";"] {
+        lists = lists
+            .into_iter()
+            .flat_map(|list| list.split(separator))
+            .collect();
+    }
+    lists
+        .into_iter()
+        .map(|list| {
+            list.split('|')
+                .filter(|segment| !segment.trim().is_empty())
+                .count()
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Reasons why a command is hard to read as a one-liner, empty if it is fine
+pub fn complexity_warnings(command: &str, max_length: usize, max_pipe_depth: usize) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let length = command.chars().count();
+    if length > max_length {
+        warnings.push(format!(
+            "is {length} characters long (more than {max_length})"
+        ));
+    }
+    let depth = pipe_depth(command);
+    if depth > max_pipe_depth {
+        warnings.push(format!(
+            "pipes {depth} commands (more than {max_pipe_depth})"
+        ));
+    }
+    warnings
+}
+
+/// Returns the program each segment of a command string runs, skipping leading `VAR=value` assignments
+pub fn command_binaries(command: &str) -> Vec<&str> {
+    command_segments(command)
+        .into_iter()
+        .filter_map(|segment| segment.split_whitespace().find(|word| !word.contains('=')))
+        .collect()
+}
+
+/// Builtins and keywords of common shells, which are not looked up in `$PATH`
+const SHELL_BUILTINS: &[&str] = &[
+    ".", ":", "[", "[[", "alias", "bg", "case", "cd", "declare", "do", "done", "echo", "elif",
+    "else", "esac", "eval", "exec", "exit", "export", "false", "fg", "fi", "for", "function",
+    "history", "if", "jobs", "kill", "local", "printf", "pwd", "read", "return", "set", "shift",
+    "source", "test", "then", "time", "trap", "true", "type", "ulimit", "umask", "unset", "until",
+    "wait", "while",
+];
+
+/// Whether `binary` is a path to a file or found in `$PATH`
+pub fn is_installed(binary: &str) -> bool {
+    if binary.contains('/') {
+        std::path::Path::new(binary).exists()
+    } else {
+        find_in_path(binary).is_some()
+    }
+}
+
+/// Returns the programs of a command string that can't be found, e.g. `kubectl` on a machine without it
+///
+/// Builtins and words that are only known when the command runs, like parameters or `$VARIABLES`, are skipped. The
+/// others are looked up with `is_installed`
+pub fn missing_binaries(
+    command: &str,
+    token: &str,
+    mut is_installed: impl FnMut(&str) -> bool,
+) -> Vec<String> {
+    let mut missing: Vec<String> = Vec::new();
+    for binary in command_binaries(command) {
+        let binary = binary.trim_start_matches(['(', '{', '!']);
+        let is_unknown_yet = binary.is_empty()
+            || binary.starts_with('~')
+            || binary.contains(['$', '`', '"', '\''])
+            || binary.contains(token);
+        if is_unknown_yet || SHELL_BUILTINS.contains(&binary) {
+            continue;
+        }
+        if !is_installed(binary) && !missing.iter().any(|m| m == binary) {
+            missing.push(binary.to_string());
+        }
+    }
+    missing
+}
+
+/// Resolves `$NAME` and `${NAME}` in a command string with `lookup`
+///
+/// Like in a shell, nothing is expanded inside of single quotes or after a backslash.
+/// Variables that `lookup` doesn't know are kept as they are, so the shell can still resolve them
+pub fn expand_env_vars(command: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(command.len());
+    let mut chars = command.char_indices();
+    let mut in_single_quotes = false;
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if !in_single_quotes => {
+                out.push(c);
+                if let Some((_, escaped)) = chars.next() {
+                    out.push(escaped);
+                }
+            }
+            '\'' => {
+                in_single_quotes = !in_single_quotes;
+                out.push(c);
+            }
+            '$' if !in_single_quotes => {
+                let rest = &command[i + 1..];
+                let (name, len) = rest.strip_prefix('{').map_or_else(
+                    || {
+                        let end = rest
+                            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                            .unwrap_or(rest.len());
+                        (&rest[..end], end)
+                    },
+                    |braced| {
+                        braced
+                            .find('}')
+                            .map_or(("", 0), |end| (&braced[..end], end + 2))
+                    },
+                );
+                let is_name = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+                match lookup(name).filter(|_| is_name) {
+                    Some(value) => {
+                        out.push_str(&value);
+                        // `len` counts bytes of ASCII characters, so it is the number of chars to skip
+                        for _ in 0..len {
+                            chars.next();
+                        }
+                    }
+                    None => out.push(c),
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Container runtimes that are looked up in `$PATH` if none is configured, in order of preference
+const CONTAINER_RUNTIMES: &[&str] = &["docker", "podman"];
+
+/// Returns the configured container runtime or the first one found in `$PATH`
+pub fn container_runtime(configured: Option<&str>) -> Option<String> {
+    configured.map_or_else(
+        || {
+            CONTAINER_RUNTIMES
+                .iter()
+                .find(|runtime| find_in_path(runtime).is_some())
+                .map(ToString::to_string)
+        },
+        |runtime| Some(runtime.to_string()),
+    )
+}
+
+/// Builds a process that runs `command` inside a throwaway container of `image`
+///
+/// The current working directory is mounted at the same path in the container and used as its working directory.
+/// Commands without their own shell are run with `sh -c`, since the user's shell is usually not available in the image.
+/// A terminal is only allocated if `with_terminal` is set and hoard itself runs in one
+pub fn container_command(
+    runtime: &str,
+    image: &str,
+    shell: Option<&str>,
+    command: &str,
+    with_terminal: bool,
+) -> Command {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let cwd = cwd.display();
+    let (program, args) = split_shell(shell.unwrap_or("sh -c"));
+
+    let mut process = Command::new(runtime);
+    process.args(["run", "--rm", "-i"]);
+    if with_terminal && std::io::stdin().is_terminal() {
+        process.arg("-t");
+    }
+    process
+        .args(["-v", &format!("{cwd}:{cwd}"), "-w", &cwd.to_string()])
+        .arg(image)
+        .arg(program)
+        .args(args)
+        .arg(command);
+    process
+}
+
+/// Version of the shell plugins printed by `hoard shell-config`. Bump it whenever the plugins change
+pub const SHELL_INTEGRATION_VERSION: u32 = 2;
+
+/// Environment variable the shell plugins set to their version
+pub const SHELL_INTEGRATION_VERSION_VAR: &str = "HOARD_SHELL_VERSION";
+
+/// Whether the shell plugin that set `version` is older than this binary. Plugins without a version predate it
+pub fn is_stale_shell_integration(version: Option<&str>) -> bool {
+    version
+        .and_then(|version| version.trim().parse::<u32>().ok())
+        .is_none_or(|version| version < SHELL_INTEGRATION_VERSION)
+}
+
+#[cfg(test)]
+mod test_shell {
+    use super::*;
+
+    #[test]
+    fn missing_binaries_of_pipeline() {
+        let missing = missing_binaries(
+            "cd /tmp && sh -c ls | hoard-missing-binary -n #lines! | $PAGER && ./not-there",
+            "#",
+            is_installed,
+        );
+        assert_eq!(vec!["hoard-missing-binary", "./not-there"], missing);
+        assert!(missing_binaries("#tool! --version", "#", is_installed).is_empty());
+        let mut looked_up = Vec::new();
+        missing_binaries("git add . && git commit", "#", |binary| {
+            looked_up.push(binary.to_string());
+            true
+        });
+        assert_eq!(vec!["git", "git"], looked_up);
+    }
+
+    #[test]
+    fn split_shell_with_flag() {
+        let (program, args) = split_shell("zsh -ic");
+        assert_eq!("zsh", program);
+        assert_eq!(vec!["-ic"], args);
+    }
+
+    #[test]
+    fn split_shell_without_flag() {
+        let (program, args) = split_shell("bash");
+        assert_eq!("bash", program);
+        assert_eq!(vec!["-c"], args);
+    }
+
+    #[test]
+    fn split_shell_multiple_flags() {
+        let (program, args) = split_shell("pwsh -NoProfile -Command");
+        assert_eq!("pwsh", program);
+        assert_eq!(vec!["-NoProfile", "-Command"], args);
+    }
+
+    #[test]
+    fn split_empty_shell() {
+        let (program, args) = split_shell("  ");
+        assert_eq!("sh", program);
+        assert_eq!(vec!["-c"], args);
+    }
+
+    #[test]
+    fn shell_command_appends_command() {
+        let process = shell_command("python3 -c", "print(1)");
+        let args: Vec<_> = process.get_args().collect();
+        assert_eq!("python3", process.get_program());
+        assert_eq!(vec!["-c", "print(1)"], args);
+    }
+
+    #[test]
+    fn container_command_runs_image_with_shell() {
+        let process = container_command("podman", "alpine:latest", None, "ls", true);
+        let args: Vec<_> = process.get_args().map(|a| a.to_str().unwrap()).collect();
+        assert_eq!("podman", process.get_program());
+        assert_eq!(["run", "--rm", "-i"], args[..3]);
+        assert_eq!(["alpine:latest", "sh", "-c", "ls"], args[args.len() - 4..]);
+    }
+
+    #[test]
+    fn command_segments_of_pipeline() {
+        let segments = command_segments("ls -l | grep foo && echo done; exit");
+        assert_eq!(vec!["ls -l", "grep foo", "echo done", "exit"], segments);
+    }
+
+    #[test]
+    fn pipe_depth_of_longest_pipeline() {
+        assert_eq!(3, pipe_depth("ls | grep foo | wc -l && echo done || exit"));
+        assert_eq!(1, pipe_depth("make || make clean"));
+        assert_eq!(
+            vec!["pipes 3 commands (more than 2)"],
+            complexity_warnings("ls | grep foo | wc -l", 100, 2)
+        );
+        assert!(complexity_warnings("ls -l", 100, 2).is_empty());
+    }
+
+    #[test]
+    fn command_binaries_skip_assignments() {
+        let binaries = command_binaries("FOO=bar cargo test | tee out.log");
+        assert_eq!(vec!["cargo", "tee"], binaries);
+    }
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "HOME" => Some(String::from("/home/alice")),
+            "KUBECONFIG" => Some(String::from("/tmp/kube")),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn expand_env_vars_in_command() {
+        assert_eq!(
+            "ls /home/alice/src --kubeconfig=/tmp/kube/config",
+            expand_env_vars("ls $HOME/src --kubeconfig=${KUBECONFIG}/config", lookup)
+        );
+    }
+
+    #[test]
+    fn expand_env_vars_keeps_unknown_quoted_and_escaped() {
+        let command = r#"echo $UNSET ${UNSET} '$HOME' \$HOME $1 "$""#;
+        assert_eq!(command, expand_env_vars(command, lookup));
+        assert_eq!(
+            "echo \"/home/alice\"",
+            expand_env_vars("echo \"$HOME\"", lookup)
+        );
+    }
+
+    #[test]
+    fn configured_container_runtime_is_used() {
+        assert_eq!(
+            Some(String::from("nerdctl")),
+            container_runtime(Some("nerdctl"))
+        );
+    }
+
+    #[test]
+    fn shell_plugins_set_current_version() {
+        let version = SHELL_INTEGRATION_VERSION.to_string();
+        for plugin in [
+            include_str!("../shell/hoard.bash"),
+            include_str!("../shell/hoard.zsh"),
+            include_str!("../shell/hoard.fish"),
+        ] {
+            let line = plugin
+                .lines()
+                .find(|line| line.contains(SHELL_INTEGRATION_VERSION_VAR))
+                .unwrap();
+            assert!(line.ends_with(&version), "{line}");
+        }
+        assert!(!is_stale_shell_integration(Some(&version)));
+        assert!(is_stale_shell_integration(Some("0")));
+        assert!(is_stale_shell_integration(None));
+    }
+}
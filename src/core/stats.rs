@@ -0,0 +1,708 @@
+use crate::core::HoardCmd;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Name of the file in the hoard home directory that every `hoard run` is logged to
+pub const RUN_LOG_FILE: &str = "runs.log";
+
+/// A single run of a command, stored as one JSON object per line in the run log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub name: String,
+    /// Empty for runs logged before namespaces were, they count for the commands of that name in every namespace
+    #[serde(default)]
+    pub namespace: String,
+    pub finished: SystemTime,
+    pub exit_code: i32,
+}
+
+impl RunRecord {
+    pub fn new(name: &str, namespace: &str, exit_code: i32) -> Self {
+        Self {
+            name: name.to_string(),
+            namespace: namespace.to_string(),
+            finished: SystemTime::now(),
+            exit_code,
+        }
+    }
+}
+
+/// Append a run to the run log at `path`
+pub fn append_run(path: &Path, record: &RunRecord) -> std::io::Result<()> {
+    append_line(path, record)
+}
+
+/// Load all runs of the run log at `path`, skipping lines that can't be read
+pub fn load_runs(path: &Path) -> Vec<RunRecord> {
+    load_lines(path)
+}
+
+/// Name of the file in the hoard home directory that every pick, copy and run of a command is logged to
+pub const USAGE_LOG_FILE: &str = "usage.log";
+
+/// How far back a use counts as recent in `hoard stats`
+pub const RECENT_USE_DAYS: u64 = 30;
+
+/// Size the usage log may grow to before its old uses are merged
+const MAX_USAGE_LOG_BYTES: u64 = 512 * 1024;
+
+/// Number of the most recent uses that are kept on their own when the usage log is merged
+const KEPT_USAGE_RECORDS: usize = 2000;
+
+/// Uses of a command, stored as one JSON object per line in the usage log
+///
+/// A record stands for a single use, or for `count` old uses of the command that were merged, the last of them at
+/// `used`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageRecord {
+    pub name: String,
+    pub namespace: String,
+    pub used: SystemTime,
+    #[serde(default = "single_use", skip_serializing_if = "is_single_use")]
+    pub count: usize,
+}
+
+const fn single_use() -> usize {
+    1
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+const fn is_single_use(count: &usize) -> bool {
+    *count == 1
+}
+
+impl UsageRecord {
+    pub fn new(command: &HoardCmd) -> Self {
+        Self {
+            name: command.name.clone(),
+            namespace: command.namespace.clone(),
+            used: SystemTime::now(),
+            count: 1,
+        }
+    }
+
+    /// Whether the use is one of `command`, the same name in another namespace is another command
+    pub fn is_use_of(&self, command: &HoardCmd) -> bool {
+        self.name == command.name && self.namespace == command.namespace
+    }
+}
+
+/// Append a use to the usage log at `path`, merging its old uses once the log gets too large
+pub fn append_usage(path: &Path, record: &UsageRecord) -> std::io::Result<()> {
+    append_line(path, record)?;
+    if fs::metadata(path)?.len() <= MAX_USAGE_LOG_BYTES {
+        return Ok(());
+    }
+    let mut content = String::new();
+    for record in compact_usage(load_usage(path), KEPT_USAGE_RECORDS) {
+        let _ = writeln!(content, "{}", serde_json::to_string(&record)?);
+    }
+    fs::write(path, content)
+}
+
+/// Keep the `kept` most recent uses and merge the older ones into a record per command, which keeps their count
+pub fn compact_usage(mut usage: Vec<UsageRecord>, kept: usize) -> Vec<UsageRecord> {
+    usage.sort_by_key(|record| record.used);
+    let recent = usage.split_off(usage.len().saturating_sub(kept));
+    let mut merged: Vec<UsageRecord> = Vec::new();
+    for record in usage {
+        match merged
+            .iter_mut()
+            .find(|merged| merged.name == record.name && merged.namespace == record.namespace)
+        {
+            Some(merged) => {
+                merged.count += record.count;
+                merged.used = merged.used.max(record.used);
+            }
+            None => merged.push(record),
+        }
+    }
+    merged.sort_by_key(|record| record.used);
+    merged.extend(recent);
+    merged
+}
+
+/// Load all uses of the usage log at `path`, skipping lines that can't be read
+pub fn load_usage(path: &Path) -> Vec<UsageRecord> {
+    load_lines(path)
+}
+
+/// Weight of a use that is at most this many days old, older uses weigh `OLD_USE_WEIGHT`
+const FRECENCY_WEIGHTS: &[(u64, usize)] = &[(4, 100), (14, 70), (31, 50), (90, 30)];
+const OLD_USE_WEIGHT: usize = 5;
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Frecency of the used commands by namespace and name
+pub type Frecency = HashMap<(String, String), usize>;
+
+/// Frecency of every used command: the weights of all its uses, which are higher the more recent a use was
+///
+/// A command used a few times today ranks above one used every day a year ago
+pub fn frecency(usage: &[UsageRecord], now: SystemTime) -> Frecency {
+    let mut scores = HashMap::new();
+    for record in usage {
+        let age_days = now
+            .duration_since(record.used)
+            .unwrap_or_default()
+            .as_secs()
+            / SECONDS_PER_DAY;
+        let weight = FRECENCY_WEIGHTS
+            .iter()
+            .find(|(days, _)| age_days < *days)
+            .map_or(OLD_USE_WEIGHT, |(_, weight)| *weight);
+        *scores
+            .entry((record.namespace.clone(), record.name.clone()))
+            .or_default() += weight * record.count;
+    }
+    scores
+}
+
+/// Frecency of `command`, 0 if it was never used
+pub fn command_frecency(frecency: &Frecency, command: &HoardCmd) -> usize {
+    frecency
+        .get(&(command.namespace.clone(), command.name.clone()))
+        .copied()
+        .unwrap_or_default()
+}
+
+fn append_line(path: &Path, record: &impl Serialize) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)
+}
+
+fn load_lines<T: for<'de> Deserialize<'de>>(path: &Path) -> Vec<T> {
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Name of the file in the hoard home directory with the values parameters were given before
+pub const USED_VALUES_FILE: &str = "used_values.json";
+
+/// How many values are remembered per parameter
+const MAX_USED_VALUES: usize = 20;
+
+/// Values given to the parameters of each command, the most recent first
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct UsedValues {
+    commands: HashMap<String, HashMap<String, Vec<String>>>,
+}
+
+impl UsedValues {
+    /// Load the used values at `path`, none if the file is missing or can't be read
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        fs::write(path, serde_json::to_string(self)?)
+    }
+
+    /// Remember `value` as the most recent value of `parameter` of `command`
+    pub fn record(&mut self, command: &str, parameter: &str, value: &str) {
+        let values = self
+            .commands
+            .entry(command.to_string())
+            .or_default()
+            .entry(parameter.to_string())
+            .or_default();
+        values.retain(|used| used != value);
+        values.insert(0, value.to_string());
+        values.truncate(MAX_USED_VALUES);
+    }
+
+    /// Values of `parameter` of `command`, the most recent first
+    pub fn values(&self, command: &str, parameter: &str) -> &[String] {
+        self.commands
+            .get(command)
+            .and_then(|parameters| parameters.get(parameter))
+            .map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Usage statistics of a single command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandStats {
+    pub name: String,
+    pub namespace: String,
+    pub usage_count: usize,
+    pub last_used: SystemTime,
+    pub runs: usize,
+    pub successful_runs: usize,
+    /// Picks, copies and runs in the last `RECENT_USE_DAYS` days
+    pub recent_uses: usize,
+}
+
+impl CommandStats {
+    /// Share of runs that exited with 0, `None` if the command was never run
+    pub fn success_rate(&self) -> Option<f64> {
+        #[allow(clippy::cast_precision_loss)]
+        (self.runs > 0).then(|| self.successful_runs as f64 / self.runs as f64)
+    }
+}
+
+/// Collect the usage statistics of `commands` from the run log and the usage log, uses from `since` on are recent
+///
+/// The uses are counted from the usage log alone, which has every pick, copy and run
+pub fn command_stats(
+    commands: &[HoardCmd],
+    runs: &[RunRecord],
+    usage: &[UsageRecord],
+    since: SystemTime,
+) -> Vec<CommandStats> {
+    commands
+        .iter()
+        .map(|command| {
+            let command_runs: Vec<&RunRecord> = runs
+                .iter()
+                .filter(|run| {
+                    run.name == command.name
+                        && (run.namespace.is_empty() || run.namespace == command.namespace)
+                })
+                .collect();
+            let uses: Vec<&UsageRecord> = usage
+                .iter()
+                .filter(|record| record.is_use_of(command))
+                .collect();
+            CommandStats {
+                name: command.name.clone(),
+                namespace: command.namespace.clone(),
+                usage_count: uses.iter().map(|record| record.count).sum(),
+                last_used: uses
+                    .iter()
+                    .map(|record| record.used)
+                    .fold(command.last_used, SystemTime::max),
+                runs: command_runs.len(),
+                successful_runs: command_runs.iter().filter(|run| run.exit_code == 0).count(),
+                recent_uses: uses
+                    .iter()
+                    .filter(|record| record.used >= since)
+                    .map(|record| record.count)
+                    .sum(),
+            }
+        })
+        .collect()
+}
+
+/// Most used commands first, by recent uses and then by all uses
+pub fn sort_by_use(stats: &mut [CommandStats]) {
+    stats.sort_by_key(|stat| std::cmp::Reverse((stat.recent_uses, stat.usage_count, stat.runs)));
+}
+
+/// Commands that were never picked, copied or run
+pub fn unused_commands(stats: &[CommandStats]) -> Vec<&CommandStats> {
+    stats
+        .iter()
+        .filter(|stat| stat.usage_count == 0 && stat.runs == 0)
+        .collect()
+}
+
+/// Number of commands and their uses in every namespace, in order of the namespaces
+pub fn namespace_stats(stats: &[CommandStats]) -> Vec<(String, usize, usize)> {
+    let mut namespaces: Vec<(String, usize, usize)> = Vec::new();
+    for stat in stats {
+        match namespaces
+            .iter_mut()
+            .find(|(name, _, _)| *name == stat.namespace)
+        {
+            Some((_, commands, namespace_uses)) => {
+                *commands += 1;
+                *namespace_uses += stat.usage_count;
+            }
+            None => namespaces.push((stat.namespace.clone(), 1, stat.usage_count)),
+        }
+    }
+    namespaces.sort_by(|a, b| a.0.cmp(&b.0));
+    namespaces
+}
+
+/// How many different values a word of a command needs in the history before it is suggested as a parameter
+const MIN_DIFFERENT_VALUES: usize = 2;
+
+/// A word of a hoarded command that is changed every time the command is used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParameterSuggestion {
+    pub name: String,
+    /// Position of the word in the command, counting words separated by whitespace
+    pub position: usize,
+    /// The word as it is hoarded
+    pub literal: String,
+    /// Values the word had in the history instead, in the order they first showed up
+    pub values: Vec<String>,
+    /// The command with the word replaced by a parameter
+    pub suggested: String,
+}
+
+/// Read the commands of a bash, zsh or fish history file, oldest first
+///
+/// zsh timestamps like `: 1700000000:0;` and fish's `- cmd: ` are stripped
+pub fn load_shell_history(path: &Path) -> Vec<String> {
+    let content = fs::read(path).unwrap_or_default();
+    let content = String::from_utf8_lossy(&content);
+    let is_fish = content.lines().any(|line| line.starts_with("- cmd: "));
+    content
+        .lines()
+        .filter_map(|line| {
+            if is_fish {
+                // Everything but the commands is metadata like `  when: 1700000000`
+                return line.strip_prefix("- cmd: ");
+            }
+            Some(line.strip_prefix(": ").map_or(line, |rest| {
+                rest.split_once(';').map_or(rest, |(_, command)| command)
+            }))
+        })
+        .map(str::trim)
+        .filter(|command| !command.is_empty())
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// The `n`th most recent entry of `history`, 1 is the last one
+///
+/// Runs of hoard itself are skipped, like the `hoard new --from-history` that asks for the entry
+pub fn recent_history_entry(history: &[String], n: usize) -> Option<&str> {
+    history
+        .iter()
+        .rev()
+        .filter(|entry| {
+            let program = entry.split_whitespace().next().unwrap_or_default();
+            program != "hoard" && !program.ends_with("/hoard")
+        })
+        .nth(n.checked_sub(1)?)
+        .map(String::as_str)
+}
+
+/// Find words of commands without parameters that the history shows with different values each time
+///
+/// A history entry counts for a command if it equals the command except for exactly one word, e.g.
+/// `ssh db2.example.com` for the hoarded `ssh db1.example.com`
+pub fn parameter_suggestions(
+    commands: &[HoardCmd],
+    history: &[String],
+    token: &str,
+) -> Vec<ParameterSuggestion> {
+    let mut suggestions = Vec::new();
+    for command in commands.iter().filter(|c| !c.command.contains(token)) {
+        let words: Vec<&str> = command.command.split_whitespace().collect();
+        let mut values_by_position: Vec<Vec<String>> = vec![Vec::new(); words.len()];
+        for entry in history {
+            let entry_words: Vec<&str> = entry.split_whitespace().collect();
+            if entry_words.len() != words.len() {
+                continue;
+            }
+            let mut differences = (0..words.len()).filter(|&i| words[i] != entry_words[i]);
+            // The program itself is not a parameter
+            if let (Some(position @ 1..), None) = (differences.next(), differences.next()) {
+                let values = &mut values_by_position[position];
+                if !values.iter().any(|value| value == entry_words[position]) {
+                    values.push(entry_words[position].to_string());
+                }
+            }
+        }
+        for (position, values) in values_by_position.into_iter().enumerate() {
+            if values.len() < MIN_DIFFERENT_VALUES {
+                continue;
+            }
+            let mut suggested_words: Vec<&str> = words.clone();
+            suggested_words[position] = token;
+            suggestions.push(ParameterSuggestion {
+                name: command.name.clone(),
+                position,
+                literal: words[position].to_string(),
+                values,
+                suggested: suggested_words.join(" "),
+            });
+        }
+    }
+    suggestions
+}
+
+/// Format a point in time the way spreadsheets understand it
+pub fn format_time(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339()
+}
+
+/// Render usage statistics as CSV with a header row
+pub fn stats_to_csv(stats: &[CommandStats]) -> String {
+    let mut csv =
+        String::from("name,namespace,usage_count,last_used,runs,successful_runs,success_rate\n");
+    for stat in stats {
+        let success_rate = stat
+            .success_rate()
+            .map(|rate| format!("{rate:.2}"))
+            .unwrap_or_default();
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{}",
+            csv_field(&stat.name),
+            csv_field(&stat.namespace),
+            stat.usage_count,
+            format_time(stat.last_used),
+            stat.runs,
+            stat.successful_runs,
+            success_rate
+        );
+    }
+    csv
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test_stats {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn command(name: &str, usage_count: usize) -> HoardCmd {
+        let mut command = HoardCmd::default().with_name(name);
+        command.namespace = String::from("default");
+        command.usage_count = usage_count;
+        command.last_used = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        command
+    }
+
+    #[test]
+    fn stats_count_runs_per_command() {
+        let runs = vec![
+            RunRecord::new("deploy", "default", 0),
+            RunRecord::new("deploy", "default", 1),
+            RunRecord::new("logs", "default", 0),
+        ];
+        let stats = command_stats(
+            &[command("deploy", 3), command("status", 0)],
+            &runs,
+            &[],
+            UNIX_EPOCH,
+        );
+        assert_eq!(2, stats[0].runs);
+        assert_eq!(1, stats[0].successful_runs);
+        assert_eq!(Some(0.5), stats[0].success_rate());
+        assert_eq!(None, stats[1].success_rate());
+    }
+
+    #[test]
+    fn most_used_and_unused_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(USAGE_LOG_FILE);
+        let logs = command("logs", 0);
+        let mut old_use = UsageRecord::new(&command("status", 5));
+        old_use.used = UNIX_EPOCH;
+        for record in [UsageRecord::new(&logs), UsageRecord::new(&logs), old_use] {
+            append_usage(&path, &record).unwrap();
+        }
+        let month_ago = SystemTime::now() - Duration::from_secs(RECENT_USE_DAYS * 24 * 60 * 60);
+        let usage = load_usage(&path);
+        assert_eq!(3, usage.len());
+
+        let mut stats = command_stats(
+            &[command("deploy", 0), command("status", 5), logs],
+            &[],
+            &usage,
+            month_ago,
+        );
+        sort_by_use(&mut stats);
+        let names: Vec<&str> = stats.iter().map(|stat| stat.name.as_str()).collect();
+        assert_eq!(vec!["logs", "status", "deploy"], names);
+        assert_eq!("deploy", unused_commands(&stats)[0].name);
+        assert_eq!(
+            vec![(String::from("default"), 3, 3)],
+            namespace_stats(&stats)
+        );
+    }
+
+    #[test]
+    fn count_uses_per_namespace() {
+        let deploy = command("deploy", 4);
+        let mut other = deploy.clone();
+        other.namespace = String::from("other");
+        let usage = vec![
+            UsageRecord::new(&deploy),
+            UsageRecord::new(&other),
+            UsageRecord::new(&other),
+        ];
+        let runs = vec![RunRecord::new("deploy", "other", 0)];
+        let stats = command_stats(&[deploy, other], &runs, &usage, UNIX_EPOCH);
+        // The count of the trove isn't added to the logged uses
+        assert_eq!((1, 0), (stats[0].usage_count, stats[0].runs));
+        assert_eq!((2, 1), (stats[1].usage_count, stats[1].runs));
+    }
+
+    #[test]
+    fn merge_old_uses_of_full_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(USAGE_LOG_FILE);
+        let mut old_use = UsageRecord::new(&command("status", 0));
+        old_use.used = UNIX_EPOCH;
+        let mut usage = vec![old_use; 3];
+        usage.push(UsageRecord::new(&command("logs", 0)));
+        let compacted = compact_usage(usage, 1);
+        assert_eq!(2, compacted.len());
+        assert_eq!(
+            ("status", 3),
+            (compacted[0].name.as_str(), compacted[0].count)
+        );
+        assert_eq!(
+            ("logs", 1),
+            (compacted[1].name.as_str(), compacted[1].count)
+        );
+
+        let record = UsageRecord::new(&command("logs", 0));
+        let line_length = serde_json::to_string(&record).unwrap().len() as u64 + 1;
+        for _ in 0..=MAX_USAGE_LOG_BYTES / line_length {
+            append_usage(&path, &record).unwrap();
+        }
+        let usage = load_usage(&path);
+        assert!(usage.len() <= KEPT_USAGE_RECORDS + 1);
+        let stats = command_stats(&[command("logs", 0)], &[], &usage, UNIX_EPOCH);
+        assert_eq!(
+            MAX_USAGE_LOG_BYTES / line_length + 1,
+            stats[0].usage_count as u64
+        );
+    }
+
+    #[test]
+    fn recent_uses_weigh_more() {
+        let now = SystemTime::now();
+        let day = Duration::from_secs(SECONDS_PER_DAY);
+        let used = |name: &str, days_ago: u32| UsageRecord {
+            name: name.to_string(),
+            namespace: String::from("default"),
+            used: now - day * days_ago,
+            count: 1,
+        };
+        let usage: Vec<UsageRecord> = (0..20)
+            .map(|days| used("daily", 200 + days))
+            .chain([used("today", 0), used("today", 0), used("last_week", 7)])
+            .chain([UsageRecord {
+                namespace: String::from("other"),
+                ..used("today", 0)
+            }])
+            .collect();
+        let scores = frecency(&usage, now);
+        let score = |namespace: &str, name: &str| {
+            command_frecency(&scores, &command(name, 0).with_namespace(namespace))
+        };
+        assert!(score("default", "today") > score("default", "daily"));
+        assert_eq!(200, score("default", "today"));
+        assert_eq!(100, score("other", "today"));
+        assert_eq!(70, score("default", "last_week"));
+        assert_eq!(0, score("default", "unused"));
+    }
+
+    #[test]
+    fn csv_export() {
+        let runs = vec![RunRecord::new("deploy", "default", 0)];
+        let deploy = command("deploy", 0);
+        let mut usage = UsageRecord::new(&deploy);
+        usage.used = deploy.last_used;
+        usage.count = 3;
+        let stats = command_stats(&[deploy, command("a,b", 0)], &runs, &[usage], UNIX_EPOCH);
+        let csv = stats_to_csv(&stats);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            "name,namespace,usage_count,last_used,runs,successful_runs,success_rate",
+            lines[0]
+        );
+        assert_eq!(
+            "deploy,default,3,2023-11-14T22:13:20+00:00,1,1,1.00",
+            lines[1]
+        );
+        assert_eq!("\"a,b\",default,0,2023-11-14T22:13:20+00:00,0,0,", lines[2]);
+    }
+
+    #[test]
+    fn suggest_parameter_for_changing_word() {
+        let history: Vec<String> = [
+            "ssh db2.example.com -p 22",
+            "ls -la",
+            "ssh db3.example.com -p 22",
+            "ssh db3.example.com -p 2222",
+            "ssh db1.example.com -p 22",
+        ]
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+        let commands = vec![
+            command("ssh_db", 0).with_command("ssh db1.example.com -p 22"),
+            command("list", 0).with_command("ls -l"),
+        ];
+        let suggestions = parameter_suggestions(&commands, &history, "#");
+        assert_eq!(1, suggestions.len());
+        assert_eq!("ssh_db", suggestions[0].name);
+        assert_eq!(
+            vec!["db2.example.com", "db3.example.com"],
+            suggestions[0].values
+        );
+        assert_eq!("ssh # -p 22", suggestions[0].suggested);
+    }
+
+    #[test]
+    fn read_zsh_and_fish_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let zsh = dir.path().join(".zsh_history");
+        fs::write(&zsh, ": 1700000000:0;kubectl get pods\ngit status\n").unwrap();
+        assert_eq!(
+            vec!["kubectl get pods", "git status"],
+            load_shell_history(&zsh)
+        );
+        let fish = dir.path().join("fish_history");
+        fs::write(&fish, "- cmd: git push\n  when: 1700000000\n").unwrap();
+        assert_eq!(vec!["git push"], load_shell_history(&fish));
+    }
+
+    #[test]
+    fn pick_recent_history_entry() {
+        let history: Vec<String> = ["git status", "make test", "hoard new --from-history 2"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(Some("make test"), recent_history_entry(&history, 1));
+        assert_eq!(Some("git status"), recent_history_entry(&history, 2));
+        assert_eq!(None, recent_history_entry(&history, 3));
+        assert_eq!(None, recent_history_entry(&history, 0));
+    }
+
+    #[test]
+    fn used_values_most_recent_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(USED_VALUES_FILE);
+        let mut used_values = UsedValues::load(&path);
+        used_values.record("ssh_db", "host", "db1");
+        used_values.record("ssh_db", "host", "db2");
+        used_values.record("ssh_db", "host", "db1");
+        used_values.save(&path).unwrap();
+        let used_values = UsedValues::load(&path);
+        assert_eq!(vec!["db1", "db2"], used_values.values("ssh_db", "host"));
+        assert!(used_values.values("ssh_db", "user").is_empty());
+    }
+
+    #[test]
+    fn run_log_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(RUN_LOG_FILE);
+        append_run(&path, &RunRecord::new("deploy", "default", 0)).unwrap();
+        append_run(&path, &RunRecord::new("deploy", "default", 2)).unwrap();
+        let runs = load_runs(&path);
+        assert_eq!(2, runs.len());
+        assert_eq!(2, runs[1].exit_code);
+    }
+}
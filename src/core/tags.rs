@@ -0,0 +1,163 @@
+use crate::core::error::HoardErr;
+use crate::core::HoardCmd;
+
+/// A change `hoard tag` makes to the tags of every command it applies to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagChange {
+    Add(String),
+    Remove(String),
+    Rename { from: String, to: String },
+}
+
+impl TagChange {
+    /// The tags of `command` after the change, `None` if it doesn't change them
+    pub fn apply(&self, command: &HoardCmd) -> Option<Vec<String>> {
+        let has = |tag: &str| command.tags.iter().any(|t| t == tag);
+        match self {
+            Self::Add(tag) if !has(tag) => {
+                let mut tags = command.tags.clone();
+                tags.push(tag.clone());
+                Some(tags)
+            }
+            Self::Remove(tag) if has(tag) => {
+                Some(command.tags.iter().filter(|t| *t != tag).cloned().collect())
+            }
+            // Renaming to a tag the command already has merges the two
+            Self::Rename { from, to } if has(from) => {
+                let mut tags: Vec<String> = Vec::new();
+                for tag in &command.tags {
+                    let tag = if tag == from { to } else { tag };
+                    if !tags.contains(tag) {
+                        tags.push(tag.clone());
+                    }
+                }
+                Some(tags)
+            }
+            _ => None,
+        }
+    }
+
+    /// Read a change typed in the GUI, `+tag` adds, `-tag` removes and `old=new` renames a tag
+    pub fn parse(change: &str) -> Result<Self, HoardErr> {
+        let change = change.trim();
+        let parsed = if let Some(tag) = change.strip_prefix('+') {
+            Self::Add(tag.trim().to_string())
+        } else if let Some(tag) = change.strip_prefix('-') {
+            Self::Remove(tag.trim().to_string())
+        } else if let Some((from, to)) = change.split_once('=') {
+            Self::Rename {
+                from: from.trim().to_string(),
+                to: to.trim().to_string(),
+            }
+        } else {
+            return Err(HoardErr::new(&format!(
+                "'{change}' is no tag change, use +tag to add, -tag to remove or old=new to rename a tag"
+            )));
+        };
+        parsed.validate()?;
+        Ok(parsed)
+    }
+
+    /// Only tags that can be told apart from a list of tags can be added
+    pub fn validate(&self) -> Result<(), HoardErr> {
+        let added = match self {
+            Self::Add(tag) | Self::Rename { to: tag, .. } => tag,
+            Self::Remove(_) => return Ok(()),
+        };
+        if added.trim().is_empty() || added.contains(',') || added.trim() != added {
+            return Err(HoardErr::new(&format!(
+                "'{added}' can't be a tag, tags can't be empty or have commas or spaces around them"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Every tag of `commands` with the number of commands it is on, the most used tags first
+pub fn tag_counts(commands: &[HoardCmd]) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for tag in commands.iter().flat_map(|command| &command.tags) {
+        match counts.iter_mut().find(|(name, _)| name == tag) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((tag.clone(), 1)),
+        }
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+#[cfg(test)]
+mod test_tags {
+    use super::*;
+
+    fn command(name: &str, tags: &str) -> HoardCmd {
+        HoardCmd::default()
+            .with_name(name)
+            .with_namespace("default")
+            .with_tags_raw(tags)
+    }
+
+    #[test]
+    fn change_tags_of_commands() {
+        let deploy = command("deploy", "k8s,prod");
+        assert_eq!(
+            Some(vec![
+                String::from("k8s"),
+                String::from("prod"),
+                String::from("ops")
+            ]),
+            TagChange::Add(String::from("ops")).apply(&deploy)
+        );
+        assert_eq!(None, TagChange::Add(String::from("k8s")).apply(&deploy));
+        assert_eq!(
+            Some(vec![String::from("k8s")]),
+            TagChange::Remove(String::from("prod")).apply(&deploy)
+        );
+        let rename = TagChange::Rename {
+            from: String::from("prod"),
+            to: String::from("k8s"),
+        };
+        assert_eq!(Some(vec![String::from("k8s")]), rename.apply(&deploy));
+        assert_eq!(None, rename.apply(&command("logs", "k8s")));
+        assert!(TagChange::Add(String::from("a,b")).validate().is_err());
+        assert!(TagChange::Remove(String::from(" ")).validate().is_ok());
+    }
+
+    #[test]
+    fn parse_tag_changes() {
+        assert_eq!(
+            TagChange::Add(String::from("ops")),
+            TagChange::parse(" +ops").unwrap()
+        );
+        assert_eq!(
+            TagChange::Remove(String::from("old")),
+            TagChange::parse("-old").unwrap()
+        );
+        assert_eq!(
+            TagChange::Rename {
+                from: String::from("k8s"),
+                to: String::from("kubernetes")
+            },
+            TagChange::parse("k8s = kubernetes").unwrap()
+        );
+        assert!(TagChange::parse("ops").is_err());
+        assert!(TagChange::parse("+a,b").is_err());
+    }
+
+    #[test]
+    fn count_tags() {
+        let commands = [
+            command("deploy", "k8s,prod"),
+            command("logs", "k8s"),
+            command("backup", "db"),
+        ];
+        assert_eq!(
+            vec![
+                (String::from("k8s"), 2),
+                (String::from("db"), 1),
+                (String::from("prod"), 1)
+            ],
+            tag_counts(&commands)
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use crate::core::HoardCmd;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fs;
+use std::path::Path;
+
+/// File in the hoard home with the day `hoard random --daily` last printed a command on
+pub const LAST_TIP_FILE: &str = "last_tip";
+
+/// One of `candidates` to rediscover, given with how often they were used
+///
+/// The less a command was used the likelier it is picked, so forgotten commands come up more often than daily ones
+pub fn pick_rarely_used<'a>(
+    candidates: &[(&'a HoardCmd, usize)],
+    rng: &mut impl Rng,
+) -> Option<&'a HoardCmd> {
+    #[allow(clippy::cast_precision_loss)]
+    candidates
+        .choose_weighted(rng, |(_, uses)| 1.0 / (*uses as f64 + 1.0).powi(2))
+        .ok()
+        .map(|(command, _)| *command)
+}
+
+/// Whether `hoard random --daily` still has to print a command on `today`, the day it last did is in `path`
+pub fn is_tip_due(path: &Path, today: &str) -> bool {
+    fs::read_to_string(path).map_or(true, |day| day.trim() != today)
+}
+
+#[cfg(test)]
+mod test_tips {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn pick_rarely_used_commands() {
+        let forgotten = HoardCmd::default().with_name("forgotten");
+        let daily = HoardCmd::default().with_name("daily");
+        let candidates = [(&forgotten, 0), (&daily, 200)];
+        let mut rng = StdRng::seed_from_u64(7);
+        let forgotten_picks = (0..100)
+            .filter_map(|_| pick_rarely_used(&candidates, &mut rng))
+            .filter(|command| command.name == "forgotten")
+            .count();
+        assert!(forgotten_picks > 95);
+        assert!(pick_rarely_used(&[], &mut rng).is_none());
+    }
+
+    #[test]
+    fn tip_once_a_day() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LAST_TIP_FILE);
+        assert!(is_tip_due(&path, "2026-10-14"));
+        fs::write(&path, "2026-10-14\n").unwrap();
+        assert!(!is_tip_due(&path, "2026-10-14"));
+        assert!(is_tip_due(&path, "2026-10-15"));
+    }
+}
@@ -0,0 +1,149 @@
+use crate::core::error::HoardErr;
+use crate::core::import::{fetch_text, imported_command_name, words};
+use crate::core::parameters::Parameterized;
+use crate::core::HoardCmd;
+use std::fmt::Write as _;
+use url::Url;
+
+/// Where the tldr pages are downloaded from
+const TLDR_PAGES_URL: &str = "https://raw.githubusercontent.com/tldr-pages/tldr/main/pages";
+
+/// Platforms of the tldr pages, in the order they are looked up
+const TLDR_PLATFORMS: &[&str] = &["common", "linux", "osx", "windows"];
+
+/// Namespace of the commands imported from tldr pages
+pub const TLDR_NAMESPACE: &str = "tldr";
+
+/// Download the tldr page of `tool`, from the first platform that has one
+pub fn fetch_page(tool: &str) -> Result<String, HoardErr> {
+    if tool.is_empty()
+        || !tool
+            .chars()
+            .all(|c| c.is_alphanumeric() || "-_.+".contains(c))
+    {
+        return Err(HoardErr::new(&format!("Invalid tool name {tool}")));
+    }
+    TLDR_PLATFORMS
+        .iter()
+        .filter_map(|platform| Url::parse(&format!("{TLDR_PAGES_URL}/{platform}/{tool}.md")).ok())
+        .find_map(|url| fetch_text(url).ok())
+        .map(|(page, _)| page)
+        .ok_or_else(|| HoardErr::new(&format!("No tldr page found for {tool}")))
+}
+
+/// Turn every example of a tldr page into a command, with its `{{placeholders}}` as named parameters
+pub fn page_commands(
+    page: &str,
+    tool: &str,
+    start_token: &str,
+    end_token: &str,
+    escape: char,
+) -> Vec<HoardCmd> {
+    let mut commands: Vec<HoardCmd> = Vec::new();
+    let mut description = None;
+    for line in page.lines().map(str::trim) {
+        if let Some(text) = line.strip_prefix("- ") {
+            description = Some(text.trim_end_matches(':').trim().to_string());
+        } else if let Some(example) = line
+            .strip_prefix('`')
+            .and_then(|line| line.strip_suffix('`'))
+        {
+            let Some(description) = description.take() else {
+                continue;
+            };
+            let command = parameterize(example, start_token, end_token, escape);
+            // The brackets tldr puts around the letters of flags, e.g. `[c]reate`, are left out of the name
+            let name = imported_command_name(tool, &description.replace(['[', ']'], ""), &commands);
+            commands.push(
+                HoardCmd::default()
+                    .with_name(&name)
+                    .with_command(&command)
+                    .with_description(&description)
+                    .with_namespace(TLDR_NAMESPACE)
+                    .with_tags_raw(&format!("tldr,{tool}")),
+            );
+        }
+    }
+    commands
+}
+
+/// Replace the `{{placeholders}}` of a tldr example by named parameters, e.g. `{{path/to/file}}` by `#path_to_file!`
+fn parameterize(example: &str, start_token: &str, end_token: &str, escape: char) -> String {
+    let mut command = String::new();
+    let mut rest = example;
+    while let Some(start) = rest.find("{{") {
+        let Some(length) = rest[start..].find("}}") else {
+            break;
+        };
+        command.push_str(&HoardCmd::escape_input(
+            &rest[..start],
+            start_token,
+            end_token,
+            escape,
+        ));
+        let placeholder = &rest[start + 2..start + length];
+        let _ = write!(
+            command,
+            "{start_token}{}{end_token}",
+            parameter_name(placeholder)
+        );
+        rest = &rest[start + length + 2..];
+    }
+    command.push_str(&HoardCmd::escape_input(
+        rest,
+        start_token,
+        end_token,
+        escape,
+    ));
+    command
+}
+
+/// Name of the parameter of a placeholder, only letters, digits and underscores
+fn parameter_name(placeholder: &str) -> String {
+    let name = words(placeholder).join("_");
+    if name.is_empty() {
+        String::from("value")
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod test_tldr {
+    use super::*;
+
+    const PAGE: &str = "# tar
+
+> Archiving utility.
+> More information: <https://www.gnu.org/software/tar>.
+
+- [c]reate an archive and write it to a [f]ile:
+
+`tar cf {{path/to/target.tar}} {{path/to/file1 path/to/file2 ...}}`
+
+- E[x]tract an archive in the current directory, showing a # progress bar:
+
+`tar xvf {{path/to/source.tar}} # and {{path/to/source.tar}} again`
+";
+
+    #[test]
+    fn examples_become_commands() {
+        let commands = page_commands(PAGE, "tar", "#", "!", '\\');
+        assert_eq!(2, commands.len());
+        assert_eq!("tar_create_an_archive", commands[0].name);
+        assert_eq!(
+            "tar cf #path_to_target_tar! #path_to_file1_path_to_file2!",
+            commands[0].command
+        );
+        assert_eq!(
+            "[c]reate an archive and write it to a [f]ile",
+            commands[0].description
+        );
+        assert_eq!(
+            "tar xvf #path_to_source_tar! \\# and #path_to_source_tar! again",
+            commands[1].command
+        );
+        assert_eq!(vec!["tldr", "tar"], commands[1].tags);
+        assert!(commands.iter().all(HoardCmd::is_valid));
+    }
+}
@@ -3,16 +3,87 @@ use log::info;
 use prettytable::{color, Attr, Cell, Row, Table};
 use serde::{Deserialize, Serialize};
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::time::SystemTime;
 use std::{fs, path::Path, path::PathBuf};
 
 use crate::config::HoardConfig;
 use crate::core::error::HoardErr;
 use crate::core::parameters::Parameterized;
+use crate::core::providers::ProviderRegistry;
 use crate::core::HoardCmd;
 
 const CARGO_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Directory a trove stored per namespace is kept in, `trove.d` for `trove.yml`
+pub fn namespace_dir(path: &Path) -> PathBuf {
+    path.with_extension("d")
+}
+
+/// Name of the file of a namespace, the namespace with everything but letters, digits, `-` and `_` replaced
+fn namespace_file_stem(namespace: &str) -> String {
+    let stem: String = namespace
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if stem.is_empty() {
+        String::from("_")
+    } else {
+        stem
+    }
+}
+
+/// Namespace and name of a command given as `namespace/name`, the namespace is `None` for a plain name and the name
+/// empty for `namespace/`
+///
+/// Namespaces can contain `/`, the name is what follows the last one
+pub fn split_command_path(path: &str) -> (Option<&str>, &str) {
+    path.rsplit_once('/')
+        .map_or((None, path), |(namespace, name)| (Some(namespace), name))
+}
+
+/// The `.yml` files in `dir`
+pub fn trove_files(dir: &Path) -> impl Iterator<Item = PathBuf> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "yml"))
+}
+
+/// Remove the `.yml` files in `dir` that aren't named like one of `keep`
+fn remove_trove_files(dir: &Path, keep: &HashSet<String>) {
+    for file in trove_files(dir) {
+        let stem = file
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+        if !keep.contains(stem) {
+            let _ = fs::remove_file(&file);
+        }
+    }
+}
+
+/// Language of the code block of a command in a cheatsheet, the program of its shell, e.g. `python3` for `python3 -c`
+fn code_block_language(command: &HoardCmd) -> String {
+    command
+        .shell
+        .as_deref()
+        .and_then(|shell| shell.split_whitespace().next())
+        .and_then(|program| Path::new(program).file_name())
+        .and_then(|program| program.to_str())
+        .unwrap_or("sh")
+        .to_string()
+}
+
 /// Container for all stored hoard commands.
 /// A `treasure trove` of commands
 ///
@@ -21,12 +92,25 @@ const CARGO_VERSION: &str = env!("CARGO_PKG_VERSION");
 ///              To potentially support migrating older collections to new ones when breaking changes happen
 /// - `commands`: Vector of `HoardCmd`s, the stored commands
 /// - `namespaces`: Set of all namespaces used in the collection
+/// - `trash`: Removed commands, kept until they are restored or purged
 #[derive(Debug, Serialize, Clone, Deserialize)]
 pub struct Trove {
     pub version: String,
     pub commands: Vec<HoardCmd>,
     #[serde(default)]
     pub namespaces: HashSet<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trash: Vec<TrashedCommand>,
+    /// Other troves, paths or URLs, whose commands are merged in read-only when the trove is loaded
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+}
+
+/// A removed command and when it was removed
+#[derive(Debug, Serialize, Clone, Deserialize)]
+pub struct TrashedCommand {
+    pub command: HoardCmd,
+    pub deleted: SystemTime,
 }
 
 impl Default for Trove {
@@ -36,6 +120,8 @@ impl Default for Trove {
             version: CARGO_VERSION.to_string(),
             commands: Vec::new(),
             namespaces: HashSet::new(),
+            trash: Vec::new(),
+            include: Vec::new(),
         }
     }
 }
@@ -54,6 +140,8 @@ impl Trove {
             version: CARGO_VERSION.to_string(),
             commands: commands.to_vec(),
             namespaces,
+            trash: Vec::new(),
+            include: Vec::new(),
         }
     }
 
@@ -106,10 +194,182 @@ impl Trove {
         serde_yaml::to_string(&self).unwrap()
     }
 
+    /// Serialize trove collection to json format, with the same fields as the yaml format
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self).unwrap()
+    }
+
+    /// Render the trove as a Markdown cheatsheet, one section per namespace with a code block for every command
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::from("# Hoard cheatsheet\n");
+        for namespace in self.namespaces() {
+            let _ = write!(markdown, "\n## {namespace}\n");
+            let mut commands: Vec<&HoardCmd> = self
+                .commands
+                .iter()
+                .filter(|command| command.namespace == namespace)
+                .collect();
+            commands.sort_by(|a, b| a.name.cmp(&b.name));
+            for command in commands {
+                let _ = write!(markdown, "\n### {}\n\n", command.name);
+                if !command.description.is_empty() {
+                    let _ = write!(markdown, "{}\n\n", command.description);
+                }
+                if !command.tags.is_empty() {
+                    let tags: Vec<String> =
+                        command.tags.iter().map(|tag| format!("`{tag}`")).collect();
+                    let _ = write!(markdown, "Tags: {}\n\n", tags.join(", "));
+                }
+                // The fence has to be longer than any run of backticks in the command
+                let longest_backticks = command
+                    .command
+                    .split(|c| c != '`')
+                    .map(str::len)
+                    .max()
+                    .unwrap_or_default();
+                let fence = "`".repeat(longest_backticks.max(2) + 1);
+                let _ = write!(
+                    markdown,
+                    "{fence}{}\n{}\n{fence}\n",
+                    code_block_language(command),
+                    command.command
+                );
+            }
+        }
+        markdown
+    }
+
     /// Save the trove collection to `path` as a yaml file
+    ///
+    /// The file is written next to `path` first and then moved in place, so the trove is never left half written
     pub fn save_trove_file(&self, path: &Path) {
         let s = self.to_yaml();
-        fs::write(path, s).expect("Unable to write config file");
+        let mut temporary_path = path.as_os_str().to_owned();
+        temporary_path.push(".tmp");
+        fs::write(&temporary_path, s).expect("Unable to write config file");
+        fs::rename(&temporary_path, path).expect("Unable to write config file");
+    }
+
+    /// Load the trove stored at `path`, from a file per namespace in its namespace directory if `per_namespace` is set
+    ///
+    /// Falls back to the other layout if the trove wasn't saved in this one yet, so the setting can be switched
+    pub fn load_stored(path: &Path, per_namespace: bool) -> Self {
+        let dir = namespace_dir(path);
+        let from_dir = if per_namespace {
+            dir.is_dir() || !path.exists()
+        } else {
+            !path.exists() && dir.is_dir()
+        };
+        if from_dir {
+            Self::load_namespace_files(&dir)
+        } else {
+            Self::load_trove_file(&Some(path.to_path_buf()))
+        }
+    }
+
+    /// Save the trove at `path`, as a file per namespace in its namespace directory if `per_namespace` is set
+    ///
+    /// The trove is removed from the layout that isn't used
+    pub fn save_stored(&self, path: &Path, per_namespace: bool) {
+        let dir = namespace_dir(path);
+        if per_namespace {
+            self.save_namespace_files(&dir);
+            let _ = fs::remove_file(path);
+        } else {
+            self.save_trove_file(path);
+            if dir.is_dir() {
+                remove_trove_files(&dir, &HashSet::new());
+                let _ = fs::remove_dir(&dir);
+            }
+        }
+    }
+
+    /// One trove for every namespace, with the trashed commands of that namespace
+    pub fn split_by_namespace(&self) -> Vec<(String, Self)> {
+        let mut namespaces: Vec<&str> = self.namespaces();
+        for trashed in &self.trash {
+            if !namespaces.contains(&trashed.command.namespace.as_str()) {
+                namespaces.push(&trashed.command.namespace);
+            }
+        }
+        namespaces.sort_unstable();
+        let mut troves: Vec<(String, Self)> = namespaces
+            .into_iter()
+            .map(|namespace| {
+                let commands: Vec<HoardCmd> = self
+                    .commands
+                    .iter()
+                    .filter(|command| command.namespace == namespace)
+                    .cloned()
+                    .collect();
+                let trash = self
+                    .trash
+                    .iter()
+                    .filter(|trashed| trashed.command.namespace == namespace)
+                    .cloned()
+                    .collect();
+                let trove = Self {
+                    version: self.version.clone(),
+                    trash,
+                    ..Self::from_commands(&commands)
+                };
+                (namespace.to_string(), trove)
+            })
+            .collect();
+        // The includes are kept in the first file, or in a file of their own if there are no commands
+        if let Some((_, first)) = troves.first_mut() {
+            first.include.clone_from(&self.include);
+        } else if !self.include.is_empty() {
+            let include = self.include.clone();
+            troves.push((
+                String::new(),
+                Self {
+                    include,
+                    ..Self::default()
+                },
+            ));
+        }
+        troves
+    }
+
+    /// Save the trove into `dir` with a file for every namespace. Files of namespaces that are gone are removed
+    pub fn save_namespace_files(&self, dir: &Path) {
+        fs::create_dir_all(dir).expect("Unable to create the trove directory");
+        let mut written = HashSet::new();
+        for (namespace, trove) in self.split_by_namespace() {
+            let base_name = namespace_file_stem(&namespace);
+            let mut file_stem = base_name.clone();
+            // Namespaces that only differ in characters that can't be in a file name
+            let mut suffix = 2;
+            while written.contains(&file_stem) {
+                file_stem = format!("{base_name}-{suffix}");
+                suffix += 1;
+            }
+            trove.save_trove_file(&dir.join(format!("{file_stem}.yml")));
+            written.insert(file_stem);
+        }
+        remove_trove_files(dir, &written);
+    }
+
+    /// Load a trove saved with `save_namespace_files`
+    pub fn load_namespace_files(dir: &Path) -> Self {
+        let mut files: Vec<PathBuf> = trove_files(dir).collect();
+        files.sort();
+        let mut commands = Vec::new();
+        let mut trash = Vec::new();
+        let mut include: Vec<String> = Vec::new();
+        for file in files {
+            let trove = Self::load_trove_file(&Some(file));
+            commands.extend(trove.commands);
+            trash.extend(trove.trash);
+            include.extend(trove.include);
+        }
+        include.dedup();
+        Self {
+            trash,
+            include,
+            ..Self::from_commands(&commands)
+        }
     }
 
     /// Given a `HoardCmd`, check if there is a command with the same name and namespace already in the collection
@@ -198,7 +458,7 @@ impl Trove {
         }
     }
 
-    /// Remove a command from the trove collection
+    /// Remove a command from the trove collection and put it in the trash
     /// 
     /// Returns `Ok(())` if the command has been removed
     /// 
@@ -208,7 +468,15 @@ impl Trove {
         if command_position.is_none() {
             return Err(anyhow!("Command not found [{}]", name));
         }
-        self.commands.retain(|x| &*x.name != name);
+        if self.is_managed(name) {
+            return Err(anyhow!(
+                "[{}] is a managed command and can't be removed",
+                name
+            ));
+        }
+        let (removed, kept) = self.commands.drain(..).partition(|x| &*x.name == name);
+        self.commands = kept;
+        self.move_to_trash(removed);
         Ok(())
     }
 
@@ -229,10 +497,68 @@ impl Trove {
         if command_position.is_none() {
             return Err(anyhow!("No Commands found in namespace [{}]", namespace));
         }
-        self.commands.retain(|x| &*x.namespace != namespace);
+        let (removed, kept) = self
+            .commands
+            .drain(..)
+            .partition(|x| &*x.namespace == namespace && !x.is_managed);
+        self.commands = kept;
+        self.move_to_trash(removed);
         Ok(())
     }
 
+    /// Put every command that `is_removed` picks in the trash, managed commands are kept. Returns the removed ones
+    pub fn remove_matching(&mut self, is_removed: impl Fn(&HoardCmd) -> bool) -> Vec<HoardCmd> {
+        let (removed, kept): (Vec<HoardCmd>, Vec<HoardCmd>) = self
+            .commands
+            .drain(..)
+            .partition(|command| !command.is_managed && is_removed(command));
+        self.commands = kept;
+        self.move_to_trash(removed.clone());
+        removed
+    }
+
+    /// Put removed `commands` in the trash
+    pub fn move_to_trash(&mut self, commands: Vec<HoardCmd>) {
+        let deleted = SystemTime::now();
+        self.trash.extend(
+            commands
+                .into_iter()
+                .map(|command| TrashedCommand { command, deleted }),
+        );
+    }
+
+    /// Put the last removed command called `name` back, or the last removed command if no name is given
+    ///
+    /// Fails if there is no such command in the trash or a command with its name is in the trove again
+    pub fn restore_command(&mut self, name: Option<&str>) -> Result<HoardCmd> {
+        let position = self
+            .trash
+            .iter()
+            .rposition(|trashed| name.is_none_or(|name| trashed.command.name == name))
+            .ok_or_else(|| {
+                name.map_or_else(
+                    || anyhow!("The trash is empty"),
+                    |name| anyhow!("No command [{}] in the trash", name),
+                )
+            })?;
+        let command = self.trash[position].command.clone();
+        if self.commands.iter().any(|c| c.name == command.name) {
+            return Err(anyhow!(
+                "There is a command [{}] already, rename it to restore this one",
+                command.name
+            ));
+        }
+        self.trash.remove(position);
+        self.add_namespace(&command.namespace);
+        self.commands.push(command.clone());
+        Ok(command)
+    }
+
+    /// Delete all commands in the trash for good, returns how many there were
+    pub fn purge_trash(&mut self) -> usize {
+        self.trash.drain(..).count()
+    }
+
     pub fn namespaces(&self) -> Vec<&str> {
         // Returns all namespaces in the trove
         let mut namespaces: Vec<_> = self
@@ -247,23 +573,94 @@ impl Trove {
         namespaces
     }
 
+    /// Groups of namespaces that only differ by case, like `Docker` and `docker`
+    pub fn case_duplicate_namespaces(&self) -> Vec<Vec<String>> {
+        let mut groups: Vec<Vec<String>> = Vec::new();
+        for namespace in self.namespaces() {
+            match groups
+                .iter_mut()
+                .find(|group| group[0].to_lowercase() == namespace.to_lowercase())
+            {
+                Some(group) => group.push(namespace.to_string()),
+                None => groups.push(vec![namespace.to_string()]),
+            }
+        }
+        groups.retain(|group| group.len() > 1);
+        groups
+    }
+
+    /// Move the commands of the namespaces `from` into the namespace `into`, returns how many were moved
+    ///
+    /// A moved command that collides with a command of `into` gets a random suffix. Managed commands stay where they are
+    pub fn merge_namespaces(&mut self, from: &[String], into: &str) -> usize {
+        let (to_move, kept): (Vec<HoardCmd>, Vec<HoardCmd>) =
+            self.commands.drain(..).partition(|command| {
+                command.namespace != into
+                    && from.contains(&command.namespace)
+                    && !command.is_managed
+            });
+        self.commands = kept;
+        let moved = to_move.len();
+        for command in to_move {
+            let _ = self.add_command(command.with_namespace(into), false);
+        }
+        let used: HashSet<String> = self
+            .namespaces()
+            .into_iter()
+            .map(ToString::to_string)
+            .collect();
+        self.namespaces.retain(|namespace| used.contains(namespace));
+        moved
+    }
+
     pub fn pick_command(&self, config: &HoardConfig, name: &str) -> Result<HoardCmd> {
         let filtered_command: Option<&HoardCmd> = self.commands.iter().find(|c| c.name == name);
         filtered_command.map_or_else(
             || Err(anyhow!("No matching command found with name: {}", name)),
             |command| {
-                let command = command.clone().with_input_parameters(
-                    &config.parameter_token.clone().unwrap(),
-                    &config.parameter_ending_token.clone().unwrap(),
-                );
+                let command = command
+                    .clone()
+                    .with_input_parameters(
+                        &config.parameter_token.clone().unwrap(),
+                        &config.parameter_ending_token.clone().unwrap(),
+                        config.escape_char(),
+                        &ProviderRegistry::from_config(config),
+                    )
+                    .with_env_vars_expanded(config);
                 Ok(command)
             },
         )
     }
 
+    /// Get a command by name without filling its parameters
+    pub fn get_command(&self, name: &str) -> Result<&HoardCmd> {
+        self.commands
+            .iter()
+            .find(|c| c.name == name)
+            .ok_or_else(|| anyhow!("No matching command found with name: {}", name))
+    }
+
+    /// Pick a command by name and fill its parameters with the given values instead of prompting for them
+    pub fn pick_command_with_values(
+        &self,
+        config: &HoardConfig,
+        name: &str,
+        named: &HashMap<String, String>,
+        positional: &[String],
+    ) -> Result<HoardCmd> {
+        let command = self.get_command(name)?.with_parameter_values(
+            &config.parameter_token.clone().unwrap(),
+            &config.parameter_ending_token.clone().unwrap(),
+            config.escape_char(),
+            named,
+            positional,
+        )?;
+        Ok(command.with_env_vars_expanded(config))
+    }
+
     pub fn update_command_by_name(&mut self, command: &HoardCmd) -> &mut Self {
         for c in &mut self.commands.iter_mut() {
-            if c.name == command.name {
+            if c.name == command.name && !c.is_managed {
                 *c = command.clone();
                 c.mut_update_last_used();
             }
@@ -271,6 +668,54 @@ impl Trove {
         self
     }
 
+    /// Check if the command with `name` was loaded from the managed trove
+    pub fn is_managed(&self, name: &str) -> bool {
+        self.commands.iter().any(|c| c.name == name && c.is_managed)
+    }
+
+    /// Add the commands of a read-only managed trove, e.g. one declared by a dotfile manager
+    ///
+    /// Managed commands are left out by `without_managed` so they are never saved. Hoarded commands with the same name win
+    pub fn merge_managed(&mut self, managed: &Self) {
+        self.merge_read_only(managed, None);
+    }
+
+    /// Add the commands of a read-only overlay trove, e.g. a file shared by a team, marked with the name of the overlay
+    ///
+    /// They are managed commands, so they are never saved either. Commands already in the trove win
+    pub fn merge_overlay(&mut self, overlay: &Self, name: &str) {
+        self.merge_read_only(overlay, Some(name));
+    }
+
+    fn merge_read_only(&mut self, other: &Self, overlay: Option<&str>) {
+        for command in &other.commands {
+            if self.commands.iter().any(|c| c.name == command.name) {
+                continue;
+            }
+            let mut command = command.clone();
+            command.is_managed = true;
+            command.overlay = overlay.map(ToString::to_string);
+            self.add_namespace(&command.namespace);
+            self.commands.push(command);
+        }
+    }
+
+    /// Copy of the trove with only the commands that were hoarded by the user
+    pub fn without_managed(&self) -> Self {
+        let commands: Vec<HoardCmd> = self
+            .commands
+            .iter()
+            .filter(|c| !c.is_managed)
+            .cloned()
+            .collect();
+        Self {
+            version: self.version.clone(),
+            trash: self.trash.clone(),
+            include: self.include.clone(),
+            ..Self::from_commands(&commands)
+        }
+    }
+
     /// check if the trove collection is empty
     pub fn is_empty(&self) -> bool {
         self.commands.is_empty()
@@ -284,6 +729,115 @@ impl Trove {
             .any(|x| x.is_ok())
     }
 
+    /// Merge `other` into the trove, `resolve` decides about incoming commands with the name of a different local one
+    ///
+    /// It gets the local and the incoming command and returns the commands to keep in place of the local one.
+    /// Returns whether the trove changed
+    pub fn merge_trove_resolving(
+        &mut self,
+        other: &Self,
+        mut resolve: impl FnMut(&HoardCmd, &HoardCmd, &Self) -> Vec<HoardCmd>,
+    ) -> bool {
+        let mut dirty = false;
+        for command in &other.commands {
+            match self.get_command_collision(command) {
+                Some(local) if local != *command => {
+                    let kept = resolve(&local, command, self);
+                    dirty |= kept.len() != 1 || kept[0] != local;
+                    let position = self.commands.iter().position(|c| c == &local);
+                    self.commands.retain(|c| c != &local);
+                    let position = position.unwrap_or(self.commands.len());
+                    self.commands.splice(position..position, kept);
+                }
+                _ => dirty |= self.add_command(command.clone(), true).unwrap_or(false),
+            }
+        }
+        dirty
+    }
+
+    /// `name`, or the first of `name_2`, `name_3`, ... that isn't taken in `namespace`
+    pub fn free_name(&self, name: &str, namespace: &str) -> String {
+        let is_taken = |candidate: &str| {
+            self.commands
+                .iter()
+                .any(|c| c.namespace == namespace && c.name == candidate)
+        };
+        let mut candidate = name.to_string();
+        let mut count = 1;
+        while is_taken(&candidate) {
+            count += 1;
+            candidate = format!("{name}_{count}");
+        }
+        candidate
+    }
+
+    /// Move the command `name` of `namespace` to `new_namespace` and call it `new_name`
+    ///
+    /// Commands that have it as a related command follow it to its new name. Fails if the command doesn't exist, is
+    /// managed or there is a command with the new name in `new_namespace` already
+    pub fn move_command(
+        &mut self,
+        namespace: &str,
+        name: &str,
+        new_namespace: &str,
+        new_name: &str,
+    ) -> Result<HoardCmd, HoardErr> {
+        HoardCmd::is_name_valid(new_name)?;
+        if new_namespace.trim().is_empty() {
+            return Err(HoardErr::new("Namespace can't be empty"));
+        }
+        let index = self
+            .commands
+            .iter()
+            .position(|c| c.namespace == namespace && c.name == name)
+            .ok_or_else(|| {
+                HoardErr::new(&format!("No command [{name}] in namespace [{namespace}]"))
+            })?;
+        if self.commands[index].is_managed {
+            return Err(HoardErr::new(&format!(
+                "[{name}] is a managed command and can't be moved"
+            )));
+        }
+        if namespace == new_namespace && name == new_name {
+            return Ok(self.commands[index].clone());
+        }
+        if self
+            .commands
+            .iter()
+            .any(|c| c.namespace == new_namespace && c.name == new_name)
+        {
+            return Err(HoardErr::new(&format!(
+                "There already is a command [{new_name}] in namespace [{new_namespace}]"
+            )));
+        }
+
+        let command = &mut self.commands[index];
+        command.name = new_name.to_string();
+        command.namespace = new_namespace.to_string();
+        command.modified = SystemTime::now();
+        let moved = command.clone();
+        // Another command of the old name would still be what related commands point to
+        if name != new_name && !self.commands.iter().any(|c| c.name == name) {
+            for command in &mut self.commands {
+                for related in command
+                    .related
+                    .iter_mut()
+                    .filter(|related| *related == name)
+                {
+                    *related = new_name.to_string();
+                }
+            }
+        }
+        self.add_namespace(new_namespace);
+        let used: HashSet<String> = self
+            .namespaces()
+            .into_iter()
+            .map(ToString::to_string)
+            .collect();
+        self.namespaces.retain(|namespace| used.contains(namespace));
+        Ok(moved)
+    }
+
     pub fn print_trove(&self) {
         // Create the table
         let mut table = Table::new();
@@ -314,6 +868,111 @@ impl Trove {
 #[cfg(test)]
 mod test_commands {
     use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn remove_matching_commands() {
+        let mut trove = Trove::from_commands(&[
+            HoardCmd::default()
+                .with_name("old")
+                .with_namespace("legacy")
+                .with_tags_raw("deprecated"),
+            HoardCmd::default()
+                .with_name("new")
+                .with_namespace("legacy"),
+        ]);
+        let mut managed = HoardCmd::default()
+            .with_name("shared")
+            .with_namespace("legacy")
+            .with_tags_raw("deprecated");
+        managed.is_managed = true;
+        trove.commands.push(managed);
+        let removed = trove.remove_matching(|c| c.tags.contains(&String::from("deprecated")));
+        assert_eq!(
+            vec!["old"],
+            removed.iter().map(|c| c.name.as_str()).collect::<Vec<_>>()
+        );
+        assert_eq!(2, trove.commands.len());
+        assert_eq!("old", trove.trash[0].command.name);
+    }
+
+    #[test]
+    fn move_commands_between_namespaces() {
+        let mut trove = Trove::from_commands(&[
+            HoardCmd::default()
+                .with_name("status")
+                .with_namespace("git")
+                .with_command("git status"),
+            HoardCmd::default()
+                .with_name("log")
+                .with_namespace("git")
+                .with_command("git log")
+                .with_related("status"),
+            HoardCmd::default()
+                .with_name("st")
+                .with_namespace("k8s/prod")
+                .with_command("kubectl get pods"),
+        ]);
+        let moved = trove.move_command("git", "status", "vcs", "st").unwrap();
+        assert_eq!(
+            ("vcs", "st"),
+            (moved.namespace.as_str(), moved.name.as_str())
+        );
+        assert_eq!(vec!["st"], trove.get_command("log").unwrap().related);
+        assert!(trove.namespaces.contains("vcs"));
+
+        assert!(trove.move_command("git", "log", "k8s/prod", "st").is_err());
+        assert!(trove.move_command("git", "missing", "vcs", "log").is_err());
+        assert!(trove.move_command("git", "log", "vcs", "git log").is_err());
+        trove.move_command("git", "log", "vcs", "log").unwrap();
+        assert!(!trove.namespaces.contains("git"));
+
+        assert_eq!((Some("k8s/prod"), "st"), split_command_path("k8s/prod/st"));
+        assert_eq!((Some("vcs"), ""), split_command_path("vcs/"));
+        assert_eq!((None, "st"), split_command_path("st"));
+    }
+
+    #[test]
+    fn store_a_file_per_namespace() {
+        let home = tempdir().unwrap();
+        let path = home.path().join("trove.yml");
+        let mut trove = Trove::from_commands(&[
+            HoardCmd::default()
+                .with_name("status")
+                .with_namespace("git"),
+            HoardCmd::default()
+                .with_name("pods")
+                .with_namespace("k8s/prod"),
+            HoardCmd::default()
+                .with_name("nodes")
+                .with_namespace("k8s prod"),
+        ]);
+        trove.remove_command("status").unwrap();
+        trove.save_trove_file(&path);
+
+        assert_eq!(2, Trove::load_stored(&path, true).commands.len());
+        trove.save_stored(&path, true);
+        assert!(!path.exists());
+        let mut files: Vec<String> = fs::read_dir(namespace_dir(&path))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        files.sort();
+        assert_eq!(vec!["git.yml", "k8s_prod-2.yml", "k8s_prod.yml"], files);
+
+        let loaded = Trove::load_stored(&path, true);
+        assert_eq!(2, loaded.commands.len());
+        assert_eq!("status", loaded.trash[0].command.name);
+
+        trove.commands.retain(|command| command.name != "pods");
+        trove.save_stored(&path, true);
+        assert_eq!(1, Trove::load_stored(&path, true).commands.len());
+        assert!(!namespace_dir(&path).join("k8s_prod-2.yml").exists());
+
+        trove.save_stored(&path, false);
+        assert!(!namespace_dir(&path).exists());
+        assert_eq!(1, Trove::load_stored(&path, true).commands.len());
+    }
 
     #[test]
     fn empty_trove() {
@@ -370,6 +1029,39 @@ mod test_commands {
         assert_eq!(vec![namespace1, namespace2], trove.namespaces());
     }
 
+    #[test]
+    fn resolve_conflicting_commands_when_merging() {
+        let command = |name: &str, command: &str| {
+            HoardCmd::default()
+                .with_name(name)
+                .with_namespace("default")
+                .with_command(command)
+        };
+        let mut trove = Trove::from_commands(&[
+            command("build", "make"),
+            command("build_2", "make all"),
+            command("test", "make test"),
+        ]);
+        let incoming = Trove::from_commands(&[
+            command("build", "make -j8"),
+            command("test", "make test"),
+            command("lint", "make lint"),
+        ]);
+
+        let mut conflicts = Vec::new();
+        let dirty = trove.merge_trove_resolving(&incoming, |local, incoming, trove| {
+            conflicts.push(local.name.clone());
+            let name = trove.free_name(&incoming.name, &incoming.namespace);
+            vec![local.clone(), incoming.clone().with_name(&name)]
+        });
+        assert!(dirty);
+        assert_eq!(vec!["build"], conflicts);
+        let names: Vec<&str> = trove.commands.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(vec!["build", "build_3", "build_2", "test", "lint"], names);
+
+        assert!(!trove.merge_trove_resolving(&incoming, |local, _, _| vec![local.clone()]));
+    }
+
     #[test]
     fn add_valid_command() {
         // test adding a valid command
@@ -386,6 +1078,27 @@ mod test_commands {
         assert!(trove.namespaces.contains("test"));
     }
 
+    #[test]
+    fn test_restore_removed_command() {
+        let mut command = HoardCmd::default();
+        command.name = "test".to_string();
+        command.namespace = "test".to_string();
+        command.command = "test".to_string();
+        let mut trove = Trove::from_commands(&[command]);
+        trove.remove_command("test").unwrap();
+        assert_eq!(1, trove.trash.len());
+        assert!(trove.restore_command(Some("other")).is_err());
+        assert_eq!("test", trove.restore_command(None).unwrap().name);
+        assert!(trove.get_command("test").is_ok());
+        assert!(trove.trash.is_empty());
+
+        trove.remove_command("test").unwrap();
+        let reloaded = Trove::load_trove_from_string(&trove.to_yaml());
+        assert_eq!(1, reloaded.trash.len());
+        assert_eq!(1, trove.purge_trash());
+        assert!(trove.restore_command(None).is_err());
+    }
+
     #[test]
     fn test_multiple_new_namespaces_added() {
         // create a new trove, add two new commands with different namespaces
@@ -524,6 +1237,81 @@ mod test_commands {
         assert!(trove.is_empty());
     }
 
+    #[test]
+    fn test_managed_commands_are_read_only() {
+        let mut trove = Trove::from_commands(&[HoardCmd::default()
+            .with_name("logs")
+            .with_command("tail -f app.log")
+            .with_namespace("default")]);
+        let managed = Trove::from_commands(&[
+            HoardCmd::default()
+                .with_name("logs")
+                .with_command("journalctl -f")
+                .with_namespace("managed"),
+            HoardCmd::default()
+                .with_name("deploy")
+                .with_command("kubectl apply -f deploy.yml")
+                .with_namespace("managed"),
+        ]);
+        trove.merge_managed(&managed);
+
+        assert_eq!(
+            "tail -f app.log",
+            trove.get_command("logs").unwrap().command
+        );
+        assert!(trove.is_managed("deploy"));
+        assert!(trove.remove_command("deploy").is_err());
+        assert!(trove.namespaces.contains("managed"));
+
+        let saved = trove.without_managed();
+        assert_eq!(1, saved.commands.len());
+        assert!(!saved.namespaces.contains("managed"));
+
+        let overlay = Trove::from_commands(&[
+            HoardCmd::default()
+                .with_name("deploy")
+                .with_command("helm upgrade")
+                .with_namespace("team"),
+            HoardCmd::default()
+                .with_name("oncall")
+                .with_command("pagerduty schedule")
+                .with_namespace("team"),
+        ]);
+        trove.merge_overlay(&overlay, "company");
+        assert_eq!(
+            "kubectl apply -f deploy.yml",
+            trove.get_command("deploy").unwrap().command
+        );
+        let oncall = trove.get_command("oncall").unwrap();
+        assert_eq!(Some(String::from("company")), oncall.overlay);
+        assert!(trove.is_managed("oncall"));
+        assert_eq!(1, trove.without_managed().commands.len());
+    }
+
+    #[test]
+    fn test_merge_case_duplicate_namespaces() {
+        let command = |name: &str, namespace: &str| {
+            HoardCmd::default()
+                .with_name(name)
+                .with_command(name)
+                .with_namespace(namespace)
+        };
+        let mut trove = Trove::from_commands(&[
+            command("ps", "Docker"),
+            command("ps", "docker"),
+            command("images", "DOCKER"),
+            command("status", "git"),
+        ]);
+        let duplicates = trove.case_duplicate_namespaces();
+        assert_eq!(vec![vec!["DOCKER", "Docker", "docker"]], duplicates);
+
+        assert_eq!(2, trove.merge_namespaces(&duplicates[0], "docker"));
+        assert_eq!(vec!["docker", "git"], trove.namespaces());
+        // The same command in both namespaces is only kept once
+        assert_eq!(3, trove.commands.len());
+        assert!(!trove.namespaces.contains("Docker"));
+    }
+
     #[test]
     fn test_is_empty_new_trove() {
         // create a new trove and check if it is empty
@@ -538,4 +1326,25 @@ mod test_commands {
         // Should not contain a command
         assert!(!trove.namespaces.contains("test"));
     }
+
+    #[test]
+    fn markdown_cheatsheet_by_namespace() {
+        let trove = Trove::from_commands(&[
+            HoardCmd::default()
+                .with_name("logs")
+                .with_namespace("kube")
+                .with_command("kubectl logs `pod`")
+                .with_description("Show logs")
+                .with_tags_raw("k8s,debug"),
+            HoardCmd::default()
+                .with_name("calc")
+                .with_namespace("dev")
+                .with_command("print(1 + 1)")
+                .with_shell("/usr/bin/python3 -c"),
+        ]);
+        assert_eq!(
+            "# Hoard cheatsheet\n\n## dev\n\n### calc\n\n```python3\nprint(1 + 1)\n```\n\n## kube\n\n### logs\n\nShow logs\n\nTags: `k8s`, `debug`\n\n```sh\nkubectl logs `pod`\n```\n",
+            trove.to_markdown()
+        );
+    }
 }
@@ -0,0 +1,122 @@
+use crate::core::error::HoardErr;
+use crate::core::git::merge_troves;
+use crate::core::trove::{namespace_dir, trove_files, Trove};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Path, modification time and size of every file a trove is stored in
+pub type Fingerprint = Vec<(PathBuf, Option<SystemTime>, u64)>;
+
+/// Notices when a trove is changed on disk while the GUI is open, e.g. by hoard in another terminal or a sync
+pub struct TroveWatch {
+    path: PathBuf,
+    fingerprint: Fingerprint,
+    /// The trove as it was on disk when it was last loaded, to tell local changes from the ones on disk
+    base: Trove,
+    reload: Box<dyn Fn() -> Trove>,
+}
+
+impl TroveWatch {
+    /// Watch the trove stored at `path`, which is `base` right now. `reload` loads it the way hoard does on start
+    pub fn new(path: &Path, base: &Trove, reload: impl Fn() -> Trove + 'static) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            fingerprint: fingerprint(path),
+            base: base.without_managed(),
+            reload: Box::new(reload),
+        }
+    }
+
+    /// The trove on disk merged with the changes made to `current`, if it changed since it was last loaded
+    ///
+    /// An error if the trove on disk can't be read, it is reported once for every change
+    pub fn poll(&mut self, current: &Trove) -> Option<Result<Trove, HoardErr>> {
+        let fingerprint = fingerprint(&self.path);
+        if fingerprint == self.fingerprint {
+            return None;
+        }
+        self.fingerprint = fingerprint;
+        // A trove that can't be read would be loaded as an empty one, which would remove every command
+        for (file, _, _) in &self.fingerprint {
+            let content = fs::read_to_string(file).unwrap_or_default();
+            if let Err(e) = serde_yaml::from_str::<Trove>(&content) {
+                return Some(Err(HoardErr::new(&format!(
+                    "{} changed but can't be read: {e}",
+                    file.display()
+                ))));
+            }
+        }
+
+        let reloaded = (self.reload)();
+        let mut merged = merge_troves(
+            &self.base,
+            &current.without_managed(),
+            &reloaded.without_managed(),
+        )
+        .trove;
+        for command in reloaded.commands.iter().filter(|c| c.is_managed) {
+            if !merged.commands.iter().any(|c| c.name == command.name) {
+                merged.add_namespace(&command.namespace);
+                merged.commands.push(command.clone());
+            }
+        }
+        merged.include.clone_from(&reloaded.include);
+        self.base = reloaded.without_managed();
+        Some(Ok(merged))
+    }
+}
+
+/// Fingerprint of the trove stored at `path`, in either layout
+pub fn fingerprint(path: &Path) -> Fingerprint {
+    let mut files: Vec<PathBuf> = trove_files(&namespace_dir(path)).collect();
+    files.push(path.to_path_buf());
+    files.sort();
+    files
+        .into_iter()
+        .filter_map(|file| {
+            let metadata = fs::metadata(&file).ok()?;
+            Some((file, metadata.modified().ok(), metadata.len()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test_watch {
+    use super::*;
+    use crate::core::HoardCmd;
+    use tempfile::tempdir;
+
+    fn command(name: &str, command: &str) -> HoardCmd {
+        HoardCmd::default()
+            .with_name(name)
+            .with_namespace("default")
+            .with_command(command)
+    }
+
+    #[test]
+    fn merge_changes_on_disk_with_local_ones() {
+        let home = tempdir().unwrap();
+        let path = home.path().join("trove.yml");
+        let loaded = Trove::from_commands(&[command("build", "make")]);
+        loaded.save_trove_file(&path);
+
+        let reload_path = path.clone();
+        let mut watch = TroveWatch::new(&path, &loaded, move || {
+            Trove::load_trove_file(&Some(reload_path.clone()))
+        });
+        let mut current = loaded;
+        current.commands.push(command("lint", "cargo clippy"));
+        assert!(watch.poll(&current).is_none());
+
+        Trove::from_commands(&[command("build", "make -j8"), command("fmt", "cargo fmt")])
+            .save_trove_file(&path);
+        let merged = watch.poll(&current).unwrap().unwrap();
+        let commands: Vec<&str> = merged.commands.iter().map(|c| c.command.as_str()).collect();
+        assert_eq!(vec!["make -j8", "cargo clippy", "cargo fmt"], commands);
+
+        fs::write(&path, "commands: [").unwrap();
+        assert!(watch.poll(&merged).unwrap().is_err());
+        assert!(watch.poll(&merged).is_none());
+    }
+}
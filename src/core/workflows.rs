@@ -0,0 +1,181 @@
+use crate::core::parameters::{map_parameters, Parameter};
+use crate::core::HoardCmd;
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// A Warp workflow, see <https://docs.warp.dev/features/warp-drive/workflows>
+#[derive(Debug, Serialize)]
+struct WarpWorkflow {
+    name: String,
+    command: String,
+    tags: Vec<String>,
+    description: String,
+    arguments: Vec<WarpArgument>,
+}
+
+#[derive(Debug, Serialize)]
+struct WarpArgument {
+    name: String,
+    description: Option<String>,
+    default_value: Option<String>,
+}
+
+/// A snippet of iTerm2, as listed in its Snippets settings
+#[derive(Debug, Serialize)]
+struct ItermSnippet {
+    title: String,
+    value: String,
+    guid: String,
+    tags: Vec<String>,
+}
+
+/// Name a parameter gets in another tool, unnamed parameters are numbered from `arg1`
+fn argument_name(parameter: &Parameter, position: usize) -> String {
+    if parameter.name.is_empty() {
+        format!("arg{}", position + 1)
+    } else {
+        parameter.name.clone()
+    }
+}
+
+/// Render `command` as a Warp workflow, its parameters become `{{arguments}}` Warp asks for
+pub fn warp_workflow(
+    command: &HoardCmd,
+    start_token: &str,
+    end_token: &str,
+    escape: char,
+) -> String {
+    let mut arguments: Vec<WarpArgument> = Vec::new();
+    let workflow_command = map_parameters(
+        &command.command,
+        start_token,
+        end_token,
+        escape,
+        |parameter, position| {
+            let name = argument_name(parameter, position);
+            // A name used more than once is the same argument, Warp fills in all of them
+            if !arguments.iter().any(|argument| argument.name == name) {
+                arguments.push(WarpArgument {
+                    name: name.clone(),
+                    description: parameter.description.clone(),
+                    default_value: None,
+                });
+            }
+            format!("{{{{{name}}}}}")
+        },
+    );
+    let workflow = WarpWorkflow {
+        name: command.name.clone(),
+        command: workflow_command,
+        tags: command.tags.clone(),
+        description: command.description.clone(),
+        arguments,
+    };
+    serde_yaml::to_string(&workflow).expect("a workflow can be serialized")
+}
+
+/// File name of the Warp workflow of `command`, unique within a trove
+pub fn warp_workflow_file_name(command: &HoardCmd) -> String {
+    let file_name: String = format!("{}-{}", command.namespace, command.name)
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{file_name}.yaml")
+}
+
+/// Render `commands` as iTerm2 snippets. iTerm2 has no parameters, they are written as `<name>` to fill in
+pub fn iterm2_snippets(
+    commands: &[HoardCmd],
+    start_token: &str,
+    end_token: &str,
+    escape: char,
+) -> String {
+    let snippets: Vec<ItermSnippet> = commands
+        .iter()
+        .map(|command| ItermSnippet {
+            title: command.name.clone(),
+            value: map_parameters(
+                &command.command,
+                start_token,
+                end_token,
+                escape,
+                |parameter, position| format!("<{}>", argument_name(parameter, position)),
+            ),
+            guid: snippet_guid(command),
+            tags: command.tags.clone(),
+        })
+        .collect();
+    serde_json::to_string_pretty(&snippets).expect("snippets can be serialized")
+}
+
+/// An id that stays the same for a command, so exporting again updates its snippet instead of duplicating it
+fn snippet_guid(command: &HoardCmd) -> String {
+    let hash = |seed: &str| {
+        let mut hasher = DefaultHasher::new();
+        (seed, &command.namespace, &command.name).hash(&mut hasher);
+        hasher.finish()
+    };
+    let (high, low) = (hash("high"), hash("low"));
+    format!(
+        "{:08X}-{:04X}-{:04X}-{:04X}-{:012X}",
+        high >> 32,
+        (high >> 16) & 0xFFFF,
+        high & 0xFFFF,
+        low >> 48,
+        low & 0xFFFF_FFFF_FFFF
+    )
+}
+
+#[cfg(test)]
+mod test_workflows {
+    use super::*;
+
+    fn command() -> HoardCmd {
+        HoardCmd::default()
+            .with_name("logs")
+            .with_namespace("k8s")
+            .with_command("kubectl logs #pod:The pod to follow! -n #ns! -c #! | grep \\#ns #ns!")
+            .with_tags_raw("kube")
+            .with_description("Follow the logs of a pod")
+    }
+
+    #[test]
+    fn parameters_become_warp_arguments() {
+        let workflow = warp_workflow(&command(), "#", "!", '\\');
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&workflow).unwrap();
+        assert_eq!(
+            "kubectl logs {{pod}} -n {{ns}} -c {{arg3}} | grep #ns {{ns}}",
+            parsed["command"].as_str().unwrap()
+        );
+        let arguments = parsed["arguments"].as_sequence().unwrap();
+        assert_eq!(3, arguments.len());
+        assert_eq!(
+            "The pod to follow",
+            arguments[0]["description"].as_str().unwrap()
+        );
+        assert_eq!("kube", parsed["tags"][0].as_str().unwrap());
+        assert_eq!("k8s-logs.yaml", warp_workflow_file_name(&command()));
+    }
+
+    #[test]
+    fn parameters_become_snippet_placeholders() {
+        let snippets = iterm2_snippets(&[command()], "#", "!", '\\');
+        let parsed: serde_json::Value = serde_json::from_str(&snippets).unwrap();
+        assert_eq!(
+            "kubectl logs <pod> -n <ns> -c <arg3> | grep #ns <ns>",
+            parsed[0]["value"].as_str().unwrap()
+        );
+        assert_eq!(
+            snippet_guid(&command()),
+            parsed[0]["guid"].as_str().unwrap()
+        );
+        assert_eq!(36, parsed[0]["guid"].as_str().unwrap().len());
+    }
+}
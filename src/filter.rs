@@ -1,20 +1,194 @@
+use crate::core::parameters::Parameterized;
+use crate::core::query::Query;
+use crate::core::shell::is_installed;
 use crate::core::trove::Trove;
 use crate::core::HoardCmd;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
 
-pub fn query_trove(trove: &Trove, query_term: &str) -> Trove {
-    // Filter out commands of `trove` based on `query_string`
-    // Construct QueryString object from &str object to validate / extract special parameters
+/// Commands of `trove` matching `query`, see `Query` for its terms
+pub fn query_trove(trove: &Trove, query: &str) -> Trove {
+    let query = Query::parse(query);
     let commands: Vec<HoardCmd> = trove
         .commands
-        .clone()
-        .into_iter()
-        .filter(|c| {
-            c.name.contains(query_term)
-                || c.namespace.contains(query_term)
-                || c.get_tags_as_string().contains(query_term)
-                || c.command.contains(query_term)
-                || c.description.contains(query_term)
-        })
+        .iter()
+        .filter(|c| query.matches(c))
+        .cloned()
         .collect();
     Trove::from_commands(&commands)
 }
+
+/// Whether `command` matches every whitespace separated term of `query`
+///
+/// Terms like `tag:aws`, `ns:work` or `name:deploy` only match that field, other terms match any field
+pub fn command_matches(command: &HoardCmd, query: &str) -> bool {
+    Query::parse(query).matches(command)
+}
+
+/// Filters toggled in the GUI, they narrow down the commands matching the query
+#[derive(Debug, Default, Clone)]
+pub struct QuickFilters {
+    /// Only commands with parameters
+    pub parameterized: bool,
+    /// Only favorite commands
+    pub favorites: bool,
+    /// Only commands whose programs are all installed
+    pub installed: bool,
+    /// Whether the programs of a command are installed by its command, shell and container, to not search `$PATH`
+    /// again on every key
+    installed_commands: HashMap<(String, Option<String>, Option<String>), bool>,
+}
+
+impl QuickFilters {
+    /// Names of the filters that are on, to show them next to the list
+    pub fn active(&self) -> Vec<&'static str> {
+        [
+            (self.parameterized, "with parameters"),
+            (self.favorites, "favorites"),
+            (self.installed, "installed"),
+        ]
+        .iter()
+        .filter(|(on, _)| *on)
+        .map(|(_, name)| *name)
+        .collect()
+    }
+
+    pub fn matches(&mut self, command: &HoardCmd, token: &str, escape: char) -> bool {
+        if self.parameterized && command.get_parameter_count(token, escape) == 0 {
+            return false;
+        }
+        if self.favorites && !command.is_favorite {
+            return false;
+        }
+        !self.installed
+            || *self
+                .installed_commands
+                .entry((
+                    command.command.clone(),
+                    command.shell.clone(),
+                    command.container.clone(),
+                ))
+                .or_insert_with(|| command.missing_binaries(token, is_installed).is_empty())
+    }
+}
+
+/// Order of the commands in the GUI, cycled through with a key
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SortMode {
+    /// Commands of the context hoard was started in first, then by frecency and by how well they match the query
+    #[default]
+    Relevance,
+    Alphabetical,
+    /// Most recently used first
+    Recent,
+    /// Most often used first
+    Frequent,
+    /// Most recently created first
+    Added,
+}
+
+impl SortMode {
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Relevance => Self::Alphabetical,
+            Self::Alphabetical => Self::Recent,
+            Self::Recent => Self::Frequent,
+            Self::Frequent => Self::Added,
+            Self::Added => Self::Relevance,
+        }
+    }
+
+    /// Sort `commands` by the mode, the search ranks them itself by relevance
+    pub fn sort(self, commands: &mut [HoardCmd]) {
+        match self {
+            Self::Relevance => {}
+            Self::Alphabetical => {
+                commands.sort_by_key(|c| (c.name.to_lowercase(), c.namespace.clone()));
+            }
+            Self::Recent => commands.sort_by_key(|c| Reverse(c.last_used)),
+            Self::Frequent => commands.sort_by_key(|c| Reverse(c.usage_count)),
+            Self::Added => commands.sort_by_key(|c| Reverse(c.created)),
+        }
+    }
+}
+
+impl fmt::Display for SortMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Relevance => write!(f, "relevance"),
+            Self::Alphabetical => write!(f, "name"),
+            Self::Recent => write!(f, "recently used"),
+            Self::Frequent => write!(f, "most used"),
+            Self::Added => write!(f, "recently added"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_filter {
+    use super::*;
+
+    #[test]
+    fn query_terms_match_fields() {
+        let command = HoardCmd::default()
+            .with_name("deploy_app")
+            .with_namespace("work")
+            .with_command("aws deploy --region us-east-1")
+            .with_tags_raw("aws,prod");
+        assert!(command_matches(&command, ""));
+        assert!(command_matches(&command, "tag:aws ns:work us-east"));
+        assert!(command_matches(&command, "name:deploy"));
+        assert!(command_matches(&command, "tag:AW"));
+        assert!(!command_matches(&command, "tag:aws ns:home"));
+    }
+
+    #[test]
+    fn quick_filters_narrow_down_commands() {
+        let mut favorite = HoardCmd::default().with_command("ls #dir!");
+        favorite.is_favorite = true;
+        let missing = HoardCmd::default().with_command("not-installed-anywhere --help");
+        let mut filters = QuickFilters::default();
+        assert!(filters.matches(&missing, "#", '\\'));
+        assert!(filters.active().is_empty());
+
+        filters.parameterized = true;
+        assert!(filters.matches(&favorite, "#", '\\'));
+        assert!(!filters.matches(&missing, "#", '\\'));
+        filters.parameterized = false;
+        filters.favorites = true;
+        assert!(!filters.matches(&missing, "#", '\\'));
+        filters.favorites = false;
+        filters.installed = true;
+        assert!(filters.matches(&favorite, "#", '\\'));
+        assert!(!filters.matches(&missing, "#", '\\'));
+        // Like `hoard check`, programs running in a container aren't looked for
+        let in_container = missing.clone().with_container("alpine");
+        assert!(filters.matches(&in_container, "#", '\\'));
+        assert_eq!(vec!["installed"], filters.active());
+    }
+
+    #[test]
+    fn sort_modes() {
+        let mut old = HoardCmd::default().with_name("b_old");
+        old.created = std::time::UNIX_EPOCH;
+        old.usage_count = 5;
+        let mut used = HoardCmd::default().with_name("C_used");
+        used.last_used += std::time::Duration::from_mins(1);
+        let added = HoardCmd::default().with_name("a_added");
+        let mut commands = vec![old, used, added];
+        let names = |commands: &[HoardCmd]| -> Vec<String> {
+            commands.iter().map(|c| c.name.clone()).collect()
+        };
+
+        SortMode::Alphabetical.sort(&mut commands);
+        assert_eq!(vec!["a_added", "b_old", "C_used"], names(&commands));
+        SortMode::Recent.sort(&mut commands);
+        assert_eq!("C_used", commands[0].name);
+        SortMode::Frequent.sort(&mut commands);
+        assert_eq!("b_old", commands[0].name);
+        SortMode::Added.sort(&mut commands);
+        assert_eq!("b_old", commands[2].name);
+        assert_eq!(SortMode::Relevance, SortMode::Added.next());
+    }
+}
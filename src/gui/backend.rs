@@ -0,0 +1,145 @@
+//! The terminal the GUI is drawn in, with termion on unix and with crossterm on Windows or with the
+//! `crossterm-backend` feature
+use crate::gui::event::Key;
+use ratatui::Terminal;
+use std::io::{self, stdout, Stdout};
+
+#[cfg(any(windows, feature = "crossterm-backend"))]
+use crate::gui::event::Event;
+#[cfg(any(windows, feature = "crossterm-backend"))]
+use crossterm::{
+    cursor::Show,
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+#[cfg(any(windows, feature = "crossterm-backend"))]
+use ratatui::backend::CrosstermBackend;
+
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+use ratatui::backend::TermionBackend;
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+use std::io::Write;
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+use termion::input::TermRead;
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+use termion::raw::{IntoRawMode, RawTerminal};
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+use termion::screen::{AlternateScreen, IntoAlternateScreen, ToAlternateScreen, ToMainScreen};
+
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+pub type GuiBackend = TermionBackend<AlternateScreen<RawTerminal<Stdout>>>;
+#[cfg(any(windows, feature = "crossterm-backend"))]
+pub type GuiBackend = CrosstermBackend<Stdout>;
+
+/// Sequences turning bracketed paste on and off, the terminal marks where pasted text starts and ends meanwhile
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+const PASTE_ON: &str = "\x1b[?2004h";
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+const PASTE_OFF: &str = "\x1b[?2004l";
+
+/// Sequences turning the reports of clicks and the scroll wheel on and off, the same ones termion uses
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+const MOUSE_ON: &str = "\x1b[?1000h\x1b[?1002h\x1b[?1015h\x1b[?1006h";
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+const MOUSE_OFF: &str = "\x1b[?1006l\x1b[?1015l\x1b[?1002l\x1b[?1000l";
+
+/// The terminal in raw mode on the alternate screen, with bracketed paste and the reports of clicks if `mouse`
+///
+/// Text can't be selected while clicks are reported. The terminal is restored when the screen is dropped
+pub struct Screen {
+    pub terminal: Terminal<GuiBackend>,
+    mouse: bool,
+}
+
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+impl Screen {
+    pub fn enter(mouse: bool) -> io::Result<Self> {
+        let stdout = stdout().into_raw_mode()?.into_alternate_screen()?;
+        let mut screen = Self {
+            terminal: Terminal::new(TermionBackend::new(stdout))?,
+            mouse,
+        };
+        screen.resume()?;
+        Ok(screen)
+    }
+
+    /// Hand the terminal to another program, e.g. an editor, until `resume`
+    pub fn suspend(&mut self) -> io::Result<()> {
+        let backend = self.terminal.backend_mut();
+        write!(
+            backend,
+            "{MOUSE_OFF}{PASTE_OFF}{ToMainScreen}{}",
+            termion::cursor::Show
+        )?;
+        backend.flush()
+    }
+
+    pub fn resume(&mut self) -> io::Result<()> {
+        let mouse = if self.mouse { MOUSE_ON } else { "" };
+        write!(
+            self.terminal.backend_mut(),
+            "{ToAlternateScreen}{PASTE_ON}{mouse}"
+        )?;
+        self.terminal.clear()
+    }
+}
+
+#[cfg(any(windows, feature = "crossterm-backend"))]
+impl Screen {
+    pub fn enter(mouse: bool) -> io::Result<Self> {
+        let mut screen = Self {
+            terminal: Terminal::new(CrosstermBackend::new(stdout()))?,
+            mouse,
+        };
+        screen.resume()?;
+        Ok(screen)
+    }
+
+    /// Hand the terminal to another program, e.g. an editor, until `resume`
+    pub fn suspend(&mut self) -> io::Result<()> {
+        let backend = self.terminal.backend_mut();
+        if self.mouse {
+            execute!(backend, DisableMouseCapture)?;
+        }
+        execute!(backend, DisableBracketedPaste, LeaveAlternateScreen, Show)?;
+        disable_raw_mode()
+    }
+
+    pub fn resume(&mut self) -> io::Result<()> {
+        enable_raw_mode()?;
+        let backend = self.terminal.backend_mut();
+        execute!(backend, EnterAlternateScreen, EnableBracketedPaste)?;
+        if self.mouse {
+            execute!(backend, EnableMouseCapture)?;
+        }
+        self.terminal.clear()
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = self.suspend();
+    }
+}
+
+/// Wait for the next key, `None` once there are no more
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+pub fn read_key() -> io::Result<Option<Key>> {
+    for key in io::stdin().keys() {
+        if let Some(key) = Key::from_termion(key?) {
+            return Ok(Some(key));
+        }
+    }
+    Ok(None)
+}
+
+/// Wait for the next key, clicks and pasted text are skipped
+#[cfg(any(windows, feature = "crossterm-backend"))]
+pub fn read_key() -> io::Result<Option<Key>> {
+    loop {
+        if let Some(Event::Input(key)) = Event::from_crossterm(crossterm::event::read()?) {
+            return Ok(Some(key));
+        }
+    }
+}
@@ -1,67 +1,166 @@
 use crate::config::HoardConfig;
+use crate::core::context::{sort_by_context, CommandContext};
+use crate::core::editor::{apply_buffer, command_buffer, edit_text};
+use crate::core::error::HoardErr;
+use crate::core::providers::ProviderRegistry;
+use crate::core::stats::{
+    append_usage, frecency, load_usage, Frecency, UsageRecord, UsedValues, USAGE_LOG_FILE,
+    USED_VALUES_FILE,
+};
+use crate::core::tags::TagChange;
 use crate::core::trove::Trove;
+use crate::core::watch::TroveWatch;
 use crate::core::HoardCmd;
+use crate::filter::{QuickFilters, SortMode};
 use crate::gpt::prompt;
-use crate::gui::event::{Config, Event, Events};
-use crate::gui::help::{draw as draw_help, key_handler as key_handler_help};
+use crate::gui::backend::Screen;
+use crate::gui::event::{Config, Event, Events, Key, MouseEvent};
+use crate::gui::help::HelpScreen;
 use crate::gui::inline_edit::controls::key_handler as key_handler_inline_edit;
+use crate::gui::inline_edit::EditForm;
+use crate::gui::keymap::Keymap;
+use crate::gui::list_search::batch_controls::key_handler as key_handler_batch;
+use crate::gui::list_search::confirm_controls::key_handler as key_handler_confirm;
+use crate::gui::list_search::controls::apply_filter;
 use crate::gui::list_search::controls::key_handler as key_handler_list_search;
+use crate::gui::list_search::controls::mouse_handler as mouse_handler_list_search;
+use crate::gui::list_search::controls::paste_handler as paste_handler_list_search;
 use crate::gui::list_search::gpt_controls::key_handler as key_handler_gpt_create;
 use crate::gui::list_search::key_not_set_controls::key_handler as key_handler_no_key_set;
+use crate::gui::list_search::move_controls::key_handler as key_handler_move;
+use crate::gui::list_search::preview_search_controls::key_handler as key_handler_preview_search;
 use crate::gui::list_search::render::draw as draw_list_search;
-use crate::gui::new_command::controls::key_handler as key_handler_create_command;
-use crate::gui::new_command::render::draw as draw_new_command_input;
 use crate::gui::parameter_input::controls::key_handler as key_handler_parameter_input;
+use crate::gui::parameter_input::controls::paste_handler as paste_handler_parameter_input;
+use crate::gui::parameter_input::controls::pasted_text;
 use crate::gui::parameter_input::render::draw as draw_parameter_input;
-use eyre::Result;
-use ratatui::{backend::TermionBackend, widgets::ListState, Terminal};
+use crate::gui::terminal::can_show_gui;
+use crate::util::{pluralize, write_clipboard};
+use eyre::{eyre, Result};
+use ratatui::{layout::Rect, widgets::ListState};
+use std::collections::{HashMap, HashSet};
 use std::fmt;
-use std::io::stdout;
-use std::time::Duration;
-use termion::raw::IntoRawMode;
-use termion::screen::IntoAlternateScreen;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How long a status message is shown in the footer
+const STATUS_MESSAGE_DURATION: Duration = Duration::from_secs(4);
 
 #[allow(clippy::struct_excessive_bools, clippy::struct_field_names)]
 pub struct State {
+    /// Batch action on the marked commands that was confirmed and is applied to the trove next
+    pub batch_action: Option<BatchAction>,
     pub buffered_tick: bool,
     pub command_list: ListState,
     pub commands: Vec<HoardCmd>,
+    /// Where the command list was drawn last, to find the command that is clicked
+    pub commands_area: Rect,
+    /// Question of the confirmation modal, and the action taken if it is confirmed
+    pub confirmation: Option<Confirmation>,
+    /// Context hoard was started in, commands hoarded in the same one are ranked higher
+    pub context: Option<CommandContext>,
     pub control: ControlState,
+    /// Namespace of commands created in the GUI while the `All` tab is shown
+    pub default_namespace: String,
     pub draw: DrawState,
-    pub edit_selection: EditSelection,
+    /// The form the selected command is edited or a new command is created in
+    pub edit_form: Option<EditForm>,
     pub error_message: String,
+    /// Screen whose keys are shown in the help over it, if the help is shown
+    pub help: Option<HelpScreen>,
+    /// Whether the programs of the previewed commands were found, each program is only looked up once
+    pub installed_binaries: HashMap<String, bool>,
     pub input: String,
+    /// Where the query was drawn last, a click on it goes back to searching
+    pub query_area: Rect,
+    /// Actions the keys in the command list are bound to
+    pub keymap: Keymap,
+    /// Namespace and name of the commands marked with <Space>, batch actions apply to all of them
+    pub marked: HashSet<(String, String)>,
     pub namespace_tab: ListState,
-    pub new_command: Option<HoardCmd>,
+    /// Selected command and scroll offset of every namespace tab that was left during this session
+    pub namespace_positions: HashMap<String, ListState>,
     pub openai_key_set: bool,
     pub parameter_ending_token: String,
+    pub parameter_escape_char: char,
+    /// Providers of the values offered for parameters like `#host@consul!`
+    pub parameter_providers: ProviderRegistry,
+    /// Values offered by the provider command of the parameter that is asked for
+    pub parameter_options: Vec<String>,
+    pub parameter_option_list: ListState,
+    /// Columns the command is scrolled in the parameter input, `None` follows the parameter that is asked for
+    pub parameter_scroll: Option<u16>,
+    /// File paths or values used before that the input of the parameter can be completed to
+    pub path_candidates: Vec<String>,
     pub parameter_token: String,
     pub popup_message: String,
+    /// Search term of the search in the preview pane
+    pub preview_query: String,
+    /// Index of the match of `preview_query` that is jumped to
+    pub preview_match: usize,
+    /// Whether `preview_query` is being typed or `n`/`N` jump between its matches
+    pub preview_typing: bool,
+    /// Rows the description is scrolled past the current match and columns the command is scrolled in the preview search
+    pub preview_offset: (u16, u16),
     pub provided_parameter_count: u16,
     pub query_gpt: bool,
+    /// Filters toggled with F-keys, applied together with the query
+    pub quick_filters: QuickFilters,
+    /// Frecency of every used command by name, the list is ranked by it
+    pub frecency: Frecency,
     pub selected_command: Option<HoardCmd>,
+    /// Whether the selected command is put on the clipboard instead of being handed to the shell
+    pub should_copy: bool,
     pub should_delete: bool,
     pub should_exit: bool,
+    /// Whether the selected command is opened in `$EDITOR` to be edited
+    pub should_open_editor: bool,
+    /// Whether the most recently removed command should be restored from the trash
+    pub should_restore: bool,
+    /// Whether the pane with the full command, description and tags of the selected command is shown
+    pub show_details: bool,
+    /// Whether the namespaces are shown in a sidebar instead of tabs
+    pub show_sidebar: bool,
+    /// Where the namespace sidebar was drawn last, to find the namespace that is clicked
+    pub sidebar_area: Rect,
+    /// Order of the commands in the list
+    pub sort_mode: SortMode,
+    /// Shown in the footer for a few seconds from the time it was set, e.g. after the trove was reloaded
+    pub status_message: Option<(String, Instant)>,
     pub string_to_edit: String,
+    /// Name of the trove the commands are from
+    pub trove_name: String,
+    /// Values given to parameters before, offered to complete the input of a parameter
+    pub used_values: UsedValues,
 }
 
 impl State {
-    pub fn update_string_to_edit(&mut self) -> &mut Self {
-        let selected_idx = self.command_list.selected().unwrap();
-        let cloned_selected_command = self.commands.get(selected_idx).unwrap().clone();
-        match self.edit_selection {
-            EditSelection::Name => self.string_to_edit = cloned_selected_command.name,
-            EditSelection::Tags => {
-                self.string_to_edit = cloned_selected_command.get_tags_as_string();
-            }
-            EditSelection::Description => {
-                self.string_to_edit = cloned_selected_command.description;
-            }
+    pub fn is_marked(&self, command: &HoardCmd) -> bool {
+        self.marked
+            .contains(&(command.namespace.clone(), command.name.clone()))
+    }
 
-            EditSelection::Command => self.string_to_edit = cloned_selected_command.command,
-            EditSelection::Namespace => (),
-        };
-        self
+    /// Mark `command` for a batch action, or unmark it if it is marked already
+    pub fn toggle_mark(&mut self, command: &HoardCmd) {
+        let key = (command.namespace.clone(), command.name.clone());
+        if !self.marked.remove(&key) {
+            self.marked.insert(key);
+        }
+    }
+
+    /// Ask in the confirmation modal before `action` is taken
+    pub fn confirm(&mut self, question: String, action: Confirmed) {
+        self.confirmation = Some(Confirmation { question, action });
+        self.control = ControlState::Confirm;
+    }
+
+    /// The status message, if it was set less than `STATUS_MESSAGE_DURATION` ago
+    pub fn current_status_message(&self) -> Option<&str> {
+        self.status_message
+            .as_ref()
+            .filter(|(_, since)| since.elapsed() < STATUS_MESSAGE_DURATION)
+            .map(|(message, _)| message.as_str())
     }
 
     pub fn get_default_popupmsg() -> String {
@@ -77,8 +176,6 @@ impl State {
 pub enum DrawState {
     Search,
     ParameterInput,
-    Help,
-    Create,
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -87,6 +184,44 @@ pub enum ControlState {
     Edit,
     Gpt,
     KeyNotSet,
+    PreviewSearch,
+    Move,
+    /// The input of a batch action on the marked commands is typed
+    Batch(BatchInput),
+    /// A destructive action waits for `<y>` in the confirmation modal
+    Confirm,
+}
+
+/// Batch actions on the marked commands that ask for an input first
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BatchInput {
+    Retag,
+    Move,
+    Export,
+}
+
+/// A change to every marked command, applied to the trove by `run`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum BatchAction {
+    Delete,
+    Retag(TagChange),
+    Move(String),
+    /// Write the marked commands to a trove file at the path
+    Export(PathBuf),
+}
+
+/// Destructive actions that are taken once they are confirmed
+#[derive(Debug, Clone, PartialEq)]
+pub enum Confirmed {
+    Delete(Box<HoardCmd>),
+    Batch(BatchAction),
+}
+
+/// A question of the confirmation modal, e.g. `Delete 'k8s/restart-pod'?`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Confirmation {
+    pub question: String,
+    pub action: Confirmed,
 }
 
 impl fmt::Display for ControlState {
@@ -95,13 +230,34 @@ impl fmt::Display for ControlState {
             Self::Search => write!(f, "Search (<Tab>/<Ctrl-E> to edit)"),
             Self::Edit => write!(
                 f,
-                "Edit (<Enter> to confirm. <Tab> to switch. <Esc> to abort)"
+                "Edit (<Enter> to save. <Tab>/<Shift-Tab> to switch fields. <Esc> to abort)"
             ),
             Self::Gpt => write!(
                 f,
                 "Describe your command (<Enter> to confirm. <Esc> to abort)"
             ),
             Self::KeyNotSet => write!(f, "(<Esc> to abort)"),
+            Self::PreviewSearch => write!(
+                f,
+                "Preview (</> to search. <Enter> to confirm. <n>/<N> to jump. <Arrows> to scroll. <Esc> to leave)"
+            ),
+            Self::Move => write!(
+                f,
+                "Move to <namespace>/<name> (<Enter> to confirm. <Esc> to abort)"
+            ),
+            Self::Batch(BatchInput::Retag) => write!(
+                f,
+                "Tag marked commands with +<tag>, -<tag> or <old>=<new> (<Enter> to confirm. <Esc> to abort)"
+            ),
+            Self::Batch(BatchInput::Move) => write!(
+                f,
+                "Move marked commands to <namespace> (<Enter> to confirm. <Esc> to abort)"
+            ),
+            Self::Batch(BatchInput::Export) => write!(
+                f,
+                "Export marked commands to <file> (<Enter> to confirm. <Esc> to abort)"
+            ),
+            Self::Confirm => write!(f, "Confirm (<y> to confirm. Any other key to abort)"),
         }
     }
 }
@@ -127,93 +283,137 @@ impl fmt::Display for EditSelection {
     }
 }
 
-impl EditSelection {
-    pub const fn next(&self) -> Self {
-        match self {
-            Self::Name | Self::Namespace | Self::Command => Self::Tags,
-            Self::Tags => Self::Description,
-            Self::Description => Self::Command,
-        }
-    }
-    pub const fn edit_next(&self) -> Self {
-        match self {
-            Self::Command => Self::Namespace,
-            Self::Namespace => Self::Name,
-            Self::Name => Self::Description,
-            Self::Description => Self::Tags,
-            Self::Tags => Self::Command,
-        }
-    }
-}
-
 #[allow(clippy::too_many_lines)]
-pub fn run(trove: &mut Trove, config: &HoardConfig) -> Result<Option<HoardCmd>> {
+pub fn run(
+    trove: &mut Trove,
+    config: &HoardConfig,
+    trove_name: &str,
+    mut watch: Option<TroveWatch>,
+    query: Option<&str>,
+) -> Result<Option<HoardCmd>> {
+    if !can_show_gui() {
+        return Err(eyre!("No terminal to show the hoard GUI in"));
+    }
     let events = Events::with_config(Config {
         tick_rate: Duration::from_millis(200),
     });
-    let trove_clone = trove.clone();
 
     let mut openai_api_key = std::env::var("OPENAI_API_KEY").unwrap_or_default();
     if openai_api_key.is_empty() {
         openai_api_key = config.gpt_api_key.clone().unwrap_or_default();
     }
 
+    let used_values_path = config
+        .hoard_home_path()
+        .map(|path| path.join(USED_VALUES_FILE));
+    let context = (config.capture_context == Some(true)).then(CommandContext::capture);
+    let usage_log_path = config
+        .hoard_home_path()
+        .map(|path| path.join(USAGE_LOG_FILE));
+    let frecency = usage_log_path
+        .as_deref()
+        .map(|path| frecency(&load_usage(path), SystemTime::now()))
+        .unwrap_or_default();
+    let mut commands = trove.commands.clone();
+    sort_by_context(&mut commands, context.as_ref(), &frecency);
+    let (keymap, keymap_problems) = Keymap::from_config(config);
     let mut app_state = State {
         input: String::new(),
-        commands: trove.get_commands_sorted_by_usage(),
+        query_area: Rect::default(),
+        commands_area: Rect::default(),
+        confirmation: None,
+        keymap,
+        commands,
+        context,
+        frecency,
         command_list: ListState::default(),
+        marked: HashSet::new(),
+        batch_action: None,
         namespace_tab: ListState::default(),
+        namespace_positions: HashMap::new(),
         should_exit: false,
+        should_open_editor: false,
+        should_copy: false,
         should_delete: false,
+        should_restore: false,
+        show_details: false,
+        show_sidebar: config.namespace_sidebar == Some(true),
+        sidebar_area: Rect::default(),
+        sort_mode: SortMode::default(),
         draw: DrawState::Search,
         control: ControlState::Search,
-        edit_selection: EditSelection::Command,
-        new_command: None,
+        edit_form: None,
+        default_namespace: config.default_namespace.clone(),
         string_to_edit: String::new(),
         parameter_token: config.parameter_token.as_ref().unwrap().clone(),
         parameter_ending_token: config.parameter_ending_token.as_ref().unwrap().clone(),
+        parameter_escape_char: config.escape_char(),
+        parameter_providers: ProviderRegistry::from_config(config),
+        parameter_options: Vec::new(),
+        parameter_option_list: ListState::default(),
+        parameter_scroll: None,
+        path_candidates: Vec::new(),
 
         selected_command: None,
         provided_parameter_count: 0,
+        // Bindings that can't be read are pointed out, the other ones work anyway
+        status_message: keymap_problems
+            .into_iter()
+            .next()
+            .map(|problem| (problem, Instant::now())),
         error_message: String::new(),
+        help: None,
+        installed_binaries: HashMap::new(),
         query_gpt: false,
+        quick_filters: QuickFilters::default(),
         popup_message: State::get_default_popupmsg(),
+        preview_query: String::new(),
+        preview_match: 0,
+        preview_typing: false,
+        preview_offset: (0, 0),
         buffered_tick: false,
         openai_key_set: !openai_api_key.is_empty(),
+        trove_name: trove_name.to_string(),
+        used_values: used_values_path
+            .as_deref()
+            .map(UsedValues::load)
+            .unwrap_or_default(),
     };
 
     app_state.command_list.select(Some(0));
     app_state.namespace_tab.select(Some(0));
 
-    let stdout = stdout().into_raw_mode()?;
-    let stdout = stdout.into_alternate_screen().unwrap();
-    let backend = TermionBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
-    terminal.clear()?;
+    let mut screen = Screen::enter(config.mouse != Some(false))?;
 
     //let menu_titles = vec!["List", "Search", "Add", "Delete", "Quit"];
-    let mut namespace_tabs: Vec<&str> = trove_clone.namespaces();
-    namespace_tabs.insert(0, "All");
+    let mut namespace_names = namespace_tab_names(trove);
+    // Start with the query the list was asked for, e.g. with `hoard list --filter`
+    if let Some(query) = query {
+        app_state.input = query.to_string();
+        let namespace_tabs: Vec<&str> = namespace_names.iter().map(String::as_str).collect();
+        apply_filter(&mut app_state, &namespace_tabs, &trove.commands);
+    }
     loop {
+        // Only while searching, so a command that is being edited or filled in doesn't change underneath
+        if app_state.draw == DrawState::Search && app_state.control == ControlState::Search {
+            if let Some(reloaded) = watch.as_mut().and_then(|watch| watch.poll(trove)) {
+                reload_trove(&mut app_state, trove, &mut namespace_names, reloaded);
+            }
+        }
+        let namespace_tabs: Vec<&str> = namespace_names.iter().map(String::as_str).collect();
         // Draw GUI
         match app_state.draw {
             DrawState::Search => {
-                draw_list_search(&mut app_state, config, &namespace_tabs, &mut terminal)?;
-            }
-            DrawState::ParameterInput => {
-                draw_parameter_input(&app_state, config, &mut terminal)?;
-            }
-            DrawState::Help => {
-                draw_help(config, &mut terminal)?;
-            }
-            DrawState::Create => {
-                draw_new_command_input(
-                    &app_state,
+                draw_list_search(
+                    &mut app_state,
                     config,
-                    &mut terminal,
-                    &config.default_namespace,
+                    &namespace_tabs,
+                    &mut screen.terminal,
                 )?;
             }
+            DrawState::ParameterInput => {
+                draw_parameter_input(&app_state, config, &mut screen.terminal)?;
+            }
         }
 
         if app_state.query_gpt && app_state.control == ControlState::Gpt {
@@ -231,56 +431,415 @@ pub fn run(trove: &mut Trove, config: &HoardConfig) -> Result<Option<HoardCmd>>
             }
         }
 
-        if let Event::Input(input) = events.next()? {
-            let command = match app_state.draw {
-                DrawState::Search => match app_state.control {
-                    ControlState::Search => key_handler_list_search(
-                        input,
-                        &mut app_state,
-                        &trove.commands,
-                        &namespace_tabs,
-                    ),
-                    ControlState::Edit => key_handler_inline_edit(input, &mut app_state),
-                    ControlState::Gpt => key_handler_gpt_create(input, &mut app_state),
-                    ControlState::KeyNotSet => key_handler_no_key_set(input, &mut app_state),
-                },
-                DrawState::ParameterInput => key_handler_parameter_input(input, &mut app_state),
-                DrawState::Help => key_handler_help(input, &mut app_state),
-                DrawState::Create => {
-                    key_handler_create_command(input, &mut app_state, &config.default_namespace)
+        let command = match events.next()? {
+            Event::Input(input) => {
+                handle_key(input, &mut app_state, &trove.commands, &namespace_tabs)
+            }
+            Event::Paste(text) => {
+                handle_paste(&text, &mut app_state, &trove.commands, &namespace_tabs)
+            }
+            Event::Mouse(mouse) => {
+                handle_mouse(mouse, &mut app_state, &trove.commands, &namespace_tabs)
+            }
+            Event::Tick => continue,
+        };
+
+        if let Some(output) = command {
+            if app_state.control == ControlState::Edit {
+                save_edited(&mut app_state, trove, &mut namespace_names, &output);
+            } else if app_state.control == ControlState::Move {
+                move_selected(&mut app_state, trove, &mut namespace_names, &output);
+            } else if app_state.should_delete {
+                delete_confirmed(&mut app_state, trove, &output);
+            } else if app_state.should_open_editor {
+                app_state.should_open_editor = false;
+                edit_in_editor(&mut app_state, trove, &output, &mut screen, &events)?;
+            } else if app_state.should_copy {
+                let _ = trove.update_command_meta(&output);
+                if let Some(path) = &usage_log_path {
+                    let _ = append_usage(path, &UsageRecord::new(&output));
                 }
-            };
+                copy_selected(&mut app_state, &output.with_env_vars_expanded(config));
+            } else {
+                // Command has been selected
+                // Update the command's meta info
+                let _ = trove.update_command_meta(&output);
+                sync_on_exit(watch.as_mut(), trove);
+                if let Some(path) = &used_values_path {
+                    let _ = app_state.used_values.save(path);
+                }
+                screen.terminal.show_cursor()?;
+                return Ok(Some(output.with_env_vars_expanded(config)));
+            }
+        }
+
+        if let Some(action) = app_state.batch_action.take() {
+            apply_batch(&mut app_state, trove, &mut namespace_names, action);
+        }
 
-            if let Some(output) = command {
-                if app_state.draw == DrawState::Create {
-                    let _ = trove.add_command(output, true);
-                    app_state.commands = trove.commands.clone();
-                    app_state.commands.sort_by(|a, b| b.usage_count.cmp(&a.usage_count));
-                    app_state.draw = DrawState::Search;
-                } else if app_state.control == ControlState::Edit {
-                    // Command has been edited
-                    trove.update_command_by_name(&output);
-                    app_state.commands = trove.commands.clone();
-                    app_state.commands.sort_by(|a, b| b.usage_count.cmp(&a.usage_count));
-                    app_state.control = ControlState::Search;
-                } else if app_state.should_delete {
-                    trove.remove_command(&output.name).ok();
-                    app_state.commands = trove.commands.clone();
-                    app_state.commands.sort_by(|a, b| b.usage_count.cmp(&a.usage_count));
-                    app_state.should_delete = false;
+        if app_state.should_restore {
+            if trove.restore_command(None).is_ok() {
+                app_state.commands = trove.get_commands_sorted_by_usage();
+            }
+            app_state.should_restore = false;
+        }
+
+        if app_state.should_exit {
+            sync_on_exit(watch.as_mut(), trove);
+            screen.terminal.show_cursor()?;
+            return Ok(None);
+        }
+    }
+}
+
+/// Leave the GUI for `$EDITOR` to edit the command, description and tags of `command`, then come back to the list
+fn edit_in_editor(
+    app_state: &mut State,
+    trove: &mut Trove,
+    command: &HoardCmd,
+    screen: &mut Screen,
+    events: &Events,
+) -> Result<()> {
+    if command.is_managed {
+        let message = format!(
+            "[{}] is a managed command and can't be edited",
+            command.name
+        );
+        app_state.status_message = Some((message, Instant::now()));
+        return Ok(());
+    }
+    events.pause();
+    // Clicks in the editor are the editor's
+    screen.suspend()?;
+    let edited = edit_text(&command_buffer(command), &format!("{}.yml", command.name))
+        .and_then(|buffer| apply_buffer(command, &buffer));
+    // The editor may have turned off bracketed paste on its way out
+    screen.resume()?;
+    events.resume();
+
+    let message = match edited {
+        Ok(Some(edited)) => {
+            trove.update_command_by_name(&edited);
+            app_state.commands.clone_from(&trove.commands);
+            sort_by_context(
+                &mut app_state.commands,
+                app_state.context.as_ref(),
+                &app_state.frecency,
+            );
+            format!("Saved [{}]", edited.name)
+        }
+        Ok(None) => format!("[{}] is unchanged", command.name),
+        Err(e) => e.to_string(),
+    };
+    app_state.status_message = Some((message, Instant::now()));
+    Ok(())
+}
+
+/// Put the selected command on the clipboard and go back to the list
+fn copy_selected(app_state: &mut State, command: &HoardCmd) {
+    let message = if write_clipboard(&command.command) {
+        format!("Copied [{}] to the clipboard", command.name)
+    } else {
+        String::from("Can't write the clipboard, install xclip, xsel or wl-clipboard")
+    };
+    app_state.status_message = Some((message, Instant::now()));
+    app_state.should_copy = false;
+    app_state.selected_command = None;
+    app_state.provided_parameter_count = 0;
+    app_state.input = String::new();
+    app_state.draw = DrawState::Search;
+    app_state.control = ControlState::Search;
+}
+
+/// Put the confirmed `command` in the trash, same-named commands of other namespaces are kept
+pub fn delete_confirmed(app_state: &mut State, trove: &mut Trove, command: &HoardCmd) {
+    trove.remove_matching(|c| c.namespace == command.namespace && c.name == command.name);
+    app_state.commands.clone_from(&trove.commands);
+    sort_by_context(
+        &mut app_state.commands,
+        app_state.context.as_ref(),
+        &app_state.frecency,
+    );
+    app_state.should_delete = false;
+}
+
+/// Move the selected command to the namespace and name of `target`, with a number appended to the name if it is taken
+fn move_selected(
+    app_state: &mut State,
+    trove: &mut Trove,
+    namespace_names: &mut Vec<String>,
+    target: &HoardCmd,
+) {
+    app_state.control = ControlState::Search;
+    let Some(command) = app_state.selected_command.take() else {
+        return;
+    };
+    let message = if command.namespace == target.namespace && command.name == target.name {
+        format!("[{}] is unchanged", command.name)
+    } else {
+        let name = trove.free_name(&target.name, &target.namespace);
+        let mut moved = trove.clone();
+        match moved.move_command(&command.namespace, &command.name, &target.namespace, &name) {
+            Ok(_) => {
+                reload_trove(app_state, trove, namespace_names, Ok(moved));
+                if name == target.name {
+                    format!("Moved [{}] to [{}/{name}]", command.name, target.namespace)
                 } else {
-                    // Command has been selected
-                    // Update the command's meta info
-                    let _ = trove.update_command_meta(&output);
-                    terminal.show_cursor()?;
-                    return Ok(Some(output));
+                    format!(
+                        "Moved [{}] to [{}/{name}], [{}] is taken",
+                        command.name, target.namespace, target.name
+                    )
+                }
+            }
+            Err(e) => e.to_string(),
+        }
+    };
+    app_state.status_message = Some((message, Instant::now()));
+}
+
+/// Save the command of the form: hoard it if it is new, or move it first if its namespace or name changed
+fn save_edited(
+    app_state: &mut State,
+    trove: &mut Trove,
+    namespace_names: &mut Vec<String>,
+    edited: &HoardCmd,
+) {
+    app_state.control = ControlState::Search;
+    let Some(form) = app_state.edit_form.take() else {
+        return;
+    };
+    let mut saved = trove.clone();
+    let saving = if form.is_new() {
+        // Saved with the context hoard was started in, like `hoard new` does
+        let context = app_state
+            .context
+            .clone()
+            .filter(|context| !context.is_empty());
+        saved
+            .add_command(edited.clone().with_context(context), true)
+            .map(|_| format!("Hoarded [{}]", edited.name))
+    } else {
+        let original = form.original;
+        saved
+            .move_command(
+                &original.namespace,
+                &original.name,
+                &edited.namespace,
+                &edited.name,
+            )
+            .map(|_| {
+                if let Some(command) = saved
+                    .commands
+                    .iter_mut()
+                    .find(|c| c.namespace == edited.namespace && c.name == edited.name)
+                {
+                    *command = edited.clone();
+                    command.modified = SystemTime::now();
+                }
+                format!("Saved [{}]", edited.name)
+            })
+    };
+    let message = match saving {
+        Ok(message) => {
+            reload_trove(app_state, trove, namespace_names, Ok(saved));
+            message
+        }
+        Err(e) => e.to_string(),
+    };
+    app_state.status_message = Some((message, Instant::now()));
+}
+
+/// Apply `action` to every marked command and unmark them, managed commands are left as they are
+fn apply_batch(
+    app_state: &mut State,
+    trove: &mut Trove,
+    namespace_names: &mut Vec<String>,
+    action: BatchAction,
+) {
+    let marked: Vec<HoardCmd> = trove
+        .commands
+        .iter()
+        .filter(|command| app_state.is_marked(command))
+        .cloned()
+        .collect();
+    app_state.marked.clear();
+    let mut changed = trove.clone();
+    let message = match action {
+        BatchAction::Delete => {
+            let removed = changed.remove_matching(|command| marked.contains(command));
+            format!(
+                "Deleted {}, <Ctrl-U> restores them one by one",
+                pluralize(removed.len(), "command", "commands")
+            )
+        }
+        BatchAction::Retag(change) => {
+            let now = SystemTime::now();
+            let mut count = 0;
+            for command in changed
+                .commands
+                .iter_mut()
+                .filter(|command| !command.is_managed && marked.contains(command))
+            {
+                if let Some(tags) = change.apply(command) {
+                    command.tags = tags;
+                    command.modified = now;
+                    count += 1;
                 }
             }
+            format!(
+                "Changed the tags of {}",
+                pluralize(count, "command", "commands")
+            )
+        }
+        BatchAction::Move(namespace) => {
+            let mut count = 0;
+            for command in marked
+                .iter()
+                .filter(|command| !command.is_managed && command.namespace != namespace)
+            {
+                let name = changed.free_name(&command.name, &namespace);
+                if changed
+                    .move_command(&command.namespace, &command.name, &namespace, &name)
+                    .is_ok()
+                {
+                    count += 1;
+                }
+            }
+            format!(
+                "Moved {} to [{namespace}]",
+                pluralize(count, "command", "commands")
+            )
+        }
+        BatchAction::Export(path) => {
+            let message = match fs::write(&path, Trove::from_commands(&marked).to_yaml()) {
+                Ok(()) => format!(
+                    "Exported {} to {}",
+                    pluralize(marked.len(), "command", "commands"),
+                    path.display()
+                ),
+                Err(e) => format!("Can't write {}: {e}", path.display()),
+            };
+            app_state.status_message = Some((message, Instant::now()));
+            return;
+        }
+    };
+    reload_trove(app_state, trove, namespace_names, Ok(changed));
+    app_state.status_message = Some((message, Instant::now()));
+}
 
-            if app_state.should_exit {
-                terminal.show_cursor()?;
-                return Ok(None);
+/// Names of the namespace tabs, `All` first
+fn namespace_tab_names(trove: &Trove) -> Vec<String> {
+    let mut names = vec![String::from("All")];
+    names.extend(trove.namespaces().into_iter().map(ToString::to_string));
+    names
+}
+
+/// Show the trove that was changed on disk, keeping the search, the namespace tab and the selection where they are
+fn reload_trove(
+    app_state: &mut State,
+    trove: &mut Trove,
+    namespace_names: &mut Vec<String>,
+    reloaded: Result<Trove, HoardErr>,
+) {
+    let reloaded = match reloaded {
+        Ok(reloaded) => reloaded,
+        Err(e) => {
+            app_state.status_message = Some((e.to_string(), Instant::now()));
+            return;
+        }
+    };
+    *trove = reloaded;
+    let selected_tab = app_state
+        .namespace_tab
+        .selected()
+        .and_then(|selected| namespace_names.get(selected))
+        .cloned();
+    *namespace_names = namespace_tab_names(trove);
+    let tab = selected_tab
+        .and_then(|tab| namespace_names.iter().position(|name| *name == tab))
+        .unwrap_or(0);
+    app_state.namespace_tab.select(Some(tab));
+    let namespace_tabs: Vec<&str> = namespace_names.iter().map(String::as_str).collect();
+    apply_filter(app_state, &namespace_tabs, &trove.commands);
+    let selected = app_state.command_list.selected().unwrap_or(0);
+    app_state.command_list.select(Some(
+        selected.min(app_state.commands.len().saturating_sub(1)),
+    ));
+    app_state.status_message = Some((
+        String::from("The trove changed on disk and was reloaded"),
+        Instant::now(),
+    ));
+}
+
+/// Take over changes made on disk since the last reload, so saving the trove on exit doesn't undo them
+fn sync_on_exit(watch: Option<&mut TroveWatch>, trove: &mut Trove) {
+    if let Some(Ok(reloaded)) = watch.and_then(|watch| watch.poll(trove)) {
+        *trove = reloaded;
+    }
+}
+
+/// Hand a key to the handler of the screen that is shown
+fn handle_key(
+    input: Key,
+    app_state: &mut State,
+    trove_commands: &[HoardCmd],
+    namespace_tabs: &[&str],
+) -> Option<HoardCmd> {
+    // Any key closes the help
+    if app_state.help.take().is_some() {
+        return None;
+    }
+    match app_state.draw {
+        DrawState::Search => match app_state.control {
+            ControlState::Search => {
+                key_handler_list_search(input, app_state, trove_commands, namespace_tabs)
             }
+            ControlState::Edit => key_handler_inline_edit(input, app_state, trove_commands),
+            ControlState::Gpt => key_handler_gpt_create(input, app_state),
+            ControlState::KeyNotSet => key_handler_no_key_set(input, app_state),
+            ControlState::PreviewSearch => key_handler_preview_search(input, app_state),
+            ControlState::Move => key_handler_move(input, app_state),
+            ControlState::Batch(batch) => key_handler_batch(input, app_state, batch),
+            ControlState::Confirm => key_handler_confirm(input, app_state),
+        },
+        DrawState::ParameterInput => key_handler_parameter_input(input, app_state),
+    }
+}
+
+/// Hand clicks and the scroll wheel to the command list, the other screens only take keys
+fn handle_mouse(
+    mouse: MouseEvent,
+    app_state: &mut State,
+    trove_commands: &[HoardCmd],
+    namespace_tabs: &[&str],
+) -> Option<HoardCmd> {
+    if app_state.draw == DrawState::Search && app_state.help.is_none() {
+        mouse_handler_list_search(mouse, app_state, trove_commands, namespace_tabs)
+    } else {
+        None
+    }
+}
+
+/// Put pasted text into the input of the screen that is shown
+///
+/// Screens without a paste handler get the text key by key, with line breaks turned into spaces so they don't submit
+fn handle_paste(
+    text: &str,
+    app_state: &mut State,
+    trove_commands: &[HoardCmd],
+    namespace_tabs: &[&str],
+) -> Option<HoardCmd> {
+    match (&app_state.draw, &app_state.control) {
+        (DrawState::Search, ControlState::Search) => {
+            paste_handler_list_search(text, app_state, trove_commands, namespace_tabs);
+            None
+        }
+        (DrawState::ParameterInput, _) => {
+            paste_handler_parameter_input(text, app_state);
+            None
         }
+        _ => pasted_text(text, false)
+            .chars()
+            .find_map(|c| handle_key(Key::Char(c), app_state, trove_commands, namespace_tabs)),
     }
 }
@@ -1,20 +1,323 @@
-use crossbeam_channel::unbounded;
+use crossbeam_channel::{unbounded, Sender};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
-use termion::event::Key;
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+use std::fs::File;
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+use std::io::{self, Read};
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+use std::os::fd::AsRawFd;
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+use termion::event::Event as TermEvent;
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
 use termion::input::TermRead;
 
+/// Sequences the terminal wraps pasted text in while bracketed paste is enabled
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+const PASTE_START: &[u8] = b"\x1b[200~";
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+const PASTE_END: &[u8] = b"\x1b[201~";
+
+/// A key as the GUI handles it, whichever backend read it from the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    Backspace,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    BackTab,
+    Delete,
+    Insert,
+    F(u8),
+    /// A typed character, also `'\n'` for `<Enter>` and `'\t'` for `<Tab>`
+    Char(char),
+    Alt(char),
+    Ctrl(char),
+    /// `<Ctrl-Space>`
+    Null,
+    Esc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    WheelUp,
+    WheelDown,
+}
+
+/// Click or scroll wheel at a column and row, both counted from 1 like terminals report them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseEvent {
+    Press(MouseButton, u16, u16),
+    Release(u16, u16),
+    Hold(u16, u16),
+}
+
+#[derive(Debug, PartialEq, Eq)]
 pub enum Event<I> {
     Input(I),
+    /// Text pasted into the terminal, delivered at once instead of key by key
+    Paste(String),
+    /// Click or scroll wheel, only reported while the GUI captures the mouse
+    Mouse(MouseEvent),
     Tick,
 }
 
-/// A small event handler that wrap termion input and tick events. Each event
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+impl Key {
+    /// `None` for keys termion has no name for
+    pub const fn from_termion(key: termion::event::Key) -> Option<Self> {
+        use termion::event::Key as TermKey;
+        Some(match key {
+            TermKey::Backspace => Self::Backspace,
+            TermKey::Left => Self::Left,
+            TermKey::Right => Self::Right,
+            TermKey::Up => Self::Up,
+            TermKey::Down => Self::Down,
+            TermKey::Home => Self::Home,
+            TermKey::End => Self::End,
+            TermKey::PageUp => Self::PageUp,
+            TermKey::PageDown => Self::PageDown,
+            TermKey::BackTab => Self::BackTab,
+            TermKey::Delete => Self::Delete,
+            TermKey::Insert => Self::Insert,
+            TermKey::F(number) => Self::F(number),
+            TermKey::Char(c) => Self::Char(c),
+            TermKey::Alt(c) => Self::Alt(c),
+            TermKey::Ctrl(c) => Self::Ctrl(c),
+            TermKey::Null => Self::Null,
+            TermKey::Esc => Self::Esc,
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+impl MouseEvent {
+    const fn from_termion(event: termion::event::MouseEvent) -> Self {
+        use termion::event::{MouseButton as TermButton, MouseEvent as TermMouse};
+        match event {
+            TermMouse::Press(button, column, row) => {
+                let button = match button {
+                    TermButton::Left => MouseButton::Left,
+                    TermButton::Right => MouseButton::Right,
+                    TermButton::Middle => MouseButton::Middle,
+                    TermButton::WheelUp => MouseButton::WheelUp,
+                    TermButton::WheelDown => MouseButton::WheelDown,
+                };
+                Self::Press(button, column, row)
+            }
+            TermMouse::Release(column, row) => Self::Release(column, row),
+            TermMouse::Hold(column, row) => Self::Hold(column, row),
+        }
+    }
+}
+
+#[cfg(any(windows, feature = "crossterm-backend"))]
+impl Event<Key> {
+    /// The event the GUI handles for an event of crossterm, `None` for the ones it ignores
+    pub fn from_crossterm(event: crossterm::event::Event) -> Option<Self> {
+        use crossterm::event::{Event as CrosstermEvent, KeyEventKind};
+        match event {
+            // Windows also reports when a key is let go
+            CrosstermEvent::Key(key) if key.kind != KeyEventKind::Release => {
+                key_from_crossterm(key.code, key.modifiers).map(Self::Input)
+            }
+            CrosstermEvent::Mouse(mouse) => mouse_from_crossterm(mouse).map(Self::Mouse),
+            CrosstermEvent::Paste(text) => Some(Self::Paste(text)),
+            _ => None,
+        }
+    }
+}
+
+/// `key` named like termion names it, which the keymap and the handlers were written against
+#[cfg(any(windows, feature = "crossterm-backend"))]
+const fn key_from_crossterm(
+    key: crossterm::event::KeyCode,
+    modifiers: crossterm::event::KeyModifiers,
+) -> Option<Key> {
+    use crossterm::event::{KeyCode, KeyModifiers};
+    Some(match key {
+        KeyCode::Char(' ') if modifiers.contains(KeyModifiers::CONTROL) => Key::Null,
+        KeyCode::Char(c) if modifiers.contains(KeyModifiers::CONTROL) => Key::Ctrl(c),
+        KeyCode::Char(c) if modifiers.contains(KeyModifiers::ALT) => Key::Alt(c),
+        KeyCode::Char(c) => Key::Char(c),
+        KeyCode::Enter => Key::Char('\n'),
+        KeyCode::Tab => Key::Char('\t'),
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::PageUp => Key::PageUp,
+        KeyCode::PageDown => Key::PageDown,
+        KeyCode::BackTab => Key::BackTab,
+        KeyCode::Delete => Key::Delete,
+        KeyCode::Insert => Key::Insert,
+        KeyCode::F(number) => Key::F(number),
+        KeyCode::Null => Key::Null,
+        KeyCode::Esc => Key::Esc,
+        _ => return None,
+    })
+}
+
+/// Crossterm counts columns and rows from 0, termion and the mouse handler from 1
+#[cfg(any(windows, feature = "crossterm-backend"))]
+const fn mouse_from_crossterm(mouse: crossterm::event::MouseEvent) -> Option<MouseEvent> {
+    use crossterm::event::{MouseButton as CrosstermButton, MouseEventKind};
+    let (column, row) = (mouse.column + 1, mouse.row + 1);
+    Some(match mouse.kind {
+        MouseEventKind::Down(button) => {
+            let button = match button {
+                CrosstermButton::Left => MouseButton::Left,
+                CrosstermButton::Right => MouseButton::Right,
+                CrosstermButton::Middle => MouseButton::Middle,
+            };
+            MouseEvent::Press(button, column, row)
+        }
+        MouseEventKind::ScrollUp => MouseEvent::Press(MouseButton::WheelUp, column, row),
+        MouseEventKind::ScrollDown => MouseEvent::Press(MouseButton::WheelDown, column, row),
+        MouseEventKind::Up(_) => MouseEvent::Release(column, row),
+        MouseEventKind::Drag(_) => MouseEvent::Hold(column, row),
+        MouseEventKind::Moved => return None,
+    })
+}
+
+/// Collects the keys between the start and end of a bracketed paste into a single `Event::Paste`
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+#[derive(Default)]
+struct PasteReader {
+    pasted: Option<String>,
+}
+
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+impl PasteReader {
+    /// The event to hand on for a terminal event, `None` while a paste is still being read
+    fn read(&mut self, event: TermEvent) -> Option<Event<Key>> {
+        match (event, self.pasted.as_mut()) {
+            (TermEvent::Unsupported(sequence), None) if sequence == PASTE_START => {
+                self.pasted = Some(String::new());
+                None
+            }
+            (TermEvent::Unsupported(sequence), Some(_)) if sequence == PASTE_END => {
+                self.pasted.take().map(Event::Paste)
+            }
+            (TermEvent::Key(termion::event::Key::Char(c)), Some(pasted)) => {
+                pasted.push(c);
+                None
+            }
+            (TermEvent::Key(key), None) => Key::from_termion(key).map(Event::Input),
+            (TermEvent::Mouse(mouse), None) => Some(Event::Mouse(MouseEvent::from_termion(mouse))),
+            _ => None,
+        }
+    }
+}
+
+/// How long the input thread waits for a key before it checks again whether it is paused
+const INPUT_POLL_MS: i32 = 50;
+
+/// The terminal, read only while the input isn't paused so another program can read it in the meantime
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+struct TtyInput {
+    tty: File,
+    paused: Arc<AtomicBool>,
+}
+
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+impl Read for TtyInput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.paused.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(INPUT_POLL_MS as u64));
+                continue;
+            }
+            let mut poll = libc::pollfd {
+                fd: self.tty.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let ready = unsafe { libc::poll(&raw mut poll, 1, INPUT_POLL_MS) };
+            if ready > 0 && !self.paused.load(Ordering::SeqCst) {
+                return self.tty.read(buf);
+            }
+        }
+    }
+}
+
+/// Reads the keys, clicks and pastes of the terminal with termion until the GUI stops listening
+#[cfg(all(unix, not(feature = "crossterm-backend")))]
+fn read_input(tx: &Sender<Event<Key>>, paused: Arc<AtomicBool>) {
+    let tty = TtyInput {
+        tty: termion::get_tty().expect("Could not find tty session"),
+        paused,
+    };
+    let mut paste_reader = PasteReader::default();
+    for event in tty.events().flatten() {
+        let Some(event) = paste_reader.read(event) else {
+            continue;
+        };
+        if let Err(err) = tx.send(event) {
+            eprintln!("{err}");
+            return;
+        }
+    }
+}
+
+/// Reads the keys, clicks and pastes of the terminal with crossterm until the GUI stops listening
+#[cfg(any(windows, feature = "crossterm-backend"))]
+#[allow(clippy::needless_pass_by_value)]
+fn read_input(tx: &Sender<Event<Key>>, paused: Arc<AtomicBool>) {
+    let poll_timeout = Duration::from_millis(INPUT_POLL_MS as u64);
+    loop {
+        if paused.load(Ordering::SeqCst) {
+            thread::sleep(poll_timeout);
+            continue;
+        }
+        // Only read once there is something, so a pause doesn't wait for the next key
+        match crossterm::event::poll(poll_timeout) {
+            Ok(true) if !paused.load(Ordering::SeqCst) => {}
+            Ok(_) => continue,
+            Err(err) => {
+                eprintln!("{err}");
+                return;
+            }
+        }
+        let event = match crossterm::event::read() {
+            Ok(event) => event,
+            Err(err) => {
+                eprintln!("{err}");
+                return;
+            }
+        };
+        let Some(event) = Event::from_crossterm(event) else {
+            continue;
+        };
+        if let Err(err) = tx.send(event) {
+            eprintln!("{err}");
+            return;
+        }
+    }
+}
+
+/// A small event handler that wrap the terminal input and tick events. Each event
 /// type is handled in its own thread and returned to a common `Receiver`
 #[allow(dead_code)]
 pub struct Events {
     rx: crossbeam_channel::Receiver<Event<Key>>,
+    /// Set while another program, e.g. an editor, reads the keys instead
+    paused: Arc<AtomicBool>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -36,21 +339,14 @@ impl Events {
         Self::with_config(Config::default())
     }
 
-    #[allow(clippy::manual_flatten)]
     pub fn with_config(config: Config) -> Self {
         let (tx, rx) = unbounded();
+        let paused = Arc::new(AtomicBool::new(false));
 
         {
             let tx = tx.clone();
-            thread::spawn(move || {
-                let tty = termion::get_tty().expect("Could not find tty session");
-                for key in tty.keys().flatten() {
-                    if let Err(err) = tx.send(Event::Input(key)) {
-                        eprintln!("{err}");
-                        return;
-                    }
-                }
-            })
+            let paused = Arc::clone(&paused);
+            thread::spawn(move || read_input(&tx, paused))
         };
 
         thread::spawn(move || loop {
@@ -60,10 +356,106 @@ impl Events {
             }
             thread::sleep(config.tick_rate);
         });
-        Self { rx }
+        Self { rx, paused }
     }
 
     pub fn next(&self) -> Result<Event<Key>, crossbeam_channel::RecvError> {
         self.rx.recv()
     }
+
+    /// Stop reading keys until `resume`, so another program can read them
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod test_event {
+    use super::*;
+
+    #[cfg(all(unix, not(feature = "crossterm-backend")))]
+    #[test]
+    fn pasted_keys_become_one_event() {
+        use termion::event::{Key as TermKey, MouseButton as TermButton, MouseEvent as TermMouse};
+        let mut reader = PasteReader::default();
+        assert_eq!(
+            Some(Event::Input(Key::Char('a'))),
+            reader.read(TermEvent::Key(TermKey::Char('a')))
+        );
+        assert_eq!(
+            None,
+            reader.read(TermEvent::Unsupported(PASTE_START.to_vec()))
+        );
+        for c in "ls -l\n".chars() {
+            assert_eq!(None, reader.read(TermEvent::Key(TermKey::Char(c))));
+        }
+        assert_eq!(
+            Some(Event::Paste(String::from("ls -l\n"))),
+            reader.read(TermEvent::Unsupported(PASTE_END.to_vec()))
+        );
+        assert_eq!(
+            Some(Event::Mouse(MouseEvent::Press(MouseButton::Left, 3, 4))),
+            reader.read(TermEvent::Mouse(TermMouse::Press(TermButton::Left, 3, 4)))
+        );
+    }
+
+    #[cfg(any(windows, feature = "crossterm-backend"))]
+    #[test]
+    fn crossterm_events_are_named_like_termion_ones() {
+        use crossterm::event::{
+            Event as CrosstermEvent, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers,
+            MouseButton as CrosstermButton, MouseEventKind,
+        };
+        let key = |code, modifiers, kind| {
+            Event::from_crossterm(CrosstermEvent::Key(KeyEvent {
+                code,
+                modifiers,
+                kind,
+                state: KeyEventState::NONE,
+            }))
+        };
+        let press = |code, modifiers| key(code, modifiers, KeyEventKind::Press);
+        assert_eq!(
+            Some(Event::Input(Key::Ctrl('w'))),
+            press(KeyCode::Char('w'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            Some(Event::Input(Key::Null)),
+            press(KeyCode::Char(' '), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            Some(Event::Input(Key::Char('\n'))),
+            press(KeyCode::Enter, KeyModifiers::NONE)
+        );
+        assert_eq!(
+            Some(Event::Input(Key::Alt('x'))),
+            press(KeyCode::Char('x'), KeyModifiers::ALT)
+        );
+        assert_eq!(
+            None,
+            key(
+                KeyCode::Char('a'),
+                KeyModifiers::NONE,
+                KeyEventKind::Release
+            )
+        );
+        assert_eq!(
+            Some(Event::Paste(String::from("ls -l"))),
+            Event::from_crossterm(CrosstermEvent::Paste(String::from("ls -l")))
+        );
+        let click = CrosstermEvent::Mouse(crossterm::event::MouseEvent {
+            kind: MouseEventKind::Down(CrosstermButton::Left),
+            column: 2,
+            row: 3,
+            modifiers: KeyModifiers::NONE,
+        });
+        assert_eq!(
+            Some(Event::Mouse(MouseEvent::Press(MouseButton::Left, 3, 4))),
+            Event::from_crossterm(click)
+        );
+    }
 }
@@ -1,83 +1,256 @@
 use crate::config::HoardConfig;
-use crate::core::HoardCmd;
-use crate::gui::commands_gui::{DrawState, State};
-use ratatui::backend::TermionBackend;
+use crate::gui::keymap::{Action, Keymap};
+use ratatui::backend::Backend;
+use ratatui::layout::Rect;
 use ratatui::style::{Color, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, BorderType, Borders, List, ListItem};
-use ratatui::Terminal;
-use termion::event::Key;
-use termion::screen::AlternateScreen;
+use ratatui::widgets::{Block, BorderType, Borders, Clear, List, ListItem};
+use ratatui::Frame;
 
-pub const HELP_KEY: &str = "<F1>";
-const HELP_CONTENT: &[(&str, &str)] = &[
-    ("Next item in command list", "<Ctrl-N> / <Down-Arrow>"),
-    (
-        "Previous item in command list",
-        "<Ctrl-P> / <Ctrl-Y> / <Up-Arrow>",
-    ),
-    ("Next namespace tab", "<Ctrl-L> / <Right-Arrow>"),
-    ("Previous namespace tab", "<Ctrl-H> / <Left-Arrow>"),
-    ("Select command", "<Enter>"),
-    ("Create new command", "<Ctrl-W>"),
-    ("Delete command", "<Ctrl-X>"),
-    ("Toggle search/edit mode", "<Tab> / <Ctrl-E>"),
-    ("Toggle Command to edit in edit mode", "<Tab>"),
-    ("Exit edit mode", "<Esc>"),
-    ("Quit", "<Ctrl-D> / <Ctrl-C> / <Ctrl-G>"),
-    ("Show help", HELP_KEY),
-    ("Close help", "<Any key>"),
+/// What the help of the list tells about, the keys of its actions in the keymap and the keys that are always the same
+const LIST_HELP: &[(&str, &[Action], &str)] = &[
+    ("Next item in command list", &[Action::Next], ""),
+    ("Previous item in command list", &[Action::Previous], ""),
+    ("Next namespace tab", &[Action::NextNamespace], ""),
+    ("Previous namespace tab", &[Action::PreviousNamespace], ""),
+    (
+        "Show or hide the namespace sidebar, switch namespace in it",
+        &[Action::Sidebar],
+        ", then the namespace tab keys",
+    ),
+    ("Select command", &[Action::Select], ""),
+    (
+        "Select command with the mouse, scroll through the list",
+        &[],
+        "<Click>, <Click> it again to pick it / <Scroll-Wheel>",
+    ),
+    (
+        "Copy command to the clipboard after filling in its parameters",
+        &[Action::Copy],
+        "",
+    ),
+    (
+        "Copy command to the clipboard as it is",
+        &[Action::CopyRaw],
+        "",
+    ),
+    ("Create new command", &[Action::Create], ""),
+    ("Describe a command for GPT to create", &[Action::Gpt], ""),
+    (
+        "Only commands with parameters / favorites / installed programs",
+        &[
+            Action::FilterParameterized,
+            Action::FilterFavorites,
+            Action::FilterInstalled,
+        ],
+        "",
+    ),
+    (
+        "Mark command for a batch action, unmark all",
+        &[Action::Mark],
+        " / <Space> with an empty query, <Esc>",
+    ),
+    (
+        "Delete command, or all marked commands",
+        &[Action::Delete],
+        "",
+    ),
+    (
+        "Add, remove or rename a tag of the marked commands",
+        &[Action::Retag],
+        "",
+    ),
+    (
+        "Export the marked commands to a trove file",
+        &[Action::Export],
+        "",
+    ),
+    ("Edit command in $EDITOR", &[Action::EditInEditor], ""),
+    (
+        "Move or rename command, or move all marked commands",
+        &[Action::Move],
+        "",
+    ),
+    ("Restore last deleted command", &[Action::Restore], ""),
+    (
+        "Edit name, namespace, tags, description and command",
+        &[Action::Edit],
+        "",
+    ),
+    (
+        "Sort by relevance, name, recently used, most used or recently added",
+        &[Action::Sort],
+        "",
+    ),
+    (
+        "Show or hide the details of the selected command",
+        &[Action::Details],
+        "",
+    ),
+    (
+        "Focus the preview, search its command and description, jump to next/previous match",
+        &[Action::SearchPreview],
+        ", then </>, <n> / <N>",
+    ),
+    (
+        "Scroll the description / the command while searching the preview",
+        &[],
+        "<Up-Arrow> / <Down-Arrow>, <Left-Arrow> / <Right-Arrow>",
+    ),
+    ("Quit", &[Action::Quit], ""),
+    (
+        "Show this help",
+        &[Action::Help],
+        " / <?> with an empty query",
+    ),
+    ("Close help", &[], "<Any key>"),
 ];
 
-pub fn draw(
-    config: &HoardConfig,
-    terminal: &mut Terminal<
-        TermionBackend<AlternateScreen<termion::raw::RawTerminal<std::io::Stdout>>>,
-    >,
-) -> Result<(), eyre::Error> {
-    terminal.draw(|rect| {
-        let help = Block::default()
-            .borders(Borders::ALL)
-            .style(Style::default().fg(Color::Rgb(
-                config.primary_color.unwrap().0,
-                config.primary_color.unwrap().1,
-                config.primary_color.unwrap().2,
-            )))
-            .title(" Help ")
-            .border_type(BorderType::Plain);
+/// What the help tells about while the parameters of a command are filled in
+const PARAMETER_INPUT_HELP: &[(&str, &[Action], &str)] = &[
+    ("Fill in the parameter", &[], "<Enter>"),
+    (
+        "Line break in a multi-line parameter, fill it in",
+        &[],
+        "<Enter>, <Alt-Enter>",
+    ),
+    (
+        "Previous/next value offered for the parameter",
+        &[],
+        "<Up-Arrow> / <Ctrl-P>, <Down-Arrow> / <Ctrl-N>",
+    ),
+    (
+        "Complete file path",
+        &[],
+        "<Tab> for file/dir parameters, <Ctrl-T> for all",
+    ),
+    ("Complete from values used before", &[], "<Tab>"),
+    ("Paste clipboard", &[], "<Ctrl-V>"),
+    (
+        "Scroll a long command, follow the parameter again",
+        &[],
+        "<Left-Arrow> / <Right-Arrow>, <Ctrl-F>",
+    ),
+    ("Quit", &[Action::Quit], ""),
+    (
+        "Show this help",
+        &[Action::Help],
+        " / <?> with an empty input",
+    ),
+    ("Close help", &[], "<Any key>"),
+];
 
-        let items: Vec<_> = HELP_CONTENT
-            .iter()
-            .map(|item| {
-                ListItem::new(vec![
-                    Line::from(Span::styled(
-                        item.0,
-                        Style::default().fg(Color::Rgb(
-                            config.command_color.unwrap().0,
-                            config.command_color.unwrap().1,
-                            config.command_color.unwrap().2,
-                        )),
-                    )),
-                    Line::from(Span::styled(
-                        format!("    {}", item.1),
-                        Style::default().fg(Color::Rgb(
-                            config.primary_color.unwrap().0,
-                            config.primary_color.unwrap().1,
-                            config.primary_color.unwrap().2,
-                        )),
-                    )),
-                    Line::from(""),
-                ])
-            })
-            .collect();
+/// What the help tells about while a command is edited or created in the form
+const EDIT_HELP: &[(&str, &[Action], &str)] = &[
+    (
+        "Next/previous field",
+        &[],
+        "<Tab> / <Down-Arrow>, <Shift-Tab> / <Up-Arrow>",
+    ),
+    ("Save the command", &[], "<Enter>"),
+    ("Close the form without saving", &[], "<Esc>"),
+    ("Quit", &[Action::Quit], ""),
+    ("Show this help", &[Action::Help], ""),
+    ("Close help", &[], "<Any key>"),
+];
+
+/// Screens with keys of their own, the help shows the keys of the one it was opened on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HelpScreen {
+    List,
+    ParameterInput,
+    Edit,
+}
+
+impl HelpScreen {
+    const fn content(self) -> &'static [(&'static str, &'static [Action], &'static str)] {
+        match self {
+            Self::List => LIST_HELP,
+            Self::ParameterInput => PARAMETER_INPUT_HELP,
+            Self::Edit => EDIT_HELP,
+        }
+    }
+}
 
-        let list = List::new(items).block(help);
-        rect.render_widget(list, rect.size());
-    })?;
-    Ok(())
+/// Keys of an item of the help
+fn help_keys(keymap: &Keymap, actions: &[Action], fixed: &str) -> String {
+    let bound: Vec<String> = actions
+        .iter()
+        .map(|action| keymap.describe(*action))
+        .collect();
+    format!("{}{fixed}", bound.join(" / "))
 }
 
-pub fn key_handler(_input: Key, app: &mut State) -> Option<HoardCmd> {
-    app.draw = DrawState::Search;
-    None
+/// Draw the help of `screen` over the middle of the screen, a line for the keys of every item
+pub fn draw_overlay<B: Backend>(
+    frame: &mut Frame<B>,
+    screen: HelpScreen,
+    keymap: &Keymap,
+    config: &HoardConfig,
+) {
+    let primary = Style::default().fg(Color::Rgb(
+        config.primary_color.unwrap().0,
+        config.primary_color.unwrap().1,
+        config.primary_color.unwrap().2,
+    ));
+    let command = Style::default().fg(Color::Rgb(
+        config.command_color.unwrap().0,
+        config.command_color.unwrap().1,
+        config.command_color.unwrap().2,
+    ));
+    let keys: Vec<(String, &str)> = screen
+        .content()
+        .iter()
+        .map(|(description, actions, fixed)| (help_keys(keymap, actions, fixed), *description))
+        .collect();
+    let width = keys
+        .iter()
+        .map(|(keys, _)| keys.chars().count())
+        .max()
+        .unwrap_or_default();
+    let items: Vec<ListItem> = keys
+        .into_iter()
+        .map(|(keys, description)| {
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{keys:<width$}  "), primary),
+                Span::styled(description, command),
+            ]))
+        })
+        .collect();
+
+    let size = frame.size();
+    let overlay_width = size.width * 9 / 10;
+    let overlay_height = u16::try_from(items.len() + 2)
+        .unwrap_or(u16::MAX)
+        .min(size.height);
+    let area = Rect::new(
+        size.x + (size.width - overlay_width) / 2,
+        size.y + (size.height - overlay_height) / 2,
+        overlay_width,
+        overlay_height,
+    );
+    let help = Block::default()
+        .borders(Borders::ALL)
+        .style(primary)
+        .title(" Help (<Any key> to close) ")
+        .border_type(BorderType::Plain);
+    frame.render_widget(Clear, area);
+    frame.render_widget(List::new(items).block(help), area);
+}
+
+#[cfg(test)]
+mod test_help {
+    use super::*;
+    use crate::gui::keymap::ACTIONS;
+
+    #[test]
+    fn every_action_of_the_list_is_in_the_help() {
+        let described: Vec<Action> = LIST_HELP
+            .iter()
+            .flat_map(|(_, actions, _)| actions.iter().copied())
+            .collect();
+        for (action, _, _) in ACTIONS {
+            assert!(described.contains(action), "{action:?} is not in the help");
+        }
+    }
 }
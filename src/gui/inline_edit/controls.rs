@@ -1,43 +1,49 @@
-use crate::core::{string_to_tags, HoardCmd};
-use crate::gui::commands_gui::{ControlState, EditSelection, State};
-use termion::event::Key;
+use crate::core::HoardCmd;
+use crate::gui::commands_gui::{ControlState, State};
+use crate::gui::event::Key;
+use crate::gui::help::HelpScreen;
 
-pub fn key_handler(input: Key, state: &mut State) -> Option<HoardCmd> {
+pub fn key_handler(input: Key, state: &mut State, trove_commands: &[HoardCmd]) -> Option<HoardCmd> {
+    let form = state.edit_form.as_mut()?;
     match input {
         // Quit command
         Key::Esc => {
-            // Only exit the edit mode
+            // Only close the form
+            state.edit_form = None;
             state.control = ControlState::Search;
             None
         }
-        Key::Char('\n') => {
-            let mut edited_command = state.selected_command.clone().unwrap();
-            let new_string = state.string_to_edit.clone();
-            match state.edit_selection {
-                EditSelection::Description => edited_command.description = new_string,
-                EditSelection::Command => edited_command.command = new_string,
-                EditSelection::Tags => edited_command.tags = string_to_tags(&new_string),
-                EditSelection::Name | EditSelection::Namespace => (),
-            };
-            Some(edited_command)
+        Key::Char('\n') => match form.command(trove_commands) {
+            Ok(edited) => Some(edited),
+            Err(error) => {
+                form.error = error;
+                None
+            }
+        },
+        Key::Char('\t') | Key::Down => {
+            form.next();
+            None
+        }
+        Key::BackTab | Key::Up => {
+            form.previous();
+            None
         }
-        Key::Char('\t') => {
-            state.edit_selection = state.edit_selection.next();
-            state.update_string_to_edit();
+        key if state.keymap.helps(key) => {
+            state.help = Some(HelpScreen::Edit);
             None
         }
-        Key::Ctrl('c' | 'd' | 'g') => {
+        key if state.keymap.quits(key) => {
             // Definitely exit program
             state.should_exit = true;
             None
         }
-        // Handle query input
+        // Handle field input
         Key::Backspace => {
-            state.string_to_edit.pop();
+            form.value_mut().pop();
             None
         }
         Key::Char(c) => {
-            state.string_to_edit.push(c);
+            form.value_mut().push(c);
             None
         }
         _ => None,
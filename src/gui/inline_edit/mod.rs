@@ -1 +1,99 @@
 pub mod controls;
+pub mod render;
+
+use crate::core::{string_to_tags, HoardCmd};
+use crate::gui::commands_gui::EditSelection;
+
+/// Fields of the form in the order `<Tab>` goes through them
+pub const FIELDS: [EditSelection; 5] = [
+    EditSelection::Name,
+    EditSelection::Namespace,
+    EditSelection::Tags,
+    EditSelection::Description,
+    EditSelection::Command,
+];
+
+/// The selected command as it is edited in the form over the list, every field as it has been typed so far
+#[derive(Debug, Clone)]
+pub struct EditForm {
+    /// The command as it was when the form was opened
+    pub original: HoardCmd,
+    /// Text of every field, in the order of `FIELDS`
+    pub values: [String; 5],
+    /// Index of the field that is typed into
+    pub field: usize,
+    pub error: String,
+}
+
+impl EditForm {
+    pub fn new(command: &HoardCmd) -> Self {
+        Self {
+            original: command.clone(),
+            values: [
+                command.name.clone(),
+                command.namespace.clone(),
+                command.get_tags_as_string(),
+                command.description.clone(),
+                command.command.clone(),
+            ],
+            field: FIELDS.len() - 1,
+            error: String::new(),
+        }
+    }
+
+    /// Form for a new command in `namespace`
+    pub fn create(namespace: &str) -> Self {
+        Self::new(&HoardCmd::default().with_namespace(namespace))
+    }
+
+    /// Whether the form is for a new command, every command that was saved has a name
+    pub const fn is_new(&self) -> bool {
+        self.original.name.is_empty()
+    }
+
+    pub const fn value_mut(&mut self) -> &mut String {
+        &mut self.values[self.field]
+    }
+
+    pub const fn next(&mut self) {
+        self.field = (self.field + 1) % FIELDS.len();
+    }
+
+    pub const fn previous(&mut self) {
+        self.field = (self.field + FIELDS.len() - 1) % FIELDS.len();
+    }
+
+    /// The edited command, or why it can't be saved
+    ///
+    /// Its namespace and name can't be the ones of another command of `commands`
+    pub fn command(&self, commands: &[HoardCmd]) -> Result<HoardCmd, String> {
+        let [name, namespace, tags, description, command] = &self.values;
+        let (name, namespace) = (name.trim(), namespace.trim());
+        HoardCmd::is_name_valid(name).map_err(|e| e.to_string())?;
+        if namespace.is_empty() {
+            return Err(String::from("Namespace can't be empty"));
+        }
+        HoardCmd::is_command_valid(command).map_err(|e| e.to_string())?;
+        let renamed = name != self.original.name || namespace != self.original.namespace;
+        if renamed
+            && commands
+                .iter()
+                .any(|c| c.namespace == namespace && c.name == name)
+        {
+            return Err(format!(
+                "There already is a command [{name}] in namespace [{namespace}]"
+            ));
+        }
+        let mut edited = self.original.clone();
+        edited.name = name.to_string();
+        edited.namespace = namespace.to_string();
+        edited.tags = if tags.trim().is_empty() {
+            Vec::new()
+        } else {
+            string_to_tags(tags)
+        };
+        edited.description.clone_from(description);
+        edited.command.clone_from(command);
+        Ok(edited)
+    }
+}
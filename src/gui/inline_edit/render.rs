@@ -0,0 +1,89 @@
+use crate::config::HoardConfig;
+use crate::gui::inline_edit::{EditForm, FIELDS};
+use ratatui::backend::Backend;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap};
+use ratatui::Frame;
+
+/// Rows of the form: a field of three rows each, the command gets two more to wrap, and the error
+const FORM_HEIGHT: u16 = 3 * 5 + 2 + 1 + 2;
+
+/// Draw the form over the middle of `area`, the field that is typed into has the secondary color
+pub fn draw<B: Backend>(frame: &mut Frame<B>, form: &EditForm, config: &HoardConfig, area: Rect) {
+    let primary = Style::default().fg(Color::Rgb(
+        config.primary_color.unwrap().0,
+        config.primary_color.unwrap().1,
+        config.primary_color.unwrap().2,
+    ));
+    let highlighted = Style::default().fg(Color::Rgb(
+        config.secondary_color.unwrap().0,
+        config.secondary_color.unwrap().1,
+        config.secondary_color.unwrap().2,
+    ));
+    let width = area.width * 7 / 10;
+    let height = FORM_HEIGHT.min(area.height);
+    let form_area = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(if form.is_new() {
+            String::from(" New command (<Enter> to save. <Tab> to switch. <Esc> to abort) ")
+        } else {
+            format!(
+                " Edit {} (<Enter> to save. <Tab> to switch. <Esc> to abort) ",
+                form.original.name
+            )
+        })
+        .border_type(BorderType::Plain)
+        .style(primary);
+    let inner = block.inner(form_area);
+    frame.render_widget(Clear, form_area);
+    frame.render_widget(block, form_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(3),
+                Constraint::Length(3),
+                Constraint::Length(3),
+                Constraint::Length(3),
+                Constraint::Min(3),
+                Constraint::Length(1),
+            ]
+            .as_ref(),
+        )
+        .split(inner);
+    for (i, (field, value)) in FIELDS.iter().zip(&form.values).enumerate() {
+        let (style, text) = if i == form.field {
+            // A block at the end of the text stands in for the cursor
+            (
+                highlighted.add_modifier(Modifier::BOLD),
+                format!("{value}█"),
+            )
+        } else {
+            (primary, value.clone())
+        };
+        let input = Paragraph::new(text)
+            .style(primary)
+            .wrap(Wrap { trim: false })
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(style)
+                    .title(format!(" {field} ")),
+            );
+        frame.render_widget(input, chunks[i]);
+    }
+    if !form.error.is_empty() {
+        frame.render_widget(
+            Paragraph::new(form.error.clone()).style(highlighted),
+            chunks[5],
+        );
+    }
+}
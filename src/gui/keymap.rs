@@ -0,0 +1,294 @@
+use crate::config::HoardConfig;
+use crate::gui::event::Key;
+
+/// What a key does in the command list, keys are bound to actions in the `keybindings` of the config
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Help,
+    FilterParameterized,
+    FilterFavorites,
+    FilterInstalled,
+    Create,
+    Gpt,
+    SearchPreview,
+    Details,
+    Edit,
+    EditInEditor,
+    Next,
+    Previous,
+    NextNamespace,
+    PreviousNamespace,
+    Sidebar,
+    Sort,
+    Mark,
+    Delete,
+    Move,
+    Retag,
+    Export,
+    Restore,
+    Select,
+    Copy,
+    CopyRaw,
+}
+
+/// Name of every action in the config, with the keys it is bound to if the config doesn't bind it
+pub const ACTIONS: &[(Action, &str, &[Key])] = &[
+    (
+        Action::Quit,
+        "quit",
+        &[Key::Ctrl('d'), Key::Ctrl('c'), Key::Ctrl('g'), Key::Esc],
+    ),
+    (Action::Help, "help", &[Key::F(1)]),
+    (
+        Action::FilterParameterized,
+        "filter_parameterized",
+        &[Key::F(2)],
+    ),
+    (Action::FilterFavorites, "filter_favorites", &[Key::F(3)]),
+    (Action::FilterInstalled, "filter_installed", &[Key::F(4)]),
+    (Action::Create, "create", &[Key::Ctrl('w')]),
+    (Action::Gpt, "gpt", &[Key::Ctrl('a')]),
+    (Action::SearchPreview, "search_preview", &[Key::Ctrl('f')]),
+    (Action::Details, "details", &[Key::F(5)]),
+    (Action::Edit, "edit", &[Key::Char('\t'), Key::Ctrl('e')]),
+    (Action::EditInEditor, "edit_in_editor", &[Key::Ctrl('o')]),
+    (
+        Action::Next,
+        "next",
+        &[Key::Ctrl('n'), Key::Down, Key::Ctrl('.')],
+    ),
+    (
+        Action::Previous,
+        "previous",
+        &[Key::Ctrl('p'), Key::Ctrl('y'), Key::Up],
+    ),
+    (
+        Action::NextNamespace,
+        "next_namespace",
+        &[Key::Ctrl('l'), Key::Right],
+    ),
+    (
+        Action::PreviousNamespace,
+        "previous_namespace",
+        &[Key::Ctrl('h'), Key::Left],
+    ),
+    (Action::Sidebar, "sidebar", &[Key::F(6)]),
+    (Action::Sort, "sort", &[Key::F(7)]),
+    (Action::Mark, "mark", &[Key::Null]),
+    (Action::Delete, "delete", &[Key::Ctrl('x')]),
+    (Action::Move, "move", &[Key::Ctrl('r')]),
+    (Action::Retag, "retag", &[Key::Ctrl('t')]),
+    (Action::Export, "export", &[Key::Ctrl('s')]),
+    (Action::Restore, "restore", &[Key::Ctrl('u')]),
+    (Action::Select, "select", &[Key::Char('\n')]),
+    (Action::Copy, "copy", &[Key::Ctrl('k')]),
+    (Action::CopyRaw, "copy_raw", &[Key::Alt('k')]),
+];
+
+/// Keys bound to actions, looked up before a key is typed into the query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap {
+    bindings: Vec<(Key, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self {
+            bindings: ACTIONS
+                .iter()
+                .flat_map(|(action, _, keys)| keys.iter().map(|key| (*key, *action)))
+                .collect(),
+        }
+    }
+}
+
+impl Keymap {
+    /// Keymap with the `keybindings` of the config, actions it doesn't bind keep their default keys
+    ///
+    /// Bindings that can't be read are left out and returned to be pointed out
+    pub fn from_config(config: &HoardConfig) -> (Self, Vec<String>) {
+        let mut bindings = Vec::new();
+        let mut problems = Vec::new();
+        let mut configured = Vec::new();
+        for (name, keys) in config.keybindings.iter().flatten() {
+            let Some((action, _, _)) = ACTIONS.iter().find(|(_, known, _)| known == name) else {
+                problems.push(format!("Unknown action in keybindings: {name}"));
+                continue;
+            };
+            let bound = bindings.len();
+            for key in keys.split(',').map(str::trim) {
+                match parse_key(key) {
+                    Some(key) => bindings.push((key, *action)),
+                    None => problems.push(format!("Unknown key for {name}: {key}")),
+                }
+            }
+            if bindings.len() > bound {
+                configured.push(*action);
+            }
+        }
+        // Keys of the config win over the same keys bound by default
+        bindings.extend(
+            Self::default()
+                .bindings
+                .into_iter()
+                .filter(|(_, action)| !configured.contains(action)),
+        );
+        problems.sort();
+        (Self { bindings }, problems)
+    }
+
+    /// Action `key` is bound to
+    pub fn action(&self, key: Key) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == key)
+            .map(|(_, action)| *action)
+    }
+
+    /// Whether `key` quits while text is typed, keys that type a character are typed instead
+    pub fn quits(&self, key: Key) -> bool {
+        self.typing_action(key) == Some(Action::Quit)
+    }
+
+    /// Whether `key` shows the help while text is typed, keys that type a character are typed instead
+    pub fn helps(&self, key: Key) -> bool {
+        self.typing_action(key) == Some(Action::Help)
+    }
+
+    /// Action `key` is bound to while text is typed, keys that type a character are typed instead
+    pub fn typing_action(&self, key: Key) -> Option<Action> {
+        self.action(key).filter(|_| !matches!(key, Key::Char(_)))
+    }
+
+    /// First key bound to `action` as shown in the footer, e.g. `<Ctrl-W>`
+    pub fn key(&self, action: Action) -> String {
+        self.bindings
+            .iter()
+            .find(|(_, bound)| *bound == action)
+            .map_or_else(|| String::from("<unbound>"), |(key, _)| key_name(*key))
+    }
+
+    /// Keys bound to `action` as shown in the help, e.g. `<Ctrl-N> / <Down-Arrow>`
+    pub fn describe(&self, action: Action) -> String {
+        self.bindings
+            .iter()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(key, _)| key_name(*key))
+            .collect::<Vec<_>>()
+            .join(" / ")
+    }
+}
+
+/// Key written like `ctrl-x`, `alt-x`, `f2`, `esc`, `enter` or `x`
+pub fn parse_key(key: &str) -> Option<Key> {
+    let lowercase = key.to_lowercase();
+    let single_char = |name: &str| {
+        let mut chars = name.chars();
+        chars.next().filter(|_| chars.next().is_none())
+    };
+    if let Some(name) = lowercase.strip_prefix("ctrl-") {
+        return match name {
+            "space" => Some(Key::Null),
+            _ => single_char(name).map(Key::Ctrl),
+        };
+    }
+    if let Some(name) = key.get(4..).filter(|_| lowercase.starts_with("alt-")) {
+        return single_char(name).map(Key::Alt);
+    }
+    let named = match lowercase.as_str() {
+        "esc" | "escape" => Key::Esc,
+        "enter" | "return" => Key::Char('\n'),
+        "tab" => Key::Char('\t'),
+        "backtab" | "shift-tab" => Key::BackTab,
+        "space" => Key::Char(' '),
+        "backspace" => Key::Backspace,
+        "delete" | "del" => Key::Delete,
+        "insert" => Key::Insert,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        name => {
+            if let Some(number) = name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                return (1..=12).contains(&number).then_some(Key::F(number));
+            }
+            return single_char(key).map(Key::Char);
+        }
+    };
+    Some(named)
+}
+
+/// Name of `key` in the help and the footer
+pub fn key_name(key: Key) -> String {
+    match key {
+        Key::Char('\n') => String::from("<Enter>"),
+        Key::Char('\t') => String::from("<Tab>"),
+        Key::Char(' ') => String::from("<Space>"),
+        Key::Char(c) => format!("<{c}>"),
+        Key::Ctrl(c) => format!("<Ctrl-{}>", c.to_uppercase()),
+        Key::Alt(c) => format!("<Alt-{c}>"),
+        Key::Null => String::from("<Ctrl-Space>"),
+        Key::F(number) => format!("<F{number}>"),
+        Key::Esc => String::from("<Esc>"),
+        Key::BackTab => String::from("<Shift-Tab>"),
+        Key::Up => String::from("<Up-Arrow>"),
+        Key::Down => String::from("<Down-Arrow>"),
+        Key::Left => String::from("<Left-Arrow>"),
+        Key::Right => String::from("<Right-Arrow>"),
+        key => format!("<{key:?}>"),
+    }
+}
+
+#[cfg(test)]
+mod test_keymap {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn parse_keys() {
+        assert_eq!(Some(Key::Ctrl('q')), parse_key("ctrl-q"));
+        assert_eq!(Some(Key::Ctrl('q')), parse_key("Ctrl-Q"));
+        assert_eq!(Some(Key::Null), parse_key("ctrl-space"));
+        assert_eq!(Some(Key::Alt('J')), parse_key("alt-J"));
+        assert_eq!(Some(Key::F(5)), parse_key("F5"));
+        assert_eq!(Some(Key::Char('\n')), parse_key("enter"));
+        assert_eq!(Some(Key::Char('j')), parse_key("j"));
+        assert_eq!(None, parse_key("f13"));
+        assert_eq!(None, parse_key("ctrl-shift-x"));
+    }
+
+    #[test]
+    fn configured_keys_replace_the_defaults() {
+        let config = HoardConfig {
+            keybindings: Some(HashMap::from([
+                (String::from("quit"), String::from("ctrl-q, esc")),
+                (String::from("delete"), String::from("ctrl-n")),
+                (String::from("jump"), String::from("ctrl-j")),
+                (String::from("copy"), String::from("ctrl-nope")),
+            ])),
+            ..HoardConfig::default()
+        };
+        let (keymap, problems) = Keymap::from_config(&config);
+        assert_eq!(Some(Action::Quit), keymap.action(Key::Ctrl('q')));
+        assert_eq!(None, keymap.action(Key::Ctrl('c')));
+        // The configured key wins over the default of another action
+        assert_eq!(Some(Action::Delete), keymap.action(Key::Ctrl('n')));
+        assert_eq!(None, keymap.action(Key::Ctrl('x')));
+        assert_eq!(Some(Action::Next), keymap.action(Key::Down));
+        assert_eq!("<Ctrl-Q> / <Esc>", keymap.describe(Action::Quit));
+        // Without a key that can be read the defaults are kept
+        assert_eq!(Some(Action::Copy), keymap.action(Key::Ctrl('k')));
+        assert_eq!(
+            vec![
+                String::from("Unknown action in keybindings: jump"),
+                String::from("Unknown key for copy: ctrl-nope"),
+            ],
+            problems
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use crate::core::tags::TagChange;
+use crate::core::HoardCmd;
+use crate::gui::commands_gui::{BatchAction, BatchInput, ControlState, State};
+use crate::gui::event::Key;
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// Keys while the input of a batch action on the marked commands is typed
+///
+/// Sets the batch action once the input is confirmed, an input that can't be applied is reported and can be fixed
+pub fn key_handler(input: Key, state: &mut State, batch: BatchInput) -> Option<HoardCmd> {
+    match input {
+        Key::Esc => {
+            state.control = ControlState::Search;
+            state.string_to_edit = String::new();
+            None
+        }
+        key if state.keymap.quits(key) => {
+            state.should_exit = true;
+            None
+        }
+        Key::Char('\n') => {
+            match batch_action(batch, state.string_to_edit.trim()) {
+                Ok(action) => {
+                    state.batch_action = Some(action);
+                    state.control = ControlState::Search;
+                    state.string_to_edit = String::new();
+                }
+                Err(message) => state.status_message = Some((message, Instant::now())),
+            }
+            None
+        }
+        Key::Backspace => {
+            state.string_to_edit.pop();
+            None
+        }
+        Key::Char(c) => {
+            state.string_to_edit.push(c);
+            None
+        }
+        _ => None,
+    }
+}
+
+fn batch_action(batch: BatchInput, input: &str) -> Result<BatchAction, String> {
+    if input.is_empty() {
+        return Err(String::from("Type something first, or <Esc> to abort"));
+    }
+    match batch {
+        BatchInput::Retag => TagChange::parse(input)
+            .map(BatchAction::Retag)
+            .map_err(|e| e.to_string()),
+        BatchInput::Move if input.contains('/') => Err(String::from(
+            "Only a namespace, the commands keep their names",
+        )),
+        BatchInput::Move => Ok(BatchAction::Move(input.to_string())),
+        BatchInput::Export => Ok(BatchAction::Export(PathBuf::from(input))),
+    }
+}
+
+#[cfg(test)]
+mod test_batch_controls {
+    use super::*;
+
+    #[test]
+    fn confirm_batch_actions() {
+        assert_eq!(
+            Ok(BatchAction::Retag(TagChange::Add(String::from("ops")))),
+            batch_action(BatchInput::Retag, "+ops")
+        );
+        assert!(batch_action(BatchInput::Retag, "ops").is_err());
+        assert_eq!(
+            Ok(BatchAction::Move(String::from("work"))),
+            batch_action(BatchInput::Move, "work")
+        );
+        assert!(batch_action(BatchInput::Move, "work/deploy").is_err());
+        assert!(batch_action(BatchInput::Export, "").is_err());
+    }
+}
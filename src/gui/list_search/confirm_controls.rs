@@ -0,0 +1,29 @@
+use crate::core::HoardCmd;
+use crate::gui::commands_gui::{Confirmed, ControlState, State};
+use crate::gui::event::Key;
+
+/// Keys while the confirmation modal is shown, only `<y>` takes the action and any other key leaves it
+///
+/// Returns the command to delete once its deletion is confirmed
+pub fn key_handler(input: Key, state: &mut State) -> Option<HoardCmd> {
+    state.control = ControlState::Search;
+    let confirmation = state.confirmation.take()?;
+    match input {
+        Key::Char('y' | 'Y') => match confirmation.action {
+            Confirmed::Delete(command) => {
+                state.should_delete = true;
+                Some(*command)
+            }
+            Confirmed::Batch(action) => {
+                state.batch_action = Some(action);
+                None
+            }
+        },
+        Key::Esc => None,
+        key if state.keymap.quits(key) => {
+            state.should_exit = true;
+            None
+        }
+        _ => None,
+    }
+}
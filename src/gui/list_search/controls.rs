@@ -1,8 +1,24 @@
+use crate::core::context::sort_by_context;
+use crate::core::fuzzy::fuzzy_score_fields;
 use crate::core::parameters::Parameterized;
+use crate::core::query::Query;
+use crate::core::stats::command_frecency;
 use crate::core::HoardCmd;
-use crate::gui::commands_gui::{ControlState, DrawState, EditSelection, State};
-use termion::event::Key;
+use crate::filter::SortMode;
+use crate::gui::commands_gui::{
+    BatchAction, BatchInput, Confirmed, ControlState, DrawState, State,
+};
+use crate::gui::event::{Key, MouseButton, MouseEvent};
+use crate::gui::help::HelpScreen;
+use crate::gui::inline_edit::EditForm;
+use crate::gui::keymap::Action;
+use crate::gui::parameter_input::controls::{load_parameter_options, pasted_text};
+use crate::util::pluralize;
+use ratatui::layout::Rect;
+use ratatui::widgets::ListState;
+use std::time::Instant;
 
+/// Keys in the command list, the keys bound to an action in the keymap first and the other ones are typed into the query
 #[allow(clippy::too_many_lines)]
 pub fn key_handler(
     input: Key,
@@ -10,27 +26,86 @@ pub fn key_handler(
     trove_commands: &[HoardCmd],
     namespace_tabs: &[&str],
 ) -> Option<HoardCmd> {
-    match input {
-        Key::Esc | Key::Ctrl('c' | 'd' | 'g') => {
+    let Some(action) = state.keymap.action(input) else {
+        return match input {
+            // Mark with a space too while nothing is searched for, a space in the query is typed as usual
+            Key::Char(' ') if state.input.is_empty() => {
+                toggle_mark(state);
+                None
+            }
+            Key::Char('?') if state.input.is_empty() => {
+                state.help = Some(HelpScreen::List);
+                None
+            }
+            // Handle query input
+            Key::Backspace => {
+                state.input.pop();
+                apply_filter(state, namespace_tabs, trove_commands);
+                None
+            }
+            Key::Char(c) => {
+                state.input.push(c);
+                apply_filter(state, namespace_tabs, trove_commands);
+                None
+            }
+            _ => None,
+        };
+    };
+    match action {
+        // Unmark the marked commands first
+        Action::Quit if input == Key::Esc && !state.marked.is_empty() => {
+            state.marked.clear();
+            None
+        }
+        Action::Quit => {
             // Definitely exit program
             state.control = ControlState::Search;
             state.should_exit = true;
             None
         }
         // Show help
-        Key::F(1) => {
-            state.draw = DrawState::Help;
+        Action::Help => {
+            state.help = Some(HelpScreen::List);
             None
         }
-        // Show help
-        Key::Ctrl('w') => {
-            state.draw = DrawState::Create;
-            state.edit_selection = EditSelection::Command;
-            state.new_command = Some(HoardCmd::default());
+        // Toggle the quick filters
+        Action::FilterParameterized => {
+            state.quick_filters.parameterized = !state.quick_filters.parameterized;
+            apply_filter(state, namespace_tabs, trove_commands);
+            None
+        }
+        Action::FilterFavorites => {
+            state.quick_filters.favorites = !state.quick_filters.favorites;
+            apply_filter(state, namespace_tabs, trove_commands);
+            None
+        }
+        Action::FilterInstalled => {
+            state.quick_filters.installed = !state.quick_filters.installed;
+            apply_filter(state, namespace_tabs, trove_commands);
+            None
+        }
+        // Sort the list another way
+        Action::Sort => {
+            state.sort_mode = state.sort_mode.next();
+            apply_filter(state, namespace_tabs, trove_commands);
+            state.command_list.select(Some(0));
+            None
+        }
+        // Create a new command
+        Action::Create => {
+            // In the namespace that is shown, `All` is none of them
+            let namespace = state
+                .namespace_tab
+                .selected()
+                .filter(|selected| *selected > 0)
+                .and_then(|selected| namespace_tabs.get(selected))
+                .map_or(state.default_namespace.as_str(), |namespace| *namespace);
+            state.edit_form = Some(EditForm::create(namespace));
+            state.control = ControlState::Edit;
             None
         }
         // Enter GPT mode
-        Key::Ctrl('a') => {
+        Action::Gpt => {
             // Same drawing state, only update how control works
             state.draw = DrawState::Search;
             if state.openai_key_set {
@@ -39,11 +114,24 @@ pub fn key_handler(
                 state.control = ControlState::KeyNotSet;
                 state.query_gpt = true;
             }
-            state.new_command = Some(HoardCmd::default());
+            None
+        }
+        // Show or hide the details of the selected command
+        Action::Details => {
+            state.show_details = !state.show_details;
+            None
+        }
+        // Focus the preview of the selected command, </> searches it then
+        Action::SearchPreview => {
+            state.control = ControlState::PreviewSearch;
+            state.preview_query = String::new();
+            state.preview_match = 0;
+            state.preview_typing = false;
+            state.preview_offset = (0, 0);
             None
         }
         // Switch to edit command mode
-        Key::Ctrl('e') | Key::Char('\t') => {
+        Action::Edit => {
             let selected_command = state
                 .commands
                 .clone()
@@ -55,28 +143,35 @@ pub fn key_handler(
                 )
                 .expect("exists")
                 .clone();
+            // Managed commands are read-only
+            if selected_command.is_managed {
+                let message = format!(
+                    "[{}] is a managed command and can't be edited",
+                    selected_command.name
+                );
+                state.status_message = Some((message, Instant::now()));
+                return None;
+            }
+            state.edit_form = Some(EditForm::new(&selected_command));
             state.control = ControlState::Edit;
-            state.selected_command = Some(selected_command);
-            state.update_string_to_edit();
             None
         }
         // Switch namespace
-        Key::Left | Key::Ctrl('h') => {
-            if let Some(selected) = state.namespace_tab.selected() {
-                let new_selected_tab = previous_index(selected, namespace_tabs.len());
-                switch_namespace(state, new_selected_tab, namespace_tabs, trove_commands);
-            }
+        Action::PreviousNamespace => {
+            step_namespace(state, previous_index, namespace_tabs, trove_commands);
             None
         }
-        Key::Right | Key::Ctrl('l') => {
-            if let Some(selected) = state.namespace_tab.selected() {
-                let new_selected_tab = next_index(selected, namespace_tabs.len());
-                switch_namespace(state, new_selected_tab, namespace_tabs, trove_commands);
-            }
+        Action::NextNamespace => {
+            step_namespace(state, next_index, namespace_tabs, trove_commands);
+            None
+        }
+        // Show or hide the namespace sidebar instead of the tabs
+        Action::Sidebar => {
+            state.show_sidebar = !state.show_sidebar;
             None
         }
         // Switch command
-        Key::Up | Key::Ctrl('y' | 'p') => {
+        Action::Previous => {
             if !state.commands.is_empty() {
                 if let Some(selected) = state.command_list.selected() {
                     let new_selected = previous_index(selected, state.commands.len());
@@ -85,7 +180,7 @@ pub fn key_handler(
             }
             None
         }
-        Key::Down | Key::Ctrl('.' | 'n') => {
+        Action::Next => {
             if !state.commands.is_empty() {
                 if let Some(selected) = state.command_list.selected() {
                     let new_selected = next_index(selected, state.commands.len());
@@ -94,7 +189,20 @@ pub fn key_handler(
             }
             None
         }
-        Key::Ctrl('x') => {
+        // Mark the command for a batch action and go to the next one
+        Action::Mark => {
+            toggle_mark(state);
+            None
+        }
+        Action::Delete if !state.marked.is_empty() => {
+            let question = format!(
+                "Delete {}?",
+                pluralize(state.marked.len(), "marked command", "marked commands")
+            );
+            state.confirm(question, Confirmed::Batch(BatchAction::Delete));
+            None
+        }
+        Action::Delete => {
             if state.commands.is_empty() {
                 return None;
             }
@@ -109,54 +217,258 @@ pub fn key_handler(
                 )
                 .expect("exists")
                 .clone();
-            state.should_delete = true;
-            Some(selected_command)
+            // Managed commands are read-only
+            if selected_command.is_managed {
+                let message = format!(
+                    "[{}] is a managed command and can't be deleted",
+                    selected_command.name
+                );
+                state.status_message = Some((message, Instant::now()));
+                return None;
+            }
+            let question = format!(
+                "Delete '{}/{}'?",
+                selected_command.namespace, selected_command.name
+            );
+            state.confirm(question, Confirmed::Delete(Box::new(selected_command)));
+            None
         }
-        // Select command
-        Key::Char('\n') => {
+        // Edit the command, description and tags in $EDITOR
+        Action::EditInEditor => {
             if state.commands.is_empty() {
-                state.should_exit = true;
                 return None;
             }
             let selected_command = state
                 .commands
-                .clone()
-                .get(
-                    state
-                        .command_list
-                        .selected()
-                        .expect("there is always a selected command"),
-                )
-                .expect("exists")
-                .clone();
-            // Check if parameters need to be supplied
-            if selected_command.get_parameter_count(&state.parameter_token) > 0 {
-                // Set next state to draw
-                state.draw = DrawState::ParameterInput;
-                // Save which command to replace parameters for
-                state.selected_command = Some(selected_command);
-                // Empty input for next screen
-                state.input = String::new();
-                // return None, otherwise drawing will quit
+                .get(state.command_list.selected()?)
+                .cloned()?;
+            state.should_open_editor = true;
+            Some(selected_command)
+        }
+        // Move the marked commands to another namespace
+        Action::Move if !state.marked.is_empty() => {
+            start_batch(state, BatchInput::Move, "");
+            None
+        }
+        // Change a tag of the marked commands, or of the selected one if none are marked
+        Action::Retag => {
+            start_batch(state, BatchInput::Retag, "");
+            None
+        }
+        // Write the marked commands, or the selected one, to a trove file
+        Action::Export => {
+            start_batch(state, BatchInput::Export, "hoard_export.yml");
+            None
+        }
+        // Move the command to another namespace or rename it
+        Action::Move => {
+            let selected_command = state
+                .commands
+                .get(state.command_list.selected()?)
+                .cloned()?;
+            // Managed commands are read-only
+            if selected_command.is_managed {
                 return None;
             }
-            Some(selected_command)
+            state.string_to_edit =
+                format!("{}/{}", selected_command.namespace, selected_command.name);
+            state.selected_command = Some(selected_command);
+            state.control = ControlState::Move;
+            None
         }
-        // Handle query input
-        Key::Backspace => {
-            state.input.pop();
-            apply_filter(state, namespace_tabs, trove_commands);
+        // Restore the most recently removed command
+        Action::Restore => {
+            state.should_restore = true;
             None
         }
-        Key::Char(c) => {
-            state.input.push(c);
-            apply_filter(state, namespace_tabs, trove_commands);
+        // Select command
+        Action::Select => {
+            if state.commands.is_empty() {
+                state.should_exit = true;
+                return None;
+            }
+            select_command(state)
+        }
+        // Copy the command to the clipboard, once its parameters are filled in
+        Action::Copy => copy_command(state, true),
+        Action::CopyRaw => copy_command(state, false),
+    }
+}
+
+/// Pick the selected command to be put on the clipboard, after its parameters are filled in if `fill_parameters`
+fn copy_command(state: &mut State, fill_parameters: bool) -> Option<HoardCmd> {
+    let selected = state.commands.get(state.command_list.selected()?)?.clone();
+    state.should_copy = true;
+    if fill_parameters {
+        select_command(state)
+    } else {
+        Some(selected)
+    }
+}
+
+/// Mark or unmark the selected command and select the next one
+fn toggle_mark(state: &mut State) {
+    let Some(selected) = state.command_list.selected() else {
+        return;
+    };
+    let Some(command) = state.commands.get(selected).cloned() else {
+        return;
+    };
+    state.toggle_mark(&command);
+    state
+        .command_list
+        .select(Some(next_index(selected, state.commands.len())));
+}
+
+/// Ask for the input of `batch`, starting with `input`. Without marked commands it applies to the selected one
+fn start_batch(state: &mut State, batch: BatchInput, input: &str) {
+    if state.marked.is_empty() {
+        let Some(command) = state
+            .command_list
+            .selected()
+            .and_then(|selected| state.commands.get(selected))
+            .cloned()
+        else {
+            return;
+        };
+        state.toggle_mark(&command);
+    }
+    state.string_to_edit = input.to_string();
+    state.control = ControlState::Batch(batch);
+}
+
+/// The selected command, or `None` if its parameters are asked for first
+fn select_command(state: &mut State) -> Option<HoardCmd> {
+    let selected_command = state
+        .commands
+        .clone()
+        .get(
+            state
+                .command_list
+                .selected()
+                .expect("there is always a selected command"),
+        )
+        .expect("exists")
+        .clone();
+    // Check if parameters need to be supplied
+    if selected_command.get_parameter_count(&state.parameter_token, state.parameter_escape_char) > 0
+    {
+        // Set next state to draw
+        state.draw = DrawState::ParameterInput;
+        // Save which command to replace parameters for
+        state.selected_command = Some(selected_command);
+        // Empty input for next screen
+        state.input = String::new();
+        load_parameter_options(state);
+        // return None, otherwise drawing will quit
+        return None;
+    }
+    Some(selected_command)
+}
+
+/// Clicks and the scroll wheel in the command list
+///
+/// A click selects the command under it and picks the command if it was selected already, or selects the namespace
+/// under it in the sidebar. The wheel scrolls through the commands, and a click on the query goes back to searching
+/// from editing or another input
+pub fn mouse_handler(
+    event: MouseEvent,
+    state: &mut State,
+    trove_commands: &[HoardCmd],
+    namespace_tabs: &[&str],
+) -> Option<HoardCmd> {
+    let MouseEvent::Press(button, column, row) = event else {
+        return None;
+    };
+    // Terminals count from 1, areas from 0
+    let (column, row) = (column.saturating_sub(1), row.saturating_sub(1));
+    if button == MouseButton::Left && contains(state.query_area, column, row) {
+        focus_query(state);
+        return None;
+    }
+    if state.show_sidebar
+        && state.control == ControlState::Search
+        && button == MouseButton::Left
+        && contains(state.sidebar_area, column, row)
+    {
+        // The border of the sidebar takes up the first row
+        let clicked = usize::from(row - state.sidebar_area.y - 1) + state.namespace_tab.offset();
+        if clicked < namespace_tabs.len() {
+            switch_namespace(state, clicked, namespace_tabs, trove_commands);
+        }
+        return None;
+    }
+    if state.control != ControlState::Search
+        || state.commands.is_empty()
+        || !contains(state.commands_area, column, row)
+    {
+        return None;
+    }
+    let selected = state.command_list.selected().unwrap_or(0);
+    match button {
+        MouseButton::WheelUp => {
+            state.command_list.select(Some(selected.saturating_sub(1)));
+            None
+        }
+        MouseButton::WheelDown => {
+            let last = state.commands.len() - 1;
+            state.command_list.select(Some((selected + 1).min(last)));
+            None
+        }
+        MouseButton::Left => {
+            // The border of the list takes up the first row
+            let clicked = usize::from(row.checked_sub(state.commands_area.y + 1)?)
+                + state.command_list.offset();
+            if clicked >= state.commands.len() {
+                return None;
+            }
+            if clicked == selected {
+                return select_command(state);
+            }
+            state.command_list.select(Some(clicked));
             None
         }
         _ => None,
     }
 }
 
+/// Whether the cell at `column` and `row` is inside of `area`, without its border
+const fn contains(area: Rect, column: u16, row: u16) -> bool {
+    column > area.x
+        && column + 1 < area.x + area.width
+        && row > area.y
+        && row + 1 < area.y + area.height
+}
+
+/// Leave editing the command or typing another input behind and search again
+fn focus_query(state: &mut State) {
+    match state.control {
+        ControlState::Edit => state.edit_form = None,
+        ControlState::Confirm => state.confirmation = None,
+        ControlState::Move | ControlState::Batch(_) => {
+            state.string_to_edit = String::new();
+        }
+        ControlState::PreviewSearch => {
+            state.preview_query = String::new();
+            state.preview_match = 0;
+            state.preview_offset = (0, 0);
+        }
+        ControlState::Search | ControlState::Gpt | ControlState::KeyNotSet => return,
+    }
+    state.control = ControlState::Search;
+}
+
+/// Put pasted text into the query as it is, without reading any of it as shortcuts
+pub fn paste_handler(
+    text: &str,
+    state: &mut State,
+    trove_commands: &[HoardCmd],
+    namespace_tabs: &[&str],
+) {
+    state.input.push_str(&pasted_text(text, false));
+    apply_filter(state, namespace_tabs, trove_commands);
+}
+
 const fn next_index(current_index: usize, collection_length: usize) -> usize {
     if current_index >= collection_length - 1 {
         0
@@ -173,12 +485,35 @@ const fn previous_index(current_index: usize, collection_length: usize) -> usize
     }
 }
 
+/// Switch to the namespace `step` gives for the selected one
+fn step_namespace(
+    state: &mut State,
+    step: fn(usize, usize) -> usize,
+    namespace_tabs: &[&str],
+    trove_commands: &[HoardCmd],
+) {
+    if let Some(selected) = state.namespace_tab.selected() {
+        let new_selected_tab = step(selected, namespace_tabs.len());
+        switch_namespace(state, new_selected_tab, namespace_tabs, trove_commands);
+    }
+}
+
 fn switch_namespace(
     state: &mut State,
     index_to_select: usize,
     namespaces: &[&str],
     commands: &[HoardCmd],
 ) {
+    // Remember where the list of the namespace that is left was, to get back there later
+    if let Some(current_namespace) = state
+        .namespace_tab
+        .selected()
+        .and_then(|selected| namespaces.get(selected))
+    {
+        state
+            .namespace_positions
+            .insert((*current_namespace).to_string(), state.command_list.clone());
+    }
     state.namespace_tab.select(Some(index_to_select));
 
     let selected_namespace = namespaces
@@ -187,33 +522,70 @@ fn switch_namespace(
 
     apply_search(state, commands, selected_namespace);
 
-    let new_selected_command = if state.commands.is_empty() {
-        0
-    } else {
-        state.commands.len() - 1
-    };
-
-    state.command_list.select(Some(new_selected_command));
+    let last_command = state.commands.len().saturating_sub(1);
+    state.command_list = state
+        .namespace_positions
+        .get(*selected_namespace)
+        .map_or_else(
+            || ListState::default().with_selected(Some(last_command)),
+            |position| {
+                // The search might have changed in the meantime, so the list can be shorter now
+                let selected = position.selected().unwrap_or(0).min(last_command);
+                ListState::default()
+                    .with_offset(position.offset().min(selected))
+                    .with_selected(Some(selected))
+            },
+        );
 }
 
+/// Points a command gets on top of its match score for every doubling of its frecency
+const FRECENCY_BONUS: i64 = 8;
+
+/// Commands of the selected tab matching the filters of the query that its text fuzzy matches, the best matches first
+///
+/// How well a command matches is its match score and a bonus for its frecency, so a daily command can outrank a
+/// slightly better match that is never used
 fn apply_search(state: &mut State, all_commands: &[HoardCmd], selected_tab: &str) {
-    let query_term = &state.input[..];
-    state.commands = all_commands
+    let query = Query::parse(&state.input);
+    let text: Vec<&str> = query.text().collect();
+    let text = text.join(" ");
+    let quick_filters = &mut state.quick_filters;
+    let mut commands: Vec<HoardCmd> = all_commands
         .iter()
         .filter(|&c| {
-            (c.name.contains(query_term)
-                || c.namespace.contains(query_term)
-                || c.get_tags_as_string().contains(query_term)
-                || c.command.contains(query_term)
-                || c.description.contains(query_term))
-                && (c.namespace.clone() == *selected_tab || selected_tab == "All")
+            (c.namespace.clone() == *selected_tab || selected_tab == "All")
+                && query.matches_filters(c)
+                && quick_filters.matches(c, &state.parameter_token, state.parameter_escape_char)
         })
         .cloned()
         .collect();
-    state.commands.sort_by(|a, b| b.usage_count.cmp(&a.usage_count));
+    sort_by_context(&mut commands, state.context.as_ref(), &state.frecency);
+    state.sort_mode.sort(&mut commands);
+    let mut scored: Vec<(i64, HoardCmd)> = commands
+        .into_iter()
+        .filter_map(|c| {
+            let tags = c.get_tags_as_string();
+            let fields = [
+                c.name.as_str(),
+                c.namespace.as_str(),
+                tags.as_str(),
+                c.command.as_str(),
+                c.description.as_str(),
+            ];
+            let frecency = command_frecency(&state.frecency, &c);
+            let bonus = i64::from((frecency + 1).ilog2()) * FRECENCY_BONUS;
+            fuzzy_score_fields(&text, &fields).map(|score| (score + bonus, c))
+        })
+        .collect();
+    // The sort is stable, commands that match as well keep the order of context and frecency. Another sort mode
+    // keeps its order for the matching commands
+    if state.sort_mode == SortMode::Relevance {
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    }
+    state.commands = scored.into_iter().map(|(_, c)| c).collect();
 }
 
-fn apply_filter(state: &mut State, namespaces: &[&str], commands: &[HoardCmd]) {
+pub fn apply_filter(state: &mut State, namespaces: &[&str], commands: &[HoardCmd]) {
     let selected_tab = namespaces
         .get(
             state
@@ -228,7 +600,14 @@ fn apply_filter(state: &mut State, namespaces: &[&str], commands: &[HoardCmd]) {
 #[cfg(test)]
 mod test_controls {
     use super::*;
-    use ratatui::widgets::ListState;
+    use crate::config::HoardConfig;
+    use crate::core::stats::UsedValues;
+    use crate::core::trove::Trove;
+    use crate::filter::QuickFilters;
+    use crate::gui::list_search::confirm_controls::key_handler as confirm_key_handler;
+    use crate::gui::list_search::gpt_controls::key_handler as gpt_key_handler;
+    use crate::gui::list_search::preview_search_controls::key_handler as preview_search_key_handler;
+    use std::collections::HashMap;
 
     const DEFAULT_NAMESPACE: &str = "default";
 
@@ -242,25 +621,57 @@ mod test_controls {
     fn create_state(commands: Vec<HoardCmd>) -> State {
         let mut state = State {
             input: String::new(),
+            query_area: Rect::default(),
+            keymap: crate::gui::keymap::Keymap::default(),
             commands,
+            commands_area: Rect::default(),
+            confirmation: None,
             command_list: ListState::default(),
+            marked: std::collections::HashSet::new(),
+            batch_action: None,
             namespace_tab: ListState::default(),
+            namespace_positions: HashMap::new(),
             should_exit: false,
+            should_open_editor: false,
+            should_copy: false,
             should_delete: false,
+            should_restore: false,
+            show_details: false,
+            show_sidebar: false,
+            sidebar_area: Rect::default(),
+            sort_mode: SortMode::default(),
+            context: None,
             draw: DrawState::Search,
             control: ControlState::Search,
-            new_command: None,
-            edit_selection: crate::gui::commands_gui::EditSelection::Command,
+            default_namespace: String::from(DEFAULT_NAMESPACE),
+            edit_form: None,
             string_to_edit: String::new(),
             parameter_token: "#".to_string(),
             parameter_ending_token: "!".to_string(),
+            parameter_escape_char: '\\',
+            parameter_providers: crate::core::providers::ProviderRegistry::default(),
+            parameter_options: Vec::new(),
+            parameter_option_list: ListState::default(),
+            parameter_scroll: None,
+            path_candidates: Vec::new(),
             selected_command: None,
             provided_parameter_count: 0,
+            status_message: None,
             error_message: String::new(),
+            help: None,
+            installed_binaries: HashMap::new(),
             query_gpt: false,
+            quick_filters: QuickFilters::default(),
+            frecency: HashMap::new(),
             buffered_tick: false,
             popup_message: State::get_default_popupmsg(),
+            preview_query: String::new(),
+            preview_match: 0,
+            preview_typing: false,
+            preview_offset: (0, 0),
             openai_key_set: false,
+            trove_name: String::from("default"),
+            used_values: UsedValues::default(),
         };
 
         state.command_list.select(Some(0));
@@ -317,6 +728,101 @@ mod test_controls {
         test_change_command(Key::Up, 0, 2);
     }
 
+    #[test]
+    fn confirm_deleting_the_selected_command() {
+        let namespaces = vec![DEFAULT_NAMESPACE];
+        let mut state = create_state(vec![create_command("first", "", DEFAULT_NAMESPACE)]);
+        let commands = state.commands.clone();
+
+        assert_eq!(
+            None,
+            key_handler(Key::Ctrl('x'), &mut state, &commands, &namespaces)
+        );
+        assert_eq!(ControlState::Confirm, state.control);
+        assert_eq!(
+            "Delete 'default/first'?",
+            state.confirmation.as_ref().unwrap().question
+        );
+        // Anything but yes keeps the command
+        assert_eq!(None, confirm_key_handler(Key::Char('n'), &mut state));
+        assert_eq!(ControlState::Search, state.control);
+        assert!(!state.should_delete);
+
+        key_handler(Key::Ctrl('x'), &mut state, &commands, &namespaces);
+        let deleted = confirm_key_handler(Key::Char('y'), &mut state);
+        assert_eq!(Some("first"), deleted.as_ref().map(|c| c.name.as_str()));
+        assert!(state.should_delete);
+        assert!(state.confirmation.is_none());
+    }
+
+    #[test]
+    fn managed_commands_are_not_deleted() {
+        let namespaces = vec![DEFAULT_NAMESPACE];
+        let mut managed = create_command("first", "", DEFAULT_NAMESPACE);
+        managed.is_managed = true;
+        let mut state = create_state(vec![managed]);
+        let commands = state.commands.clone();
+
+        key_handler(Key::Ctrl('x'), &mut state, &commands, &namespaces);
+        assert!(state.confirmation.is_none());
+        assert_eq!(
+            "[first] is a managed command and can't be deleted",
+            state.status_message.as_ref().unwrap().0
+        );
+    }
+
+    #[test]
+    fn delete_only_the_confirmed_namespace() {
+        let mut trove = Trove::from_commands(&[
+            create_command("first", "", DEFAULT_NAMESPACE),
+            create_command("first", "", "other"),
+        ]);
+        let mut state = create_state(trove.commands.clone());
+        state.should_delete = true;
+
+        let confirmed = create_command("first", "", DEFAULT_NAMESPACE);
+        crate::gui::commands_gui::delete_confirmed(&mut state, &mut trove, &confirmed);
+        assert_eq!(
+            vec!["other"],
+            trove
+                .commands
+                .iter()
+                .map(|c| c.namespace.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(1, state.commands.len());
+        assert_eq!(DEFAULT_NAMESPACE, trove.trash[0].command.namespace);
+        assert!(!state.should_delete);
+    }
+
+    #[test]
+    fn mark_commands_for_batch_action() {
+        let namespaces = vec![DEFAULT_NAMESPACE];
+        let mut state = create_state(vec![
+            create_command("first", "", DEFAULT_NAMESPACE),
+            create_command("second", "", DEFAULT_NAMESPACE),
+        ]);
+        let commands = state.commands.clone();
+        key_handler(Key::Char(' '), &mut state, &commands, &namespaces);
+        assert!(state.is_marked(&commands[0]));
+        assert_eq!(Some(1), state.command_list.selected());
+
+        key_handler(Key::Ctrl('x'), &mut state, &commands, &namespaces);
+        assert_eq!(
+            "Delete 1 marked command?",
+            state.confirmation.as_ref().unwrap().question
+        );
+        confirm_key_handler(Key::Char('y'), &mut state);
+        assert_eq!(Some(BatchAction::Delete), state.batch_action);
+
+        key_handler(Key::Char('f'), &mut state, &commands, &namespaces);
+        key_handler(Key::Char(' '), &mut state, &commands, &namespaces);
+        assert_eq!("f ", state.input);
+        key_handler(Key::Esc, &mut state, &commands, &namespaces);
+        assert!(state.marked.is_empty());
+        assert!(!state.should_exit);
+    }
+
     // Namespaces
     #[test]
     fn next_namespace() {
@@ -338,6 +844,186 @@ mod test_controls {
         test_change_namespace(Key::Left, 0, 2);
     }
 
+    #[test]
+    fn paste_into_query() {
+        let namespaces = vec![DEFAULT_NAMESPACE];
+        let cmd1 = create_command("logs", "kubectl logs -f", DEFAULT_NAMESPACE);
+        let cmd2 = create_command("pods", "kubectl get pods", DEFAULT_NAMESPACE);
+        let mut state = create_state(vec![cmd1, cmd2]);
+
+        let commands = state.commands.clone();
+        paste_handler("get pods\n", &mut state, &commands, &namespaces);
+
+        assert_eq!("get pods", state.input);
+        assert_eq!(1, state.commands.len());
+        assert!(!state.should_exit);
+    }
+
+    #[test]
+    fn fuzzy_search_ranks_best_matches_first() {
+        let namespaces = vec![DEFAULT_NAMESPACE];
+        let image = create_command("image", "docker image ls", DEFAULT_NAMESPACE);
+        let commit = create_command("commit", "git commit -m", DEFAULT_NAMESPACE);
+        let push = create_command("push", "git push", DEFAULT_NAMESPACE);
+        let mut state = create_state(vec![image, commit, push]);
+        let commands = state.commands.clone();
+
+        for c in "gcm".chars() {
+            key_handler(Key::Char(c), &mut state, &commands, &namespaces);
+        }
+        assert_eq!("commit", names(&state.commands)[0]);
+        assert!(!names(&state.commands).contains(&"push"));
+    }
+
+    #[test]
+    fn frequently_used_matches_outrank_better_ones() {
+        let namespaces = vec![DEFAULT_NAMESPACE];
+        let status = create_command("status", "git status", DEFAULT_NAMESPACE);
+        let start = create_command("start", "systemctl start", DEFAULT_NAMESPACE);
+        let weak = fuzzy_score_fields("stat", &["start", "systemctl start"]);
+        assert!(weak < fuzzy_score_fields("stat", &["status", "git status"]));
+        let mut state = create_state(vec![status, start]);
+        let commands = state.commands.clone();
+
+        for c in "stat".chars() {
+            key_handler(Key::Char(c), &mut state, &commands, &namespaces);
+        }
+        assert_eq!(vec!["status", "start"], names(&state.commands));
+
+        state.frecency.insert(
+            (String::from(DEFAULT_NAMESPACE), String::from("start")),
+            1000,
+        );
+        apply_filter(&mut state, &namespaces, &commands);
+        assert_eq!(vec!["start", "status"], names(&state.commands));
+    }
+
+    #[test]
+    fn edit_every_field_in_the_form() {
+        use crate::gui::inline_edit::controls::key_handler as form_key_handler;
+
+        let namespaces = vec![DEFAULT_NAMESPACE];
+        let cmd1 = create_command("first", "echo 1", DEFAULT_NAMESPACE);
+        let cmd2 = create_command("second", "echo 2", DEFAULT_NAMESPACE);
+        let mut state = create_state(vec![cmd1, cmd2]);
+        let commands = state.commands.clone();
+
+        key_handler(Key::Ctrl('e'), &mut state, &commands, &namespaces);
+        assert_eq!(ControlState::Edit, state.control);
+        // The form starts on the command
+        form_key_handler(Key::Backspace, &mut state, &commands);
+        form_key_handler(Key::Char('!'), &mut state, &commands);
+        // Up to the description, then around to the name
+        form_key_handler(Key::BackTab, &mut state, &commands);
+        form_key_handler(Key::Char('d'), &mut state, &commands);
+        form_key_handler(Key::Char('\t'), &mut state, &commands);
+        form_key_handler(Key::Char('\t'), &mut state, &commands);
+        for _ in 0.."first".len() {
+            form_key_handler(Key::Backspace, &mut state, &commands);
+        }
+        for c in "second".chars() {
+            form_key_handler(Key::Char(c), &mut state, &commands);
+        }
+        assert_eq!(
+            None,
+            form_key_handler(Key::Char('\n'), &mut state, &commands)
+        );
+        assert_eq!(
+            "There already is a command [second] in namespace [default]",
+            state.edit_form.as_ref().unwrap().error
+        );
+
+        form_key_handler(Key::Char('s'), &mut state, &commands);
+        let edited = form_key_handler(Key::Char('\n'), &mut state, &commands).unwrap();
+        assert_eq!("seconds", edited.name);
+        assert_eq!(DEFAULT_NAMESPACE, edited.namespace);
+        assert_eq!("echo !", edited.command);
+        assert_eq!("d", edited.description);
+
+        form_key_handler(Key::Esc, &mut state, &commands);
+        assert_eq!(ControlState::Search, state.control);
+        assert!(state.edit_form.is_none());
+    }
+
+    #[test]
+    fn create_a_command_in_the_form() {
+        use crate::gui::inline_edit::controls::key_handler as form_key_handler;
+
+        let namespaces = vec!["All", "git"];
+        let mut state = create_state(vec![create_command("log", "git log", "git")]);
+        let commands = state.commands.clone();
+
+        key_handler(Key::Ctrl('w'), &mut state, &commands, &namespaces);
+        assert_eq!(
+            DEFAULT_NAMESPACE,
+            state.edit_form.as_ref().unwrap().values[1]
+        );
+        form_key_handler(Key::Esc, &mut state, &commands);
+
+        // A new command goes into the namespace that is shown
+        state.namespace_tab.select(Some(1));
+        key_handler(Key::Ctrl('w'), &mut state, &commands, &namespaces);
+        assert!(state.edit_form.as_ref().unwrap().is_new());
+        for c in "git status".chars() {
+            form_key_handler(Key::Char(c), &mut state, &commands);
+        }
+        assert_eq!(
+            None,
+            form_key_handler(Key::Char('\n'), &mut state, &commands)
+        );
+        assert_eq!(
+            "Name can't be empty",
+            state.edit_form.as_ref().unwrap().error
+        );
+        form_key_handler(Key::Char('\t'), &mut state, &commands);
+        form_key_handler(Key::Char('s'), &mut state, &commands);
+        let created = form_key_handler(Key::Char('\n'), &mut state, &commands).unwrap();
+        assert_eq!("s", created.name);
+        assert_eq!("git", created.namespace);
+        assert_eq!("git status", created.command);
+        assert!(created.tags.is_empty());
+    }
+
+    #[test]
+    fn filter_fields_in_the_query() {
+        let namespaces = vec!["All"];
+        let mut logs = create_command("logs", "kubectl logs", "k8s");
+        logs.tags = vec![String::from("docker")];
+        let compose = create_command("compose", "docker compose logs", "docker");
+        let mut state = create_state(vec![logs, compose]);
+        let commands = state.commands.clone();
+
+        paste_handler("ns:k8s lgs", &mut state, &commands, &namespaces);
+        assert_eq!(vec!["logs"], names(&state.commands));
+        state.input = String::new();
+        paste_handler("tag:docker", &mut state, &commands, &namespaces);
+        assert_eq!(vec!["logs"], names(&state.commands));
+    }
+
+    #[test]
+    fn toggle_quick_filters() {
+        let namespaces = vec![DEFAULT_NAMESPACE];
+        let mut favorite = create_command("logs", "kubectl logs #pod!", DEFAULT_NAMESPACE);
+        favorite.is_favorite = true;
+        let pods = create_command("pods", "kubectl get pods", DEFAULT_NAMESPACE);
+        let mut state = create_state(vec![favorite, pods]);
+        let commands = state.commands.clone();
+
+        key_handler(Key::F(3), &mut state, &commands, &namespaces);
+        assert_eq!(vec!["logs"], names(&state.commands));
+        key_handler(Key::F(3), &mut state, &commands, &namespaces);
+        key_handler(Key::Char('p'), &mut state, &commands, &namespaces);
+        assert_eq!(2, state.commands.len());
+        key_handler(Key::F(2), &mut state, &commands, &namespaces);
+        assert_eq!(vec!["logs"], names(&state.commands));
+        key_handler(Key::F(2), &mut state, &commands, &namespaces);
+        assert_eq!(2, state.commands.len());
+    }
+
+    fn names(commands: &[HoardCmd]) -> Vec<&str> {
+        commands.iter().map(|c| c.name.as_str()).collect()
+    }
+
     #[test]
     fn filter_commands_when_namespace_changed() {
         let namespace1 = "first_namespace";
@@ -357,6 +1043,28 @@ mod test_controls {
         assert_eq!(cmd2_name, filtered_commands.first().unwrap().name);
     }
 
+    #[test]
+    fn restore_position_when_namespace_changed_back() {
+        let namespace1 = "first_namespace";
+        let namespace2 = "second_namespace";
+        let all_namespaces = vec![namespace1, namespace2];
+
+        let commands: Vec<HoardCmd> = ["a", "b", "c"]
+            .iter()
+            .map(|name| create_command(name, "", namespace1))
+            .chain(std::iter::once(create_command("d", "", namespace2)))
+            .collect();
+        let mut state = create_state(commands.clone());
+        switch_namespace(&mut state, 0, &all_namespaces, &commands);
+        state.command_list = ListState::default().with_offset(1).with_selected(Some(1));
+
+        key_handler(Key::Right, &mut state, &commands, &all_namespaces);
+        key_handler(Key::Left, &mut state, &commands, &all_namespaces);
+
+        assert_eq!(Some(1), state.command_list.selected());
+        assert_eq!(1, state.command_list.offset());
+    }
+
     #[test]
     fn select_last_command_when_namespace_changed() {
         let namespace1 = "first_namespace";
@@ -405,6 +1113,72 @@ mod test_controls {
         assert_eq!(DrawState::ParameterInput, state.draw);
     }
 
+    #[test]
+    fn search_in_preview() {
+        use crate::gui::list_search::preview_search_controls::{
+            current_match, key_handler as preview_key_handler, match_counts,
+        };
+
+        let namespaces = vec![DEFAULT_NAMESPACE];
+        let cmd =
+            create_command("First", "cat a.txt", DEFAULT_NAMESPACE).with_description("a b a b a");
+        let mut state = create_state(vec![cmd]);
+        let commands = state.commands.clone();
+        key_handler(Key::Ctrl('f'), &mut state, &commands, &namespaces);
+        assert_eq!(ControlState::PreviewSearch, state.control);
+        // Keys jump until </> starts a search
+        preview_key_handler(Key::Char('a'), &mut state);
+        assert_eq!("", state.preview_query);
+
+        for key in [
+            Key::Char('/'),
+            Key::Char('a'),
+            Key::Char('\n'),
+            Key::Char('n'),
+            Key::Char('n'),
+        ] {
+            preview_key_handler(key, &mut state);
+        }
+        assert_eq!("a", state.preview_query);
+        // The two matches in the command come before the three in the description
+        assert_eq!((2, 3), match_counts(&state));
+        assert_eq!(2, state.preview_match);
+        assert_eq!((None, Some(0)), current_match(&state));
+
+        // Jumping past the last match wraps around to the first one
+        for _ in 0..3 {
+            preview_key_handler(Key::Char('n'), &mut state);
+        }
+        assert_eq!(0, state.preview_match);
+        assert_eq!((Some(0), None), current_match(&state));
+        preview_key_handler(Key::Char('N'), &mut state);
+        assert_eq!(4, state.preview_match);
+    }
+
+    #[test]
+    fn move_the_selected_command() {
+        use crate::gui::list_search::move_controls::key_handler as move_key_handler;
+
+        let namespaces = vec![DEFAULT_NAMESPACE];
+        let mut state = create_state(vec![create_command("st", "git status", "git")]);
+        let commands = state.commands.clone();
+        key_handler(Key::Ctrl('r'), &mut state, &commands, &namespaces);
+        assert_eq!(ControlState::Move, state.control);
+        assert_eq!("git/st", state.string_to_edit);
+
+        for _ in 0.."st".len() {
+            move_key_handler(Key::Backspace, &mut state);
+        }
+        for c in "status".chars() {
+            move_key_handler(Key::Char(c), &mut state);
+        }
+        let moved = move_key_handler(Key::Char('\n'), &mut state).unwrap();
+        assert_eq!(
+            ("git", "status"),
+            (moved.namespace.as_str(), moved.name.as_str())
+        );
+    }
+
     #[test]
     fn quit_on_nothing_to_pick() {
         let mut state = create_state(vec![]);
@@ -428,7 +1202,199 @@ mod test_controls {
         let mut state = create_state(vec![]);
 
         key_handler(Key::F(1), &mut state, &[], &[]);
+        assert_eq!(Some(HelpScreen::List), state.help);
+
+        // `?` is typed into a query
+        state.help = None;
+        state.input = String::from("a");
+        key_handler(Key::Char('?'), &mut state, &[], &["All"]);
+        assert_eq!(None, state.help);
+        assert_eq!("a?", state.input);
+        state.input = String::new();
+        key_handler(Key::Char('?'), &mut state, &[], &["All"]);
+        assert_eq!(Some(HelpScreen::List), state.help);
+    }
+
+    #[test]
+    fn scroll_long_commands() {
+        use crate::gui::list_search::preview_search_controls::key_handler as preview_key_handler;
+        use crate::gui::parameter_input::controls::{
+            command_scroll, key_handler as parameter_key_handler,
+        };
+
+        let command = create_command(
+            "deploy",
+            "kubectl --context production --namespace payments rollout restart deployment/#name!",
+            DEFAULT_NAMESPACE,
+        )
+        .with_description("first\nsecond");
+        let mut state = create_state(vec![command.clone()]);
+        let commands = state.commands.clone();
+        key_handler(Key::Ctrl('f'), &mut state, &commands, &[DEFAULT_NAMESPACE]);
+        for key in [Key::Right, Key::Right, Key::Left, Key::Down, Key::Down] {
+            preview_key_handler(key, &mut state);
+        }
+        // The description has no more rows to scroll to
+        assert_eq!((1, 1), state.preview_offset);
+        preview_key_handler(Key::Esc, &mut state);
+        assert_eq!((0, 0), state.preview_offset);
+
+        // The parameter is followed until the command is scrolled by hand
+        state.selected_command = Some(command);
+        state.draw = DrawState::ParameterInput;
+        assert_eq!(67, command_scroll(&state));
+        parameter_key_handler(Key::Left, &mut state);
+        assert_eq!(66, command_scroll(&state));
+        parameter_key_handler(Key::Ctrl('f'), &mut state);
+        assert_eq!(67, command_scroll(&state));
+    }
+
+    #[test]
+    fn yank_the_selected_command() {
+        let namespaces = vec![DEFAULT_NAMESPACE];
+        let command = create_command("ssh", "ssh #host!", DEFAULT_NAMESPACE);
+        let mut state = create_state(vec![command.clone()]);
+        let commands = state.commands.clone();
+
+        let yanked = key_handler(Key::Alt('k'), &mut state, &commands, &namespaces);
+        assert_eq!(Some(command.command.clone()), yanked.map(|c| c.command));
+        assert!(state.should_copy);
+
+        // The parameters are filled in first
+        state.should_copy = false;
+        assert!(key_handler(Key::Ctrl('k'), &mut state, &commands, &namespaces).is_none());
+        assert!(state.should_copy);
+        assert_eq!(DrawState::ParameterInput, state.draw);
+
+        // Typed into the query unless it is bound in the keymap
+        let mut state = create_state(vec![command]);
+        key_handler(Key::Char('y'), &mut state, &commands, &namespaces);
+        assert_eq!("y", state.input);
+        assert!(!state.should_copy);
+    }
+
+    #[test]
+    fn toggle_details() {
+        let mut state = create_state(vec![]);
+
+        key_handler(Key::F(5), &mut state, &[], &[]);
+        assert!(state.show_details);
+        key_handler(Key::F(5), &mut state, &[], &[]);
+        assert!(!state.show_details);
+    }
+
+    #[test]
+    fn switch_namespace_in_the_sidebar() {
+        let namespaces = vec!["All", "git", "k8s"];
+        let mut state = create_state(vec![]);
+        let commands = state.commands.clone();
+
+        key_handler(Key::F(6), &mut state, &commands, &namespaces);
+        assert!(state.show_sidebar);
+        key_handler(Key::Right, &mut state, &commands, &namespaces);
+        assert_eq!(Some(1), state.namespace_tab.selected());
+        key_handler(Key::Left, &mut state, &commands, &namespaces);
+        assert_eq!(Some(0), state.namespace_tab.selected());
+        // Typed into the query with the sidebar shown too
+        key_handler(Key::Char('l'), &mut state, &commands, &namespaces);
+        assert_eq!("l", state.input);
+        assert_eq!(Some(0), state.namespace_tab.selected());
+
+        // The third row is the last namespace, below the border of the sidebar
+        state.sidebar_area = Rect::new(0, 3, 12, 10);
+        let click = MouseEvent::Press(MouseButton::Left, 3, 7);
+        mouse_handler(click, &mut state, &commands, &namespaces);
+        assert_eq!(Some(2), state.namespace_tab.selected());
+    }
+
+    #[test]
+    fn click_and_scroll_through_commands() {
+        let mut state = create_state(vec![
+            create_command("a", "ls", DEFAULT_NAMESPACE),
+            create_command("b", "pwd", DEFAULT_NAMESPACE),
+            create_command("c", "whoami", DEFAULT_NAMESPACE),
+        ]);
+        state.commands_area = Rect::new(0, 3, 30, 10);
+        state.query_area = Rect::new(0, 13, 80, 3);
+        let commands = state.commands.clone();
+        let namespaces = vec![DEFAULT_NAMESPACE];
+        let press = |button, column: u16, row: u16| MouseEvent::Press(button, column + 1, row + 1);
+
+        // Below the border of the list, the second row is the second command
+        assert!(mouse_handler(
+            press(MouseButton::Left, 2, 5),
+            &mut state,
+            &commands,
+            &namespaces
+        )
+        .is_none());
+        assert_eq!(Some(1), state.command_list.selected());
+        let picked = mouse_handler(
+            press(MouseButton::Left, 2, 5),
+            &mut state,
+            &commands,
+            &namespaces,
+        );
+        assert_eq!(Some(String::from("b")), picked.map(|command| command.name));
+
+        for _ in 0..3 {
+            mouse_handler(
+                press(MouseButton::WheelDown, 2, 8),
+                &mut state,
+                &commands,
+                &namespaces,
+            );
+        }
+        assert_eq!(Some(2), state.command_list.selected());
+        mouse_handler(
+            press(MouseButton::WheelUp, 2, 8),
+            &mut state,
+            &commands,
+            &namespaces,
+        );
+        assert_eq!(Some(1), state.command_list.selected());
+        // Outside of the list the wheel does nothing
+        mouse_handler(
+            press(MouseButton::WheelUp, 50, 8),
+            &mut state,
+            &commands,
+            &namespaces,
+        );
+        assert_eq!(Some(1), state.command_list.selected());
+
+        state.control = ControlState::Edit;
+        state.edit_form = Some(EditForm::new(&commands[0]));
+        mouse_handler(
+            press(MouseButton::Left, 5, 14),
+            &mut state,
+            &commands,
+            &namespaces,
+        );
+        assert_eq!(ControlState::Search, state.control);
+        assert!(state.edit_form.is_none());
+    }
+
+    #[test]
+    fn rebound_keys_work_in_preview_search_and_gpt() {
+        let config = HoardConfig {
+            keybindings: Some(HashMap::from([
+                (String::from("help"), String::from("f2")),
+                (String::from("search_preview"), String::from("ctrl-s")),
+            ])),
+            ..HoardConfig::default()
+        };
+        let mut state = create_state(vec![HoardCmd::default().with_name("a")]);
+        state.keymap = crate::gui::keymap::Keymap::from_config(&config).0;
+
+        state.control = ControlState::PreviewSearch;
+        preview_search_key_handler(Key::F(2), &mut state);
+        assert_eq!(Some(HelpScreen::List), state.help);
+        preview_search_key_handler(Key::Ctrl('s'), &mut state);
+        assert_eq!(ControlState::Search, state.control);
 
-        assert_eq!(DrawState::Help, state.draw);
+        state.help = None;
+        state.control = ControlState::Gpt;
+        gpt_key_handler(Key::F(2), &mut state);
+        assert_eq!(Some(HelpScreen::List), state.help);
     }
 }
@@ -1,6 +1,7 @@
 use crate::core::HoardCmd;
-use crate::gui::commands_gui::{ControlState, DrawState, State};
-use termion::event::Key;
+use crate::gui::commands_gui::{ControlState, State};
+use crate::gui::event::Key;
+use crate::gui::help::HelpScreen;
 
 #[allow(clippy::too_many_lines)]
 pub fn key_handler(input: Key, state: &mut State) -> Option<HoardCmd> {
@@ -12,8 +13,8 @@ pub fn key_handler(input: Key, state: &mut State) -> Option<HoardCmd> {
             None
         }
         // Show help
-        Key::F(1) => {
-            state.draw = DrawState::Help;
+        key if state.keymap.helps(key) => {
+            state.help = Some(HelpScreen::List);
             None
         }
         // Select command
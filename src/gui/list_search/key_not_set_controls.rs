@@ -1,6 +1,6 @@
 use crate::core::HoardCmd;
 use crate::gui::commands_gui::{ControlState, State};
-use termion::event::Key;
+use crate::gui::event::Key;
 
 #[allow(clippy::too_many_lines)]
 pub fn key_handler(input: Key, state: &mut State) -> Option<HoardCmd> {
@@ -1,4 +1,8 @@
+pub mod batch_controls;
+pub mod confirm_controls;
 pub mod controls;
 pub mod gpt_controls;
 pub mod key_not_set_controls;
+pub mod move_controls;
+pub mod preview_search_controls;
 pub mod render;
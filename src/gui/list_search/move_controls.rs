@@ -0,0 +1,41 @@
+use crate::core::trove::split_command_path;
+use crate::core::HoardCmd;
+use crate::gui::commands_gui::{ControlState, State};
+use crate::gui::event::Key;
+
+/// Keys while the new place of the selected command is typed as `namespace/name`
+///
+/// Returns the selected command at its new place once it is confirmed
+pub fn key_handler(input: Key, state: &mut State) -> Option<HoardCmd> {
+    match input {
+        Key::Esc => {
+            state.control = ControlState::Search;
+            state.selected_command = None;
+            None
+        }
+        key if state.keymap.quits(key) => {
+            state.should_exit = true;
+            None
+        }
+        Key::Char('\n') => {
+            let command = state.selected_command.clone()?;
+            let (namespace, name) = split_command_path(state.string_to_edit.trim());
+            let namespace = namespace.unwrap_or(&command.namespace).to_string();
+            let name = if name.is_empty() {
+                command.name.clone()
+            } else {
+                name.to_string()
+            };
+            Some(command.with_namespace(&namespace).with_name(&name))
+        }
+        Key::Backspace => {
+            state.string_to_edit.pop();
+            None
+        }
+        Key::Char(c) => {
+            state.string_to_edit.push(c);
+            None
+        }
+        _ => None,
+    }
+}
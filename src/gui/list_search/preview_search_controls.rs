@@ -0,0 +1,168 @@
+use crate::core::HoardCmd;
+use crate::gui::commands_gui::{ControlState, State};
+use crate::gui::event::Key;
+use crate::gui::help::HelpScreen;
+use crate::gui::keymap::Action;
+
+pub fn key_handler(input: Key, state: &mut State) -> Option<HoardCmd> {
+    match input {
+        key if key == Key::Esc
+            || state.keymap.typing_action(key) == Some(Action::SearchPreview) =>
+        {
+            state.control = ControlState::Search;
+            state.preview_query = String::new();
+            state.preview_match = 0;
+            state.preview_offset = (0, 0);
+            None
+        }
+        // Show help
+        key if state.keymap.helps(key) => {
+            state.help = Some(HelpScreen::List);
+            None
+        }
+        Key::Char('\n') => {
+            state.preview_typing = false;
+            None
+        }
+        Key::Char('/') if !state.preview_typing => {
+            state.preview_typing = true;
+            state.preview_query = String::new();
+            state.preview_match = 0;
+            state.preview_offset.0 = 0;
+            None
+        }
+        Key::Char('n') if !state.preview_typing => {
+            jump_to_match(state, 1);
+            None
+        }
+        Key::Char('N') if !state.preview_typing => {
+            jump_to_match(state, -1);
+            None
+        }
+        // Scroll the description up and down, the command left and right
+        Key::Up => {
+            state.preview_offset.0 = state.preview_offset.0.saturating_sub(1);
+            None
+        }
+        Key::Down => {
+            let rows = preview_text(state).lines().count();
+            if usize::from(state.preview_offset.0) + 1 < rows {
+                state.preview_offset.0 += 1;
+            }
+            None
+        }
+        Key::Left => {
+            state.preview_offset.1 = state.preview_offset.1.saturating_sub(1);
+            None
+        }
+        Key::Right => {
+            if usize::from(state.preview_offset.1) + 1 < command_width(state) {
+                state.preview_offset.1 += 1;
+            }
+            None
+        }
+        // Handle query input
+        Key::Backspace if state.preview_typing => {
+            state.preview_query.pop();
+            state.preview_match = 0;
+            state.preview_offset.0 = 0;
+            None
+        }
+        Key::Char(c) if state.preview_typing => {
+            state.preview_query.push(c);
+            state.preview_match = 0;
+            state.preview_offset.0 = 0;
+            None
+        }
+        _ => None,
+    }
+}
+
+/// Byte positions of all occurrences of `query` in `text`
+pub fn preview_matches(text: &str, query: &str) -> Vec<usize> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    text.match_indices(query).map(|(i, _)| i).collect()
+}
+
+/// Description of the selected command
+pub fn preview_text(state: &State) -> String {
+    state
+        .command_list
+        .selected()
+        .and_then(|selected| state.commands.get(selected))
+        .map(|command| command.description.clone())
+        .unwrap_or_default()
+}
+
+/// Command of the selected command, searched before its description
+pub fn preview_command(state: &State) -> String {
+    state
+        .command_list
+        .selected()
+        .and_then(|selected| state.commands.get(selected))
+        .map(|command| command.command.clone())
+        .unwrap_or_default()
+}
+
+/// Matches of the preview search in the command and in the description of the selected command
+pub fn match_counts(state: &State) -> (usize, usize) {
+    (
+        preview_matches(&preview_command(state), &state.preview_query).len(),
+        preview_matches(&preview_text(state), &state.preview_query).len(),
+    )
+}
+
+/// The current match as an index among the matches of the command, or of the description
+pub fn current_match(state: &State) -> (Option<usize>, Option<usize>) {
+    let (in_command, in_description) = match_counts(state);
+    let current = state.preview_match;
+    if current < in_command {
+        (Some(current), None)
+    } else if current - in_command < in_description {
+        (None, Some(current - in_command))
+    } else {
+        (None, None)
+    }
+}
+
+/// Columns of the longest line of the selected command, the preview isn't scrolled past it
+fn command_width(state: &State) -> usize {
+    state
+        .command_list
+        .selected()
+        .and_then(|selected| state.commands.get(selected))
+        .and_then(|command| {
+            command
+                .command
+                .lines()
+                .map(|line| line.chars().count())
+                .max()
+        })
+        .unwrap_or_default()
+}
+
+/// Move to the next or previous match, wrapping around at the ends
+fn jump_to_match(state: &mut State, step: isize) {
+    let (in_command, in_description) = match_counts(state);
+    let count = in_command + in_description;
+    if count == 0 {
+        return;
+    }
+    let current = isize::try_from(state.preview_match).unwrap_or(0);
+    let count_signed = isize::try_from(count).unwrap_or(isize::MAX);
+    state.preview_match = usize::try_from((current + step).rem_euclid(count_signed)).unwrap_or(0);
+    state.preview_offset = (0, 0);
+}
+
+#[cfg(test)]
+mod test_preview_search_controls {
+    use super::*;
+
+    #[test]
+    fn matches_of_query() {
+        assert_eq!(vec![0, 8], preview_matches("foo bar foo", "foo"));
+        assert!(preview_matches("foo", "").is_empty());
+    }
+}
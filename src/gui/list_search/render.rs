@@ -1,26 +1,39 @@
-use crate::config::HoardConfig;
+use crate::config::{HoardConfig, DEFAULT_TROVE};
+use crate::core::highlight::{shell_tokens, ShellToken};
+use crate::core::privilege::privilege_warning;
+use crate::core::shell::is_installed;
 use crate::core::HoardCmd;
 use crate::gui::commands_gui::State;
-use crate::gui::commands_gui::{ControlState, EditSelection};
-use crate::gui::help::HELP_KEY;
-use ratatui::backend::TermionBackend;
+use crate::gui::commands_gui::{BatchInput, ControlState};
+use crate::gui::help::draw_overlay as draw_help;
+use crate::gui::inline_edit::render::draw as draw_edit_form;
+use crate::gui::keymap::Action;
+use crate::gui::list_search::preview_search_controls::{
+    current_match, match_counts, preview_matches, preview_text,
+};
+use crate::gui::palette::Palette;
+use ratatui::backend::Backend;
 use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
-use ratatui::text::{Line, Span};
+use ratatui::text::{Line, Span, Text};
 use ratatui::widgets::{Block, BorderType, Borders, Clear, List, ListItem, Paragraph, Tabs, Wrap};
 use ratatui::Terminal;
-use termion::screen::AlternateScreen;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Colors of the syntax highlighting of the command preview that the theme has none for, from the gruvbox palette
+const HIGHLIGHT_FLAG: Color = Color::Rgb(131, 165, 152);
+const HIGHLIGHT_STRING: Color = Color::Rgb(184, 187, 38);
+const HIGHLIGHT_OPERATOR: Color = Color::Rgb(251, 73, 52);
+const HIGHLIGHT_VARIABLE: Color = Color::Rgb(142, 192, 124);
+const HIGHLIGHT_PARAMETER: Color = Color::Rgb(211, 134, 155);
+
 #[allow(clippy::too_many_lines)]
-pub fn draw(
+pub fn draw<B: Backend>(
     app_state: &mut State,
     config: &HoardConfig,
     namespace_tabs: &[&str],
-    terminal: &mut Terminal<
-        TermionBackend<AlternateScreen<termion::raw::RawTerminal<std::io::Stdout>>>,
-    >,
+    terminal: &mut Terminal<B>,
 ) -> Result<(), eyre::Error> {
     terminal.draw(|rect| {
         let size = rect.size();
@@ -29,7 +42,8 @@ pub fn draw(
             .margin(1)
             .constraints(
                 [
-                    Constraint::Length(3),
+                    // The sidebar shows the namespaces instead of the tabs
+                    Constraint::Length(if app_state.show_sidebar { 0 } else { 3 }),
                     Constraint::Min(2),
                     Constraint::Length(3),
                     Constraint::Length(1),
@@ -79,12 +93,48 @@ pub fn draw(
             )
             .divider(Span::raw("|"));
 
-        rect.render_widget(tabs, chunks[0]);
+        if !app_state.show_sidebar {
+            rect.render_widget(tabs, chunks[0]);
+        }
 
+        let (sidebar_area, list_area) = if app_state.show_sidebar {
+            let longest = namespace_tabs
+                .iter()
+                .map(|namespace| namespace.chars().count())
+                .max()
+                .unwrap_or_default();
+            // Room for the border and the branch in front of the name
+            let width = u16::try_from(longest + 4).unwrap_or(u16::MAX).min(30);
+            let areas = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(width), Constraint::Min(0)].as_ref())
+                .split(chunks[1]);
+            (Some(areas[0]), areas[1])
+        } else {
+            (None, chunks[1])
+        };
+        if let Some(area) = sidebar_area {
+            rect.render_stateful_widget(
+                render_namespace_sidebar(namespace_tabs, config),
+                area,
+                &mut app_state.namespace_tab,
+            );
+            app_state.sidebar_area = area;
+        }
+        // The details of the selected command take up the lower part, below the list and the preview
+        let (main_area, details_area) = if app_state.show_details {
+            let areas = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Percentage(55), Constraint::Percentage(45)].as_ref())
+                .split(list_area);
+            (areas[0], Some(areas[1]))
+        } else {
+            (list_area, None)
+        };
         let commands_chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
-            .split(chunks[1]);
+            .split(main_area);
         let command_detail_chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints(
@@ -103,10 +153,23 @@ pub fn draw(
             commands_chunks[0],
             &mut app_state.command_list,
         );
+        app_state.commands_area = commands_chunks[0];
         rect.render_widget(tags_widget, command_detail_chunks[0]);
+        let description = if app_state.control == ControlState::PreviewSearch {
+            // The border takes up two columns
+            let width = command_detail_chunks[1].width.saturating_sub(2);
+            let rows = preview_scroll(app_state, width).saturating_add(app_state.preview_offset.0);
+            description.scroll((rows, 0))
+        } else {
+            description
+        };
         rect.render_widget(description, command_detail_chunks[1]);
         rect.render_widget(command, command_detail_chunks[2]);
+        if let Some(area) = details_area {
+            rect.render_widget(render_details(app_state, config), area);
+        }
         rect.render_widget(input, chunks[2]);
+        app_state.query_area = chunks[2];
 
         let (footer_left, footer_right) = get_footer_constraints(&app_state.control);
         let footer_chunk = Layout::default()
@@ -118,16 +181,30 @@ pub fn draw(
             ])
             .split(chunks[3]);
 
-        let control_str = &app_state.control;
-        let help_hint_l = Paragraph::new(format!("{control_str}"))
+        let control_str = app_state.current_status_message().map_or_else(
+            || {
+                if app_state.control == ControlState::Search {
+                    format!("{} | Sorted by {}", app_state.control, app_state.sort_mode)
+                } else {
+                    app_state.control.to_string()
+                }
+            },
+            ToString::to_string,
+        );
+        let help_hint_l = Paragraph::new(control_str)
             .style(Style::default().fg(Color::Rgb(
                 config.primary_color.unwrap().0,
                 config.primary_color.unwrap().1,
                 config.primary_color.unwrap().2,
             )))
             .alignment(Alignment::Left);
+        let keymap = &app_state.keymap;
         let help_hint = Paragraph::new(format!(
-            "Create <Ctrl-W> | Delete <Ctrl-X> | GPT <Ctrl-A> | Help {HELP_KEY}"
+            "Create {} | Delete {} | GPT {} | Help {}",
+            keymap.key(Action::Create),
+            keymap.key(Action::Delete),
+            keymap.key(Action::Gpt),
+            keymap.key(Action::Help)
         ))
         .style(Style::default().fg(Color::Rgb(
             config.primary_color.unwrap().0,
@@ -158,10 +235,10 @@ pub fn draw(
                 .block(
                     Block::default()
                         .borders(Borders::ALL)
-                        .style(Style::default().fg(get_color(
-                            app_state,
-                            config,
-                            &EditSelection::Description,
+                        .style(Style::default().fg(Color::Rgb(
+                            config.primary_color.unwrap().0,
+                            config.primary_color.unwrap().1,
+                            config.primary_color.unwrap().2,
                         )))
                         .title("GPT")
                         .border_type(BorderType::Plain),
@@ -170,6 +247,37 @@ pub fn draw(
             rect.render_widget(Clear, area); //this clears out the background
             rect.render_widget(description, area);
         }
+        if let Some(form) = &app_state.edit_form {
+            draw_edit_form(rect, form, config, size);
+        }
+        if let Some(confirmation) = &app_state.confirmation {
+            let question = Paragraph::new(format!("{} y/N", confirmation.question))
+                .style(Style::default().fg(Color::Rgb(
+                    config.primary_color.unwrap().0,
+                    config.primary_color.unwrap().1,
+                    config.primary_color.unwrap().2,
+                )))
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true })
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::Rgb(
+                            config.secondary_color.unwrap().0,
+                            config.secondary_color.unwrap().1,
+                            config.secondary_color.unwrap().2,
+                        )))
+                        .title(" Confirm ")
+                        .border_type(BorderType::Plain),
+                );
+            let area = centered_rows(50, 3, size);
+            rect.render_widget(Clear, area);
+            rect.render_widget(question, area);
+        }
+        if let Some(screen) = app_state.help {
+            draw_help(rect, screen, &app_state.keymap, config);
+        }
+        rect.render_widget(Palette::from_config(config), rect.size());
     })?;
     Ok(())
 }
@@ -201,42 +309,120 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         .split(popup_layout[1])[1]
 }
 
-fn get_color(
-    app: &State,
-    config: &HoardConfig,
-    command_render: &EditSelection,
-) -> ratatui::style::Color {
-    let highlighted = Color::Rgb(
-        config.secondary_color.unwrap().0,
-        config.secondary_color.unwrap().1,
-        config.secondary_color.unwrap().2,
-    );
-    let normal = Color::Rgb(
-        config.primary_color.unwrap().0,
-        config.primary_color.unwrap().1,
-        config.primary_color.unwrap().2,
-    );
-    match app.control {
-        ControlState::Search | ControlState::Gpt | ControlState::KeyNotSet => normal,
-        ControlState::Edit => {
-            if command_render == &app.edit_selection {
-                return highlighted;
-            }
-            normal
+/// The namespaces as branches of `All`, the selected one is the namespace the list shows
+fn render_namespace_sidebar<'a>(namespace_tabs: &[&str], config: &HoardConfig) -> List<'a> {
+    let items: Vec<ListItem> = namespace_tabs
+        .iter()
+        .enumerate()
+        .map(|(i, namespace)| {
+            let branch = match i {
+                0 => "",
+                _ if i + 1 == namespace_tabs.len() => "└ ",
+                _ => "├ ",
+            };
+            ListItem::new(format!("{branch}{namespace}"))
+        })
+        .collect();
+    List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Namespaces ")
+                .border_type(BorderType::Plain),
+        )
+        .style(Style::default().fg(Color::Rgb(
+            config.primary_color.unwrap().0,
+            config.primary_color.unwrap().1,
+            config.primary_color.unwrap().2,
+        )))
+        .highlight_style(
+            Style::default()
+                .fg(Color::Rgb(
+                    config.secondary_color.unwrap().0,
+                    config.secondary_color.unwrap().1,
+                    config.secondary_color.unwrap().2,
+                ))
+                .add_modifier(Modifier::BOLD),
+        )
+}
+
+/// Tags separated by commas, the ones with a color of their own as chips
+fn tag_chips<'a>(tags: &[String], config: &HoardConfig) -> Vec<Span<'a>> {
+    let mut chips = Vec::new();
+    for tag in tags {
+        if !chips.is_empty() {
+            chips.push(Span::raw(","));
         }
+        chips.push(tag_chip(tag, config));
     }
+    chips
 }
 
-fn coerce_string_by_mode(s: String, app: &State, command_render: &EditSelection) -> String {
-    match app.control {
-        ControlState::Search | ControlState::Gpt | ControlState::KeyNotSet => s,
-        ControlState::Edit => {
-            if command_render == &app.edit_selection {
-                return app.string_to_edit.clone();
-            }
-            s
-        }
+/// Everything about the selected command, its command is wrapped instead of cut off
+fn render_details<'a>(app: &State, config: &HoardConfig) -> Paragraph<'a> {
+    let command = app
+        .command_list
+        .selected()
+        .and_then(|selected| app.commands.get(selected))
+        .cloned()
+        .unwrap_or_else(HoardCmd::default);
+    let label = Style::default().add_modifier(Modifier::BOLD);
+    let mut tags = vec![Span::styled("Tags: ", label)];
+    tags.extend(tag_chips(&command.tags, config));
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("Namespace: ", label),
+            Span::raw(command.namespace.clone()),
+        ]),
+        Line::from(tags),
+        Line::default(),
+    ];
+    if config.syntax_highlighting == Some(false) {
+        lines.extend(Text::from(command.command.clone()).lines);
+    } else {
+        lines.extend(highlighted_command(&command.command, app, config).lines);
     }
+    lines.push(Line::default());
+    lines.extend(
+        command
+            .description
+            .lines()
+            .map(|line| Line::from(line.to_string())),
+    );
+    Paragraph::new(Text::from(lines))
+        .style(Style::default().fg(Color::Rgb(
+            config.primary_color.unwrap().0,
+            config.primary_color.unwrap().1,
+            config.primary_color.unwrap().2,
+        )))
+        .alignment(Alignment::Left)
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" Details of {} ", command.name))
+                .border_type(BorderType::Plain),
+        )
+}
+
+/// A tag drawn on its configured color, tags without a color just get their name
+fn tag_chip<'a>(tag: &str, config: &HoardConfig) -> Span<'a> {
+    config.tag_color(tag).map_or_else(
+        || Span::raw(tag.to_string()),
+        |(r, g, b)| {
+            Span::styled(
+                format!(" {tag} "),
+                Style::default()
+                    .bg(Color::Rgb(r, g, b))
+                    .fg(Color::Rgb(
+                        config.tertiary_color.unwrap().0,
+                        config.tertiary_color.unwrap().1,
+                        config.tertiary_color.unwrap().2,
+                    ))
+                    .add_modifier(Modifier::BOLD),
+            )
+        },
+    )
 }
 
 #[allow(clippy::too_many_lines)]
@@ -251,19 +437,64 @@ fn render_commands<'a>(
     Paragraph<'a>,
     Paragraph<'a>,
 ) {
+    let mut notes: Vec<String> = app
+        .quick_filters
+        .active()
+        .into_iter()
+        .map(ToString::to_string)
+        .collect();
+    if !app.marked.is_empty() {
+        notes.push(format!("{} marked", app.marked.len()));
+    }
+    let commands_title = if notes.is_empty() {
+        String::from(" Commands ")
+    } else {
+        format!(" Commands ({}) ", notes.join(", "))
+    };
     let commands = Block::default()
         .borders(Borders::ALL)
-        .style(Style::default().fg(get_color(app, config, &EditSelection::Name)))
-        .title(" Commands ")
+        .style(Style::default().fg(Color::Rgb(
+            config.primary_color.unwrap().0,
+            config.primary_color.unwrap().1,
+            config.primary_color.unwrap().2,
+        )))
+        .title(commands_title)
         .border_type(BorderType::Plain);
 
     let items: Vec<_> = commands_list
         .iter()
         .map(|command| {
-            ListItem::new(Line::from(vec![Span::styled(
-                command.name.clone(),
-                Style::default(),
-            )]))
+            let mut spans = Vec::new();
+            if app.is_marked(command) {
+                spans.push(Span::styled(
+                    "● ",
+                    Style::default().add_modifier(Modifier::BOLD),
+                ));
+            }
+            spans.push(Span::styled(command.name.clone(), Style::default()));
+            if let Some(warning) = privilege_warning(&command.command) {
+                spans.push(Span::styled(
+                    format!(" {}", warning.badge()),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ));
+            }
+            if command.is_managed {
+                let source = command.overlay.as_deref().unwrap_or("managed");
+                spans.push(Span::styled(
+                    format!(" [{source}]"),
+                    Style::default().add_modifier(Modifier::DIM),
+                ));
+            }
+            // Only tags with a color of their own, to keep the list readable
+            for tag in command
+                .tags
+                .iter()
+                .filter(|tag| config.tag_color(tag).is_some())
+            {
+                spans.push(Span::raw(" "));
+                spans.push(tag_chip(tag, config));
+            }
+            ListItem::new(Line::from(spans))
         })
         .collect();
 
@@ -301,69 +532,160 @@ fn render_commands<'a>(
             .add_modifier(Modifier::BOLD),
     );
 
-    let hoarded_command_title = format!(" Hoarded command --- Times selected: {} ", selected_command.usage_count);
-    let command = Paragraph::new(coerce_string_by_mode(
-        selected_command.command.clone(),
-        app,
-        &EditSelection::Command,
-    ))
-    .style(Style::default().fg(Color::Rgb(
-        config.primary_color.unwrap().0,
-        config.primary_color.unwrap().1,
-        config.primary_color.unwrap().2,
-    )))
-    .alignment(Alignment::Left)
-    .wrap(Wrap { trim: true })
-    .block(
-        Block::default()
-            .borders(Borders::ALL)
-            .style(Style::default().fg(get_color(app, config, &EditSelection::Command)))
-            .title(hoarded_command_title)
-            .border_type(BorderType::Plain),
+    let privilege_note = privilege_warning(&selected_command.command)
+        .map(|warning| format!(" --- {} {warning}", warning.badge()))
+        .unwrap_or_default();
+    let installed_binaries = &mut app.installed_binaries;
+    let missing = selected_command.missing_binaries(
+        config.parameter_token.as_deref().unwrap_or("#"),
+        |binary| {
+            *installed_binaries
+                .entry(binary.to_string())
+                .or_insert_with(|| is_installed(binary))
+        },
     );
-
-    let tags     = Paragraph::new(coerce_string_by_mode(
-        selected_command.get_tags_as_string(),
-        app,
-        &EditSelection::Tags,
-    ))
-    .style(Style::default().fg(Color::Rgb(
-        config.primary_color.unwrap().0,
-        config.primary_color.unwrap().1,
-        config.primary_color.unwrap().2,
-    )))
-    .alignment(Alignment::Left)
-    .block(
-        Block::default()
-            .borders(Borders::ALL)
-            .style(Style::default().fg(get_color(app, config, &EditSelection::Tags)))
-            .title(" Tags ")
-            .border_type(BorderType::Plain),
+    let missing_note = if missing.is_empty() {
+        String::new()
+    } else {
+        format!(" --- [!] {} not found", missing.join(", "))
+    };
+    let hoarded_command_title = format!(
+        " Hoarded command ({}) --- Times selected: {}{privilege_note}{missing_note} ",
+        selected_command.effective_shell(),
+        selected_command.usage_count
     );
+    let (command_match, description_match) = current_match(app);
+    let command_text =
+        if app.control == ControlState::PreviewSearch && !app.preview_query.is_empty() {
+            Text::from(highlight_matches(
+                &selected_command.command,
+                &app.preview_query,
+                command_match.unwrap_or(usize::MAX),
+                search_highlight(config),
+            ))
+        } else if config.syntax_highlighting == Some(false) {
+            Text::from(selected_command.command.clone())
+        } else {
+            highlighted_command(&selected_command.command, app, config)
+        };
+    let command = Paragraph::new(command_text)
+        .style(Style::default().fg(Color::Rgb(
+            config.primary_color.unwrap().0,
+            config.primary_color.unwrap().1,
+            config.primary_color.unwrap().2,
+        )))
+        .alignment(Alignment::Left)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::Rgb(
+                    config.primary_color.unwrap().0,
+                    config.primary_color.unwrap().1,
+                    config.primary_color.unwrap().2,
+                )))
+                .title(hoarded_command_title)
+                .border_type(BorderType::Plain),
+        );
 
-    let description = Paragraph::new(coerce_string_by_mode(
-        selected_command.description,
-        app,
-        &EditSelection::Description,
-    ))
-    .style(Style::default().fg(Color::Rgb(
-        config.primary_color.unwrap().0,
-        config.primary_color.unwrap().1,
-        config.primary_color.unwrap().2,
-    )))
-    .alignment(Alignment::Left)
-    .wrap(Wrap { trim: true })
-    .block(
-        Block::default()
-            .borders(Borders::ALL)
-            .style(Style::default().fg(get_color(app, config, &EditSelection::Description)))
-            .title(" Description ")
-            .border_type(BorderType::Plain),
-    );
+    // Wrapped text can't be scrolled sideways, so the command is cut off at the pane while the preview is searched
+    let command = if app.control == ControlState::PreviewSearch {
+        let scroll = command_match
+            .and_then(|current| {
+                preview_matches(&selected_command.command, &app.preview_query)
+                    .get(current)
+                    .copied()
+            })
+            .map_or((0, app.preview_offset.1), |position| {
+                match_scroll(&selected_command.command, position)
+            });
+        command.scroll(scroll)
+    } else {
+        command.wrap(Wrap { trim: true })
+    };
+
+    let tags = Paragraph::new(Line::from(tag_chips(&selected_command.tags, config)))
+        .style(Style::default().fg(Color::Rgb(
+            config.primary_color.unwrap().0,
+            config.primary_color.unwrap().1,
+            config.primary_color.unwrap().2,
+        )))
+        .alignment(Alignment::Left)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::Rgb(
+                    config.primary_color.unwrap().0,
+                    config.primary_color.unwrap().1,
+                    config.primary_color.unwrap().2,
+                )))
+                .title(" Tags ")
+                .border_type(BorderType::Plain),
+        );
+
+    let description_text = if app.control == ControlState::PreviewSearch {
+        Text::from(highlight_matches(
+            &selected_command.description,
+            &app.preview_query,
+            description_match.unwrap_or(usize::MAX),
+            search_highlight(config),
+        ))
+    } else {
+        Text::from(selected_command.description)
+    };
+    let description_title = if app.control == ControlState::PreviewSearch {
+        let (in_command, in_description) = match_counts(app);
+        let match_count = in_command + in_description;
+        let current_match = if match_count == 0 {
+            0
+        } else {
+            app.preview_match + 1
+        };
+        format!(
+            " Description --- /{} [{current_match}/{match_count}] ",
+            app.preview_query
+        )
+    } else {
+        String::from(" Description ")
+    };
+    let description = Paragraph::new(description_text)
+        .style(Style::default().fg(Color::Rgb(
+            config.primary_color.unwrap().0,
+            config.primary_color.unwrap().1,
+            config.primary_color.unwrap().2,
+        )))
+        .alignment(Alignment::Left)
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::Rgb(
+                    config.primary_color.unwrap().0,
+                    config.primary_color.unwrap().1,
+                    config.primary_color.unwrap().2,
+                )))
+                .title(description_title)
+                .border_type(BorderType::Plain),
+        );
 
-    let mut query_string = config.query_prefix.clone();
-    query_string.push_str(&app.input.clone()[..]);
-    let query_title = format!(" hoard v{VERSION} ");
+    let query_string = if app.control == ControlState::Move {
+        format!("Move to: {}", app.string_to_edit)
+    } else if let ControlState::Batch(batch) = app.control {
+        let label = match batch {
+            BatchInput::Retag => "Tags",
+            BatchInput::Move => "Move to namespace",
+            BatchInput::Export => "Export to",
+        };
+        format!("{label}: {}", app.string_to_edit)
+    } else {
+        let mut query_string = config.query_prefix.clone();
+        query_string.push_str(&app.input.clone()[..]);
+        query_string
+    };
+    let query_title = if app.trove_name == DEFAULT_TROVE {
+        format!(" hoard v{VERSION} ")
+    } else {
+        format!(" hoard v{VERSION} --- trove: {} ", app.trove_name)
+    };
     let input = Paragraph::new(query_string).block(
         Block::default()
             .style(Style::default().fg(Color::Rgb(
@@ -378,9 +700,159 @@ fn render_commands<'a>(
     (list, command, tags, description, input)
 }
 
+/// `command` with its programs, flags, strings, operators and parameters in colors of their own
+fn highlighted_command<'a>(command: &str, app: &State, config: &HoardConfig) -> Text<'a> {
+    let rgb = |color: Option<(u8, u8, u8)>| {
+        let (r, g, b) = color.unwrap_or_default();
+        Color::Rgb(r, g, b)
+    };
+    let mut lines = vec![Line::default()];
+    for (kind, piece) in shell_tokens(
+        command,
+        &app.parameter_token,
+        &app.parameter_ending_token,
+        app.parameter_escape_char,
+    ) {
+        let style = match kind {
+            ShellToken::Program => Style::default()
+                .fg(rgb(config.command_color))
+                .add_modifier(Modifier::BOLD),
+            ShellToken::Subcommand => Style::default().fg(rgb(config.command_color)),
+            ShellToken::Flag => Style::default().fg(HIGHLIGHT_FLAG),
+            ShellToken::String => Style::default().fg(HIGHLIGHT_STRING),
+            ShellToken::Operator => Style::default()
+                .fg(HIGHLIGHT_OPERATOR)
+                .add_modifier(Modifier::BOLD),
+            ShellToken::Variable => Style::default().fg(HIGHLIGHT_VARIABLE),
+            ShellToken::Parameter => Style::default()
+                .fg(HIGHLIGHT_PARAMETER)
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            ShellToken::Text => Style::default().fg(rgb(config.primary_color)),
+        };
+        for (i, part) in piece.split('\n').enumerate() {
+            if i > 0 {
+                lines.push(Line::default());
+            }
+            if !part.is_empty() {
+                if let Some(line) = lines.last_mut() {
+                    line.spans.push(Span::styled(part.to_string(), style));
+                }
+            }
+        }
+    }
+    Text::from(lines)
+}
+
+/// Split `text` into lines with every match of `query` highlighted, the current match additionally in bold
+fn highlight_matches(
+    text: &str,
+    query: &str,
+    current: usize,
+    highlight: Style,
+) -> Vec<Line<'static>> {
+    let matches = preview_matches(text, query);
+    let mut lines = Vec::new();
+    let mut spans = Vec::new();
+    let mut position = 0;
+    let push_text =
+        |spans: &mut Vec<Span<'static>>, lines: &mut Vec<Line<'static>>, s: &str, style: Style| {
+            for (i, part) in s.split('\n').enumerate() {
+                if i > 0 {
+                    lines.push(Line::from(std::mem::take(spans)));
+                }
+                if !part.is_empty() {
+                    spans.push(Span::styled(part.to_string(), style));
+                }
+            }
+        };
+    for (i, start) in matches.into_iter().enumerate() {
+        let style = if i == current {
+            highlight.add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+        } else {
+            highlight
+        };
+        push_text(
+            &mut spans,
+            &mut lines,
+            &text[position..start],
+            Style::default(),
+        );
+        push_text(
+            &mut spans,
+            &mut lines,
+            &text[start..start + query.len()],
+            style,
+        );
+        position = start + query.len();
+    }
+    push_text(&mut spans, &mut lines, &text[position..], Style::default());
+    lines.push(Line::from(spans));
+    lines
+}
+
+/// Style of the matches of the preview search
+fn search_highlight(config: &HoardConfig) -> Style {
+    let rgb = |color: Option<(u8, u8, u8)>| {
+        let (r, g, b) = color.unwrap_or_default();
+        Color::Rgb(r, g, b)
+    };
+    Style::default()
+        .bg(rgb(config.secondary_color))
+        .fg(rgb(config.tertiary_color))
+}
+
+/// Columns a match is shown after when the command is scrolled to it
+const MATCH_MARGIN: usize = 10;
+
+/// Rows and columns to scroll the unwrapped `command` so the match at byte `position` is visible
+fn match_scroll(command: &str, position: usize) -> (u16, u16) {
+    let before = &command[..position];
+    let row = before.matches('\n').count();
+    let column = before
+        .rsplit('\n')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .count();
+    (
+        u16::try_from(row).unwrap_or(u16::MAX),
+        u16::try_from(column.saturating_sub(MATCH_MARGIN)).unwrap_or(u16::MAX),
+    )
+}
+
+/// Number of wrapped lines to scroll a pane of `width` so the current match of the preview search is visible
+fn preview_scroll(app: &State, width: u16) -> u16 {
+    let text = preview_text(app);
+    let Some(current) = current_match(app).1 else {
+        return 0;
+    };
+    let Some(&position) = preview_matches(&text, &app.preview_query).get(current) else {
+        return 0;
+    };
+    let width = usize::from(width.max(1));
+    let lines_before: usize = text[..position]
+        .split('\n')
+        .rev()
+        .skip(1)
+        .map(|line| line.chars().count().max(1).div_ceil(width))
+        .sum();
+    u16::try_from(lines_before).unwrap_or(u16::MAX)
+}
+
+/// A rect of `rows` in the middle of `r`, `percent_x` of its width
+fn centered_rows(percent_x: u16, rows: u16, r: Rect) -> Rect {
+    let rows = rows.min(r.height);
+    let area = centered_rect(percent_x, 100, r);
+    Rect::new(area.x, r.y + (r.height - rows) / 2, area.width, rows)
+}
+
 const fn get_footer_constraints(control: &ControlState) -> (u16, u16) {
     match control {
         ControlState::Search | ControlState::Gpt | ControlState::KeyNotSet => (50, 50),
-        ControlState::Edit => (99, 1),
+        ControlState::Edit
+        | ControlState::PreviewSearch
+        | ControlState::Move
+        | ControlState::Batch(_)
+        | ControlState::Confirm => (99, 1),
     }
 }
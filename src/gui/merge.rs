@@ -1,7 +1,19 @@
-use crate::gui::prompts::prompt_select_with_options;
+use crate::config::HoardConfig;
+use crate::core::trove::Trove;
+use crate::core::HoardCmd;
+use crate::gui::backend::{read_key, Screen};
+use crate::gui::event::Key;
+use crate::gui::palette::Palette;
+use crate::gui::prompts::{prompt_select_with_options, prompts_scripted};
+use crate::gui::terminal::can_show_gui;
+use chrono::{DateTime, Local};
 use enum_iterator::{all, Sequence};
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
 
-#[derive(Sequence)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Sequence)]
 pub enum ConflictResolve {
     /// Describes the mode of how to handle a merge conflict by adding in a command to a trove with a name that is already present
 
@@ -11,14 +23,27 @@ pub enum ConflictResolve {
     Keep,
     /// Find a new name for the new command and keep the old one
     New,
+    /// Keep both, the new command gets the name with a number appended
+    KeepBoth,
 }
 
 impl ConflictResolve {
-    const fn as_str(&self) -> &'static str {
+    const fn as_str(self) -> &'static str {
         match self {
             Self::Replace => "Replace your local command with the new one",
             Self::Keep => "Keep your local command and ignore the new one",
             Self::New => "Keep both, but choose a new name",
+            Self::KeepBoth => "Keep both, the new one gets a number appended to its name",
+        }
+    }
+
+    /// Key choosing the resolution in the diff view
+    const fn key(self) -> char {
+        match self {
+            Self::Replace => 'r',
+            Self::Keep => 'l',
+            Self::New => 'n',
+            Self::KeepBoth => 'b',
         }
     }
 }
@@ -33,7 +58,187 @@ pub fn with_conflict_resolve_prompt(
         "You already have a command with the name: {name} in namespace: {namespace}\nYour local command: {colliding_command_string}\nIncoming command: {command_string}\nWhat do you want to do?"
     );
     let conflict_modes = all::<ConflictResolve>().collect::<Vec<_>>();
-    let items: Vec<&str> = conflict_modes.iter().map(ConflictResolve::as_str).collect();
+    let items: Vec<&str> = conflict_modes.iter().map(|mode| mode.as_str()).collect();
     let selection = prompt_select_with_options(&conflict_prompt, &items);
     conflict_modes.into_iter().nth(selection).unwrap()
 }
+
+/// Ask what to do about `incoming`, which has the name of the different `local` command in `trove`
+///
+/// Shows both side by side if there is a terminal for it, asks with a prompt otherwise.
+/// Returns the commands to keep in place of `local`
+pub fn resolve_conflict(
+    local: &HoardCmd,
+    incoming: &HoardCmd,
+    trove: &Trove,
+    config: &HoardConfig,
+) -> Vec<HoardCmd> {
+    let diff_view = || {
+        show_diff(local, incoming, config).unwrap_or_else(|e| {
+            eprintln!("Could not show the conflict: {e}");
+            std::process::exit(1);
+        })
+    };
+    let mode = if can_show_gui() && !prompts_scripted() {
+        diff_view()
+    } else {
+        with_conflict_resolve_prompt(
+            &incoming.name,
+            &incoming.namespace,
+            &incoming.command,
+            &local.command,
+        )
+    };
+    match mode {
+        ConflictResolve::Replace => vec![incoming.clone()],
+        ConflictResolve::Keep => vec![local.clone()],
+        ConflictResolve::New => vec![
+            local.clone(),
+            incoming.clone().with_name_input_prompt(
+                None,
+                trove,
+                &format!(
+                    "Enter a new name for the incoming command: '{}'\nOld name: {} in namespace: {}\nEnter new name: ",
+                    incoming.command, incoming.name, incoming.namespace
+                ),
+            ),
+        ],
+        ConflictResolve::KeepBoth => {
+            let name = trove.free_name(&incoming.name, &incoming.namespace);
+            vec![local.clone(), incoming.clone().with_name(&name)]
+        }
+    }
+}
+
+/// Full screen view of both commands with the fields that differ highlighted, until a resolution is picked
+fn show_diff(
+    local: &HoardCmd,
+    incoming: &HoardCmd,
+    config: &HoardConfig,
+) -> Result<ConflictResolve, eyre::Error> {
+    let modes = all::<ConflictResolve>().collect::<Vec<_>>();
+    let mut selection = ListState::default();
+    selection.select(Some(0));
+    let mut screen = Screen::enter(false)?;
+
+    loop {
+        screen.terminal.draw(|rect| {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Length(3),
+                        Constraint::Min(8),
+                        Constraint::Length(6),
+                    ]
+                    .as_ref(),
+                )
+                .split(rect.size());
+            let commands_chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+                .split(chunks[1]);
+
+            let title = Paragraph::new(format!(
+                "You already have a command named {} in the {} namespace",
+                local.name, local.namespace
+            ))
+            .style(color_style(config.primary_color))
+            .block(Block::default().borders(Borders::ALL).title(" Conflict "));
+            rect.render_widget(title, chunks[0]);
+            rect.render_widget(
+                command_fields(" Your local command ", local, incoming, config),
+                commands_chunks[0],
+            );
+            rect.render_widget(
+                command_fields(" Incoming command ", incoming, local, config),
+                commands_chunks[1],
+            );
+
+            let options: Vec<ListItem> = modes
+                .iter()
+                .map(|mode| ListItem::new(format!("<{}> {}", mode.key(), mode.as_str())))
+                .collect();
+            let options = List::new(options)
+                .style(color_style(config.primary_color))
+                .highlight_style(color_style(config.secondary_color).add_modifier(Modifier::BOLD))
+                .highlight_symbol("> ")
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(" What do you want to do? "),
+                );
+            rect.render_stateful_widget(options, chunks[2], &mut selection);
+            rect.render_widget(Palette::from_config(config), rect.size());
+        })?;
+
+        let selected = selection.selected().unwrap_or_default();
+        match read_key()? {
+            Some(Key::Char(key)) if modes.iter().any(|mode| mode.key() == key) => {
+                return Ok(modes.into_iter().find(|mode| mode.key() == key).unwrap());
+            }
+            Some(Key::Char('\n')) => return Ok(modes[selected]),
+            Some(Key::Down | Key::Ctrl('n')) => {
+                selection.select(Some((selected + 1) % modes.len()));
+            }
+            Some(Key::Up | Key::Ctrl('p')) => {
+                selection.select(Some((selected + modes.len() - 1) % modes.len()));
+            }
+            // Leaving the view changes nothing
+            None | Some(Key::Esc | Key::Char('q') | Key::Ctrl('c' | 'd')) => {
+                return Ok(ConflictResolve::Keep);
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+/// Fields of `command`, the ones that differ from `other` are highlighted
+fn command_fields<'a>(
+    title: &'a str,
+    command: &HoardCmd,
+    other: &HoardCmd,
+    config: &HoardConfig,
+) -> Paragraph<'a> {
+    let modified = |c: &HoardCmd| {
+        DateTime::<Local>::from(c.modified)
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    };
+    let fields = [
+        ("Command", command.command.clone(), other.command.clone()),
+        (
+            "Description",
+            command.description.clone(),
+            other.description.clone(),
+        ),
+        (
+            "Tags",
+            command.get_tags_as_string(),
+            other.get_tags_as_string(),
+        ),
+        ("Modified", modified(command), modified(other)),
+    ];
+    let mut lines = Vec::new();
+    for (label, value, other_value) in fields {
+        let style = if value == other_value {
+            color_style(config.command_color)
+        } else {
+            color_style(config.secondary_color).add_modifier(Modifier::BOLD)
+        };
+        lines.push(Line::from(Span::styled(
+            format!("{label}:"),
+            color_style(config.primary_color),
+        )));
+        lines.push(Line::from(Span::styled(value, style)));
+        lines.push(Line::from(""));
+    }
+    Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title(title))
+}
+
+fn color_style(color: Option<(u8, u8, u8)>) -> Style {
+    let (r, g, b) = color.unwrap_or((255, 255, 255));
+    Style::default().fg(Color::Rgb(r, g, b))
+}
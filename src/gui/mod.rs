@@ -1,12 +1,16 @@
+pub mod backend;
 #[allow(clippy::module_name_repetitions)]
 pub mod commands_gui;
 pub mod event;
 #[allow(clippy::module_name_repetitions)]
 mod help;
 mod inline_edit;
+pub mod keymap;
 mod list_search;
 pub mod merge;
-mod new_command;
+pub mod output_pane;
+pub mod palette;
 mod parameter_input;
 pub mod prompts;
+pub mod terminal;
 pub mod theme;
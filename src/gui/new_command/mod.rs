@@ -1,2 +0,0 @@
-pub mod controls;
-pub mod render;
@@ -0,0 +1,311 @@
+use crate::config::HoardConfig;
+use crate::core::encoding::OutputEncoding;
+use crate::core::process::{OutputLine, RunOutcome, StreamedProcess};
+use crate::gui::backend::Screen;
+use crate::gui::event::{Config, Event, Events, Key};
+use crate::gui::list_search::preview_search_controls::preview_matches;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// How often the pane is redrawn with the latest output while the command runs
+const REFRESH_RATE: Duration = Duration::from_millis(100);
+
+/// Lines a page up or down moves
+const PAGE_LINES: usize = 10;
+
+/// The output a command wrote so far, how far it is scrolled and what is searched in it
+#[derive(Debug, Default)]
+pub struct OutputState {
+    pub lines: Vec<OutputLine>,
+    /// First line shown, `None` follows the latest output
+    pub scroll: Option<usize>,
+    pub query: String,
+    /// Whether `query` is being typed or `n`/`N` jump between its matches
+    pub typing: bool,
+    /// Index of the match of `query` that is jumped to
+    pub current: usize,
+}
+
+impl OutputState {
+    /// First line shown in a pane `height` lines high
+    pub fn first_line(&self, height: usize) -> usize {
+        let last_page = self.lines.len().saturating_sub(height);
+        self.scroll
+            .map_or(last_page, |scroll| scroll.min(last_page))
+    }
+
+    /// Scroll `lines` up, or down if negative, in a pane `height` lines high. Scrolling down to the end follows
+    /// the output again
+    pub fn scroll_by(&mut self, lines: isize, height: usize) {
+        let last_page = self.lines.len().saturating_sub(height);
+        let first_line = self.first_line(height).saturating_add_signed(-lines);
+        self.scroll = (first_line < last_page).then_some(first_line);
+    }
+
+    /// Line and byte position of every match of the query
+    pub fn matches(&self) -> Vec<(usize, usize)> {
+        self.lines
+            .iter()
+            .enumerate()
+            .flat_map(|(i, line)| {
+                preview_matches(&line.text, &self.query)
+                    .into_iter()
+                    .map(move |position| (i, position))
+            })
+            .collect()
+    }
+
+    /// Handle a key while the query is typed, `<Enter>` jumps to the first match
+    pub fn type_query(&mut self, key: Key, height: usize) {
+        match key {
+            Key::Char('\n') => {
+                self.typing = false;
+                self.jump(0, height);
+            }
+            Key::Esc => {
+                self.typing = false;
+                self.query.clear();
+            }
+            Key::Backspace => {
+                self.query.pop();
+            }
+            Key::Char(c) => self.query.push(c),
+            _ => {}
+        }
+        self.current = 0;
+    }
+
+    /// Move `step` matches on, wrapping around at the ends, and scroll the match into the middle of the pane
+    pub fn jump(&mut self, step: isize, height: usize) {
+        let matches = self.matches();
+        let Ok(count) = isize::try_from(matches.len()) else {
+            return;
+        };
+        if count == 0 {
+            return;
+        }
+        let current = isize::try_from(self.current).unwrap_or_default();
+        self.current = usize::try_from((current + step).rem_euclid(count)).unwrap_or_default();
+        let (line, _) = matches[self.current];
+        let first_line = line.saturating_sub(height / 2);
+        self.scroll = (first_line < self.lines.len().saturating_sub(height)).then_some(first_line);
+    }
+}
+
+/// Run `process` and show its output live in a pane until it is closed after the command finished
+///
+/// Ctrl+C cancels the command and everything it started, hoard keeps running. Returns how the command ended and
+/// everything it wrote, to print it once the terminal is back
+pub fn run(
+    name: &str,
+    process: Command,
+    timeout: Option<Duration>,
+    encoding: OutputEncoding,
+    config: &HoardConfig,
+) -> Result<(RunOutcome, Vec<OutputLine>), eyre::Error> {
+    let mut streamed = StreamedProcess::spawn(process, timeout, encoding)?;
+    let events = Events::with_config(Config {
+        tick_rate: REFRESH_RATE,
+    });
+    let mut screen = Screen::enter(false)?;
+    let mut state = OutputState::default();
+    let mut outcome: Option<RunOutcome> = None;
+    let started = Instant::now();
+    let mut height = 0;
+
+    loop {
+        state.lines.extend(streamed.lines());
+        if outcome.is_none() {
+            outcome = streamed.poll()?;
+            state.lines.extend(streamed.lines());
+        }
+        let search = if state.typing || !state.query.is_empty() {
+            let count = state.matches().len();
+            let current = if count == 0 { 0 } else { state.current + 1 };
+            format!(" /{} [{current}/{count}] ---", state.query)
+        } else {
+            String::new()
+        };
+        let status = outcome.as_ref().map_or_else(
+            || {
+                format!(
+                    "{search} Running for {}s, <Ctrl-C> cancels, </> searches ",
+                    started.elapsed().as_secs()
+                )
+            },
+            |outcome| format!("{search} {}, <q> closes, </> searches ", describe(outcome)),
+        );
+        screen.terminal.draw(|rect| {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(3), Constraint::Length(1)].as_ref())
+                .split(rect.size());
+            height = usize::from(chunks[0].height.saturating_sub(2));
+            rect.render_widget(output(name, &state, chunks[0], config), chunks[0]);
+            rect.render_widget(
+                Paragraph::new(status.as_str()).style(color_style(config.secondary_color)),
+                chunks[1],
+            );
+        })?;
+
+        let page = isize::try_from(PAGE_LINES).unwrap_or_default();
+        match events.next()? {
+            Event::Input(Key::Ctrl('c')) if outcome.is_none() => {
+                outcome = Some(streamed.cancel()?);
+            }
+            Event::Input(key) if state.typing => state.type_query(key, height),
+            Event::Input(Key::Char('/')) => {
+                state.typing = true;
+                state.query.clear();
+                state.current = 0;
+            }
+            Event::Input(Key::Char('n')) => state.jump(1, height),
+            Event::Input(Key::Char('N')) => state.jump(-1, height),
+            Event::Input(Key::Esc | Key::Char('q' | '\n') | Key::Ctrl('c' | 'd'))
+                if outcome.is_some() =>
+            {
+                break;
+            }
+            Event::Input(Key::Up | Key::Char('k')) => state.scroll_by(1, height),
+            Event::Input(Key::Down | Key::Char('j')) => state.scroll_by(-1, height),
+            Event::Input(Key::PageUp) => state.scroll_by(page, height),
+            Event::Input(Key::PageDown) => state.scroll_by(-page, height),
+            Event::Input(Key::Home) => state.scroll = Some(0),
+            Event::Input(Key::End) => state.scroll = None,
+            _ => {}
+        }
+    }
+    // The keys typed next belong to the terminal again
+    events.pause();
+    drop(screen);
+    let outcome = outcome.expect("The pane is only closed once the command finished");
+    Ok((outcome, state.lines))
+}
+
+/// How the command ended, for the status line
+fn describe(outcome: &RunOutcome) -> String {
+    match outcome {
+        RunOutcome::Exited(_) => format!("Exited with {}", outcome.exit_code()),
+        RunOutcome::TimedOut => String::from("Timed out"),
+        RunOutcome::Cancelled => String::from("Cancelled"),
+    }
+}
+
+/// The lines of the output that fit into `area`, the ones written to stderr in red and the matches of the search
+/// highlighted
+fn output<'a>(
+    name: &'a str,
+    state: &'a OutputState,
+    area: Rect,
+    config: &HoardConfig,
+) -> Paragraph<'a> {
+    let height = usize::from(area.height.saturating_sub(2));
+    let first_line = state.first_line(height);
+    let matches = state.matches();
+    let (r, g, b) = config.secondary_color.unwrap_or_default();
+    let highlight = color_style(config.tertiary_color).bg(Color::Rgb(r, g, b));
+    let lines: Vec<Line> = state
+        .lines
+        .iter()
+        .enumerate()
+        .skip(first_line)
+        .take(height)
+        .map(|(i, line)| {
+            let style = if line.is_error {
+                Style::default().fg(Color::Red)
+            } else {
+                color_style(config.command_color)
+            };
+            let mut spans = Vec::new();
+            let mut position = 0;
+            for (index, &(_, start)) in matches
+                .iter()
+                .enumerate()
+                .filter(|(_, (match_line, _))| *match_line == i)
+            {
+                let end = start + state.query.len();
+                let match_style = if index == state.current {
+                    highlight.add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+                } else {
+                    highlight
+                };
+                spans.push(Span::styled(&line.text[position..start], style));
+                spans.push(Span::styled(&line.text[start..end], match_style));
+                position = end;
+            }
+            spans.push(Span::styled(&line.text[position..], style));
+            Line::from(spans)
+        })
+        .collect();
+    Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(Span::styled(
+        format!(" {name} "),
+        color_style(config.primary_color).add_modifier(Modifier::BOLD),
+    )))
+}
+
+fn color_style(color: Option<(u8, u8, u8)>) -> Style {
+    let (r, g, b) = color.unwrap_or((255, 255, 255));
+    Style::default().fg(Color::Rgb(r, g, b))
+}
+
+#[cfg(test)]
+mod test_output_pane {
+    use super::*;
+
+    fn state(lines: usize) -> OutputState {
+        OutputState {
+            lines: (0..lines)
+                .map(|i| OutputLine {
+                    text: i.to_string(),
+                    is_error: false,
+                })
+                .collect(),
+            ..OutputState::default()
+        }
+    }
+
+    #[test]
+    fn follow_the_output_until_scrolled_up() {
+        let mut state = state(30);
+        assert_eq!(20, state.first_line(10));
+        state.scroll_by(5, 10);
+        assert_eq!(Some(15), state.scroll);
+        // New output doesn't move what is shown
+        state.lines.extend(self::state(5).lines);
+        assert_eq!(15, state.first_line(10));
+        state.scroll_by(-50, 10);
+        assert_eq!(None, state.scroll);
+        assert_eq!(25, state.first_line(10));
+        state.scroll_by(50, 10);
+        assert_eq!(0, state.first_line(10));
+    }
+
+    #[test]
+    fn search_the_output() {
+        let mut state = state(30);
+        state.typing = true;
+        for key in [
+            Key::Char('2'),
+            Key::Backspace,
+            Key::Char('1'),
+            Key::Char('\n'),
+        ] {
+            state.type_query(key, 10);
+        }
+        assert!(!state.typing);
+        assert_eq!("1", state.query);
+        // 1, 10 to 19 with 11 twice, and 21
+        assert_eq!(13, state.matches().len());
+        assert_eq!(Some(0), state.scroll);
+        state.jump(-1, 10);
+        assert_eq!((21, 1), state.matches()[state.current]);
+        // The match is scrolled into the middle of the pane
+        assert_eq!(Some(16), state.scroll);
+        state.jump(1, 10);
+        assert_eq!(0, state.current);
+    }
+}
@@ -0,0 +1,268 @@
+use crate::config::{ColorSupport, HoardConfig};
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier};
+use ratatui::widgets::Widget;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Default RGB values of the 16 ANSI colors in xterm, to find the one closest to a theme color
+const ANSI_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// Levels of each channel in the 6x6x6 color cube of 256 color terminals
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Index of the `colors` number in the numbers section of a compiled terminfo entry
+const TERMINFO_COLORS: usize = 13;
+
+/// Maps the colors drawn into the buffer onto the ones the terminal can show. Rendered last over the whole frame
+pub struct Palette(pub ColorSupport);
+
+impl Palette {
+    /// The palette set in the config, or detected for the terminal if there is none
+    pub fn from_config(config: &HoardConfig) -> Self {
+        static DETECTED: OnceLock<ColorSupport> = OnceLock::new();
+        Self(
+            config
+                .color_support
+                .unwrap_or_else(|| *DETECTED.get_or_init(detect_color_support)),
+        )
+    }
+}
+
+impl Widget for Palette {
+    fn render(self, _area: Rect, buf: &mut Buffer) {
+        if self.0 == ColorSupport::Truecolor {
+            return;
+        }
+        for cell in &mut buf.content {
+            if self.0 == ColorSupport::None {
+                // Without colors a background is shown by swapping it with the text, so the selection stays visible
+                if cell.bg != Color::Reset {
+                    cell.modifier.insert(Modifier::REVERSED);
+                }
+                cell.fg = Color::Reset;
+                cell.bg = Color::Reset;
+            } else {
+                cell.fg = map_color(cell.fg, self.0);
+                cell.bg = map_color(cell.bg, self.0);
+            }
+        }
+    }
+}
+
+/// The color closest to `color` that a terminal with `support` can show
+pub fn map_color(color: Color, support: ColorSupport) -> Color {
+    let Color::Rgb(r, g, b) = color else {
+        return match support {
+            ColorSupport::None => Color::Reset,
+            _ => color,
+        };
+    };
+    match support {
+        ColorSupport::Truecolor => color,
+        ColorSupport::Ansi256 => Color::Indexed(nearest_indexed((r, g, b))),
+        ColorSupport::Ansi16 => nearest_ansi((r, g, b)),
+        ColorSupport::None => Color::Reset,
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let channel = |a: u8, b: u8| u32::from(a.abs_diff(b)).pow(2);
+    channel(a.0, b.0) + channel(a.1, b.1) + channel(a.2, b.2)
+}
+
+fn nearest_ansi(rgb: (u8, u8, u8)) -> Color {
+    ANSI_COLORS
+        .iter()
+        .min_by_key(|(_, ansi)| distance(rgb, *ansi))
+        .map_or(Color::Reset, |(color, _)| *color)
+}
+
+/// Closest color of the 256 color palette, either from the color cube or the grayscale ramp
+pub fn nearest_indexed(rgb: (u8, u8, u8)) -> u8 {
+    let cube_index = |channel: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| CUBE_LEVELS[i].abs_diff(channel))
+            .unwrap_or(0)
+    };
+    let (r, g, b) = (cube_index(rgb.0), cube_index(rgb.1), cube_index(rgb.2));
+    let cube = (CUBE_LEVELS[r], CUBE_LEVELS[g], CUBE_LEVELS[b]);
+
+    // The ramp goes from 8 to 238 in steps of 10
+    let average = (u32::from(rgb.0) + u32::from(rgb.1) + u32::from(rgb.2)) / 3;
+    let gray_index = (average.saturating_sub(3) / 10).min(23);
+    let gray_level = u8::try_from(8 + gray_index * 10).unwrap_or(u8::MAX);
+    let gray = (gray_level, gray_level, gray_level);
+
+    if distance(rgb, gray) < distance(rgb, cube) {
+        232 + u8::try_from(gray_index).unwrap_or(0)
+    } else {
+        u8::try_from(16 + 36 * r + 6 * g + b).unwrap_or(u8::MAX)
+    }
+}
+
+/// What the terminal supports, from `$NO_COLOR`, `$COLORTERM` and the terminfo entry of `$TERM`
+pub fn detect_color_support() -> ColorSupport {
+    if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+        return ColorSupport::None;
+    }
+    if std::env::var("COLORTERM").is_ok_and(|value| value == "truecolor" || value == "24bit") {
+        return ColorSupport::Truecolor;
+    }
+    let term = std::env::var("TERM").unwrap_or_default();
+    if term.is_empty() || term == "dumb" {
+        return ColorSupport::None;
+    }
+    match terminfo_colors(&term) {
+        Some(colors) => color_support_for(colors),
+        // Without a terminfo entry guess from the name, most terminals today handle 256 colors
+        None if term.contains("256color") || term.contains("direct") => ColorSupport::Ansi256,
+        None if term.contains("mono") => ColorSupport::None,
+        None => ColorSupport::Ansi16,
+    }
+}
+
+const fn color_support_for(colors: i32) -> ColorSupport {
+    match colors {
+        c if c >= 1 << 24 => ColorSupport::Truecolor,
+        c if c >= 256 => ColorSupport::Ansi256,
+        c if c >= 8 => ColorSupport::Ansi16,
+        _ => ColorSupport::None,
+    }
+}
+
+/// Number of colors in the terminfo entry of `term`, `None` if there is no entry
+fn terminfo_colors(term: &str) -> Option<i32> {
+    let first = term.chars().next()?;
+    let terminfo_dirs = std::env::var("TERMINFO_DIRS").unwrap_or_default();
+    let home = std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".terminfo"));
+    let dirs = std::env::var_os("TERMINFO")
+        .map(PathBuf::from)
+        .into_iter()
+        .chain(home)
+        .chain(
+            terminfo_dirs
+                .split(':')
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from),
+        )
+        .chain(
+            ["/etc/terminfo", "/lib/terminfo", "/usr/share/terminfo"]
+                .iter()
+                .map(PathBuf::from),
+        );
+    for dir in dirs {
+        // Entries are grouped by their first letter, or its hex code on macOS
+        for group in [first.to_string(), format!("{:x}", u32::from(first))] {
+            if let Ok(entry) = std::fs::read(dir.join(group).join(term)) {
+                return Some(parse_terminfo_colors(&entry).unwrap_or(-1));
+            }
+        }
+    }
+    None
+}
+
+/// Read the `colors` number of a compiled terminfo entry, see term(5)
+fn parse_terminfo_colors(entry: &[u8]) -> Option<i32> {
+    let short = |offset: usize| -> Option<usize> {
+        let bytes = entry.get(offset..offset + 2)?;
+        Some(usize::from(u16::from_le_bytes([bytes[0], bytes[1]])))
+    };
+    let number_size = match short(0)? {
+        0o432 => 2,
+        // Extended number format of ncurses 6.1
+        0o1036 => 4,
+        _ => return None,
+    };
+    let names_size = short(2)?;
+    let bools_count = short(4)?;
+    let numbers_count = short(6)?;
+    if TERMINFO_COLORS >= numbers_count {
+        return None;
+    }
+    let mut numbers_start = 12 + names_size + bools_count;
+    // The numbers start at an even offset
+    numbers_start += numbers_start % 2;
+    let offset = numbers_start + TERMINFO_COLORS * number_size;
+    let bytes = entry.get(offset..offset + number_size)?;
+    Some(if number_size == 2 {
+        i32::from(i16::from_le_bytes([bytes[0], bytes[1]]))
+    } else {
+        i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    })
+}
+
+#[cfg(test)]
+mod test_palette {
+    use super::*;
+
+    #[test]
+    fn theme_colors_map_onto_the_palette() {
+        let orange = Color::Rgb(242, 142, 43);
+        assert_eq!(orange, map_color(orange, ColorSupport::Truecolor));
+        assert_eq!(
+            Color::Indexed(208),
+            map_color(orange, ColorSupport::Ansi256)
+        );
+        assert_eq!(
+            Color::Indexed(244),
+            map_color(Color::Rgb(128, 128, 130), ColorSupport::Ansi256)
+        );
+        assert_eq!(Color::Yellow, map_color(orange, ColorSupport::Ansi16));
+        assert_eq!(
+            Color::LightBlue,
+            map_color(Color::Rgb(100, 110, 250), ColorSupport::Ansi16)
+        );
+        assert_eq!(Color::Reset, map_color(Color::Red, ColorSupport::None));
+        assert_eq!(Color::Red, map_color(Color::Red, ColorSupport::Ansi16));
+    }
+
+    #[test]
+    fn backgrounds_without_colors_are_reversed() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 2, 1));
+        buf.get_mut(0, 0).set_bg(Color::Rgb(1, 2, 3));
+        buf.get_mut(1, 0).set_fg(Color::Rgb(1, 2, 3));
+        Palette(ColorSupport::None).render(buf.area, &mut buf);
+        assert!(buf.get(0, 0).modifier.contains(Modifier::REVERSED));
+        assert_eq!(Color::Reset, buf.get(0, 0).bg);
+        assert!(!buf.get(1, 0).modifier.contains(Modifier::REVERSED));
+        assert_eq!(Color::Reset, buf.get(1, 0).fg);
+    }
+
+    #[test]
+    fn read_colors_from_terminfo() {
+        // Header, the names "x\0", one boolean, a padding byte and 14 numbers
+        let mut entry = vec![];
+        for short in [0o432_u16, 2, 1, 14, 0, 0] {
+            entry.extend_from_slice(&u16::to_le_bytes(short));
+        }
+        entry.extend_from_slice(b"x\0\x01\0");
+        for number in 0..14_u16 {
+            let value: u16 = if number == 13 { 256 } else { 0xffff };
+            entry.extend_from_slice(&value.to_le_bytes());
+        }
+        assert_eq!(Some(256), parse_terminfo_colors(&entry));
+        assert_eq!(None, parse_terminfo_colors(&entry[..20]));
+        assert_eq!(ColorSupport::Ansi256, color_support_for(256));
+        assert_eq!(ColorSupport::Ansi16, color_support_for(8));
+        assert_eq!(ColorSupport::None, color_support_for(-1));
+    }
+}
@@ -1,60 +1,547 @@
-use crate::core::parameters::Parameterized;
+use crate::core::parameters::{
+    first_parameter_span, mark_secret, mask_secrets, protect_value, restore_values, Parameter,
+    ParameterType, Parameterized, SECRET_MASK,
+};
 use crate::core::HoardCmd;
 use crate::gui::commands_gui::State;
-use termion::event::Key;
+use crate::gui::event::Key;
+use crate::gui::help::HelpScreen;
+use crate::util::read_clipboard;
+use std::fs;
+use std::path::PathBuf;
 
 pub fn key_handler(input: Key, app: &mut State) -> Option<HoardCmd> {
     match input {
         // Quit command
-        Key::Esc | Key::Ctrl('c' | 'd' | 'g') => {
+        key if key == Key::Esc || app.keymap.quits(key) => {
             app.should_exit = true;
             None
         }
-        Key::Char('\n') => {
-            let command = app.selected_command.clone().unwrap();
-
-            let mut safe_parameter = app.input.clone();
-            safe_parameter = safe_parameter.replace(&app.parameter_token, "\u{E000}");
-            if !app.parameter_ending_token.is_empty() {
-                safe_parameter = safe_parameter.replace(&app.parameter_ending_token, "\u{E001}");
-            }
-
-            let replaced_command = command.replace_parameter(
-                &app.parameter_token,
-                &app.parameter_ending_token,
-                &safe_parameter,
-            );
-
-            app.input = String::new();
-
-            if replaced_command.get_parameter_count(&app.parameter_token) == 0 {
-                let mut final_command = replaced_command
-                    .cleanup_escapes(&app.parameter_token, &app.parameter_ending_token);
-
-                let mut restored_cmd = final_command.command.clone();
-                restored_cmd = restored_cmd.replace('\u{E000}', &app.parameter_token);
-                if !app.parameter_ending_token.is_empty() {
-                    restored_cmd = restored_cmd.replace('\u{E001}', &app.parameter_ending_token);
-                }
-                final_command.command = restored_cmd;
-
-                return Some(final_command);
+        Key::Char('?') if app.input.is_empty() => {
+            app.help = Some(HelpScreen::ParameterInput);
+            None
+        }
+        key if app.keymap.helps(key) => {
+            app.help = Some(HelpScreen::ParameterInput);
+            None
+        }
+        Key::Up | Key::Ctrl('p') => {
+            select_option(app, -1);
+            None
+        }
+        Key::Down | Key::Ctrl('n') => {
+            select_option(app, 1);
+            None
+        }
+        // Scroll a command that is too long for the screen, until the parameter is followed again
+        Key::Left => {
+            app.parameter_scroll = Some(command_scroll(app).saturating_sub(1));
+            None
+        }
+        Key::Right => {
+            let width = app
+                .selected_command
+                .as_ref()
+                .map_or(0, |command| command.command.chars().count());
+            let scroll = command_scroll(app).saturating_add(1);
+            app.parameter_scroll = Some(scroll.min(u16::try_from(width).unwrap_or(u16::MAX)));
+            None
+        }
+        Key::Ctrl('f') => {
+            app.parameter_scroll = None;
+            None
+        }
+        // Complete file paths, always for file and directory parameters and on request for all others
+        Key::Ctrl('t') => {
+            complete_input_path(app);
+            None
+        }
+        Key::Char('\t') => {
+            let parameter = app
+                .selected_command
+                .as_ref()
+                .and_then(|command| {
+                    command.get_next_parameter(
+                        &app.parameter_token,
+                        &app.parameter_ending_token,
+                        app.parameter_escape_char,
+                    )
+                })
+                .unwrap_or_default();
+            if matches!(parameter.kind, ParameterType::File | ParameterType::Dir) {
+                complete_input_path(app);
+            } else if parameter.kind != ParameterType::Secret {
+                complete_input_value(app, &parameter);
             }
-
-            app.selected_command = Some(replaced_command);
-            app.provided_parameter_count += 1;
             None
         }
+        Key::Ctrl('v') => {
+            paste_clipboard(app);
+            None
+        }
+        // Multi-line parameters take line breaks as input and are submitted with Alt+Enter
+        Key::Char('\n') if next_parameter_kind(app) == ParameterType::Multiline => {
+            app.input.push('\n');
+            app.parameter_option_list.select(None);
+            None
+        }
+        Key::Alt('\r' | '\n') | Key::Char('\n') => submit_input(app),
 
         // Handle query input
         Key::Backspace => {
             app.input.pop();
+            app.parameter_option_list.select(None);
+            app.path_candidates = Vec::new();
             None
         }
         Key::Char(c) => {
             app.input.push(c);
+            app.parameter_option_list.select(None);
+            app.path_candidates = Vec::new();
             None
         }
         _ => None,
     }
 }
+
+/// Columns of the command shown before the parameter that is asked for while it is followed
+const FOLLOW_MARGIN: usize = 10;
+
+/// Columns a command too long for the screen is scrolled, as scrolled with `<Left>` / `<Right>` or to the parameter
+/// that is asked for
+pub fn command_scroll(app: &State) -> u16 {
+    if let Some(scroll) = app.parameter_scroll {
+        return scroll;
+    }
+    let Some(command) = &app.selected_command else {
+        return 0;
+    };
+    let command = mask_secrets(&command.command);
+    let column = first_parameter_span(
+        &command,
+        &app.parameter_token,
+        &app.parameter_ending_token,
+        app.parameter_escape_char,
+    )
+    .map_or(0, |(position, _)| command[..position].chars().count());
+    u16::try_from(column.saturating_sub(FOLLOW_MARGIN)).unwrap_or(u16::MAX)
+}
+
+/// Append the clipboard to the input
+///
+/// Parameter tokens in the pasted text stay literal, the input is protected like typed input when it is submitted
+fn paste_clipboard(app: &mut State) {
+    let Some(text) = read_clipboard() else {
+        app.error_message =
+            String::from("Can't read the clipboard, install xclip, xsel or wl-clipboard");
+        return;
+    };
+    paste_handler(&text, app);
+}
+
+/// Append pasted text to the input
+pub fn paste_handler(text: &str, app: &mut State) {
+    let multiline = next_parameter_kind(app) == ParameterType::Multiline;
+    app.input.push_str(&pasted_text(text, multiline));
+    app.parameter_option_list.select(None);
+    app.path_candidates = Vec::new();
+}
+
+/// Clipboard text as input of a parameter
+///
+/// Line breaks are only kept for multi-line parameters, the lines are joined with spaces for all others
+pub fn pasted_text(text: &str, multiline: bool) -> String {
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+    let text = text.trim_end_matches('\n');
+    if multiline {
+        return text.to_string();
+    }
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Type of the parameter the input is currently given for
+fn next_parameter_kind(app: &State) -> ParameterType {
+    app.selected_command
+        .as_ref()
+        .and_then(|command| {
+            command.get_next_parameter(
+                &app.parameter_token,
+                &app.parameter_ending_token,
+                app.parameter_escape_char,
+            )
+        })
+        .unwrap_or_default()
+        .kind
+}
+
+/// Put the input into the command as the value of its next parameter
+///
+/// Returns the command once all of its parameters are filled
+fn submit_input(app: &mut State) -> Option<HoardCmd> {
+    app.path_candidates = Vec::new();
+    let command = app.selected_command.clone().unwrap();
+    if let Some(option) = app
+        .parameter_option_list
+        .selected()
+        .and_then(|selected| filtered_options(app).get(selected).cloned())
+    {
+        app.input = option;
+    }
+
+    // Keep the parameter screen open until the input matches the type of the parameter
+    let parameter = command
+        .get_next_parameter(
+            &app.parameter_token,
+            &app.parameter_ending_token,
+            app.parameter_escape_char,
+        )
+        .unwrap_or_default();
+    if let Err(e) = parameter.validate(&app.input) {
+        app.error_message = e.to_string();
+        return None;
+    }
+    app.error_message = String::new();
+
+    let mut safe_parameter = protect_value(
+        &app.input,
+        &app.parameter_token,
+        &app.parameter_ending_token,
+        app.parameter_escape_char,
+    );
+    if parameter.kind == ParameterType::Secret {
+        safe_parameter = mark_secret(&safe_parameter);
+    } else {
+        app.used_values.record(
+            &command.name,
+            &parameter_key(&parameter, app.provided_parameter_count),
+            &app.input,
+        );
+    }
+
+    let replaced_command = fill_parameter(
+        &command,
+        &parameter,
+        &safe_parameter,
+        &app.parameter_token,
+        &app.parameter_ending_token,
+        app.parameter_escape_char,
+    );
+
+    app.input = String::new();
+
+    if replaced_command.get_parameter_count(&app.parameter_token, app.parameter_escape_char) == 0 {
+        let mut final_command = replaced_command.cleanup_escapes(
+            &app.parameter_token,
+            &app.parameter_ending_token,
+            app.parameter_escape_char,
+        );
+
+        final_command.command = restore_values(
+            &final_command.command,
+            &app.parameter_token,
+            &app.parameter_ending_token,
+            app.parameter_escape_char,
+        );
+
+        return Some(final_command);
+    }
+
+    app.selected_command = Some(replaced_command);
+    app.provided_parameter_count += 1;
+    app.parameter_scroll = None;
+    load_parameter_options(app);
+    None
+}
+
+/// Put `value` in place of `parameter` in `command`
+///
+/// Named parameters are filled everywhere at once, unnamed ones one at a time
+fn fill_parameter(
+    command: &HoardCmd,
+    parameter: &Parameter,
+    value: &str,
+    token: &str,
+    ending_token: &str,
+    escape: char,
+) -> HoardCmd {
+    if parameter.name.is_empty() {
+        command.replace_parameter(token, ending_token, escape, value)
+    } else {
+        command.replace_named_parameter(token, ending_token, escape, &parameter.name, value)
+    }
+}
+
+/// The command as it would look with `input` as the value of its next parameter
+///
+/// The values of secret parameters, the ones given before and the one being typed, are masked
+pub fn preview_command(
+    command: &HoardCmd,
+    input: &str,
+    token: &str,
+    ending_token: &str,
+    escape: char,
+) -> String {
+    let parameter = command
+        .get_next_parameter(token, ending_token, escape)
+        .unwrap_or_default();
+    let preview = if input.is_empty() {
+        command.clone()
+    } else if parameter.kind == ParameterType::Secret {
+        fill_parameter(
+            command,
+            &parameter,
+            SECRET_MASK,
+            token,
+            ending_token,
+            escape,
+        )
+    } else {
+        let value = protect_value(input, token, ending_token, escape);
+        fill_parameter(command, &parameter, &value, token, ending_token, escape)
+    };
+    restore_values(&mask_secrets(&preview.command), token, ending_token, escape)
+}
+
+/// Complete the input as far as it is unambiguous and list the candidates if there are several
+fn complete_input_path(app: &mut State) {
+    let (completed, candidates) = complete_path(&app.input);
+    app.input = completed;
+    app.path_candidates = if candidates.len() > 1 {
+        candidates
+    } else {
+        Vec::new()
+    };
+}
+
+/// Complete the input to the values the parameter was given before, as far as they agree
+fn complete_input_value(app: &mut State, parameter: &Parameter) {
+    let Some(command) = &app.selected_command else {
+        return;
+    };
+    let key = parameter_key(parameter, app.provided_parameter_count);
+    let (completed, candidates) =
+        complete_value(&app.input, app.used_values.values(&command.name, &key));
+    app.input = completed;
+    app.path_candidates = if candidates.len() > 1 {
+        candidates
+    } else {
+        Vec::new()
+    };
+}
+
+/// Name the values of a parameter are remembered by, its position for unnamed parameters
+fn parameter_key(parameter: &Parameter, position: u16) -> String {
+    if parameter.name.is_empty() {
+        position.to_string()
+    } else {
+        parameter.name.clone()
+    }
+}
+
+/// Complete `input` against values used before
+///
+/// Returns the input extended by the longest prefix all values starting with it share, and those values
+pub fn complete_value(input: &str, used_values: &[String]) -> (String, Vec<String>) {
+    let candidates: Vec<String> = used_values
+        .iter()
+        .filter(|value| value.starts_with(input))
+        .cloned()
+        .collect();
+    let completed = common_prefix(&candidates).unwrap_or(input).to_string();
+    (completed, candidates)
+}
+
+/// Longest prefix all `candidates` share, `None` if there are none
+fn common_prefix(candidates: &[String]) -> Option<&str> {
+    let first = candidates.first()?;
+    let common_len = candidates
+        .iter()
+        .skip(1)
+        .fold(first.len(), |len, candidate| {
+            first
+                .char_indices()
+                .zip(candidate.chars())
+                .take_while(|((i, a), b)| *i < len && a == b)
+                .last()
+                .map_or(0, |((i, a), _)| i + a.len_utf8())
+        });
+    Some(&first[..common_len])
+}
+
+/// Complete the file path `input` against the file system
+///
+/// Returns the input extended by the longest prefix all matching entries share, and the names of those entries.
+/// Directories get a trailing `/`, so a unique directory can be completed further right away
+pub fn complete_path(input: &str) -> (String, Vec<String>) {
+    let (dir, prefix) = input
+        .rfind('/')
+        .map_or(("", input), |i| (&input[..=i], &input[i + 1..]));
+    let dir_to_read = match dir.strip_prefix('~') {
+        Some(rest) => dirs::home_dir().map_or_else(
+            || PathBuf::from(dir),
+            |home| home.join(rest.trim_start_matches('/')),
+        ),
+        None if dir.is_empty() => PathBuf::from("."),
+        None => PathBuf::from(dir),
+    };
+    let Ok(entries) = fs::read_dir(dir_to_read) else {
+        return (input.to_string(), Vec::new());
+    };
+    let mut candidates: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            // Hidden files are only offered once the input asks for them
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            let is_dir = entry.path().is_dir();
+            Some(if is_dir { format!("{name}/") } else { name })
+        })
+        .collect();
+    candidates.sort();
+
+    let Some(common) = common_prefix(&candidates) else {
+        return (input.to_string(), candidates);
+    };
+    (format!("{dir}{common}"), candidates)
+}
+
+/// Ask the provider of the next parameter of the selected command for the values to offer
+pub fn load_parameter_options(app: &mut State) {
+    app.parameter_options = app
+        .selected_command
+        .as_ref()
+        .and_then(|command| {
+            command.get_next_parameter(
+                &app.parameter_token,
+                &app.parameter_ending_token,
+                app.parameter_escape_char,
+            )
+        })
+        .map(|parameter| parameter.provided_values(&app.parameter_providers))
+        .unwrap_or_default();
+    app.parameter_option_list.select(None);
+}
+
+/// Offered values that contain the current input
+pub fn filtered_options(app: &State) -> Vec<String> {
+    app.parameter_options
+        .iter()
+        .filter(|option| option.contains(&app.input))
+        .cloned()
+        .collect()
+}
+
+/// Move the selection through the offered values, starting at the top or bottom if nothing is selected
+fn select_option(app: &mut State, step: isize) {
+    let count = filtered_options(app).len();
+    if count == 0 {
+        return;
+    }
+    let selected = match app.parameter_option_list.selected() {
+        Some(selected) => selected.saturating_add_signed(step).min(count - 1),
+        None if step < 0 => count - 1,
+        None => 0,
+    };
+    app.parameter_option_list.select(Some(selected));
+}
+
+#[cfg(test)]
+mod test_parameter_input_controls {
+    use super::*;
+
+    #[test]
+    fn preview_with_current_input() {
+        let command = HoardCmd::default().with_command("psql -h #host! -U #user! -d #host!");
+        assert_eq!(
+            "psql -h db1 -U #user! -d db1",
+            preview_command(&command, "db1", "#", "!", '\\')
+        );
+        assert_eq!(
+            "psql -h #host! -U #user! -d #host!",
+            preview_command(&command, "", "#", "!", '\\')
+        );
+    }
+
+    #[test]
+    fn preview_masks_secrets() {
+        let command = HoardCmd::default().with_command("login #user! #password:secret! #user!");
+        let command = command.replace_named_parameter("#", "!", '\\', "user", &mark_secret("ab"));
+        assert_eq!(
+            "login ***** #password:secret! *****",
+            preview_command(&command, "", "#", "!", '\\')
+        );
+        let command = HoardCmd::default().with_command("login #password:secret|upper! #user!");
+        let command =
+            command.replace_named_parameter("#", "!", '\\', "password", &mark_secret("ab"));
+        assert_eq!(
+            "login ***** #user!",
+            preview_command(&command, "", "#", "!", '\\')
+        );
+        // Only the value of the secret is masked, not the same text typed for another parameter
+        let command = HoardCmd::default().with_command("login #password:secret! #user!");
+        assert_eq!(
+            "login ***** #user!",
+            preview_command(&command, "ab", "#", "!", '\\')
+        );
+        let command =
+            command.replace_named_parameter("#", "!", '\\', "password", &mark_secret("ab"));
+        assert_eq!(
+            "login ***** ab",
+            preview_command(&command, "ab", "#", "!", '\\')
+        );
+    }
+
+    #[test]
+    fn paste_joins_lines_of_single_line_parameters() {
+        assert_eq!("a b #c!", pasted_text("a\r\n  b\n#c!\n", false));
+        assert_eq!("a\n  b\n#c!", pasted_text("a\r\n  b\n#c!\n", true));
+        // Pasted tokens are no parameters of the command
+        let command = HoardCmd::default().with_command("echo #text! #other!");
+        assert_eq!(
+            "echo a b #c! #other!",
+            preview_command(&command, &pasted_text("a\nb #c!", false), "#", "!", '\\')
+        );
+    }
+
+    #[test]
+    fn complete_previous_values() {
+        let used_values: Vec<String> = ["prod-db-1", "prod-db-2", "dev-db"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let (completed, candidates) = complete_value("pr", &used_values);
+        assert_eq!("prod-db-", completed);
+        assert_eq!(vec!["prod-db-1", "prod-db-2"], candidates);
+        assert_eq!("dev-db", complete_value("d", &used_values).0);
+        assert_eq!("x", complete_value("x", &used_values).0);
+    }
+
+    #[test]
+    fn complete_unique_path() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("configs")).unwrap();
+        fs::write(dir.path().join("readme.md"), "").unwrap();
+        let input = format!("{}/con", dir.path().display());
+        let (completed, candidates) = complete_path(&input);
+        assert_eq!(format!("{}/configs/", dir.path().display()), completed);
+        assert_eq!(vec!["configs/"], candidates);
+    }
+
+    #[test]
+    fn complete_common_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("deploy_prod.yml"), "").unwrap();
+        fs::write(dir.path().join("deploy_dev.yml"), "").unwrap();
+        fs::write(dir.path().join(".deploy_secret"), "").unwrap();
+        let input = format!("{}/d", dir.path().display());
+        let (completed, candidates) = complete_path(&input);
+        assert_eq!(format!("{}/deploy_", dir.path().display()), completed);
+        assert_eq!(vec!["deploy_dev.yml", "deploy_prod.yml"], candidates);
+    }
+}
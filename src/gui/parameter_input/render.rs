@@ -1,24 +1,44 @@
 use crate::config::HoardConfig;
+use crate::core::parameters::{first_parameter_span, mask_secrets, ParameterType, Parameterized};
 use crate::gui::commands_gui::State;
-use crate::util::translate_number_to_nth;
-use ratatui::backend::TermionBackend;
+use crate::gui::help::draw_overlay as draw_help;
+use crate::gui::palette::Palette;
+use crate::gui::parameter_input::controls::{command_scroll, filtered_options, preview_command};
+use crate::util::ordinal;
+use ratatui::backend::Backend;
 use ratatui::layout::{Alignment, Constraint, Direction, Layout};
 use ratatui::style::{Color, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Paragraph, Wrap};
+use ratatui::widgets::{Block, List, ListItem, Paragraph, Wrap};
 use ratatui::Terminal;
-use termion::screen::AlternateScreen;
 
-pub fn draw(
+#[allow(clippy::too_many_lines)]
+pub fn draw<B: Backend>(
     app_state: &State,
     config: &HoardConfig,
-    terminal: &mut Terminal<
-        TermionBackend<AlternateScreen<termion::raw::RawTerminal<std::io::Stdout>>>,
-    >,
+    terminal: &mut Terminal<B>,
 ) -> Result<(), eyre::Error> {
     terminal.draw(|rect| {
         let size = rect.size();
-        // Overlay
+        let parameter = app_state
+            .selected_command
+            .as_ref()
+            .unwrap()
+            .get_next_parameter(
+                &app_state.parameter_token,
+                &app_state.parameter_ending_token,
+                app_state.parameter_escape_char,
+            )
+            .unwrap_or_default();
+        let is_multiline = parameter.kind == ParameterType::Multiline;
+
+        // Overlay, the input grows with every line of a multi-line value
+        let input_constraint = if is_multiline {
+            let lines = u16::try_from(app_state.input.split('\n').count()).unwrap_or(u16::MAX);
+            Constraint::Length(lines.saturating_add(2).max(size.height / 10))
+        } else {
+            Constraint::Percentage(10)
+        };
         let overlay_chunks = Layout::default()
             .direction(Direction::Vertical)
             .margin(1)
@@ -26,19 +46,35 @@ pub fn draw(
                 [
                     Constraint::Percentage(40),
                     Constraint::Percentage(10),
-                    Constraint::Percentage(10),
-                    Constraint::Percentage(40),
+                    input_constraint,
+                    Constraint::Min(0),
                 ]
                 .as_ref(),
             )
             .split(size);
 
+        let mut title_string = parameter.description.clone().unwrap_or_else(|| {
+            format!(
+                "Provide {} parameter",
+                ordinal(usize::from(app_state.provided_parameter_count))
+            )
+        });
+        if parameter.kind != ParameterType::Text {
+            title_string = format!("{title_string} ({})", parameter.kind);
+        }
+        if let Some(pattern) = &parameter.pattern {
+            title_string = format!("{title_string} matching {pattern}");
+        }
+        if is_multiline {
+            title_string = format!("{title_string} <Alt-Enter> to submit");
+        }
+
         let mut query_string = config.query_prefix.clone();
-        query_string.push_str(&app_state.input.clone()[..]);
-        let title_string = format!(
-            "Provide {} parameter",
-            translate_number_to_nth(app_state.provided_parameter_count)
-        );
+        if parameter.kind == ParameterType::Secret {
+            query_string.push_str(&"*".repeat(app_state.input.chars().count()));
+        } else {
+            query_string.push_str(&app_state.input);
+        }
 
         let command_style = Style::default().fg(Color::Rgb(
             config.command_color.unwrap().0,
@@ -56,100 +92,85 @@ pub fn draw(
             .style(primary_style)
             .block(Block::default().style(command_style).title(title_string));
 
-        let command_text = app_state
-            .selected_command
-            .as_ref()
-            .unwrap()
-            .command
-            .as_str();
+        let command_text = mask_secrets(&app_state.selected_command.as_ref().unwrap().command);
+        let command_text = command_text.as_str();
 
         let token = config.parameter_token.as_ref().unwrap().as_str();
         let ending_token = config.parameter_ending_token.as_ref().unwrap().as_str();
+        let escape = app_state.parameter_escape_char;
 
         let mut command_spans: Vec<Span> = Vec::new();
-
-        let mut i = 0;
-        let mut found_pos = None;
-        let bytes = command_text.as_bytes();
-
-        while i < command_text.len() {
-            if bytes[i] == b'\\' {
-                i += 1;
-                if i < command_text.len() {
-                    if command_text[i..].starts_with(token) {
-                        i += token.len();
-                    } else {
-                        let ch = command_text[i..].chars().next().unwrap();
-                        i += ch.len_utf8();
-                    }
-                }
-                continue;
-            }
-            if command_text[i..].starts_with(token) {
-                found_pos = Some(i);
-                break;
-            }
-            let ch = command_text[i..].chars().next().unwrap();
-            i += ch.len_utf8();
-        }
-
-        if let Some(pos) = found_pos {
-            let mut full_param_len = token.len();
-
-            if !ending_token.is_empty() {
-                let rest = &command_text[pos + token.len()..];
-                let mut search_idx = 0;
-                let mut found_end_at = None;
-
-                while search_idx < rest.len() {
-                    if rest.as_bytes()[search_idx] == b'\\' {
-                        search_idx += 1;
-                        if search_idx < rest.len() {
-                            let ch = rest[search_idx..].chars().next().unwrap();
-                            search_idx += ch.len_utf8();
-                        }
-                        continue;
-                    }
-                    if rest[search_idx..].starts_with(token) {
-                        break;
-                    }
-                    if rest[search_idx..].starts_with(ending_token) {
-                        found_end_at = Some(search_idx + ending_token.len());
-                        break;
-                    }
-                    if rest.as_bytes()[search_idx] == b' ' {
-                        break;
-                    }
-
-                    let ch = rest[search_idx..].chars().next().unwrap();
-                    search_idx += ch.len_utf8();
-                }
-
-                if let Some(offset) = found_end_at {
-                    full_param_len = token.len() + offset;
-                }
-            }
-
+        if let Some((pos, len)) = first_parameter_span(command_text, token, ending_token, escape) {
             command_spans.push(Span::styled(&command_text[..pos], command_style));
-            command_spans.push(Span::styled(
-                &command_text[pos..pos + full_param_len],
-                primary_style,
-            ));
-            command_spans.push(Span::styled(
-                &command_text[pos + full_param_len..],
-                command_style,
-            ));
+            command_spans.push(Span::styled(&command_text[pos..pos + len], primary_style));
+            command_spans.push(Span::styled(&command_text[pos + len..], command_style));
         } else {
             command_spans.push(Span::styled(command_text, command_style));
         }
 
-        let command = Paragraph::new(Line::from(command_spans))
-            .alignment(Alignment::Center)
-            .wrap(Wrap { trim: true })
-            .block(Block::default().style(primary_style));
+        let command_line = Line::from(command_spans);
+        // A command that doesn't fit is scrolled sideways instead of wrapped, which can't be scrolled
+        let command = if command_line.width() > usize::from(overlay_chunks[1].width) {
+            Paragraph::new(command_line).scroll((0, command_scroll(app_state)))
+        } else {
+            Paragraph::new(command_line)
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true })
+        }
+        .block(Block::default().style(primary_style));
+
+        let error_message = Paragraph::new(app_state.error_message.clone())
+            .style(primary_style)
+            .block(Block::default().style(command_style).title("Error:"));
+
+        let preview = Paragraph::new(preview_command(
+            app_state.selected_command.as_ref().unwrap(),
+            &app_state.input,
+            token,
+            ending_token,
+            escape,
+        ))
+        .style(command_style)
+        .wrap(Wrap { trim: true })
+        .block(Block::default().style(command_style).title("Preview:"));
+
+        let detail_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+            .split(overlay_chunks[3]);
 
         rect.render_widget(command, overlay_chunks[1]);
         rect.render_widget(input, overlay_chunks[2]);
+        rect.render_widget(preview, detail_chunks[0]);
+        if !app_state.error_message.is_empty() {
+            rect.render_widget(error_message, detail_chunks[1]);
+        } else if !app_state.parameter_options.is_empty() {
+            let options: Vec<ListItem> = filtered_options(app_state)
+                .into_iter()
+                .map(ListItem::new)
+                .collect();
+            let options = List::new(options)
+                .style(command_style)
+                .block(
+                    Block::default()
+                        .style(command_style)
+                        .title("Values (<Up>/<Down> to select)"),
+                )
+                .highlight_style(primary_style)
+                .highlight_symbol("> ");
+            let mut option_list = app_state.parameter_option_list.clone();
+            rect.render_stateful_widget(options, detail_chunks[1], &mut option_list);
+        } else if !app_state.path_candidates.is_empty() {
+            let candidates = Paragraph::new(app_state.path_candidates.join("  "))
+                .style(command_style)
+                .wrap(Wrap { trim: true })
+                .block(Block::default().style(command_style).title("Completions:"));
+            rect.render_widget(candidates, detail_chunks[1]);
+        }
+        if let Some(screen) = app_state.help {
+            draw_help(rect, screen, &app_state.keymap, config);
+        }
+        rect.render_widget(Palette::from_config(config), rect.size());
     })?;
     Ok(())
 }
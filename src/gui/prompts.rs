@@ -1,5 +1,43 @@
-use crate::gui::theme::HoardTheme;
+use crate::gui::terminal::can_prompt;
+use crate::gui::theme::{HoardTheme, ThemeColors};
 use dialoguer::{Input, MultiSelect, Password, Select};
+use std::collections::VecDeque;
+use std::io::{stdin, stdout, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+/// Environment variable holding the answers to all prompts as a JSON list, e.g. `["deploy", "yes"]`
+///
+/// Lets tests and scripted demos drive the interactive flows without a terminal. Every prompt takes the next
+/// answer instead of asking; hoard exits with an error if a prompt finds no answer left or an invalid one.
+pub const TEST_INPUTS_VAR: &str = "HOARD_TEST_INPUTS";
+
+/// Set by `hoard pick --plain`, prompts are then numbered plain text on stdout instead of drawn menus
+static PLAIN_PROMPTS: AtomicBool = AtomicBool::new(false);
+
+/// Ask all following prompts as plain text, for screen readers and terminals that can't draw the menus
+pub fn use_plain_prompts() {
+    PLAIN_PROMPTS.store(true, Ordering::SeqCst);
+}
+
+fn plain_prompts() -> bool {
+    PLAIN_PROMPTS.load(Ordering::SeqCst)
+}
+
+/// Colors of the theme in the config, prompts keep the colors of the terminal without one
+static PROMPT_COLORS: OnceLock<ThemeColors> = OnceLock::new();
+
+/// Draw all following prompts in `colors`
+pub fn use_prompt_colors(colors: ThemeColors) {
+    let _ = PROMPT_COLORS.set(colors);
+}
+
+fn theme() -> HoardTheme {
+    PROMPT_COLORS
+        .get()
+        .map_or_else(HoardTheme::default, HoardTheme::with_colors)
+}
+
 pub enum Confirmation {
     Yes,
     No,
@@ -19,7 +57,24 @@ where
     let options_texts: Vec<S> = options.iter().map(text_extractor).collect();
 
     if matches!(prompt_yes_or_no(question), Confirmation::Yes) {
-        let selected_indices = MultiSelect::with_theme(&HoardTheme::default())
+        if let Some(answer) = next_test_input(selection_prompt) {
+            let texts: Vec<String> = options_texts.iter().map(ToString::to_string).collect();
+            let selected_indices = answer
+                .split(',')
+                .filter(|choice| !choice.trim().is_empty())
+                .map(|choice| {
+                    option_index(choice, &texts)
+                        .unwrap_or_else(|| invalid_test_input(selection_prompt, choice))
+                })
+                .collect::<Vec<_>>();
+            return take_elements_by_indices(options, &selected_indices);
+        }
+        if plain_prompts() {
+            let texts: Vec<String> = options_texts.iter().map(ToString::to_string).collect();
+            let selected_indices = plain_multiselect(selection_prompt, &texts);
+            return take_elements_by_indices(options, &selected_indices);
+        }
+        let selected_indices = MultiSelect::with_theme(&theme())
             .with_prompt(selection_prompt)
             .items(&options_texts)
             .interact()
@@ -34,7 +89,22 @@ where
 pub fn prompt_yes_or_no(text: &str) -> Confirmation {
     const YES_ANSWER: usize = 0;
 
-    let answer = Select::with_theme(&HoardTheme::default())
+    if let Some(answer) = next_test_input(text) {
+        return match answer.trim().to_lowercase().as_str() {
+            "yes" | "y" => Confirmation::Yes,
+            "no" | "n" => Confirmation::No,
+            _ => invalid_test_input(text, &answer),
+        };
+    }
+    if plain_prompts() {
+        let answer = plain_select(text, &[String::from("Yes"), String::from("No")], YES_ANSWER);
+        return if answer == YES_ANSWER {
+            Confirmation::Yes
+        } else {
+            Confirmation::No
+        };
+    }
+    let answer = Select::with_theme(&theme())
         .with_prompt(text)
         .items(&["Yes", "No"])
         .default(YES_ANSWER)
@@ -49,10 +119,24 @@ pub fn prompt_yes_or_no(text: &str) -> Confirmation {
 }
 
 pub fn prompt_select_with_options(text_prompt: &str, options: &[&str]) -> usize {
-    Select::with_theme(&HoardTheme::default())
+    prompt_select_with_default(text_prompt, options, 0)
+}
+
+/// Like `prompt_select_with_options`, with the option at `default` selected to begin with
+pub fn prompt_select_with_default(text_prompt: &str, options: &[&str], default: usize) -> usize {
+    if let Some(answer) = next_test_input(text_prompt) {
+        let texts: Vec<String> = options.iter().map(ToString::to_string).collect();
+        return option_index(&answer, &texts)
+            .unwrap_or_else(|| invalid_test_input(text_prompt, &answer));
+    }
+    if plain_prompts() {
+        let texts: Vec<String> = options.iter().map(ToString::to_string).collect();
+        return plain_select(text_prompt, &texts, default);
+    }
+    Select::with_theme(&theme())
         .with_prompt(text_prompt)
         .items(options)
-        .default(0)
+        .default(default)
         .interact()
         .unwrap()
 }
@@ -76,7 +160,24 @@ pub fn prompt_input_validate<F>(
 where
     F: FnMut(&String) -> Result<(), String>,
 {
-    let theme = HoardTheme::default();
+    if let Some(answer) = next_test_input(text) {
+        let answer = match default_value {
+            Some(default_value) if answer.is_empty() => default_value,
+            _ => answer,
+        };
+        if answer.is_empty() && !allow_empty {
+            invalid_test_input(text, &answer);
+        }
+        if let Some(Err(e)) = validator.map(|mut validator| validator(&answer)) {
+            eprintln!("{e}");
+            invalid_test_input(text, &answer);
+        }
+        return answer;
+    }
+    if plain_prompts() {
+        return plain_input(text, allow_empty, default_value.as_deref(), validator);
+    }
+    let theme = theme();
     let mut input: Input<String> = Input::with_theme(&theme);
     // Add default value to input prompt
     if let Some(val) = default_value {
@@ -91,7 +192,10 @@ where
 }
 
 pub fn prompt_password_repeat(text: &str) -> String {
-    Password::with_theme(&HoardTheme::default())
+    if let Some(answer) = next_test_input(text) {
+        return answer;
+    }
+    Password::with_theme(&theme())
         .with_prompt(text)
         .with_confirmation("Repeat password", "Error: the passwords don't match.")
         .interact()
@@ -99,12 +203,175 @@ pub fn prompt_password_repeat(text: &str) -> String {
 }
 
 pub fn prompt_password(text: &str) -> String {
-    Password::with_theme(&HoardTheme::default())
+    if let Some(answer) = next_test_input(text) {
+        return answer;
+    }
+    // A password prompt without a theme only hides the typing, which screen readers handle fine
+    if plain_prompts() && !stdin().is_terminal() {
+        return read_plain_answer(&format!("{text}: "));
+    }
+    if plain_prompts() {
+        println!("{text}");
+        return Password::new().interact().unwrap();
+    }
+    Password::with_theme(&theme())
         .with_prompt(text)
         .interact()
         .unwrap()
 }
 
+/// Whether prompts get answers, either from the terminal, stdin in plain mode or from `HOARD_TEST_INPUTS`
+pub fn prompts_answerable() -> bool {
+    can_prompt() || plain_prompts() || test_inputs().lock().is_ok_and(|inputs| inputs.is_some())
+}
+
+/// Whether prompts are answered in plain mode or from `HOARD_TEST_INPUTS`, so nothing is drawn full screen
+pub fn prompts_scripted() -> bool {
+    plain_prompts() || test_inputs().lock().is_ok_and(|inputs| inputs.is_some())
+}
+
+/// Answers of `HOARD_TEST_INPUTS` that were not used yet, `None` if the prompts are interactive
+fn test_inputs() -> &'static Mutex<Option<VecDeque<String>>> {
+    static TEST_INPUTS: OnceLock<Mutex<Option<VecDeque<String>>>> = OnceLock::new();
+    TEST_INPUTS.get_or_init(|| {
+        let inputs = std::env::var(TEST_INPUTS_VAR).ok().map(|inputs| {
+            parse_test_inputs(&inputs).unwrap_or_else(|e| {
+                eprintln!("{TEST_INPUTS_VAR} has to be a JSON list of strings: {e}");
+                std::process::exit(1);
+            })
+        });
+        Mutex::new(inputs)
+    })
+}
+
+fn parse_test_inputs(inputs: &str) -> Result<VecDeque<String>, serde_json::Error> {
+    serde_json::from_str(inputs)
+}
+
+/// Take the next injected answer for the prompt `text`
+///
+/// Without injected answers the prompt is interactive, hoard exits with an error if there is no terminal to ask in
+fn next_test_input(text: &str) -> Option<String> {
+    let Some(answer) = test_inputs().lock().ok()?.as_mut().map(VecDeque::pop_front) else {
+        if !can_prompt() && !plain_prompts() {
+            eprintln!("No terminal to ask for: {text}\nPass the values on the command line or set {TEST_INPUTS_VAR} instead");
+            std::process::exit(1);
+        }
+        return None;
+    };
+    if answer.is_none() {
+        eprintln!("{TEST_INPUTS_VAR} has no answer left for: {text}");
+        std::process::exit(1);
+    }
+    answer
+}
+
+fn invalid_test_input(text: &str, answer: &str) -> ! {
+    eprintln!("{TEST_INPUTS_VAR} has no valid answer for: {text} (got '{answer}')");
+    std::process::exit(1);
+}
+
+/// Print `text` and read one line of stdin, hoard exits with an error once stdin is closed
+fn read_plain_answer(text: &str) -> String {
+    print!("{text}");
+    stdout().flush().ok();
+    let mut answer = String::new();
+    if !matches!(stdin().read_line(&mut answer), Ok(read) if read > 0) {
+        println!();
+        eprintln!("No answer for: {}", text.trim_end_matches([':', ' ']));
+        std::process::exit(1);
+    }
+    answer.trim_end_matches(['\r', '\n']).to_string()
+}
+
+/// Numbered menu of `options`, answered with the number of an option or its text
+fn plain_select(text: &str, options: &[String], default: usize) -> usize {
+    println!("{text}");
+    for (index, option) in options.iter().enumerate() {
+        println!("{}) {option}", index + 1);
+    }
+    loop {
+        let answer = read_plain_answer(&format!("Enter a number [{}]: ", default + 1));
+        if answer.trim().is_empty() {
+            return default;
+        }
+        if let Some(index) = plain_option_index(&answer, options) {
+            return index;
+        }
+        println!("Enter a number from 1 to {}", options.len());
+    }
+}
+
+/// Numbered menu of `options` to choose several of, separated by commas
+fn plain_multiselect(text: &str, options: &[String]) -> Vec<usize> {
+    println!("{text}");
+    for (index, option) in options.iter().enumerate() {
+        println!("{}) {option}", index + 1);
+    }
+    loop {
+        let answer = read_plain_answer("Enter numbers separated by commas, or nothing for none: ");
+        let indices: Option<Vec<usize>> = answer
+            .split(',')
+            .filter(|choice| !choice.trim().is_empty())
+            .map(|choice| plain_option_index(choice, options))
+            .collect();
+        if let Some(indices) = indices {
+            return indices;
+        }
+        println!("Enter numbers from 1 to {}", options.len());
+    }
+}
+
+fn plain_input<F>(
+    text: &str,
+    allow_empty: bool,
+    default_value: Option<&str>,
+    mut validator: Option<F>,
+) -> String
+where
+    F: FnMut(&String) -> Result<(), String>,
+{
+    let question = default_value.map_or_else(
+        || format!("{}: ", text.trim_end()),
+        |default_value| format!("{} [{default_value}]: ", text.trim_end()),
+    );
+    loop {
+        let answer = read_plain_answer(&question);
+        let answer = match &default_value {
+            Some(default_value) if answer.is_empty() => default_value.to_string(),
+            _ => answer,
+        };
+        if answer.is_empty() && !allow_empty {
+            println!("A value is required");
+            continue;
+        }
+        match validator.as_mut().map(|validator| validator(&answer)) {
+            Some(Err(e)) => println!("{e}"),
+            _ => return answer,
+        }
+    }
+}
+
+/// Position of the option a plain answer chooses, by its number counting from 1 or by its text
+fn plain_option_index(answer: &str, options: &[String]) -> Option<usize> {
+    let answer = answer.trim();
+    answer
+        .parse::<usize>()
+        .ok()
+        .filter(|number| (1..=options.len()).contains(number))
+        .map(|number| number - 1)
+        .or_else(|| options.iter().position(|option| option == answer))
+}
+
+/// Position of the option an answer chooses, either by its text or by its index
+fn option_index(answer: &str, options: &[String]) -> Option<usize> {
+    let answer = answer.trim();
+    options
+        .iter()
+        .position(|option| option == answer)
+        .or_else(|| answer.parse().ok().filter(|index| *index < options.len()))
+}
+
 fn take_elements_by_indices<T>(elements: &[T], indices: &[usize]) -> Vec<T>
 where
     T: Clone,
@@ -134,4 +401,23 @@ mod test_prompts {
 
         assert_eq!(expected_items, take_elements_by_indices(&items, &indices));
     }
+
+    #[test]
+    fn test_inputs_choose_options() {
+        let inputs = parse_test_inputs(r#"["deploy", "1"]"#).unwrap();
+        assert_eq!(vec!["deploy", "1"], Vec::from(inputs));
+        let options = vec![String::from("default"), String::from("work")];
+        assert_eq!(Some(1), option_index("work", &options));
+        assert_eq!(Some(0), option_index("0", &options));
+        assert_eq!(None, option_index("2", &options));
+    }
+
+    #[test]
+    fn plain_answers_count_from_one() {
+        let options = vec![String::from("default"), String::from("work")];
+        assert_eq!(Some(0), plain_option_index(" 1", &options));
+        assert_eq!(Some(1), plain_option_index("work", &options));
+        assert_eq!(None, plain_option_index("0", &options));
+        assert_eq!(None, plain_option_index("3", &options));
+    }
 }
@@ -0,0 +1,15 @@
+use std::io::{stderr, stdin, stdout, IsTerminal};
+
+/// Whether the full screen GUI can be shown, it reads keys from stdin and draws on stdout
+///
+/// The shell plugins swap stdout and stderr to capture the picked command, so stdout still is the terminal there
+pub fn can_show_gui() -> bool {
+    stdin().is_terminal() && stdout().is_terminal()
+}
+
+/// Whether hoard can ask questions, they read answers from stdin and are drawn on stderr
+///
+/// Prompts still work with piped output like `hoard pick deploy | pbcopy`, but not from cron or CI jobs
+pub fn can_prompt() -> bool {
+    stdin().is_terminal() && stderr().is_terminal()
+}
@@ -1,7 +1,80 @@
+use crate::gui::palette::nearest_indexed;
 use console::{style, Style, StyledObject};
 use dialoguer::theme::Theme;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
+/// Name of the theme with the colors of `custom_theme` in the config
+pub const CUSTOM_THEME: &str = "custom";
+
+/// Colors the GUI and the prompts are drawn in, colors left out of a custom theme are the ones of gruvbox
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeColors {
+    /// Text and borders
+    pub primary: (u8, u8, u8),
+    /// Highlights, like the selected namespace
+    pub secondary: (u8, u8, u8),
+    /// Background of the selected command
+    pub tertiary: (u8, u8, u8),
+    /// Commands in the preview
+    pub command: (u8, u8, u8),
+}
+
+impl Default for ThemeColors {
+    fn default() -> Self {
+        THEMES[0].1
+    }
+}
+
+/// The themes that can be picked by name, gruvbox is the one hoard always came with
+pub const THEMES: &[(&str, ThemeColors)] = &[
+    (
+        "gruvbox",
+        ThemeColors {
+            primary: (242, 229, 188),
+            secondary: (181, 118, 20),
+            tertiary: (50, 48, 47),
+            command: (180, 118, 20),
+        },
+    ),
+    (
+        "dracula",
+        ThemeColors {
+            primary: (248, 248, 242),
+            secondary: (189, 147, 249),
+            tertiary: (68, 71, 90),
+            command: (80, 250, 123),
+        },
+    ),
+    (
+        "solarized",
+        ThemeColors {
+            primary: (147, 161, 161),
+            secondary: (181, 137, 0),
+            tertiary: (7, 54, 66),
+            command: (42, 161, 152),
+        },
+    ),
+    (
+        "nord",
+        ThemeColors {
+            primary: (216, 222, 233),
+            secondary: (136, 192, 208),
+            tertiary: (59, 66, 82),
+            command: (163, 190, 140),
+        },
+    ),
+];
+
+/// Colors of the theme called `name`
+pub fn theme_colors(name: &str) -> Option<ThemeColors> {
+    THEMES
+        .iter()
+        .find(|(theme, _)| theme.eq_ignore_ascii_case(name))
+        .map(|(_, colors)| *colors)
+}
+
 #[allow(clippy::module_name_repetitions)]
 pub struct HoardTheme {
     /// The style for default values
@@ -76,6 +149,25 @@ impl Default for HoardTheme {
     }
 }
 
+impl HoardTheme {
+    /// Prompts in the colors of a theme instead of the colors of the terminal
+    pub fn with_colors(colors: &ThemeColors) -> Self {
+        let highlight = nearest_indexed(colors.secondary);
+        let command = nearest_indexed(colors.command);
+        Self {
+            defaults_style: Style::new().for_stderr().color256(command),
+            prompt_prefix: style("?".to_string()).for_stderr().color256(highlight),
+            success_prefix: style("✔".to_string()).for_stderr().color256(highlight),
+            values_style: Style::new().for_stderr().color256(command),
+            active_item_style: Style::new().for_stderr().color256(command),
+            active_item_prefix: style("❯".to_string()).for_stderr().color256(highlight),
+            checked_item_prefix: style("✔".to_string()).for_stderr().color256(highlight),
+            picked_item_prefix: style("❯".to_string()).for_stderr().color256(highlight),
+            ..Self::default()
+        }
+    }
+}
+
 impl Theme for HoardTheme {
     /// Formats a prompt.
     fn format_prompt(&self, f: &mut dyn fmt::Write, prompt: &str) -> fmt::Result {
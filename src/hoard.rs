@@ -1,32 +1,98 @@
 use crate::cli_commands::{Cli, Commands};
 use base64::engine::general_purpose;
-use clap::Parser;
+use chrono::{DateTime, Local};
+use console::style;
 use dotenv::dotenv;
 use log::info;
+use prettytable::Table;
 use reqwest::{StatusCode, Url};
+use std::collections::HashMap;
+use std::fmt::Write as _;
 use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::{Duration, SystemTime};
 use url::ParseError;
 
-use crate::cli_commands::Mode;
-use crate::config::HoardConfig;
-use crate::config::{load_or_build_config, save_hoard_config_file, save_parameter_token};
-use crate::core::trove::Trove;
+use crate::cli_commands::{
+    ExportFormat, GitCommand, ImportSource, Mode, OutputFormat, StatsFormat, TagCommand,
+    TrashCommand, TroveCommand,
+};
+use crate::config::{
+    is_valid_trove_name, load_or_build_config, load_or_build_profile_config, save_active_trove,
+    save_hoard_config_file, save_parameter_token, HOARD_CONFIG, HOARD_FILE,
+};
+use crate::config::{ColorSupport, DangerPolicy, HoardConfig};
+use crate::core::aliases::{shell_aliases, AliasShell};
+use crate::core::apply::{apply_changes, parse_changes};
+use crate::core::backup::{back_up_trove, find_backup, list_backups, DEFAULT_BACKUP_COUNT};
+use crate::core::cache::{load_cached, save_cached};
+use crate::core::check::{check_commands, read_trove_entries};
+use crate::core::context::CommandContext;
+use crate::core::diagnose::{
+    check_colors, check_config_file, check_shell_integration, check_sync, check_trove_file, Status,
+};
+use crate::core::editor::{apply_buffer, command_buffer, edit_text};
+use crate::core::encoding::OutputEncoding;
+use crate::core::error::HoardErr;
+use crate::core::export::{to_csv, to_html, to_toml};
+use crate::core::git::GitTrove;
+use crate::core::grep::{grep_command, grep_pattern, FieldMatch};
+use crate::core::import::{detect_format, fetch_text, parse_trove, read_trove_file, ImportFormat};
+use crate::core::include::load_includes;
+use crate::core::migrate::{is_legacy_config, migrate_config, migrate_trove};
+use crate::core::output::{to_json, CommandOutput};
+use crate::core::parameters::{
+    expand_list_markers, parse_values_file, split_list_value, Parameterized,
+};
+use crate::core::privilege::privilege_warning;
+use crate::core::process::{run_parallel, run_process, RunOutcome};
+use crate::core::providers::ProviderRegistry;
+use crate::core::replace::Substitution;
+use crate::core::scrub::Scrubber;
+use crate::core::shell::{
+    container_command, container_runtime, is_installed, is_stale_shell_integration, shell_command,
+    SHELL_INTEGRATION_VERSION_VAR,
+};
+use crate::core::stats::{
+    append_run, append_usage, command_stats, format_time, load_runs, load_shell_history,
+    load_usage, namespace_stats, parameter_suggestions, recent_history_entry, sort_by_use,
+    stats_to_csv, unused_commands, RunRecord, UsageRecord, UsedValues, RECENT_USE_DAYS,
+    RUN_LOG_FILE, USAGE_LOG_FILE, USED_VALUES_FILE,
+};
+use crate::core::tags::{tag_counts, TagChange};
+use crate::core::tips::{is_tip_due, pick_rarely_used, LAST_TIP_FILE};
+use crate::core::tldr::{fetch_page, page_commands};
+use crate::core::trove::{namespace_dir, split_command_path, trove_files, Trove};
+use crate::core::watch::TroveWatch;
+use crate::core::workflows::{iterm2_snippets, warp_workflow, warp_workflow_file_name};
 use crate::core::HoardCmd;
-use crate::filter::query_trove;
+use crate::filter::{command_matches, query_trove};
 use crate::gui::commands_gui;
+use crate::gui::merge::resolve_conflict;
+use crate::gui::output_pane;
+use crate::gui::palette::{detect_color_support, Palette};
 use crate::gui::prompts::{
-    prompt_input, prompt_multiselect_options, prompt_password, prompt_password_repeat,
-    prompt_yes_or_no, Confirmation,
+    prompt_input, prompt_input_validate, prompt_multiselect_options, prompt_password,
+    prompt_password_repeat, prompt_select_with_options, prompt_yes_or_no, prompts_answerable,
+    prompts_scripted, use_plain_prompts, use_prompt_colors, Confirmation,
 };
+use crate::gui::terminal::{can_prompt, can_show_gui};
 use crate::sync_models::TokenResponse;
-use crate::util::rem_first_and_last;
+use crate::util::{pluralize, rem_first_and_last, write_clipboard};
 use base64::Engine as _;
+
+/// Values of the parameters of a command by name and in order of appearance, e.g. from `--param` and `--params`
+type ParameterValues = (HashMap<String, String>, Vec<String>);
+
 #[derive(Default, Debug)]
 pub struct Hoard {
     config: HoardConfig,
     trove: Trove,
+    /// Name of the trove in use and the file it is kept in
+    trove_name: String,
+    trove_path: Option<PathBuf>,
 }
 
 impl Hoard {
@@ -42,13 +108,53 @@ impl Hoard {
                 std::process::exit(1);
             }
         };
+        self.use_theme_in_prompts();
         self
     }
 
-    pub fn start(&mut self) -> (String, bool) {
+    /// Use the config of `profile` instead of the default config
+    pub fn with_profile(&mut self, profile: &str) -> &mut Self {
+        info!("Loading config of profile {profile}");
+        match load_or_build_profile_config(profile) {
+            Ok(config) => self.config = config,
+            Err(err) => {
+                eprintln!("ERROR: {err}");
+                std::process::exit(1);
+            }
+        }
+        self.use_theme_in_prompts();
+        self
+    }
+
+    /// Draw the prompts in the colors of the theme, if the terminal can show them
+    fn use_theme_in_prompts(&self) {
+        let Ok(Some(colors)) = self.config.theme_colors() else {
+            return;
+        };
+        if matches!(
+            Palette::from_config(&self.config).0,
+            ColorSupport::Truecolor | ColorSupport::Ansi256
+        ) {
+            use_prompt_colors(colors);
+        }
+    }
+
+    /// Use the trove called `name`, or the active trove if no name is given
+    pub fn with_trove(&mut self, name: Option<&str>) -> &mut Self {
+        let name = self.config.trove_name(name).to_string();
+        if !is_valid_trove_name(&name) {
+            eprintln!("Invalid trove name {name}, use letters, digits, '-' and '_'");
+            std::process::exit(1);
+        }
+        self.trove_path = self.config.trove_file(&name);
+        self.trove_name = name;
+        self
+    }
+
+    #[allow(clippy::too_many_lines)]
+    pub fn start(&mut self, cli: &Cli) -> (String, bool) {
         dotenv().ok();
         let mut autocomplete_command = String::new();
-        let cli = Cli::parse();
 
         match &cli.command {
             Commands::Info {} => {
@@ -58,13 +164,31 @@ impl Hoard {
                 name,
                 tags,
                 command,
+                from_history,
+                stdin,
                 description,
+                shell,
+                container,
+                timeout,
+                encoding,
+                related,
             } => {
+                let command = if *stdin {
+                    Some(stdin_command())
+                } else {
+                    from_history.map_or_else(|| command.clone(), |n| Some(history_entry(n)))
+                };
                 self.new_command(
                     name.clone(),
                     tags.clone(),
-                    command.clone(),
+                    command,
                     description.clone(),
+                    shell.clone(),
+                    container.clone(),
+                    *timeout,
+                    *encoding,
+                    related.clone(),
+                    *stdin,
                 );
             }
             Commands::List {
@@ -72,44 +196,239 @@ impl Hoard {
                 json,
                 simple,
             } => {
-                let commands =
-                    self.list_commands(simple.to_owned(), json.to_owned(), filter.clone());
+                let output = cli.output.or(if *json {
+                    Some(OutputFormat::Json)
+                } else if *simple {
+                    Some(OutputFormat::Simple)
+                } else {
+                    None
+                });
+                if output == Some(OutputFormat::Csv) {
+                    eprintln!("hoard list can't print csv, use table, simple or json");
+                    std::process::exit(1);
+                }
+                let commands = self.list_commands(
+                    output == Some(OutputFormat::Simple),
+                    output == Some(OutputFormat::Json),
+                    filter.clone(),
+                );
                 if let Some(c) = commands {
                     autocomplete_command = c;
                 }
             }
-            Commands::Pick { name } => {
-                self.pick_command(name);
+            Commands::Pick {
+                name,
+                params,
+                positional_params,
+                params_file,
+                plain,
+                print_only,
+            } => {
+                if *plain {
+                    use_plain_prompts();
+                }
+                let json = Self::json_output(cli.output, "pick");
+                let print_only = *print_only || self.config.pick_print_only == Some(true);
+                let name = &name.clone().unwrap_or_else(|| self.prompt_command_name());
+                let picked =
+                    if params.is_empty() && positional_params.is_empty() && params_file.is_none() {
+                        self.pick_command(name, print_only, json)
+                    } else {
+                        self.pick_command_with_args(
+                            name,
+                            params,
+                            positional_params,
+                            params_file.as_deref(),
+                            print_only,
+                            json,
+                        )
+                    };
+                // Printed as the only output, without the empty line other commands end with
+                if let Some(command) = picked {
+                    autocomplete_command = command;
+                }
             }
-            Commands::Remove { name } => {
-                self.remove_command(name);
+            Commands::Copy {
+                name,
+                params,
+                positional_params,
+                params_file,
+            } => {
+                let values = (!params.is_empty()
+                    || !positional_params.is_empty()
+                    || params_file.is_some())
+                .then(|| Self::parameter_values(params, positional_params, params_file.as_deref()));
+                self.copy_command(name.as_deref(), values.as_ref());
             }
+            Commands::Run {
+                name,
+                params,
+                positional_params,
+                params_file,
+                in_container,
+                timeout,
+                parallel,
+                encoding,
+                no_pane,
+            } => {
+                let values = (!params.is_empty()
+                    || !positional_params.is_empty()
+                    || params_file.is_some())
+                .then(|| Self::parameter_values(params, positional_params, params_file.as_deref()));
+                self.run_with_follow_ups(
+                    name.as_deref(),
+                    values.as_ref(),
+                    in_container.as_deref(),
+                    *timeout,
+                    *parallel,
+                    *encoding,
+                    *no_pane,
+                );
+            }
+            Commands::Remove { name, filter, yes } => match (name, filter) {
+                (Some(name), _) => self.remove_command(name),
+                (None, Some(filter)) => self.remove_matching(filter, *yes),
+                (None, None) => {}
+            },
             Commands::RemoveNamespace { namespace } => {
                 self.remove_namespace(namespace);
             }
             Commands::SetParameterToken { name } => {
                 self.set_parameter_token(name);
             }
-            Commands::Import { uri } => {
-                self.import_trove(uri);
+            Commands::Import {
+                source: Some(ImportSource::Tldr { tool, yes }),
+                ..
+            } => {
+                self.import_tldr(tool, *yes);
+            }
+            Commands::Import {
+                uri,
+                uri_flag,
+                format,
+                yes,
+                ..
+            } => {
+                let uri = uri
+                    .as_ref()
+                    .or(uri_flag.as_ref())
+                    .expect("required by clap");
+                self.import_trove(uri, *format, *yes);
+            }
+            Commands::Apply { changes } => {
+                self.apply_changes(changes);
+            }
+            Commands::Grep {
+                pattern,
+                ignore_case,
+            } => {
+                let json = Self::json_output(cli.output, "grep");
+                self.grep_commands(pattern, *ignore_case, json);
+            }
+            Commands::Tag { command } => {
+                self.tag_command(command);
+            }
+            Commands::Replace {
+                expression,
+                query,
+                yes,
+            } => {
+                self.replace_in_commands(expression, query.as_deref().unwrap_or_default(), *yes);
+            }
+            Commands::Stats { export } => {
+                let export = match cli.output {
+                    Some(OutputFormat::Csv) => Some(StatsFormat::Csv),
+                    Some(OutputFormat::Simple | OutputFormat::Json) => {
+                        eprintln!("hoard stats can't print that format, use table or csv");
+                        std::process::exit(1);
+                    }
+                    Some(OutputFormat::Table) | None => *export,
+                };
+                self.show_stats(export);
+            }
+            Commands::Doctor {
+                history,
+                trove_file,
+                fix,
+                env,
+            } => {
+                if *env {
+                    self.diagnose_environment();
+                } else if *trove_file {
+                    self.check_trove(*fix);
+                } else {
+                    self.doctor(history.as_deref());
+                }
+            }
+            Commands::Export {
+                path,
+                format,
+                scrub,
+                shell,
+            } => {
+                self.export_command(path, *format, *scrub, *shell);
+            }
+            Commands::Edit { name, editor } => {
+                if *editor {
+                    self.edit_command_in_editor(name);
+                } else {
+                    self.edit_command(name);
+                }
             }
-            Commands::Export { path } => {
-                self.export_command(path);
+            Commands::Mv { from, to } => {
+                self.move_command(from, to);
             }
-            Commands::Edit { name } => {
-                self.edit_command(name);
+            Commands::Show { command } => {
+                let json = Self::json_output(cli.output, "show");
+                self.show_command(command, json);
+            }
+            Commands::Random { namespace, daily } => {
+                self.random_command(namespace.as_deref(), *daily);
             }
             Commands::ShellConfig { shell } => {
                 Self::shell_config_command(shell);
             }
+            Commands::Trove { command } => {
+                self.trove_command(command);
+            }
+            Commands::Trash { command } => {
+                self.trash_command(command);
+            }
+            Commands::Migrate {
+                from_legacy,
+                output,
+                yes,
+            } => {
+                self.migrate_legacy(from_legacy, output.as_deref(), *yes);
+            }
+            Commands::Restore { from, yes } => {
+                self.restore_command(from.as_deref(), *yes);
+            }
+            Commands::Git { command } => {
+                self.git_command(command);
+            }
             Commands::Sync { command } => {
                 self.sync(*command);
             }
         }
 
+        if cli.autocomplete {
+            Self::warn_stale_shell_integration();
+        }
         (autocomplete_command, cli.autocomplete)
     }
 
+    /// Ask to update the shell plugin when it is older than this binary, outdated plugins break picking commands
+    fn warn_stale_shell_integration() {
+        let version = std::env::var(SHELL_INTEGRATION_VERSION_VAR).ok();
+        if is_stale_shell_integration(version.as_deref()) {
+            // The shell plugins swap stdout and stderr, so this goes to the terminal
+            println!(
+                "Your hoard shell plugin is outdated. Run [ hoard shell-config --shell <SHELL> ] and source its output again"
+            );
+        }
+    }
+
     pub fn show_info(&self) {
         // Print out path to hoard config file and path to where the trove file is stored
         if let Some(config_home_path) = self.config.config_home_path.clone() {
@@ -119,126 +438,1755 @@ impl Hoard {
             );
         }
 
-        if let Some(trove_path) = self.config.trove_path.clone() {
-            println!("✨ Trove file is located at {}", trove_path.display());
+        if let Some(trove_path) = self.trove_path.clone() {
+            println!(
+                "✨ Trove file of the {} trove is located at {}",
+                self.trove_name,
+                trove_path.display()
+            );
+        }
+    }
+
+    fn trove_command(&self, command: &TroveCommand) {
+        match command {
+            TroveCommand::Switch { name } => {
+                if !is_valid_trove_name(name) {
+                    eprintln!("Invalid trove name {name}, use letters, digits, '-' and '_'");
+                    std::process::exit(1);
+                }
+                if let Some(config_path) = self.config.config_home_path.clone() {
+                    if !save_active_trove(&self.config, &config_path, name) {
+                        std::process::exit(1);
+                    }
+                }
+                println!("Switched to the {name} trove");
+            }
+            TroveCommand::List {} => {
+                let active = self.config.trove_name(None);
+                for name in self.config.trove_names() {
+                    let marker = if name == active { "*" } else { " " };
+                    println!("{marker} {name}");
+                }
+            }
+        }
+    }
+
+    fn git_command(&mut self, command: &GitCommand) {
+        let Some(hoard_home) = self.config.hoard_home_path() else {
+            eprintln!("Could not find the hoard home directory");
+            std::process::exit(1);
+        };
+        let repository = match command {
+            GitCommand::Init { remote } => GitTrove::init(&hoard_home, remote.as_deref()),
+            GitCommand::Pull {} | GitCommand::Push {} => GitTrove::open(&hoard_home).ok_or_else(|| {
+                HoardErr::new("No git repository for the troves, run `hoard git init --remote <URL>` first")
+            }),
+        };
+        let repository = repository
+            .map(|repository| repository.with_namespace_files(self.stores_per_namespace()));
+        let trove_name = self.trove_name.clone();
+        // The repository always has the latest local changes before talking to the remote
+        let result = repository.and_then(|repository| {
+            repository
+                .commit(
+                    &self.trove.without_managed(),
+                    &trove_name,
+                    &format!("Update the {trove_name} trove"),
+                )
+                .map(|_| repository)
+        });
+        let repository = result.unwrap_or_else(|e| {
+            eprintln!("{e}");
+            std::process::exit(1);
+        });
+        let result = match command {
+            GitCommand::Init { remote } => {
+                println!(
+                    "Committing the troves to {} on every change",
+                    repository.dir().display()
+                );
+                if remote.is_some() {
+                    println!("Run `hoard git pull` to merge the troves already on the remote");
+                }
+                Ok(())
+            }
+            GitCommand::Pull {} => repository
+                .pull(&self.trove.without_managed(), &trove_name)
+                .map(|merge| {
+                    let Some(merge) = merge else {
+                        println!("Already up to date");
+                        return;
+                    };
+                    if !merge.conflicts.is_empty() {
+                        println!(
+                        "Kept the more recently modified version of {} changed on both sides: {}",
+                        pluralize(merge.conflicts.len(), "command", "commands"),
+                        merge.conflicts.join(", ")
+                    );
+                    }
+                    for (name, trove) in &merge.other_troves {
+                        let Some(path) = self.config.trove_file(name) else {
+                            continue;
+                        };
+                        if let Some(parent) = path.parent() {
+                            let _ = fs::create_dir_all(parent);
+                        }
+                        trove.save_stored(&path, self.config.split_namespaces == Some(true));
+                        println!("Merged the {name} trove of the remote");
+                    }
+                    self.trove = merge.trove;
+                    self.save_trove(None);
+                    println!("Merged the trove of the remote");
+                }),
+            GitCommand::Push {} => repository.push().map(|()| println!("Pushed the troves")),
+        };
+        if let Err(e) = result {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    }
+
+    /// Commit the trove to the git repository of `hoard git init`, if there is one
+    fn commit_to_git(&self) {
+        let Some(repository) = self
+            .config
+            .hoard_home_path()
+            .and_then(|hoard_home| GitTrove::open(&hoard_home))
+            .map(|repository| repository.with_namespace_files(self.stores_per_namespace()))
+        else {
+            return;
+        };
+        let trove_name = &self.trove_name;
+        if let Err(e) = repository.commit(
+            &self.trove.without_managed(),
+            trove_name,
+            &format!("Update the {trove_name} trove"),
+        ) {
+            eprintln!("Could not commit the trove: {e}");
+        }
+    }
+
+    /// Copy the trove file into the backups directory before it is overwritten
+    fn back_up_trove(&self, trove_file: &Path) {
+        let Some(hoard_home) = self.config.hoard_home_path() else {
+            return;
+        };
+        let keep = self.config.backup_count.unwrap_or(DEFAULT_BACKUP_COUNT);
+        let namespace_dir = namespace_dir(trove_file);
+        let trove_file = if trove_file.exists() || !namespace_dir.is_dir() {
+            trove_file
+        } else {
+            &namespace_dir
+        };
+        if let Err(e) = back_up_trove(&hoard_home, &self.trove_name, trove_file, keep) {
+            eprintln!("{e}");
+        }
+    }
+
+    fn migrate_legacy(&mut self, from: &Path, output: Option<&Path>, yes: bool) {
+        let read = |path: &Path| {
+            fs::read_to_string(path).unwrap_or_else(|e| {
+                eprintln!("Could not read {}: {e}", path.display());
+                std::process::exit(1);
+            })
+        };
+        let (config_file, mut trove_file) = if from.is_dir() {
+            let existing = |name: &str| Some(from.join(name)).filter(|path| path.exists());
+            (existing(HOARD_CONFIG), existing(HOARD_FILE))
+        } else if is_legacy_config(&read(from)) {
+            (Some(from.to_path_buf()), None)
+        } else {
+            (None, Some(from.to_path_buf()))
+        };
+
+        let mut notes = Vec::new();
+        if let Some(config_file) = config_file {
+            let migration = match migrate_config(&read(&config_file), &self.config) {
+                Ok(migration) => migration,
+                Err(e) => {
+                    eprintln!("{e}");
+                    std::process::exit(1);
+                }
+            };
+            notes.extend(migration.notes);
+            if trove_file.is_none() {
+                trove_file = migration.trove_path.filter(|path| path.exists());
+            }
+            if !migration.settings.is_empty() {
+                println!(
+                    "Settings of the legacy config: {}",
+                    migration.settings.join(", ")
+                );
+                let take_over = yes
+                    || matches!(
+                        prompt_yes_or_no("Take them over into your config?"),
+                        Confirmation::Yes
+                    );
+                if take_over {
+                    if let Some(hoard_home) = self.config.hoard_home_path() {
+                        if let Err(e) = save_hoard_config_file(&migration.config, &hoard_home) {
+                            eprintln!("Could not save the config: {e}");
+                            std::process::exit(1);
+                        }
+                    }
+                    self.config = migration.config;
+                }
+            }
+        }
+
+        let Some(trove_file) = trove_file else {
+            print_migration_notes(&notes);
+            println!("No legacy trove found in {}", from.display());
+            return;
+        };
+        let migration = match migrate_trove(&read(&trove_file), &self.config.default_namespace) {
+            Ok(migration) => migration,
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        };
+        notes.extend(migration.notes);
+        print_migration_notes(&notes);
+        let source = migration.version.as_deref().map_or_else(
+            || String::from("the legacy trove"),
+            |version| format!("the trove of hoard {version}"),
+        );
+        println!(
+            "Translated {} from {source}",
+            pluralize(migration.trove.commands.len(), "command", "commands")
+        );
+
+        if let Some(output) = output {
+            migration.trove.save_trove_file(output);
+            println!("Wrote the migrated trove to {}", output.display());
+            return;
+        }
+        let current = self.trove.without_managed().commands.len();
+        if !yes
+            && current > 0
+            && !matches!(
+                prompt_yes_or_no(&format!(
+                    "Replace the {} of the {} trove with the migrated ones? The current trove is backed up",
+                    pluralize(current, "command", "commands"),
+                    self.trove_name
+                )),
+                Confirmation::Yes
+            )
+        {
+            println!("Keeping the current trove, write the migrated one to another file with --output");
+            return;
+        }
+        let mut trove = migration.trove;
+        trove
+            .commands
+            .extend(self.trove.commands.iter().filter(|c| c.is_managed).cloned());
+        self.trove = trove;
+        self.save_trove(None);
+        println!("Saved the migrated trove");
+    }
+
+    fn restore_command(&mut self, from: Option<&str>, yes: bool) {
+        let Some(hoard_home) = self.config.hoard_home_path() else {
+            eprintln!("Could not find the hoard home directory");
+            std::process::exit(1);
+        };
+        let trove_name = self.trove_name.clone();
+        let Some(from) = from else {
+            let backups = list_backups(&hoard_home, None);
+            if backups.is_empty() {
+                println!("There are no backups yet, one is taken every time a trove is saved");
+                return;
+            }
+            let mut table = Table::new();
+            table.add_row(row!["Backup", "trove", "commands", "taken"]);
+            for backup in &backups {
+                let commands = backup.load().map_or_else(
+                    |_| String::from("invalid"),
+                    |trove| trove.commands.len().to_string(),
+                );
+                table.add_row(row![
+                    backup.file_name(),
+                    backup.trove_name,
+                    commands,
+                    backup.taken.to_rfc3339()
+                ]);
+            }
+            table.printstd();
+            return;
+        };
+
+        let restored = find_backup(&hoard_home, from).and_then(|backup| {
+            if backup.trove_name == trove_name {
+                backup.load().map(|trove| (backup, trove))
+            } else {
+                Err(HoardErr::new(&format!(
+                    "{} is a backup of the {} trove, restore it with [ hoard --trove {} restore ]",
+                    backup.file_name(),
+                    backup.trove_name,
+                    backup.trove_name
+                )))
+            }
+        });
+        let (backup, mut trove) = match restored {
+            Ok(restored) => restored,
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        };
+        let current = self.trove.without_managed().commands.len();
+        if !yes
+            && !matches!(
+                prompt_yes_or_no(&format!(
+                    "Replace the {} of the {trove_name} trove with the {} of the backup from {}?",
+                    pluralize(current, "command", "commands"),
+                    pluralize(trove.commands.len(), "command", "commands"),
+                    backup.taken.to_rfc3339()
+                )),
+                Confirmation::Yes
+            )
+        {
+            println!("Keeping the current trove");
+            return;
+        }
+        // Managed commands come from the dotfile manager and aren't part of the backup
+        trove
+            .commands
+            .extend(self.trove.commands.iter().filter(|c| c.is_managed).cloned());
+        self.trove = trove;
+        self.save_trove(None);
+        println!(
+            "Restored {}. The replaced trove was backed up as well, so this can be undone",
+            backup.file_name()
+        );
+    }
+
+    fn trash_command(&mut self, command: &TrashCommand) {
+        match command {
+            TrashCommand::List {} => {
+                if self.trove.trash.is_empty() {
+                    println!("The trash is empty");
+                    return;
+                }
+                let mut table = Table::new();
+                table.add_row(row!["Name", "namespace", "command", "removed"]);
+                for trashed in &self.trove.trash {
+                    table.add_row(row![
+                        trashed.command.name,
+                        trashed.command.namespace,
+                        trashed.command.command,
+                        format_time(trashed.deleted)
+                    ]);
+                }
+                table.printstd();
+            }
+            TrashCommand::Restore { name } => match self.trove.restore_command(name.as_deref()) {
+                Ok(restored) => {
+                    println!("Restored [{}]", restored.name);
+                    self.save_trove(None);
+                }
+                Err(e) => {
+                    eprintln!("{e}");
+                    std::process::exit(1);
+                }
+            },
+            TrashCommand::Purge {} => {
+                let purged = self.trove.purge_trash();
+                self.save_trove(None);
+                println!(
+                    "Deleted {} for good",
+                    pluralize(purged, "command", "commands")
+                );
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn new_command(
+        &mut self,
+        name: Option<String>,
+        tags: Option<String>,
+        command: Option<String>,
+        description: Option<String>,
+        shell: Option<String>,
+        container: Option<String>,
+        timeout: Option<u64>,
+        encoding: Option<OutputEncoding>,
+        related: Option<String>,
+        from_stdin: bool,
+    ) {
+        let new_command = if from_stdin {
+            // stdin is taken by the command, so nothing else can be asked
+            self.unprompted_command(
+                &command.unwrap_or_default(),
+                &name.unwrap_or_default(),
+                tags.as_deref(),
+                description.as_deref(),
+            )
+        } else {
+            let trove_namespaces = self.trove.namespaces();
+            //trove_namespaces.push(&default_ns_clone);
+            let new_command = HoardCmd::default().with_command_string_input(
+                command,
+                &self.config.parameter_token.clone().unwrap(),
+                &self.config.parameter_ending_token.clone().unwrap(),
+            );
+            let suggested_namespace = self.config.suggested_namespace(&new_command.command);
+            new_command
+                .with_namespace_input(&trove_namespaces, suggested_namespace.as_deref())
+                .with_name_input(name, &self.trove)
+                .with_description_input(description.unwrap_or_default())
+                .with_tags_input(tags)
+        };
+        let new_command = new_command
+            .with_shell(&shell.unwrap_or_default())
+            .with_container(&container.unwrap_or_default())
+            .with_timeout(timeout)
+            .with_encoding(encoding)
+            .with_related(&related.unwrap_or_default());
+        let new_command = if self.config.capture_context == Some(true) {
+            let context = CommandContext::capture();
+            // Shown so nobody is surprised by what ends up in a shared trove
+            println!("Saving the context of the command: {context}");
+            new_command.with_context((!context.is_empty()).then_some(context))
+        } else {
+            new_command
+        };
+        self.warn_complex_command(&new_command);
+        let _ = self.trove.add_command(new_command, true);
+        self.save_trove(None);
+    }
+
+    /// A new command with the fields given on the command line, in the namespace suggested by the program it runs
+    ///
+    /// Exits with 1 if the name is taken in that namespace or can't be a name
+    fn unprompted_command(
+        &self,
+        command: &str,
+        name: &str,
+        tags: Option<&str>,
+        description: Option<&str>,
+    ) -> HoardCmd {
+        let namespace = self
+            .config
+            .suggested_namespace(command)
+            .unwrap_or_else(|| self.config.default_namespace.clone());
+        if let Err(e) = HoardCmd::is_name_valid(name) {
+            eprintln!("'{name}' can't be the name of a command: {e}");
+            std::process::exit(1);
+        }
+        if self
+            .trove
+            .commands
+            .iter()
+            .any(|c| c.namespace == namespace && c.name == name)
+        {
+            eprintln!("[{name}] exists in namespace [{namespace}] already, pass another --name");
+            std::process::exit(1);
+        }
+        println!("Saving [{name}] in namespace [{namespace}]");
+        HoardCmd::default()
+            .with_command(command)
+            .with_name(name)
+            .with_namespace(&namespace)
+            .with_description(description.unwrap_or_default())
+            .with_tags_raw(tags.unwrap_or_default())
+    }
+
+    fn list_commands(
+        &mut self,
+        is_simple: bool,
+        is_structured: bool,
+        filter: Option<String>,
+    ) -> Option<String> {
+        if self.trove.is_empty() {
+            println!("No command hoarded.\nRun [ hoard new ] first to hoard a command.");
+        } else if is_structured {
+            // Return list of commands in json format, filtered by `filter`
+            let query_string: String = filter.unwrap_or_default();
+            let filtered_trove = query_trove(&self.trove, &query_string);
+            let commands: Vec<CommandOutput> = filtered_trove
+                .commands
+                .iter()
+                .map(|command| self.command_output(command))
+                .collect();
+            return Some(to_json(&commands));
+        } else if is_simple || !can_show_gui() {
+            // Piped or run from cron, print the commands instead of showing the GUI
+            query_trove(&self.trove, filter.as_deref().unwrap_or_default()).print_trove();
+        } else {
+            let watch = self.trove_watch();
+            match commands_gui::run(
+                &mut self.trove,
+                &self.config,
+                &self.trove_name,
+                watch,
+                filter.as_deref(),
+            ) {
+                Ok(selected_command) => {
+                    self.save_trove(None);
+                    if let Some(c) = selected_command {
+                        // Is set if a command is selected in GUI
+                        if !c.command.is_empty() {
+                            self.log_use(&c);
+                            //TODO: If run as cli program, copy command into clipboard, else will be written to READLINE_LINE
+                            return Some(c.command);
+                        }
+                    }
+                }
+                Err(e) => {
+                    println!("{e}");
+                }
+            }
+        }
+        None
+    }
+
+    /// Watch of the trove file, to reload it in the GUI when it is changed somewhere else
+    fn trove_watch(&self) -> Option<TroveWatch> {
+        let path = self.trove_path.clone()?;
+        let (config, trove_name) = (self.config.clone(), self.trove_name.clone());
+        let reload_path = path.clone();
+        Some(TroveWatch::new(&path, &self.trove, move || {
+            let mut hoard = Self {
+                config: config.clone(),
+                trove: Trove::default(),
+                trove_name: trove_name.clone(),
+                trove_path: Some(reload_path.clone()),
+            };
+            hoard.load_trove();
+            hoard.trove
+        }))
+    }
+
+    /// Pick a command, prompting for its parameters, and print it
+    ///
+    /// With `print_only` the command is returned to be printed on its own, its use isn't recorded and a failure exits
+    /// with an error. With `json` the command is printed with its details
+    fn pick_command(&self, name: &str, print_only: bool, json: bool) -> Option<String> {
+        let command_result = self.trove.pick_command(&self.config, name);
+        match command_result {
+            Ok(c) => {
+                let output = self.picked_output(&c, json);
+                if print_only {
+                    return Some(output);
+                }
+                self.log_use(&c);
+                println!("{output}");
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                if print_only {
+                    std::process::exit(1);
+                }
+            }
+        }
+        None
+    }
+
+    /// Ask which command of the trove to pick, the most used ones first
+    fn prompt_command_name(&self) -> String {
+        let commands: Vec<HoardCmd> = self
+            .trove
+            .get_commands_sorted_by_usage()
+            .into_iter()
+            .filter(|command| !command.is_hidden)
+            .collect();
+        if commands.is_empty() {
+            eprintln!("No commands to pick, save one with `hoard new`");
+            std::process::exit(1);
+        }
+        let options: Vec<String> = commands
+            .iter()
+            .map(|command| format!("{}: {}", command.name, command.description))
+            .collect();
+        let options: Vec<&str> = options.iter().map(String::as_str).collect();
+        let selected = prompt_select_with_options("Pick a command", &options);
+        commands[selected].name.clone()
+    }
+
+    /// Pick a command with the parameter values of the command line and an optional values file
+    fn pick_command_with_args(
+        &self,
+        name: &str,
+        params: &[(String, String)],
+        positional_params: &[String],
+        params_file: Option<&Path>,
+        print_only: bool,
+        json: bool,
+    ) -> Option<String> {
+        let (named, positional) = Self::parameter_values(params, positional_params, params_file);
+        self.pick_command_with_values(name, &named, &positional, print_only, json)
+    }
+
+    /// What `hoard pick` prints for the `picked` command, the command itself or its details as JSON
+    fn picked_output(&self, picked: &HoardCmd, json: bool) -> String {
+        if !json {
+            return picked.command.clone();
+        }
+        // The parameters are listed from the command as it is saved, before they were filled in
+        let saved = self.trove.get_command(&picked.name).unwrap_or(picked);
+        to_json(&self.command_output(saved).with_resolved(&picked.command))
+    }
+
+    /// `command` as `--output json` prints it
+    fn command_output(&self, command: &HoardCmd) -> CommandOutput {
+        CommandOutput::new(
+            command,
+            self.config.parameter_token.as_deref().unwrap(),
+            self.config.parameter_ending_token.as_deref().unwrap(),
+            self.config.escape_char(),
+        )
+    }
+
+    /// Whether `hoard <command_name>` prints JSON instead of text, it can't print simple tables or csv
+    fn json_output(output: Option<OutputFormat>, command_name: &str) -> bool {
+        match output {
+            Some(OutputFormat::Json) => true,
+            Some(OutputFormat::Table) | None => false,
+            Some(OutputFormat::Simple | OutputFormat::Csv) => {
+                eprintln!("hoard {command_name} can't print simple or csv, use table or json");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    /// Parameter values by name and in order, from the command line and an optional values file
+    fn parameter_values(
+        params: &[(String, String)],
+        positional_params: &[String],
+        params_file: Option<&Path>,
+    ) -> ParameterValues {
+        let (mut named, mut positional) = params_file
+            .map_or_else(|| Ok(Default::default()), read_values_file)
+            .unwrap_or_else(|e| {
+                eprintln!("{e}");
+                std::process::exit(1);
+            });
+        named.extend(params.iter().cloned());
+        // Positional values on the command line replace the ones from the file
+        if !positional_params.is_empty() {
+            positional = positional_params.to_vec();
+        }
+        (named, positional)
+    }
+
+    fn pick_command_with_values(
+        &self,
+        name: &str,
+        named: &HashMap<String, String>,
+        positional: &[String],
+        print_only: bool,
+        json: bool,
+    ) -> Option<String> {
+        match self
+            .trove
+            .pick_command_with_values(&self.config, name, named, positional)
+        {
+            Ok(c) => {
+                let output = self.picked_output(&c, json);
+                if print_only {
+                    return Some(output);
+                }
+                Self::warn_missing_binaries(&c, self.config.parameter_token.as_deref().unwrap());
+                self.log_use(&c);
+                println!("{output}");
+                None
+            }
+            Err(e) => {
+                // Scripts rely on the exit code to notice missing parameters
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    /// Run a command and offer its related commands to run next, until the user is done
+    ///
+    /// Without a `name` the command is picked in the GUI, which fills in its parameters as well, and its output is
+    /// shown in a pane unless `no_pane`
+    #[allow(clippy::too_many_arguments)]
+    fn run_with_follow_ups(
+        &mut self,
+        name: Option<&str>,
+        values: Option<&ParameterValues>,
+        in_container: Option<&str>,
+        timeout: Option<u64>,
+        parallel: Option<u16>,
+        encoding: Option<OutputEncoding>,
+        no_pane: bool,
+    ) {
+        let (mut name, mut exit_code) = match name {
+            Some(name) => (
+                name.to_string(),
+                self.run_command(name, values, in_container, timeout, parallel, encoding),
+            ),
+            None => match self.pick_command_interactively() {
+                Ok(command) if !no_pane && can_show_gui() => (
+                    command.name.clone(),
+                    self.execute_in_pane(&command, in_container, timeout, encoding),
+                ),
+                Ok(command) => (
+                    command.name.clone(),
+                    self.execute_command(&command, &[], in_container, timeout, parallel, encoding),
+                ),
+                Err(name) => (
+                    name.clone(),
+                    self.run_command(&name, values, in_container, timeout, parallel, encoding),
+                ),
+            },
+        };
+        self.log_run(&name, exit_code);
+        // The overrides only apply to the command that was asked for, follow-ups run with their own settings
+        while let Some(next) = self.pick_related_command(&name, exit_code) {
+            exit_code = self.run_command(&next, None, None, None, None, None);
+            self.log_run(&next, exit_code);
+            name = next;
+        }
+        // Hand the exit code of the last command back to the caller
+        std::process::exit(exit_code);
+    }
+
+    /// Add a run to the run log used by `hoard stats`, and to the usage log
+    fn log_run(&self, name: &str, exit_code: i32) {
+        let Some(hoard_home_path) = self.config.hoard_home_path() else {
+            return;
+        };
+        let command = self.trove.get_command(name).ok();
+        let namespace = command.map(|command| command.namespace.as_str());
+        let record = RunRecord::new(name, namespace.unwrap_or_default(), exit_code);
+        if let Err(e) = append_run(&hoard_home_path.join(RUN_LOG_FILE), &record) {
+            info!("Could not log the run of [{name}]: {e}");
+        }
+        if let Some(command) = command {
+            self.log_use(command);
+        }
+    }
+
+    /// Add a pick, copy or run of `command` to the usage log used by `hoard stats` and the order of the GUI
+    fn log_use(&self, command: &HoardCmd) {
+        let Some(hoard_home_path) = self.config.hoard_home_path() else {
+            return;
+        };
+        let record = UsageRecord::new(command);
+        if let Err(e) = append_usage(&hoard_home_path.join(USAGE_LOG_FILE), &record) {
+            info!("Could not log the use of [{}]: {e}", command.name);
+        }
+    }
+
+    /// Point out commands that are hard to read, and suggest parameters for commands that are used with a different
+    /// value every time
+    fn doctor(&mut self, history: Option<&Path>) {
+        for command in &self.trove.commands {
+            for warning in self.config.complexity_warnings(&command.command) {
+                println!(
+                    "[{}] {warning}, consider moving it into a script",
+                    command.name
+                );
+            }
+        }
+        self.merge_case_duplicate_namespaces();
+        let Some(history_path) = history.map(Path::to_path_buf).or_else(shell_history_path) else {
+            eprintln!("Could not find your shell history, pass it with --history");
+            std::process::exit(1);
+        };
+        let history = load_shell_history(&history_path);
+        let token = self.config.parameter_token.clone().unwrap();
+        let suggestions = parameter_suggestions(&self.trove.commands, &history, &token);
+        if suggestions.is_empty() {
+            println!(
+                "No suggestions, checked {} against {} of {}",
+                pluralize(self.trove.commands.len(), "command", "commands"),
+                pluralize(history.len(), "entry", "entries"),
+                history_path.display()
+            );
+            return;
+        }
+        for suggestion in suggestions {
+            println!(
+                "[{}] '{}' was also used as {}, consider making it a parameter:\n    {}",
+                suggestion.name,
+                suggestion.literal,
+                suggestion
+                    .values
+                    .iter()
+                    .map(|value| format!("'{value}'"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                suggestion.suggested
+            );
+        }
+    }
+
+    /// Check what hoard depends on outside of the trove and print how to fix what doesn't work
+    ///
+    /// Exits with 1 if something hoard needs is broken
+    fn diagnose_environment(&self) {
+        let config_file = self
+            .config
+            .hoard_home_path()
+            .unwrap_or_default()
+            .join(HOARD_CONFIG);
+        let diagnoses = [
+            check_config_file(&config_file),
+            check_trove_file(self.trove_path.as_deref()),
+            check_shell_integration(
+                std::env::var(SHELL_INTEGRATION_VERSION_VAR).ok().as_deref(),
+                std::env::var("SHELL").ok().as_deref(),
+            ),
+            check_colors(detect_color_support(), self.config.color_support),
+            check_sync(
+                self.config.sync_server_url.as_deref(),
+                self.config.api_token.as_deref(),
+                |url| {
+                    // hoard runs in an async runtime, which the blocking client must not be used in
+                    let url = url.to_string();
+                    std::thread::spawn(move || {
+                        reqwest::blocking::Client::builder()
+                            .timeout(Duration::from_secs(5))
+                            .build()
+                            .and_then(|client| client.get(url).send())
+                            .map(|_| ())
+                            .map_err(|e| e.to_string())
+                    })
+                    .join()
+                    .unwrap_or_else(|_| Err(String::from("the request failed")))
+                },
+            ),
+        ];
+        for diagnosis in &diagnoses {
+            let status = match diagnosis.status {
+                Status::Ok => style("ok").green(),
+                Status::Warning => style("warning").yellow(),
+                Status::Error => style("error").red(),
+            };
+            println!("[{status}] {}: {}", diagnosis.check, diagnosis.message);
+            if let Some(fix) = &diagnosis.fix {
+                println!("    {fix}");
+            }
+        }
+        if diagnoses.iter().any(|d| d.status == Status::Error) {
+            std::process::exit(1);
+        }
+    }
+
+    /// Check the trove file entry by entry and fix the problems that can be fixed if `fix` is set
+    ///
+    /// Exits with 1 if there are problems left
+    fn check_trove(&mut self, fix: bool) {
+        let Some(trove_path) = self.trove_path.clone() else {
+            eprintln!("There is no trove file to check");
+            std::process::exit(1);
+        };
+        let files: Vec<PathBuf> = if trove_path.exists() {
+            vec![trove_path]
+        } else {
+            let mut files: Vec<PathBuf> = trove_files(&namespace_dir(&trove_path)).collect();
+            files.sort();
+            files
+        };
+        let mut trove = Trove::default();
+        let mut problems = Vec::new();
+        let mut is_readable = true;
+        for file in &files {
+            let file_name = file
+                .file_name()
+                .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+            let entries = fs::read_to_string(file)
+                .map_err(|e| HoardErr::new(&format!("Could not read {file_name}: {e}")))
+                .and_then(|content| read_trove_entries(&content, &file_name));
+            match entries {
+                Ok((file_trove, file_problems)) => {
+                    trove.commands.extend(file_trove.commands);
+                    trove.trash.extend(file_trove.trash);
+                    trove.include.extend(file_trove.include);
+                    problems.extend(file_problems);
+                }
+                Err(e) => {
+                    eprintln!("{e}");
+                    is_readable = false;
+                }
+            }
+        }
+        let checked = trove.commands.len();
+        problems.extend(check_commands(&mut trove, &self.config));
+        let fixable = problems.iter().filter(|p| p.fix.is_some()).count();
+        let is_fixed = fix && is_readable && fixable > 0;
+        for problem in &problems {
+            println!("{}", problem.describe(is_fixed));
+        }
+
+        if is_fixed {
+            trove
+                .commands
+                .extend(self.trove.commands.iter().filter(|c| c.is_managed).cloned());
+            self.trove = trove;
+            self.save_trove(None);
+            println!(
+                "Fixed {}, the trove before is in the backups",
+                pluralize(fixable, "problem", "problems")
+            );
+        } else if problems.is_empty() && is_readable {
+            println!(
+                "No problems found in {}",
+                pluralize(checked, "command", "commands")
+            );
+            return;
+        } else if !is_readable {
+            eprintln!("Fix the trove file by hand first, it can't be read as a whole");
+        } else if fixable > 0 {
+            println!("Run `hoard doctor --trove-file --fix` to fix {fixable} of them");
+        }
+        let remaining = if is_fixed {
+            problems.len() - fixable
+        } else {
+            problems.len()
+        };
+        if !is_readable || remaining > 0 {
+            std::process::exit(1);
+        }
+    }
+
+    /// Offer to merge namespaces like `Docker` and `docker` into one
+    fn merge_case_duplicate_namespaces(&mut self) {
+        let mut is_changed = false;
+        for group in self.trove.case_duplicate_namespaces() {
+            println!("Namespaces {} only differ by case", group.join(", "));
+            if !prompts_answerable() {
+                continue;
+            }
+            let mut options: Vec<&str> = group.iter().map(String::as_str).collect();
+            options.push("Keep them apart");
+            let selected = prompt_select_with_options("Merge them into", &options);
+            if let Some(into) = group.get(selected) {
+                let moved = self.trove.merge_namespaces(&group, into);
+                println!(
+                    "Moved {} to {into}",
+                    pluralize(moved, "command", "commands")
+                );
+                is_changed = true;
+            }
+        }
+        if is_changed {
+            self.save_trove(None);
+        }
+    }
+
+    /// Most used commands, the ones never used and the use of every namespace
+    fn show_stats(&self, export: Option<StatsFormat>) {
+        let hoard_home_path = self.config.hoard_home_path();
+        let runs = hoard_home_path
+            .as_ref()
+            .map(|path| load_runs(&path.join(RUN_LOG_FILE)))
+            .unwrap_or_default();
+        let month_ago = SystemTime::now() - Duration::from_secs(RECENT_USE_DAYS * 24 * 60 * 60);
+        let usage = hoard_home_path
+            .as_ref()
+            .map(|path| load_usage(&path.join(USAGE_LOG_FILE)))
+            .unwrap_or_default();
+        let mut stats = command_stats(&self.trove.commands, &runs, &usage, month_ago);
+        sort_by_use(&mut stats);
+        match export {
+            Some(StatsFormat::Csv) => print!("{}", stats_to_csv(&stats)),
+            None => {
+                let mut table = Table::new();
+                table.add_row(row![
+                    "Name",
+                    "namespace",
+                    format!("last {RECENT_USE_DAYS} days"),
+                    "times used",
+                    "last used",
+                    "runs",
+                    "success rate"
+                ]);
+                for stat in stats
+                    .iter()
+                    .filter(|stat| stat.usage_count > 0 || stat.runs > 0)
+                {
+                    let success_rate = stat
+                        .success_rate()
+                        .map(|rate| format!("{:.0}%", rate * 100.0))
+                        .unwrap_or_default();
+                    table.add_row(row![
+                        stat.name,
+                        stat.namespace,
+                        stat.recent_uses,
+                        stat.usage_count,
+                        format_time(stat.last_used),
+                        stat.runs,
+                        success_rate
+                    ]);
+                }
+                println!("Most used commands");
+                table.printstd();
+
+                let mut table = Table::new();
+                table.add_row(row!["Namespace", "commands", "times used"]);
+                for (namespace, commands, uses) in namespace_stats(&stats) {
+                    table.add_row(row![namespace, commands, uses]);
+                }
+                println!("\nNamespaces");
+                table.printstd();
+
+                let unused = unused_commands(&stats);
+                if !unused.is_empty() {
+                    let names: Vec<String> = unused
+                        .iter()
+                        .map(|stat| format!("[{}] {}", stat.namespace, stat.name))
+                        .collect();
+                    println!("\nNever used\n{}", names.join("\n"));
+                }
+            }
+        }
+    }
+
+    /// Ask which related command of `name` to run next, telling the user how the command ended
+    fn pick_related_command(&self, name: &str, exit_code: i32) -> Option<String> {
+        // Nobody to ask when run from a script, and a cancelled command shouldn't be followed up
+        if exit_code == 130 || !can_prompt() {
+            return None;
+        }
+        let command = self.trove.get_command(name).ok()?;
+        let related: Vec<&str> = command
+            .related
+            .iter()
+            .map(String::as_str)
+            .filter(|related| self.trove.get_command(related).is_ok())
+            .collect();
+        if related.is_empty() {
+            return None;
+        }
+        let result = if exit_code == 0 {
+            String::from("finished successfully")
+        } else {
+            format!("failed with exit code {exit_code}")
+        };
+        let mut options = related.clone();
+        options.push("Done");
+        let selected =
+            prompt_select_with_options(&format!("[{name}] {result}. Run next:"), &options);
+        related.get(selected).map(ToString::to_string)
+    }
+
+    /// Put the command `name` on the clipboard with its parameters filled in, picking it in the GUI without a name
+    fn copy_command(&mut self, name: Option<&str>, values: Option<&ParameterValues>) {
+        let picked = name.map_or_else(
+            || self.pick_command_interactively(),
+            |name| Err(name.to_string()),
+        );
+        let command = picked.or_else(|name| match values {
+            Some((named, positional)) => {
+                self.trove
+                    .pick_command_with_values(&self.config, &name, named, positional)
+            }
+            None => self.trove.pick_command(&self.config, &name),
+        });
+        let command = command.unwrap_or_else(|e| {
+            eprintln!("{e}");
+            std::process::exit(1);
+        });
+        if !write_clipboard(&command.command) {
+            eprintln!(
+                "Could not copy [{}], install xclip, xsel or wl-clipboard",
+                command.name
+            );
+            std::process::exit(1);
+        }
+        self.log_use(&command);
+        println!("Copied [{}] to the clipboard", command.name);
+    }
+
+    /// Pick a command in the GUI with its parameters filled in, or its name with a prompt if there is no GUI
+    fn pick_command_interactively(&mut self) -> Result<HoardCmd, String> {
+        if !can_show_gui() || prompts_scripted() {
+            return Err(self.prompt_command_name());
+        }
+        let watch = self.trove_watch();
+        let picked =
+            commands_gui::run(&mut self.trove, &self.config, &self.trove_name, watch, None);
+        self.save_trove(None);
+        match picked {
+            Ok(Some(command)) if !command.command.is_empty() => Ok(command),
+            Ok(_) => std::process::exit(0),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    /// Run the command `name` of the trove, with the parameter `values` of the command line or asking for them, and return its exit code
+    fn run_command(
+        &self,
+        name: &str,
+        values: Option<&ParameterValues>,
+        in_container: Option<&str>,
+        timeout: Option<u64>,
+        parallel: Option<u16>,
+        encoding: Option<OutputEncoding>,
+    ) -> i32 {
+        let token = self.config.parameter_token.clone().unwrap();
+        let ending_token = self.config.parameter_ending_token.clone().unwrap();
+        let escape = self.config.escape_char();
+        if let Some((named, positional)) = values {
+            let command = self
+                .trove
+                .pick_command_with_values(&self.config, name, named, positional)
+                .unwrap_or_else(|e| {
+                    eprintln!("{e}");
+                    std::process::exit(1);
+                });
+            return self.execute_command(&command, &[], in_container, timeout, parallel, encoding);
+        }
+        let command = match self.trove.get_command(name) {
+            Ok(c) => c.clone(),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        };
+
+        // List parameters expand the command into one invocation per value, the other parameters are asked for once
+        let list_parameters = command.list_parameters(&token, &ending_token, escape);
+        let list_values: Vec<(String, Vec<String>)> = list_parameters
+            .iter()
+            .map(|parameter| {
+                let validator = |input: &String| -> Result<(), String> {
+                    parameter.validate(input).map_err(|e| e.to_string())
+                };
+                let values = prompt_input_validate(
+                    &format!(
+                        "Enter the values of {token}{}{ending_token}, comma separated",
+                        parameter.name
+                    ),
+                    false,
+                    None,
+                    Some(validator),
+                );
+                (parameter.name.clone(), split_list_value(&values))
+            })
+            .collect();
+        let mut command =
+            command.with_list_markers(&token, &ending_token, escape, &list_parameters);
+        let command = command.with_input_parameters(
+            &token,
+            &ending_token,
+            escape,
+            &ProviderRegistry::from_config(&self.config),
+        );
+        self.execute_command(
+            &command,
+            &list_values,
+            in_container,
+            timeout,
+            parallel,
+            encoding,
+        )
+    }
+
+    /// Execute `command`, whose parameters are filled in, once for every combination of the `list_values`
+    fn execute_command(
+        &self,
+        command: &HoardCmd,
+        list_values: &[(String, Vec<String>)],
+        in_container: Option<&str>,
+        timeout: Option<u64>,
+        parallel: Option<u16>,
+        encoding: Option<OutputEncoding>,
+    ) -> i32 {
+        let name = &command.name;
+        let token = self.config.parameter_token.clone().unwrap();
+        Self::confirm_privileged_command(self.config.danger_policy, name, &command.command);
+        Self::warn_missing_binaries(command, &token);
+
+        let image = in_container
+            .or(command.container.as_deref())
+            .map(ToString::to_string);
+        let image = image.as_deref();
+        let timeout = timeout.or(command.timeout);
+        let encoding = encoding.or(command.encoding);
+        let mut invocations = expand_list_markers(&command.command, list_values);
+        if invocations.len() > 1 {
+            let processes = invocations
+                .into_iter()
+                .map(|(values, invocation)| {
+                    let invocation = command.clone().with_command(&invocation);
+                    (
+                        values.join(" "),
+                        self.build_process(&invocation, image, false),
+                    )
+                })
+                .collect();
+            let parallel = usize::from(parallel.unwrap_or(1));
+            match run_parallel(
+                processes,
+                parallel,
+                timeout.map(Duration::from_secs),
+                encoding.unwrap_or_default(),
+            ) {
+                Ok(outcomes) => return Self::print_run_summary(&outcomes),
+                Err(e) => {
+                    eprintln!("Could not run [{name}]: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        let (_, invocation) = invocations.remove(0);
+        let command = command.clone().with_command(&invocation);
+        let process = self.build_process(&command, image, true);
+        match run_process(process, timeout.map(Duration::from_secs), encoding) {
+            Ok(outcome) => {
+                match outcome {
+                    RunOutcome::TimedOut => {
+                        eprintln!("[{name}] timed out after {}s", timeout.unwrap_or_default());
+                    }
+                    RunOutcome::Cancelled => eprintln!("Cancelled [{name}]"),
+                    RunOutcome::Exited(_) => {}
+                }
+                outcome.exit_code()
+            }
+            Err(e) => {
+                eprintln!(
+                    "Could not run [{name}] with '{}': {e}",
+                    command.effective_shell()
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
+    /// Run `command` with its output shown live in a pane of the GUI, and print it once the pane is closed
+    fn execute_in_pane(
+        &self,
+        command: &HoardCmd,
+        in_container: Option<&str>,
+        timeout: Option<u64>,
+        encoding: Option<OutputEncoding>,
+    ) -> i32 {
+        let name = &command.name;
+        Self::confirm_privileged_command(self.config.danger_policy, name, &command.command);
+        let image = in_container.or(command.container.as_deref());
+        let timeout = timeout.or(command.timeout);
+        let encoding = encoding.or(command.encoding).unwrap_or_default();
+        let process = self.build_process(command, image, false);
+        let (outcome, lines) = output_pane::run(
+            name,
+            process,
+            timeout.map(Duration::from_secs),
+            encoding,
+            &self.config,
+        )
+        .unwrap_or_else(|e| {
+            eprintln!("Could not run [{name}]: {e}");
+            std::process::exit(1);
+        });
+        for line in lines {
+            if line.is_error {
+                eprintln!("{}", line.text);
+            } else {
+                println!("{}", line.text);
+            }
+        }
+        match outcome {
+            RunOutcome::TimedOut => {
+                eprintln!("[{name}] timed out after {}s", timeout.unwrap_or_default());
+            }
+            RunOutcome::Cancelled => eprintln!("Cancelled [{name}]"),
+            RunOutcome::Exited(_) => {}
+        }
+        outcome.exit_code()
+    }
+
+    /// Apply the danger policy to commands that use sudo or touch system paths, exiting if the user declines
+    fn confirm_privileged_command(policy: Option<DangerPolicy>, name: &str, command: &str) {
+        let Some(warning) = privilege_warning(command) else {
+            return;
+        };
+        match policy.unwrap_or_default() {
+            DangerPolicy::Confirm => {
+                let question = format!("[{name}] {warning}. Do you want to run it?");
+                if matches!(prompt_yes_or_no(&question), Confirmation::No) {
+                    eprintln!("Did not run [{name}]");
+                    std::process::exit(1);
+                }
+            }
+            DangerPolicy::Warn => eprintln!("⚠️  [{name}] {warning}"),
+            DangerPolicy::Allow => {}
+        }
+    }
+
+    /// Point out a command that is too long or complex to read, it is saved anyway
+    fn warn_complex_command(&self, command: &HoardCmd) {
+        for warning in self.config.complexity_warnings(&command.command) {
+            eprintln!(
+                "⚠️  [{}] {warning}, consider moving it into a script",
+                command.name
+            );
+        }
+    }
+
+    /// Point out programs of `command` that are not installed, without stopping it from being used
+    fn warn_missing_binaries(command: &HoardCmd, token: &str) {
+        let missing = command.missing_binaries(token, is_installed);
+        if !missing.is_empty() {
+            eprintln!("⚠️  [{}] {} not found", command.name, missing.join(", "));
+        }
+    }
+
+    /// Build the process running `command` locally with its shell or inside a container of `image`
+    fn build_process(
+        &self,
+        command: &HoardCmd,
+        image: Option<&str>,
+        with_terminal: bool,
+    ) -> std::process::Command {
+        let Some(image) = image else {
+            return shell_command(&command.effective_shell(), &command.command);
+        };
+        let Some(runtime) = container_runtime(self.config.container_runtime.as_deref()) else {
+            eprintln!("Could not find docker or podman to run [{}] in {image}. Set `container_runtime` in your config", command.name);
+            std::process::exit(1);
+        };
+        container_command(
+            &runtime,
+            image,
+            command.shell.as_deref(),
+            &command.command,
+            with_terminal,
+        )
+    }
+
+    /// Print which invocations of an expanded command succeeded and return the exit code for hoard
+    fn print_run_summary(outcomes: &[(String, RunOutcome)]) -> i32 {
+        let failed: Vec<_> = outcomes
+            .iter()
+            .filter(|(_, outcome)| outcome.exit_code() != 0)
+            .collect();
+        eprintln!(
+            "\n{} of {} succeeded",
+            outcomes.len() - failed.len(),
+            pluralize(outcomes.len(), "invocation", "invocations")
+        );
+        for (label, outcome) in &failed {
+            let reason = match outcome {
+                RunOutcome::TimedOut => String::from("timed out"),
+                RunOutcome::Cancelled => String::from("cancelled"),
+                RunOutcome::Exited(_) => format!("exit code {}", outcome.exit_code()),
+            };
+            eprintln!("  ❌ [{label}] {reason}");
+        }
+        if outcomes
+            .iter()
+            .any(|(_, outcome)| matches!(outcome, RunOutcome::Cancelled))
+        {
+            return 130;
+        }
+        i32::from(!failed.is_empty())
+    }
+
+    fn remove_command(&mut self, command_name: &str) {
+        let command_result = self.trove.remove_command(command_name);
+        match command_result {
+            Ok(()) => {
+                println!("Removed [{command_name}], [ hoard trash restore ] brings it back");
+            }
+            Err(e) => eprintln!("{e}"),
+        }
+        self.save_trove(None);
+    }
+
+    /// Remove every command matching `filter` after listing them, they all go to the trash
+    fn remove_matching(&mut self, filter: &str, yes: bool) {
+        let matching: Vec<&HoardCmd> = self
+            .trove
+            .commands
+            .iter()
+            .filter(|command| !command.is_managed && command_matches(command, filter))
+            .collect();
+        if matching.is_empty() {
+            println!("No commands match {filter}");
+            return;
+        }
+
+        let mut table = Table::new();
+        table.add_row(row!["Name", "namespace", "tags", "command"]);
+        for command in &matching {
+            table.add_row(row![
+                command.name,
+                command.namespace,
+                command.get_tags_as_string(),
+                command.command
+            ]);
+        }
+        table.printstd();
+        let question = format!(
+            "Remove {}?",
+            pluralize(matching.len(), "command", "commands")
+        );
+        if !yes && matches!(prompt_yes_or_no(&question), Confirmation::No) {
+            println!("Nothing removed");
+            return;
+        }
+
+        let removed = self
+            .trove
+            .remove_matching(|command| command_matches(command, filter));
+        self.save_trove(None);
+        println!(
+            "Removed {}, [ hoard trash restore ] brings them back",
+            pluralize(removed.len(), "command", "commands")
+        );
+    }
+
+    fn apply_changes(&mut self, path: &Path) {
+        let result = fs::read_to_string(path)
+            .map_err(|e| HoardErr::new(&format!("Could not read {}: {e}", path.display())))
+            .and_then(|content| parse_changes(&content))
+            .and_then(|changes| {
+                apply_changes(&self.trove, &changes, &self.config.default_namespace)
+            });
+        match result {
+            Ok((trove, summary)) => {
+                self.trove = trove;
+                self.save_trove(None);
+                println!(
+                    "Added {}, updated {} and deleted {}",
+                    pluralize(summary.added, "command", "commands"),
+                    pluralize(summary.updated, "command", "commands"),
+                    pluralize(summary.deleted, "command", "commands")
+                );
+            }
+            Err(e) => {
+                // Provisioning tools rely on the exit code to notice that nothing was changed
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    /// Print the commands with a field matching `pattern`, with the matches highlighted. Exits with 1 if none match
+    fn grep_commands(&self, pattern: &str, ignore_case: bool, json: bool) {
+        let pattern = grep_pattern(pattern, ignore_case).unwrap_or_else(|e| {
+            eprintln!("{e}");
+            std::process::exit(1);
+        });
+        let mut found = 0;
+        let mut outputs: Vec<CommandOutput> = Vec::new();
+        for command in &self.trove.commands {
+            let matches = grep_command(command, &pattern);
+            if matches.is_empty() {
+                continue;
+            }
+            found += 1;
+            if json {
+                let fields = matches.iter().map(|m| m.field).collect();
+                outputs.push(self.command_output(command).with_matched_fields(fields));
+                continue;
+            }
+            let name = matches
+                .iter()
+                .find(|m| m.field == "name")
+                .map_or_else(|| style(&command.name).bold().to_string(), highlight_ranges);
+            println!("[{}] {name}", command.namespace);
+            for field_match in matches.iter().filter(|m| m.field != "name") {
+                println!(
+                    "    {}: {}",
+                    field_match.field,
+                    highlight_ranges(field_match)
+                );
+            }
+        }
+        if json {
+            println!("{}", to_json(&outputs));
+        }
+        if found == 0 {
+            eprintln!("No command matches {pattern}");
+            std::process::exit(1);
+        }
+    }
+
+    fn tag_command(&mut self, command: &TagCommand) {
+        let (change, filter, yes) = match command {
+            TagCommand::List {} => {
+                let counts = tag_counts(&self.trove.commands);
+                if counts.is_empty() {
+                    println!("No command has a tag");
+                    return;
+                }
+                let mut table = Table::new();
+                table.add_row(row!["Tag", "commands"]);
+                for (tag, count) in counts {
+                    table.add_row(row![tag, count]);
+                }
+                table.printstd();
+                return;
+            }
+            TagCommand::Add { tag, filter, yes } => {
+                (TagChange::Add(tag.clone()), Some(filter.as_str()), *yes)
+            }
+            TagCommand::Remove { tag, filter, yes } => {
+                (TagChange::Remove(tag.clone()), filter.as_deref(), *yes)
+            }
+            TagCommand::Rename {
+                tag,
+                new_tag,
+                filter,
+                yes,
+            } => (
+                TagChange::Rename {
+                    from: tag.clone(),
+                    to: new_tag.clone(),
+                },
+                filter.as_deref(),
+                *yes,
+            ),
+        };
+        if let Err(e) = change.validate() {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        let changes: Vec<(usize, Vec<String>)> = self
+            .trove
+            .commands
+            .iter()
+            .enumerate()
+            .filter(|(_, command)| {
+                !command.is_managed && command_matches(command, filter.unwrap_or_default())
+            })
+            .filter_map(|(index, command)| change.apply(command).map(|tags| (index, tags)))
+            .collect();
+        if changes.is_empty() {
+            println!("No commands to change");
+            return;
+        }
+
+        let mut table = Table::new();
+        table.add_row(row!["Name", "namespace", "tags", "new tags"]);
+        for (index, tags) in &changes {
+            let command = &self.trove.commands[*index];
+            table.add_row(row![
+                command.name,
+                command.namespace,
+                command.get_tags_as_string(),
+                tags.join(",")
+            ]);
+        }
+        table.printstd();
+        let question = format!(
+            "Change the tags of {}?",
+            pluralize(changes.len(), "command", "commands")
+        );
+        if !yes && matches!(prompt_yes_or_no(&question), Confirmation::No) {
+            println!("Nothing changed");
+            return;
         }
-    }
 
-    fn new_command(
-        &mut self,
-        name: Option<String>,
-        tags: Option<String>,
-        command: Option<String>,
-        description: Option<String>,
-    ) {
-        let trove_namespaces = self.trove.namespaces();
-        //trove_namespaces.push(&default_ns_clone);
-        let new_command = HoardCmd::default()
-            .with_command_string_input(
-                command,
-                &self.config.parameter_token.clone().unwrap(),
-                &self.config.parameter_ending_token.clone().unwrap(),
-            )
-            .with_namespace_input(&trove_namespaces)
-            .with_name_input(name, &self.trove)
-            .with_description_input(description.unwrap_or_default())
-            .with_tags_input(tags);
-        let _ = self.trove.add_command(new_command, true);
+        let now = SystemTime::now();
+        for (index, tags) in &changes {
+            let command = &mut self.trove.commands[*index];
+            command.tags.clone_from(tags);
+            command.modified = now;
+        }
         self.save_trove(None);
+        println!(
+            "Changed the tags of {}",
+            pluralize(changes.len(), "command", "commands")
+        );
     }
 
-    fn list_commands(
-        &mut self,
-        is_simple: bool,
-        is_structured: bool,
-        filter: Option<String>,
-    ) -> Option<String> {
-        if self.trove.is_empty() {
-            println!("No command hoarded.\nRun [ hoard new ] first to hoard a command.");
-        } else if is_simple {
-            self.trove.print_trove();
-        } else if is_structured {
-            // Return list of commands in json format, filtered by `filter`
-            let query_string: String = filter.unwrap_or_default();
-            let filtered_trove = query_trove(&self.trove, &query_string);
-            return Some(filtered_trove.to_yaml());
-        } else {
-            match commands_gui::run(&mut self.trove, &self.config) {
-                Ok(selected_command) => {
-                    self.save_trove(None);
-                    if let Some(c) = selected_command {
-                        // Is set if a command is selected in GUI
-                        if !c.command.is_empty() {
-                            //TODO: If run as cli program, copy command into clipboard, else will be written to READLINE_LINE
-                            return Some(c.command);
-                        }
+    /// Apply a substitution to the commands matching `query`, saving the approved replacements all at once
+    fn replace_in_commands(&mut self, expression: &str, query: &str, yes: bool) {
+        let substitution = Substitution::parse(expression).unwrap_or_else(|e| {
+            eprintln!("{e}");
+            std::process::exit(1);
+        });
+        let replacements: Vec<(usize, String)> = self
+            .trove
+            .commands
+            .iter()
+            .enumerate()
+            .filter(|(_, command)| !command.is_managed && command_matches(command, query))
+            .filter_map(|(index, command)| {
+                substitution
+                    .apply(&command.command)
+                    .map(|replaced| (index, replaced))
+            })
+            .collect();
+        if replacements.is_empty() {
+            println!("No commands to change");
+            return;
+        }
+
+        let mut approved = Vec::new();
+        let mut approve_all = yes;
+        for (index, replaced) in replacements {
+            let command = &self.trove.commands[index];
+            println!("[{}] {}", command.namespace, style(&command.name).bold());
+            print_line_diff(&command.command, &replaced);
+            if !approve_all {
+                let options = [
+                    "Replace",
+                    "Skip",
+                    "Replace all remaining",
+                    "Quit without changes",
+                ];
+                match prompt_select_with_options("Replace in this command?", &options) {
+                    0 => {}
+                    1 => continue,
+                    2 => approve_all = true,
+                    _ => {
+                        println!("Nothing changed");
+                        return;
                     }
                 }
-                Err(e) => {
-                    println!("{e}");
-                }
             }
+            approved.push((index, replaced));
         }
-        None
-    }
 
-    fn pick_command(&mut self, name: &str) {
-        let command_result = self.trove.pick_command(&self.config, name);
-        match command_result {
-            Ok(c) => {
-                println!("{}", c.command);
-            }
-            Err(e) => eprintln!("{e}"),
+        let now = SystemTime::now();
+        for (index, replaced) in &approved {
+            let command = &mut self.trove.commands[*index];
+            command.command.clone_from(replaced);
+            command.modified = now;
         }
+        if !approved.is_empty() {
+            self.save_trove(None);
+        }
+        println!(
+            "Replaced in {}",
+            pluralize(approved.len(), "command", "commands")
+        );
     }
 
-    fn remove_command(&mut self, command_name: &str) {
-        let command_result = self.trove.remove_command(command_name);
+    fn remove_namespace(&mut self, namespace: &str) {
+        let command_result = self.trove.remove_namespace_commands(namespace);
         match command_result {
             Ok(()) => {
-                println!("Removed [{command_name}]");
+                println!("Removed all commands of namespace [{namespace}]");
             }
             Err(e) => eprintln!("{e}"),
         }
         self.save_trove(None);
     }
 
-    fn remove_namespace(&mut self, namespace: &str) {
-        let command_result = self.trove.remove_namespace_commands(namespace);
-        match command_result {
-            Ok(()) => {
-                println!("Removed all commands of namespace [{namespace}]");
+    fn import_trove(&mut self, path: &str, format: Option<ImportFormat>, yes: bool) {
+        let fetched = match Url::parse(path) {
+            Ok(url) => fetch_text(url),
+            Err(ParseError::RelativeUrlWithoutBase) => {
+                read_trove_file(Path::new(path)).map(|content| (content, None))
             }
-            Err(e) => eprintln!("{e}"),
+            Err(_) => Err(HoardErr::new("Not a valid URL or file path")),
+        };
+        let imported_trove = fetched.and_then(|(content, content_type)| {
+            parse_trove(
+                &content,
+                format.unwrap_or_else(|| detect_format(content_type.as_deref(), path, &content)),
+                &self.config,
+            )
+        });
+        let imported_trove = match imported_trove {
+            Ok(trove) => trove,
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        };
+        self.merge_imported(&imported_trove, yes);
+    }
+
+    fn import_tldr(&mut self, tool: &str, yes: bool) {
+        let page = match fetch_page(tool) {
+            Ok(page) => page,
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        };
+        let commands = page_commands(
+            &page,
+            tool,
+            &self.config.parameter_token.clone().unwrap(),
+            &self.config.parameter_ending_token.clone().unwrap(),
+            self.config.escape_char(),
+        );
+        self.merge_imported(&Trove::from_commands(&commands), yes);
+    }
+
+    /// Merge the commands of an imported trove that aren't hoarded yet, after the user reviewed them
+    fn merge_imported(&mut self, imported_trove: &Trove, yes: bool) {
+        let new_commands: Vec<&HoardCmd> = imported_trove
+            .commands
+            .iter()
+            .filter(|command| !self.trove.commands.contains(command))
+            .collect();
+        if new_commands.is_empty() {
+            println!("All commands of the trove are hoarded already");
+            return;
+        }
+        if !yes && !self.review_import(&new_commands) {
+            eprintln!("Did not import the trove");
+            std::process::exit(1);
+        }
+        let imported = new_commands.len();
+        if yes {
+            self.trove.merge_trove(imported_trove);
+        } else {
+            self.merge_resolving_conflicts(imported_trove);
         }
         self.save_trove(None);
+        println!("Imported {}", pluralize(imported, "command", "commands"));
     }
 
-    fn import_trove(&mut self, path: &str) {
-        match Url::parse(path) {
-            Ok(url) => match reqwest_trove(url) {
-                Ok(trove_string) => {
-                    let imported_trove = Trove::load_trove_from_string(&trove_string[..]);
-                    self.trove.merge_trove(&imported_trove);
-                    self.save_trove(None);
-                }
-                Err(e) => {
-                    println!("Could not import trove from url: {e}");
-                }
-            },
-            Err(err) => {
-                if err == ParseError::RelativeUrlWithoutBase {
-                    let imported_trove = Trove::load_trove_file(&Some(PathBuf::from(path)));
-                    self.trove.merge_trove(&imported_trove);
-                    self.save_trove(None);
-                } else {
-                    eprintln!("Not a valid URL or file path");
-                }
+    /// Merge `incoming` into the trove, asking what to do about every command with the name of a different local one
+    fn merge_resolving_conflicts(&mut self, incoming: &Trove) -> bool {
+        let config = &self.config;
+        self.trove
+            .merge_trove_resolving(incoming, |local, incoming, trove| {
+                resolve_conflict(local, incoming, trove, config)
+            })
+    }
+
+    /// Show the commands an import adds with what to look out for, returns whether the user wants to import them
+    fn review_import(&self, commands: &[&HoardCmd]) -> bool {
+        let mut table = Table::new();
+        table.add_row(row!["Name", "namespace", "command", "review"]);
+        for command in commands {
+            let mut notes: Vec<String> = privilege_warning(&command.command)
+                .map(|warning| format!("⚠️  {warning}"))
+                .into_iter()
+                .collect();
+            if self.trove.get_command_collision(command).is_some() {
+                notes.push(String::from("has the name of one of your commands"));
             }
+            table.add_row(row![
+                command.name,
+                command.namespace,
+                command.command,
+                notes.join(", ")
+            ]);
         }
+        table.printstd();
+        let question = format!(
+            "Import {}?",
+            pluralize(commands.len(), "command", "commands")
+        );
+        matches!(prompt_yes_or_no(&question), Confirmation::Yes)
     }
 
-    fn export_command(&self, path: &str) {
+    fn export_command(
+        &self,
+        path: &str,
+        format: ExportFormat,
+        scrub: bool,
+        shell: Option<AliasShell>,
+    ) {
+        let shell = shell.or_else(AliasShell::from_env);
+        if format == ExportFormat::Aliases && shell.is_none() {
+            eprintln!("Choose the shell to write aliases for with --shell bash, zsh or fish");
+            std::process::exit(1);
+        }
         let target_path = PathBuf::from(path);
         if target_path.file_name().is_some() {
             let namespaces = self.trove.namespaces();
@@ -275,16 +2223,104 @@ impl Hoard {
             }
 
             let mut trove_for_export = Trove::default();
+            let mut scrubbed_count = 0;
             for command in selected_commands {
-                let _ = trove_for_export.add_command(command.clone(), true);
+                let exported = self.command_for_export(command, scrub);
+                if exported.command != command.command
+                    || exported.description != command.description
+                {
+                    scrubbed_count += 1;
+                }
+                let _ = trove_for_export.add_command(exported, true);
+            }
+            if scrub {
+                println!(
+                    "Replaced details in {scrubbed_count} commands with parameters, review the trove before sharing it"
+                );
             }
 
-            trove_for_export.save_trove_file(&target_path);
+            let start_token = self.config.parameter_token.as_deref().unwrap();
+            let end_token = self.config.parameter_ending_token.as_deref().unwrap();
+            let escape = self.config.escape_char();
+            let content = match format {
+                ExportFormat::Yaml => trove_for_export.to_yaml(),
+                ExportFormat::Json => trove_for_export.to_json(),
+                ExportFormat::Md => trove_for_export.to_markdown(),
+                ExportFormat::Csv => to_csv(&trove_for_export),
+                ExportFormat::Toml => to_toml(&trove_for_export),
+                ExportFormat::Html => to_html(&trove_for_export),
+                ExportFormat::Iterm2 => {
+                    iterm2_snippets(&trove_for_export.commands, start_token, end_token, escape)
+                }
+                ExportFormat::Aliases => shell_aliases(
+                    &trove_for_export.commands,
+                    shell.unwrap_or(AliasShell::Bash),
+                    start_token,
+                    end_token,
+                    escape,
+                ),
+                ExportFormat::Warp => {
+                    Self::export_warp_workflows(
+                        &trove_for_export,
+                        &target_path,
+                        (start_token, end_token, escape),
+                    );
+                    return;
+                }
+            };
+            if let Err(e) = fs::write(&target_path, content) {
+                eprintln!("Could not write {}: {e}", target_path.display());
+                std::process::exit(1);
+            }
         } else {
             println!("No valid path with filename provided.");
         }
     }
 
+    /// Write a Warp workflow file for every command into `dir`
+    fn export_warp_workflows(trove: &Trove, dir: &Path, tokens: (&str, &str, char)) {
+        let (start_token, end_token, escape) = tokens;
+        if let Err(e) = fs::create_dir_all(dir) {
+            eprintln!("Could not create {}: {e}", dir.display());
+            std::process::exit(1);
+        }
+        for command in &trove.commands {
+            let path = dir.join(warp_workflow_file_name(command));
+            let workflow = warp_workflow(command, start_token, end_token, escape);
+            if let Err(e) = fs::write(&path, workflow) {
+                eprintln!("Could not write {}: {e}", path.display());
+                std::process::exit(1);
+            }
+        }
+        println!(
+            "Wrote {} to {}",
+            pluralize(trove.commands.len(), "Warp workflow", "Warp workflows"),
+            dir.display()
+        );
+    }
+
+    /// Copy of `command` to write to an export. With `scrub` the infrastructure details and secrets in the command
+    /// and its description are replaced with parameters and the context it was hoarded in is left out
+    fn command_for_export(&self, command: &HoardCmd, scrub: bool) -> HoardCmd {
+        let mut command = command.clone();
+        command.is_managed = false;
+        command.overlay = None;
+        if scrub {
+            let username = std::env::var("USER")
+                .or_else(|_| std::env::var("USERNAME"))
+                .ok();
+            let mut scrubber = Scrubber::new(
+                self.config.parameter_token.as_ref().unwrap(),
+                self.config.parameter_ending_token.as_ref().unwrap(),
+                username.as_deref(),
+            );
+            command.command = scrubber.scrub(&command.command);
+            command.description = scrubber.scrub(&command.description);
+            command.context = None;
+        }
+        command
+    }
+
     pub fn set_parameter_token(&self, parameter_token: &str) {
         if let Some(config_path) = self.config.config_home_path.clone() {
             if !save_parameter_token(&self.config, &config_path, parameter_token) {
@@ -294,6 +2330,10 @@ impl Hoard {
     }
 
     fn edit_command(&mut self, command_name: &str) {
+        if self.trove.is_managed(command_name) {
+            eprintln!("[{command_name}] is a managed command and can't be edited");
+            return;
+        }
         println!("Editing {command_name}");
         let command_to_edit = self.trove.pick_command(&self.config, command_name);
 
@@ -310,7 +2350,12 @@ impl Hoard {
                     .with_name_input(Some(c.name.clone()), &self.trove)
                     .with_description_input(c.description.clone())
                     .with_tags_input(Some(c.get_tags_as_string()))
-                    .with_namespace_input(&trove_namespaces);
+                    .with_namespace_input(&trove_namespaces, Some(&c.namespace))
+                    .with_shell(c.shell.as_deref().unwrap_or_default())
+                    .with_container(c.container.as_deref().unwrap_or_default())
+                    .with_timeout(c.timeout)
+                    .with_related(&c.related.join(","));
+                self.warn_complex_command(&new_command);
                 self.trove.remove_command(command_name).ok();
                 let _ = self.trove.add_command(new_command, true);
                 self.save_trove(None);
@@ -319,6 +2364,303 @@ impl Hoard {
         }
     }
 
+    /// The namespace and name of the command at `path`, which can leave out the namespace if only one has the name
+    fn command_path_namespace<'a>(&self, path: &'a str) -> (String, &'a str) {
+        let fail = |message: &str| -> ! {
+            eprintln!("{message}");
+            std::process::exit(1);
+        };
+        let (namespace, name) = split_command_path(path);
+        let namespace = namespace.map_or_else(
+            || {
+                let namespaces: Vec<&str> = self
+                    .trove
+                    .commands
+                    .iter()
+                    .filter(|c| c.name == name)
+                    .map(|c| c.namespace.as_str())
+                    .collect();
+                match namespaces.as_slice() {
+                    [namespace] => (*namespace).to_string(),
+                    [] => fail(&format!("No command [{name}] in the trove")),
+                    _ => fail(&format!(
+                        "There is a command [{name}] in the namespaces {}, choose one like {}/{name}",
+                        namespaces.join(", "),
+                        namespaces[0]
+                    )),
+                }
+            },
+            ToString::to_string,
+        );
+        (namespace, name)
+    }
+
+    /// Move the command at `from` to `to`, both given as `namespace/name`, and ask what to do if `to` is taken
+    fn move_command(&mut self, from: &str, to: &str) {
+        let fail = |message: &str| -> ! {
+            eprintln!("{message}");
+            std::process::exit(1);
+        };
+        let (namespace, name) = self.command_path_namespace(from);
+        let (new_namespace, new_name) = split_command_path(to);
+        let new_namespace = new_namespace.unwrap_or(&namespace).to_string();
+        let mut new_name = if new_name.is_empty() { name } else { new_name }.to_string();
+
+        let taken = self
+            .trove
+            .get_command_collision(
+                &HoardCmd::default()
+                    .with_namespace(&new_namespace)
+                    .with_name(&new_name),
+            )
+            .filter(|existing| existing.namespace != namespace || existing.name != name);
+        if let Some(existing) = taken {
+            let free_name = self.trove.free_name(&new_name, &new_namespace);
+            if existing.is_managed || !prompts_answerable() {
+                fail(&format!(
+                    "There already is a command [{new_name}] in namespace [{new_namespace}], [{free_name}] is free"
+                ));
+            }
+            let Some(command) = self
+                .trove
+                .commands
+                .iter()
+                .find(|c| c.namespace == namespace && c.name == name)
+                .cloned()
+            else {
+                fail(&format!("No command [{name}] in namespace [{namespace}]"));
+            };
+            let incoming = command.with_namespace(&new_namespace).with_name(&new_name);
+            match resolve_conflict(&existing, &incoming, &self.trove, &self.config).as_slice() {
+                [kept] if *kept == existing => {
+                    println!("Nothing moved");
+                    return;
+                }
+                [_] => {
+                    self.trove.commands.retain(|c| *c != existing);
+                    self.trove.move_to_trash(vec![existing]);
+                }
+                [_, renamed, ..] => new_name.clone_from(&renamed.name),
+                [] => return,
+            }
+        }
+
+        match self
+            .trove
+            .move_command(&namespace, name, &new_namespace, &new_name)
+        {
+            Ok(moved) => {
+                self.save_trove(None);
+                println!(
+                    "Moved [{namespace}/{name}] to [{}/{}]",
+                    moved.namespace, moved.name
+                );
+            }
+            Err(e) => fail(&e.to_string()),
+        }
+    }
+
+    /// Print a command with its parameters, its dates and how often it was used
+    fn show_command(&self, path: &str, json: bool) {
+        let (namespace, name) = self.command_path_namespace(path);
+        let Some(command) = self
+            .trove
+            .commands
+            .iter()
+            .find(|c| c.namespace == namespace && c.name == name)
+        else {
+            eprintln!("No command [{name}] in namespace [{namespace}]");
+            std::process::exit(1);
+        };
+        let output = self.command_output(command);
+        if json {
+            println!("{}", to_json(&output));
+            return;
+        }
+
+        let hoard_home_path = self.config.hoard_home_path();
+        let runs = hoard_home_path
+            .as_ref()
+            .map(|path| load_runs(&path.join(RUN_LOG_FILE)))
+            .unwrap_or_default();
+        let usage = hoard_home_path
+            .as_ref()
+            .map(|path| load_usage(&path.join(USAGE_LOG_FILE)))
+            .unwrap_or_default();
+        let used_values = hoard_home_path
+            .as_ref()
+            .map(|path| UsedValues::load(&path.join(USED_VALUES_FILE)))
+            .unwrap_or_default();
+        let month_ago = SystemTime::now() - Duration::from_secs(RECENT_USE_DAYS * 24 * 60 * 60);
+        let stats = command_stats(std::slice::from_ref(command), &runs, &usage, month_ago);
+        let time = |time: SystemTime| {
+            DateTime::<Local>::from(time)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        };
+        // Continuation lines of a value line up under its first line
+        let field = |label: &str, value: &str| {
+            println!(
+                "{} {}",
+                style(format!("{label:<11}")).bold(),
+                value.replace('\n', &format!("\n{:12}", ""))
+            );
+        };
+
+        println!(
+            "{} {}",
+            style(&command.name).bold().cyan(),
+            style(format!("({namespace})")).dim()
+        );
+        if !command.description.is_empty() {
+            println!("{}", command.description);
+        }
+        println!();
+        field("Command", &command.command);
+        if !command.tags.is_empty() {
+            field("Tags", &command.tags.join(", "));
+        }
+        if !output.parameters.is_empty() {
+            let parameters: Vec<String> = output
+                .parameters
+                .iter()
+                .map(|parameter| {
+                    let last_used = used_values.values(&command.name, &parameter.name).first();
+                    parameter.summary(
+                        last_used
+                            .filter(|_| !parameter.name.is_empty())
+                            .map(String::as_str),
+                    )
+                })
+                .collect();
+            field("Parameters", &parameters.join("\n"));
+        }
+        field("Created", &time(command.created));
+        field("Modified", &time(command.modified));
+        if let Some(stat) = stats.first() {
+            if stat.usage_count == 0 && stat.runs == 0 {
+                field("Used", "never");
+            } else {
+                let mut used = format!(
+                    "{}, last on {}",
+                    pluralize(stat.usage_count, "time", "times"),
+                    time(stat.last_used)
+                );
+                if let Some(rate) = stat.success_rate() {
+                    let _ = write!(
+                        used,
+                        "\nran {}, {:.0}% successful",
+                        pluralize(stat.runs, "time", "times"),
+                        rate * 100.0
+                    );
+                }
+                field("Used", &used);
+            }
+        }
+        for (label, value) in command_settings(command) {
+            field(label, &value);
+        }
+    }
+
+    /// Print a rarely used command to rediscover, with `daily` only if none was printed today
+    fn random_command(&self, namespace: Option<&str>, daily: bool) {
+        let hoard_home_path = self.config.hoard_home_path();
+        let last_tip_path = hoard_home_path
+            .as_ref()
+            .map(|path| path.join(LAST_TIP_FILE));
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        if daily
+            && !last_tip_path
+                .as_ref()
+                .is_some_and(|path| is_tip_due(path, &today))
+        {
+            return;
+        }
+        let commands: Vec<HoardCmd> = self
+            .trove
+            .commands
+            .iter()
+            .filter(|command| !command.is_hidden)
+            .filter(|command| namespace.is_none_or(|namespace| command.namespace == namespace))
+            .cloned()
+            .collect();
+        let runs = hoard_home_path
+            .as_ref()
+            .map(|path| load_runs(&path.join(RUN_LOG_FILE)))
+            .unwrap_or_default();
+        let usage = hoard_home_path
+            .as_ref()
+            .map(|path| load_usage(&path.join(USAGE_LOG_FILE)))
+            .unwrap_or_default();
+        let stats = command_stats(&commands, &runs, &usage, SystemTime::now());
+        let candidates: Vec<(&HoardCmd, usize)> = commands
+            .iter()
+            .zip(&stats)
+            .map(|(command, stat)| (command, stat.usage_count.max(stat.runs)))
+            .collect();
+        let Some(command) = pick_rarely_used(&candidates, &mut rand::thread_rng()) else {
+            // Nothing to tell at the prompt, the trove may just be empty
+            if daily {
+                return;
+            }
+            match namespace {
+                Some(namespace) => eprintln!("No commands in namespace [{namespace}]"),
+                None => eprintln!("No commands in the trove"),
+            }
+            std::process::exit(1);
+        };
+
+        let path = format!("{}/{}", command.namespace, command.name);
+        if command.description.is_empty() {
+            println!("{} {}", style("Did you know?").bold(), style(&path).cyan());
+        } else {
+            println!(
+                "{} {} {}",
+                style("Did you know?").bold(),
+                style(&path).cyan(),
+                command.description
+            );
+        }
+        println!("  {}", command.command.replace('\n', "\n  "));
+        println!("{}", style(format!("More with `hoard show {path}`")).dim());
+        if daily {
+            if let Some(path) = last_tip_path {
+                let _ = fs::write(path, format!("{today}\n"));
+            }
+        }
+    }
+
+    /// Edit the command, description and tags of a command as YAML in `$EDITOR`
+    fn edit_command_in_editor(&mut self, command_name: &str) {
+        if self.trove.is_managed(command_name) {
+            eprintln!("[{command_name}] is a managed command and can't be edited");
+            std::process::exit(1);
+        }
+        let command = self
+            .trove
+            .get_command(command_name)
+            .cloned()
+            .unwrap_or_else(|e| {
+                eprintln!("{e}");
+                std::process::exit(1);
+            });
+        let edited = edit_text(&command_buffer(&command), &format!("{command_name}.yml"))
+            .and_then(|buffer| apply_buffer(&command, &buffer));
+        match edited {
+            Ok(Some(edited)) => {
+                self.warn_complex_command(&edited);
+                self.trove.update_command_by_name(&edited);
+                self.save_trove(None);
+                println!("Saved [{command_name}]");
+            }
+            Ok(None) => println!("[{command_name}] is unchanged"),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+    }
+
     fn shell_config_command(shell: &str) {
         let src = match shell {
             "bash" => include_str!("shell/hoard.bash"),
@@ -333,27 +2675,114 @@ impl Hoard {
     }
 
     pub fn load_trove(&mut self) -> &mut Self {
-        self.trove = Trove::load_trove_file(&self.config.trove_path);
+        self.trove = self
+            .trove_path
+            .as_ref()
+            .map_or_else(Trove::default, |path| self.load_stored_trove(path));
+        let managed_path = self
+            .config
+            .managed_trove_file()
+            .filter(|path| path.exists() && Some(path) != self.trove_path.as_ref());
+        if let Some(path) = managed_path {
+            self.trove
+                .merge_managed(&Trove::load_trove_file(&Some(path)));
+        }
+        self.merge_overlays();
+        self.merge_includes();
         self
     }
 
+    /// The trove stored at `path`, read from its parsed copy in the cache if it didn't change since it was cached
+    fn load_stored_trove(&self, path: &Path) -> Trove {
+        let Some(hoard_home) = self.config.hoard_home_path() else {
+            return Trove::load_stored(path, self.stores_per_namespace());
+        };
+        load_cached(&hoard_home, path).unwrap_or_else(|| {
+            let trove = Trove::load_stored(path, self.stores_per_namespace());
+            save_cached(&hoard_home, path, &trove);
+            trove
+        })
+    }
+
+    /// Merge the troves listed under `include:` in the trove and in the config, named by their file
+    fn merge_includes(&mut self) {
+        let mut included = Vec::new();
+        let hoard_home = self.config.hoard_home_path();
+        if let Some(trove_path) = &self.trove_path {
+            included.extend(load_includes(
+                &self.trove.include,
+                trove_path,
+                hoard_home.as_deref(),
+            ));
+        }
+        if let (Some(includes), Some(hoard_home)) = (&self.config.include, &hoard_home) {
+            included.extend(load_includes(
+                includes,
+                &hoard_home.join(HOARD_CONFIG),
+                Some(hoard_home),
+            ));
+        }
+        for include in included {
+            self.trove.merge_overlay(&include.trove, &include.name);
+        }
+    }
+
+    /// Merge the read-only troves of `overlay_trove_paths`, named by their file. Files that aren't there are skipped,
+    /// e.g. on a network drive that isn't mounted
+    fn merge_overlays(&mut self) {
+        let paths = self.config.overlay_trove_paths.clone().unwrap_or_default();
+        for path in paths
+            .into_iter()
+            .filter(|path| path.is_file() && Some(path) != self.trove_path.as_ref())
+        {
+            let name = path
+                .file_stem()
+                .map_or_else(String::new, |stem| stem.to_string_lossy().to_string());
+            self.trove
+                .merge_overlay(&Trove::load_trove_file(&Some(path)), &name);
+        }
+    }
+
     pub fn save_trove(&self, path: Option<&Path>) {
-        let path_to_save = path.unwrap_or_else(|| self.config.trove_path.as_ref().unwrap());
-        self.trove.save_trove_file(path_to_save);
+        let path_to_save = path.unwrap_or_else(|| self.trove_path.as_ref().unwrap());
+        // Named troves are kept in a directory of their own, which doesn't exist before the first one is saved
+        if let Some(parent) = path_to_save
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            let _ = fs::create_dir_all(parent);
+        }
+        if path.is_some() {
+            self.trove.without_managed().save_trove_file(path_to_save);
+            return;
+        }
+        self.back_up_trove(path_to_save);
+        let trove = self.trove.without_managed();
+        trove.save_stored(path_to_save, self.stores_per_namespace());
+        if let Some(hoard_home) = self.config.hoard_home_path() {
+            save_cached(&hoard_home, path_to_save, &trove);
+        }
+        self.commit_to_git();
+    }
+
+    /// Whether the trove is kept in a file per namespace
+    ///
+    /// A `trove.yml` read from the current directory stays a single file, it belongs to the project that is in there
+    fn stores_per_namespace(&self) -> bool {
+        self.config.split_namespaces == Some(true)
+            && self.trove_path.as_deref() != Some(Path::new(HOARD_FILE))
     }
 
     fn save_backup_trove(&self, path: Option<&Path>) {
-        let backup_trove_path_str = format!(
-            "{}.bk",
-            self.config.trove_path.as_ref().unwrap().to_str().unwrap()
-        );
+        let backup_trove_path_str =
+            format!("{}.bk", self.trove_path.as_ref().unwrap().to_str().unwrap());
         let backup_trove_path = PathBuf::from_str(&backup_trove_path_str).ok().unwrap();
         let path_to_save = path.unwrap_or(&backup_trove_path);
-        self.trove.save_trove_file(path_to_save);
+        self.trove.without_managed().save_trove_file(path_to_save);
     }
 
     fn revert_trove(&self) {
-        let trove_path = self.config.trove_path.as_ref().unwrap();
+        let trove_path = self.trove_path.as_ref().unwrap();
         let backup_trove_path_str = format!("{}.bk", trove_path.to_str().unwrap());
         let backup_trove_path = PathBuf::from_str(&backup_trove_path_str).ok().unwrap();
         if backup_trove_path.exists() {
@@ -443,7 +2872,7 @@ impl Hoard {
         let token = self.config.api_token.clone();
         let client = reqwest::blocking::Client::new();
         let save_url = format!("{}v1/trove", self.config.sync_server_url.clone().unwrap());
-        let trove_file = fs::read_to_string(self.config.trove_path.clone().unwrap()).unwrap();
+        let trove_file = fs::read_to_string(self.trove_path.clone().unwrap()).unwrap();
         let body = client
             .put(save_url)
             .body(trove_file)
@@ -498,7 +2927,11 @@ impl Hoard {
                 if let Some(t) = trove {
                     // Prepare backup trove to enable reverting if merge goes all wrong, or user incorrectly removes commands they wanted to keep
                     self.save_backup_trove(None);
-                    let was_dirty = self.trove.merge_trove(&t);
+                    let was_dirty = if prompts_answerable() {
+                        self.merge_resolving_conflicts(&t)
+                    } else {
+                        self.trove.merge_trove(&t)
+                    };
                     if was_dirty {
                         self.save_trove(None);
                         println!("All done!");
@@ -520,8 +2953,222 @@ impl Hoard {
     }
 }
 
-#[tokio::main]
-async fn reqwest_trove(url: Url) -> Result<String, Box<dyn std::error::Error>> {
-    let resp = reqwest::get(url).await?.text().await?;
-    Ok(resp)
+/// Read the parameter values of `hoard pick --params-file`
+fn read_values_file(path: &Path) -> Result<ParameterValues, HoardErr> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| HoardErr::new(&format!("Could not read {}: {e}", path.display())))?;
+    parse_values_file(&content)
+}
+
+/// Print what `hoard migrate` couldn't translate as it was
+fn print_migration_notes(notes: &[String]) {
+    if notes.is_empty() {
+        return;
+    }
+    println!("{}", style("Not everything could be translated:").yellow());
+    for note in notes {
+        println!("  - {note}");
+    }
+}
+
+/// The text of a field `hoard grep` matched, with the matches in bold red
+fn highlight_ranges(field_match: &FieldMatch) -> String {
+    let text = &field_match.text;
+    let mut highlighted = String::new();
+    let mut end = 0;
+    for range in &field_match.ranges {
+        highlighted.push_str(&text[end..range.start]);
+        highlighted.push_str(&style(&text[range.clone()]).red().bold().to_string());
+        end = range.end;
+    }
+    highlighted.push_str(&text[end..]);
+    highlighted
+}
+
+/// Print the lines `hoard replace` changes, the old ones in red and the new ones in green
+fn print_line_diff(old: &str, new: &str) {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    if old_lines.len() == new_lines.len() {
+        for (old_line, new_line) in old_lines.iter().zip(&new_lines) {
+            if old_line == new_line {
+                println!("  {old_line}");
+            } else {
+                println!("{}", style(format!("- {old_line}")).red());
+                println!("{}", style(format!("+ {new_line}")).green());
+            }
+        }
+    } else {
+        for old_line in old_lines {
+            println!("{}", style(format!("- {old_line}")).red());
+        }
+        for new_line in new_lines {
+            println!("{}", style(format!("+ {new_line}")).green());
+        }
+    }
+}
+
+/// Labels and values of the settings of `command` that `hoard show` prints if they are set
+fn command_settings(command: &HoardCmd) -> Vec<(&'static str, String)> {
+    let mut settings = Vec::new();
+    if command.is_favorite {
+        settings.push(("Favorite", String::from("yes")));
+    }
+    if command.is_hidden {
+        settings.push(("Hidden", String::from("yes")));
+    }
+    settings.extend(command.shell.clone().map(|shell| ("Shell", shell)));
+    settings.extend(
+        command
+            .container
+            .clone()
+            .map(|container| ("Container", container)),
+    );
+    settings.extend(
+        command
+            .timeout
+            .map(|timeout| ("Timeout", format!("{timeout}s"))),
+    );
+    settings.extend(
+        command
+            .encoding
+            .map(|encoding| ("Encoding", format!("{encoding:?}").to_lowercase())),
+    );
+    if !command.related.is_empty() {
+        settings.push(("Related", command.related.join(", ")));
+    }
+    settings.extend(
+        command
+            .context
+            .as_ref()
+            .map(|context| ("Context", context.to_string())),
+    );
+    if let Some(overlay) = &command.overlay {
+        settings.push(("Managed", format!("yes, from overlay {overlay}")));
+    } else if command.is_managed {
+        settings.push(("Managed", String::from("yes")));
+    }
+    settings
+}
+
+/// The command piped into `hoard new --stdin`, without the line break it ends with
+///
+/// Exits with 1 if nothing was piped in
+fn stdin_command() -> String {
+    let mut command = String::new();
+    if let Err(e) = std::io::stdin().read_to_string(&mut command) {
+        eprintln!("Could not read the command from stdin: {e}");
+        std::process::exit(1);
+    }
+    let command = command.trim_end();
+    if command.trim().is_empty() {
+        eprintln!("Nothing to save, stdin was empty");
+        std::process::exit(1);
+    }
+    command.to_string()
+}
+
+/// The `n`th most recent command of the shell history, to start a new command from
+///
+/// Exits with 1 if there is no history or it is shorter
+fn history_entry(n: usize) -> String {
+    let Some(path) = shell_history_path() else {
+        eprintln!("Could not find your shell history, export $HISTFILE to point at it");
+        std::process::exit(1);
+    };
+    let history = load_shell_history(&path);
+    let Some(entry) = recent_history_entry(&history, n) else {
+        eprintln!(
+            "{} has no command {n} entries back, not counting runs of hoard",
+            path.display()
+        );
+        std::process::exit(1);
+    };
+    entry.to_string()
+}
+
+/// History file of the user's shell, `$HISTFILE` if it is set
+fn shell_history_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("HISTFILE").filter(|path| !path.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    let home = dirs::home_dir()?;
+    let shell = std::env::var("SHELL").unwrap_or_default();
+    let path = match shell.rsplit('/').next().unwrap_or_default() {
+        "zsh" => home.join(".zsh_history"),
+        "fish" => home.join(".local/share/fish/fish_history"),
+        _ => home.join(".bash_history"),
+    };
+    path.exists().then_some(path)
+}
+
+#[cfg(test)]
+mod test_hoard {
+    use super::*;
+    use crate::config::DEFAULT_TROVE;
+    use crate::core::git::GIT_DIR;
+    use tempfile::tempdir;
+
+    #[test]
+    fn commits_the_trove_in_use() {
+        let home = tempdir().unwrap();
+        GitTrove::init(home.path(), None).unwrap();
+        let mut hoard = Hoard {
+            config: HoardConfig::new(home.path()),
+            ..Hoard::default()
+        };
+        hoard.with_trove(Some("work"));
+        hoard.trove = Trove::from_commands(&[HoardCmd::default()
+            .with_name("deploy")
+            .with_namespace("default")
+            .with_command("make deploy")]);
+        hoard.commit_to_git();
+
+        let git_dir = home.path().join(GIT_DIR);
+        assert!(git_dir.join("work.yml").exists());
+        assert!(!git_dir.join(format!("{DEFAULT_TROVE}.yml")).exists());
+    }
+
+    #[test]
+    fn backs_up_the_trove_in_use() {
+        let home = tempdir().unwrap();
+        let mut hoard = Hoard {
+            config: HoardConfig::new(home.path()),
+            ..Hoard::default()
+        };
+        hoard.with_trove(Some("work"));
+        let trove_file = hoard.trove_path.clone().unwrap();
+        fs::create_dir_all(trove_file.parent().unwrap()).unwrap();
+        Trove::default().save_trove_file(&trove_file);
+        hoard.back_up_trove(&trove_file);
+
+        assert_eq!(1, list_backups(home.path(), Some("work")).len());
+        assert!(list_backups(home.path(), Some(DEFAULT_TROVE)).is_empty());
+    }
+
+    #[test]
+    fn scrub_description_and_context_of_exports() {
+        let home = tempdir().unwrap();
+        let hoard = Hoard {
+            config: HoardConfig::new(home.path()),
+            ..Hoard::default()
+        };
+        let command = HoardCmd::default()
+            .with_name("db")
+            .with_command("psql -h db1.prod.example.com")
+            .with_description("Connect to db1.prod.example.com")
+            .with_context(Some(CommandContext {
+                cwd: Some(String::from("/srv/payments")),
+                git_remote: Some(String::from("git@git.example.com:acme/payments.git")),
+                kube_context: None,
+            }));
+        let trove = Trove::from_commands(&[hoard.command_for_export(&command, true)]);
+
+        let yaml = trove.to_yaml();
+        assert!(!yaml.contains("db1.prod.example.com"));
+        assert!(!yaml.contains("payments"));
+        assert_eq!("Connect to #host!", trove.commands[0].description);
+        // Without scrubbing the context is kept
+        assert!(hoard.command_for_export(&command, false).context.is_some());
+    }
 }
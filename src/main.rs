@@ -18,14 +18,23 @@ mod gui;
 mod hoard;
 mod sync_models;
 mod util;
+use clap::Parser;
+use cli_commands::Cli;
 use hoard::Hoard;
 
 #[tokio::main]
 async fn main() {
-    let (command, is_autocomplete) = Hoard::default()
-        .with_config(None)
+    let cli = Cli::parse();
+    let mut hoard = Hoard::default();
+    if let Some(profile) = &cli.profile {
+        hoard.with_profile(profile);
+    } else {
+        hoard.with_config(None);
+    }
+    let (command, is_autocomplete) = hoard
+        .with_trove(cli.trove.as_deref())
         .load_trove()
-        .start();
+        .start(&cli);
     if is_autocomplete {
         eprintln!("{}", command.trim());
     } else {
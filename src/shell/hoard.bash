@@ -1,3 +1,6 @@
+# Version of this plugin, hoard asks you to update it when it is older than hoard itself
+export HOARD_SHELL_VERSION=2
+
 __hoard_list ()
 {
     ## Thanks github.com/ellie/atuin for the inspiration how to get TUI with termion working as a bash Plugin
@@ -12,3 +15,21 @@ __hoard_list ()
 if [[ -z $HOARD_NOBIND ]]; then
 	bind -x '"\C-h": __hoard_list'
 fi
+
+# With HOARD_DAILY_TIP set, print a rarely used command at the first prompt of a day
+__hoard_daily_tip ()
+{
+    local today tip
+    today=$(date +%F)
+    if [[ $__HOARD_TIP_DAY != "$today" ]]; then
+        __HOARD_TIP_DAY=$today
+        tip="$(hoard random --daily 2>/dev/null)"
+        if [[ -n $tip ]]; then
+            echo "$tip"
+        fi
+    fi
+}
+
+if [[ -n $HOARD_DAILY_TIP ]]; then
+	PROMPT_COMMAND="__hoard_daily_tip${PROMPT_COMMAND:+;$PROMPT_COMMAND}"
+fi
@@ -1,4 +1,7 @@
 # Hoard bindings
+# Version of this plugin, hoard asks you to update it when it is older than hoard itself
+set -gx HOARD_SHELL_VERSION 2
+
 function __hoard_list
     set hoard_command (hoard --autocomplete list 3>&1 1>&2 2>&3)
     commandline -j $hoard_command
@@ -7,3 +10,16 @@ end
 if ! set -q HOARD_NOBIND
     bind \ch __hoard_list
 end
+
+# With HOARD_DAILY_TIP set, print a rarely used command at the first prompt of a day
+function __hoard_daily_tip --on-event fish_prompt
+    set -q HOARD_DAILY_TIP; or return
+    set today (date +%F)
+    if test "$__hoard_tip_day" != "$today"
+        set -g __hoard_tip_day $today
+        set tip (hoard random --daily 2>/dev/null)
+        if test -n "$tip"
+            printf '%s\n' $tip
+        end
+    end
+end
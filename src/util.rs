@@ -1,15 +1,118 @@
-pub fn translate_number_to_nth(count: u16) -> String {
-    match count {
-        0 => "first".to_string(),
-        1 => "second".to_string(),
-        2 => "third".to_string(),
-        3 => "fourth".to_string(),
-        4 => "fifth".to_string(),
-        5 => "sixth".to_string(),
-        6 => "seventh".to_string(),
-        7 => "eighth".to_string(),
-        _ => "nth".to_string(),
+use base64::Engine as _;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Ordinals that are written as words, larger ones are written with digits
+const ORDINAL_WORDS: &[&str] = &[
+    "first", "second", "third", "fourth", "fifth", "sixth", "seventh", "eighth", "ninth", "tenth",
+];
+
+/// Ordinal of the zero based `index`, e.g. `first` for 0 and `21st` for 20
+pub fn ordinal(index: usize) -> String {
+    if let Some(word) = ORDINAL_WORDS.get(index) {
+        return (*word).to_string();
     }
+    let number = index + 1;
+    let suffix = match (number % 10, number % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{number}{suffix}")
+}
+
+/// `count` followed by the singular or plural of a noun, e.g. `1 command` or `3 commands`
+pub fn pluralize(count: usize, singular: &str, plural: &str) -> String {
+    if count == 1 {
+        format!("{count} {singular}")
+    } else {
+        format!("{count} {plural}")
+    }
+}
+
+/// Look up an executable `binary` in the directories of `$PATH`
+pub fn find_in_path(binary: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(binary))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Programs that print the clipboard, tried in this order
+const PASTE_COMMANDS: &[&[&str]] = &[
+    &["pbpaste"],
+    &["wl-paste", "--no-newline"],
+    &["xclip", "-selection", "clipboard", "-o"],
+    &["xsel", "--clipboard", "--output"],
+];
+
+/// Text of the system clipboard, read with the first clipboard program that is installed
+pub fn read_clipboard() -> Option<String> {
+    let program = PASTE_COMMANDS
+        .iter()
+        .find(|program| find_in_path(program[0]).is_some())?;
+    let output = std::process::Command::new(program[0])
+        .args(&program[1..])
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Programs that put their input on the clipboard, tried in this order
+const COPY_COMMANDS: &[&[&str]] = &[
+    &["pbcopy"],
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+    &["clip.exe"],
+];
+
+/// Put `text` on the system clipboard with the first clipboard program that works, returns whether it was copied
+///
+/// Without one it is sent to the terminal as an OSC 52 sequence, which most terminals copy, over SSH as well
+pub fn write_clipboard(text: &str) -> bool {
+    let copied = COPY_COMMANDS
+        .iter()
+        .filter(|program| find_in_path(program[0]).is_some())
+        .any(|program| pipe_into(program, text));
+    copied
+        || std::fs::OpenOptions::new()
+            .write(true)
+            .open("/dev/tty")
+            .and_then(|mut tty| tty.write_all(osc52(text).as_bytes()))
+            .is_ok()
+}
+
+/// Run `program` with `text` as its input, whether it succeeded
+fn pipe_into(program: &[&str], text: &str) -> bool {
+    let child = std::process::Command::new(program[0])
+        .args(&program[1..])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn();
+    let Ok(mut child) = child else {
+        return false;
+    };
+    let written = child
+        .stdin
+        .take()
+        .is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+    child.wait().is_ok_and(|status| status.success()) && written
+}
+
+/// Terminal escape sequence that sets the clipboard to `text`
+fn osc52(text: &str) -> String {
+    format!(
+        "\x1b]52;c;{}\x07",
+        base64::engine::general_purpose::STANDARD.encode(text)
+    )
 }
 
 pub fn rem_first_and_last(value: &str) -> &str {
@@ -63,6 +166,22 @@ pub fn split_with_delim(s: &str, delim: &str) -> Vec<String> {
 mod test_commands {
     use super::*;
 
+    #[test]
+    fn copy_through_the_terminal() {
+        assert_eq!("\x1b]52;c;Z2l0IHN0YXR1cw==\x07", osc52("git status"));
+    }
+
+    #[test]
+    fn ordinals_beyond_words() {
+        assert_eq!("first", ordinal(0));
+        assert_eq!("tenth", ordinal(9));
+        assert_eq!("11th", ordinal(10));
+        assert_eq!("21st", ordinal(20));
+        assert_eq!("112th", ordinal(111));
+        assert_eq!("1 command", pluralize(1, "command", "commands"));
+        assert_eq!("0 entries", pluralize(0, "entry", "entries"));
+    }
+
     #[test]
     fn extract_named_parameter() {
         let s = String::from("Hello #test command");